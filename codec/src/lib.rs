@@ -0,0 +1,613 @@
+//! Encoding and decoding of ISO-TP (ISO 15765-2) frames without the standard library.
+//!
+//! The protocol control information of single, first, consecutive and flow control
+//! frames, including the CAN FD escape sequences for single frames beyond 7 bytes and
+//! PDUs beyond 4095 bytes. Frames are written into and decoded from caller provided
+//! buffers, nothing is allocated, so embedded projects reuse the framing of the
+//! socketcan-isotp userspace stack unchanged, it is re-exported as
+//! `socketcan_isotp::codec`.
+//!
+//! With extended addressing every frame starts with the address extension byte (N_AE).
+//! The encoders write it when passed an `ext_address`, [`decode`] expects it removed.
+//!
+//! ```rust
+//! use socketcan_isotp_codec::{decode, encode_consecutive, encode_first, Frame};
+//!
+//! let pdu = [0x62, 0xF1, 0x90, 0x57, 0x56, 0x57, 0x5A, 0x5A, 0x5A];
+//! let mut frame = [0; 8];
+//! let consumed = encode_first(&mut frame, None, &pdu).unwrap();
+//! assert_eq!(frame, [0x10, 0x09, 0x62, 0xF1, 0x90, 0x57, 0x56, 0x57]);
+//!
+//! let (len, _) = encode_consecutive(&mut frame, None, 1, &pdu[consumed..]).unwrap();
+//! assert_eq!(
+//!     decode(&frame[..len]),
+//!     Ok(Frame::Consecutive {
+//!         sequence_number: 1,
+//!         data: &[0x5A, 0x5A, 0x5A]
+//!     })
+//! );
+//! ```
+
+#![no_std]
+#![deny(clippy::all)]
+
+use core::fmt;
+
+/// Largest PDU length encoded in a first frame without escape sequence
+pub const FF_DL_MAX: usize = 0xFFF;
+
+/// Largest single frame payload of classic CAN frames without address extension
+pub const SF_DL_MAX: usize = 7;
+
+/// Flow status of a flow control frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FlowStatus {
+    /// Send the next block of consecutive frames
+    ContinueToSend,
+    /// Wait for the next flow control frame
+    Wait,
+    /// The PDU exceeds the receive buffer, abort
+    Overflow,
+}
+
+/// A decoded ISO-TP frame (N_PDU), the data borrows from the CAN frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Frame<'a> {
+    /// A complete PDU in a single frame
+    Single { data: &'a [u8] },
+    /// The beginning of a PDU of `len` bytes
+    First { len: u32, data: &'a [u8] },
+    /// A continuation of a PDU, `sequence_number` wraps from 15 to 0
+    Consecutive { sequence_number: u8, data: &'a [u8] },
+    /// Flow control of the receiver, `stmin` is the raw separation time byte
+    FlowControl {
+        status: FlowStatus,
+        block_size: u8,
+        stmin: u8,
+    },
+}
+
+/// Errors of the frame codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CodecError {
+    /// Frame ends within its protocol control information or announced data
+    Truncated,
+    /// Length of zero, or data too long for the frame type
+    InvalidLength,
+    /// Reserved frame type or flow status
+    Reserved,
+    /// Frame buffer too small for the encoded frame
+    BufferTooSmall,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CodecError::Truncated => "ISO-TP frame truncated",
+            CodecError::InvalidLength => "invalid ISO-TP length",
+            CodecError::Reserved => "reserved ISO-TP frame type or flow status",
+            CodecError::BufferTooSmall => "frame buffer too small",
+        })
+    }
+}
+
+impl core::error::Error for CodecError {}
+
+/// Decode a frame, `payload` starts after the address extension if any
+pub fn decode(payload: &[u8]) -> Result<Frame<'_>, CodecError> {
+    let (&pci, rest) = payload.split_first().ok_or(CodecError::Truncated)?;
+    match pci >> 4 {
+        0x0 => {
+            let (len, data) = match pci & 0x0F {
+                // CAN FD single frame with escape sequence
+                0 => {
+                    let (&len, data) = rest.split_first().ok_or(CodecError::Truncated)?;
+                    (usize::from(len), data)
+                }
+                len => (usize::from(len), rest),
+            };
+            if len == 0 {
+                return Err(CodecError::InvalidLength);
+            }
+            Ok(Frame::Single {
+                data: data.get(..len).ok_or(CodecError::Truncated)?,
+            })
+        }
+        0x1 => {
+            let (&low, rest) = rest.split_first().ok_or(CodecError::Truncated)?;
+            match u16::from_be_bytes([pci & 0x0F, low]) {
+                // first frame with escape sequence, 32 bit length
+                0 => {
+                    let (len, data) = rest.split_first_chunk::<4>().ok_or(CodecError::Truncated)?;
+                    Ok(Frame::First {
+                        len: u32::from_be_bytes(*len),
+                        data,
+                    })
+                }
+                len => Ok(Frame::First {
+                    len: u32::from(len),
+                    data: rest,
+                }),
+            }
+        }
+        0x2 => Ok(Frame::Consecutive {
+            sequence_number: pci & 0x0F,
+            data: rest,
+        }),
+        0x3 => {
+            let status = match pci & 0x0F {
+                0 => FlowStatus::ContinueToSend,
+                1 => FlowStatus::Wait,
+                2 => FlowStatus::Overflow,
+                _ => return Err(CodecError::Reserved),
+            };
+            let [block_size, stmin, ..] = *rest else {
+                return Err(CodecError::Truncated);
+            };
+            Ok(Frame::FlowControl {
+                status,
+                block_size,
+                stmin,
+            })
+        }
+        _ => Err(CodecError::Reserved),
+    }
+}
+
+/// Largest single frame payload of frames with `tx_dl` bytes, `extended` for an address
+/// extension
+pub fn max_single_frame_len(tx_dl: usize, extended: bool) -> usize {
+    let ext_len = usize::from(extended);
+    if tx_dl <= 8 {
+        tx_dl.saturating_sub(1 + ext_len)
+    } else {
+        tx_dl.saturating_sub(2 + ext_len)
+    }
+}
+
+/// Encode a single frame, returning the frame length
+///
+/// Data of up to 7 bytes minus the address extension uses the classic encoding, longer
+/// data the CAN FD escape sequence.
+pub fn encode_single(
+    frame: &mut [u8],
+    ext_address: Option<u8>,
+    data: &[u8],
+) -> Result<usize, CodecError> {
+    let ext_len = write_ext_address(frame, ext_address)?;
+    let len = data.len();
+    if len == 0 || len > usize::from(u8::MAX) {
+        return Err(CodecError::InvalidLength);
+    }
+    let header_len = if len <= SF_DL_MAX - ext_len { 1 } else { 2 };
+    let frame = frame
+        .get_mut(ext_len..ext_len + header_len + len)
+        .ok_or(CodecError::BufferTooSmall)?;
+    if header_len == 1 {
+        frame[0] = len as u8;
+    } else {
+        frame[..2].copy_from_slice(&[0x00, len as u8]);
+    }
+    frame[header_len..].copy_from_slice(data);
+    Ok(ext_len + header_len + len)
+}
+
+/// Encode the first frame of `pdu` filling all of `frame`, returning the number of PDU
+/// bytes consumed
+///
+/// PDUs beyond [`FF_DL_MAX`] bytes use the escape sequence with a 32 bit length. Fails
+/// with [`CodecError::InvalidLength`] if `pdu` fits into the first frame.
+pub fn encode_first(
+    frame: &mut [u8],
+    ext_address: Option<u8>,
+    pdu: &[u8],
+) -> Result<usize, CodecError> {
+    let ext_len = write_ext_address(frame, ext_address)?;
+    let pdu_len = u32::try_from(pdu.len()).map_err(|_| CodecError::InvalidLength)?;
+    let header_len = if pdu.len() <= FF_DL_MAX { 2 } else { 6 };
+    let frame = frame
+        .get_mut(ext_len..)
+        .filter(|frame| frame.len() > header_len)
+        .ok_or(CodecError::BufferTooSmall)?;
+    let consumed = frame.len() - header_len;
+    if pdu.len() <= consumed {
+        return Err(CodecError::InvalidLength);
+    }
+    if header_len == 2 {
+        frame[..2].copy_from_slice(&(0x1000 | pdu_len as u16).to_be_bytes());
+    } else {
+        frame[..2].copy_from_slice(&[0x10, 0x00]);
+        frame[2..6].copy_from_slice(&pdu_len.to_be_bytes());
+    }
+    frame[header_len..].copy_from_slice(&pdu[..consumed]);
+    Ok(consumed)
+}
+
+/// Encode a consecutive frame with as much of `data` as fits into `frame`, returning the
+/// frame length and the number of data bytes consumed
+pub fn encode_consecutive(
+    frame: &mut [u8],
+    ext_address: Option<u8>,
+    sequence_number: u8,
+    data: &[u8],
+) -> Result<(usize, usize), CodecError> {
+    let ext_len = write_ext_address(frame, ext_address)?;
+    let frame = frame
+        .get_mut(ext_len..)
+        .filter(|frame| frame.len() > 1)
+        .ok_or(CodecError::BufferTooSmall)?;
+    let consumed = data.len().min(frame.len() - 1);
+    frame[0] = 0x20 | (sequence_number & 0x0F);
+    frame[1..1 + consumed].copy_from_slice(&data[..consumed]);
+    Ok((ext_len + 1 + consumed, consumed))
+}
+
+/// Encode a flow control frame, returning the frame length
+pub fn encode_flow_control(
+    frame: &mut [u8],
+    ext_address: Option<u8>,
+    status: FlowStatus,
+    block_size: u8,
+    stmin: u8,
+) -> Result<usize, CodecError> {
+    let ext_len = write_ext_address(frame, ext_address)?;
+    let flow_status = match status {
+        FlowStatus::ContinueToSend => 0,
+        FlowStatus::Wait => 1,
+        FlowStatus::Overflow => 2,
+    };
+    frame
+        .get_mut(ext_len..ext_len + 3)
+        .ok_or(CodecError::BufferTooSmall)?
+        .copy_from_slice(&[0x30 | flow_status, block_size, stmin]);
+    Ok(ext_len + 3)
+}
+
+/// Round `len` up to the next valid CAN FD data length
+pub fn fd_frame_len(len: usize) -> usize {
+    match len {
+        0..=8 => len,
+        9..=12 => 12,
+        13..=16 => 16,
+        17..=20 => 20,
+        21..=24 => 24,
+        25..=32 => 32,
+        33..=48 => 48,
+        _ => 64,
+    }
+}
+
+/// Length of a frame with `len` significant bytes after padding
+///
+/// Classic CAN frames are padded to 8 bytes if `padding` is enabled, CAN FD frames always
+/// to a valid CAN FD data length and to at least 8 bytes if `padding` is enabled.
+pub fn padded_len(len: usize, fd: bool, padding: bool) -> usize {
+    match (fd, padding) {
+        (false, false) => len,
+        (false, true) => len.max(8),
+        (true, false) => fd_frame_len(len),
+        (true, true) => fd_frame_len(len).max(8),
+    }
+}
+
+/// Write the address extension, returning its length
+fn write_ext_address(frame: &mut [u8], ext_address: Option<u8>) -> Result<usize, CodecError> {
+    match ext_address {
+        Some(ext_address) => {
+            *frame.first_mut().ok_or(CodecError::BufferTooSmall)? = ext_address;
+            Ok(1)
+        }
+        None => Ok(0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_frames() {
+        let table: [(&[u8], Result<Frame<'_>, CodecError>); 10] = [
+            (
+                &[0x03, 0x22, 0xF1, 0x90, 0x55],
+                Ok(Frame::Single {
+                    data: &[0x22, 0xF1, 0x90],
+                }),
+            ),
+            // CAN FD escape sequence, the length byte follows the PCI
+            (
+                &[0x00, 0x02, 0x3E, 0x00, 0xCC],
+                Ok(Frame::Single {
+                    data: &[0x3E, 0x00],
+                }),
+            ),
+            (
+                &[0x10, 0x0A, 1, 2, 3, 4, 5, 6],
+                Ok(Frame::First {
+                    len: 10,
+                    data: &[1, 2, 3, 4, 5, 6],
+                }),
+            ),
+            (
+                &[0x1F, 0xFF, 1],
+                Ok(Frame::First {
+                    len: 0xFFF,
+                    data: &[1],
+                }),
+            ),
+            // FF_DL escape sequence, 32 bit length
+            (
+                &[0x10, 0x00, 0x00, 0x00, 0x13, 0x88, 1, 2],
+                Ok(Frame::First {
+                    len: 5000,
+                    data: &[1, 2],
+                }),
+            ),
+            (
+                &[0x2F, 7, 8],
+                Ok(Frame::Consecutive {
+                    sequence_number: 15,
+                    data: &[7, 8],
+                }),
+            ),
+            (
+                &[0x20],
+                Ok(Frame::Consecutive {
+                    sequence_number: 0,
+                    data: &[],
+                }),
+            ),
+            (
+                &[0x30, 0x08, 0x14, 0xAA],
+                Ok(Frame::FlowControl {
+                    status: FlowStatus::ContinueToSend,
+                    block_size: 8,
+                    stmin: 0x14,
+                }),
+            ),
+            (
+                &[0x31, 0x00, 0x00],
+                Ok(Frame::FlowControl {
+                    status: FlowStatus::Wait,
+                    block_size: 0,
+                    stmin: 0,
+                }),
+            ),
+            (
+                &[0x32, 0x00, 0xF1],
+                Ok(Frame::FlowControl {
+                    status: FlowStatus::Overflow,
+                    block_size: 0,
+                    stmin: 0xF1,
+                }),
+            ),
+        ];
+        for (payload, expected) in table {
+            assert_eq!(decode(payload), expected, "{payload:02X?}");
+        }
+    }
+
+    #[test]
+    fn decode_malformed_frames() {
+        let table: [(&[u8], CodecError); 13] = [
+            (&[], CodecError::Truncated),
+            (&[0x05, 1, 2, 3], CodecError::Truncated),
+            // escape sequence without length byte, and with fewer bytes than announced
+            (&[0x00], CodecError::Truncated),
+            (&[0x00, 0x0A, 1, 2], CodecError::Truncated),
+            (&[0x10], CodecError::Truncated),
+            (&[0x10, 0x00, 0x00, 0x00, 0x13], CodecError::Truncated),
+            (&[0x30, 0x00], CodecError::Truncated),
+            (&[0x00, 0x00, 1], CodecError::InvalidLength),
+            (&[0x33, 0x00, 0x00], CodecError::Reserved),
+            (&[0x3F, 0x00, 0x00], CodecError::Reserved),
+            (&[0x40, 0x00], CodecError::Reserved),
+            (&[0x70, 0x00], CodecError::Reserved),
+            (&[0xF0, 0x00], CodecError::Reserved),
+        ];
+        for (payload, expected) in table {
+            assert_eq!(decode(payload), Err(expected), "{payload:02X?}");
+        }
+    }
+
+    #[test]
+    fn max_single_frame_len_per_data_length() {
+        let table = [
+            (8, false, 7),
+            (8, true, 6),
+            (12, false, 10),
+            (12, true, 9),
+            (64, false, 62),
+            (64, true, 61),
+            (0, true, 0),
+        ];
+        for (tx_dl, extended, expected) in table {
+            assert_eq!(
+                max_single_frame_len(tx_dl, extended),
+                expected,
+                "{tx_dl} {extended}"
+            );
+        }
+    }
+
+    #[test]
+    fn encode_single_frames() {
+        let data = [0xA5; 62];
+        let table: [(Option<u8>, usize, &[u8]); 6] = [
+            (None, 7, &[0x07]),
+            (Some(0xF1), 6, &[0xF1, 0x06]),
+            // beyond 7 bytes minus the address extension the escape sequence is used
+            (None, 8, &[0x00, 0x08]),
+            (Some(0xF1), 7, &[0xF1, 0x00, 0x07]),
+            (None, 62, &[0x00, 62]),
+            (Some(0xF1), 61, &[0xF1, 0x00, 61]),
+        ];
+        for (ext_address, len, header) in table {
+            let mut frame = [0xCC; 64];
+            let frame_len = encode_single(&mut frame, ext_address, &data[..len]).unwrap();
+            assert_eq!(frame_len, header.len() + len, "{ext_address:?} {len}");
+            assert_eq!(&frame[..header.len()], header, "{ext_address:?} {len}");
+            assert_eq!(&frame[header.len()..frame_len], &data[..len]);
+
+            let ext_len = usize::from(ext_address.is_some());
+            assert_eq!(
+                decode(&frame[ext_len..frame_len]),
+                Ok(Frame::Single { data: &data[..len] })
+            );
+        }
+    }
+
+    #[test]
+    fn encode_single_rejects_invalid_lengths() {
+        let mut frame = [0; 8];
+        assert_eq!(
+            encode_single(&mut frame, None, &[]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(
+            encode_single(&mut [0; 300], None, &[0; 256]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(
+            encode_single(&mut frame, None, &[0; 8]),
+            Err(CodecError::BufferTooSmall)
+        );
+        assert_eq!(
+            encode_single(&mut frame, Some(0xF1), &[0; 7]),
+            Err(CodecError::BufferTooSmall)
+        );
+        assert_eq!(
+            encode_single(&mut [], Some(0xF1), &[0]),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn encode_first_frames() {
+        let pdu = [0x5A; 5000];
+        let table: [(Option<u8>, usize, usize, &[u8]); 5] = [
+            (None, 8, 8, &[0x10, 0x08]),
+            (Some(0xF1), 8, 7, &[0xF1, 0x10, 0x07]),
+            (None, 64, FF_DL_MAX, &[0x1F, 0xFF]),
+            // FF_DL escape sequence beyond 4095 bytes
+            (
+                None,
+                64,
+                FF_DL_MAX + 1,
+                &[0x10, 0x00, 0x00, 0x00, 0x10, 0x00],
+            ),
+            (
+                Some(0xF1),
+                64,
+                5000,
+                &[0xF1, 0x10, 0x00, 0x00, 0x00, 0x13, 0x88],
+            ),
+        ];
+        for (ext_address, frame_len, pdu_len, header) in table {
+            let mut frame = [0xCC; 64];
+            let frame = &mut frame[..frame_len];
+            let consumed = encode_first(frame, ext_address, &pdu[..pdu_len]).unwrap();
+            assert_eq!(
+                consumed,
+                frame_len - header.len(),
+                "{ext_address:?} {pdu_len}"
+            );
+            assert_eq!(&frame[..header.len()], header, "{ext_address:?} {pdu_len}");
+
+            let ext_len = usize::from(ext_address.is_some());
+            assert_eq!(
+                decode(&frame[ext_len..]),
+                Ok(Frame::First {
+                    len: pdu_len as u32,
+                    data: &pdu[..consumed]
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn encode_first_rejects_pdus_fitting_the_frame() {
+        let mut frame = [0; 8];
+        assert_eq!(
+            encode_first(&mut frame, None, &[0; 6]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(
+            encode_first(&mut frame, Some(0xF1), &[0; 5]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(
+            encode_first(&mut frame[..2], None, &[0; 6]),
+            Err(CodecError::BufferTooSmall)
+        );
+        assert_eq!(
+            encode_first(&mut frame[..6], None, &[0; 5000]),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn encode_consecutive_frames() {
+        let data = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let table: [(Option<u8>, u8, usize, &[u8]); 4] = [
+            (None, 1, 8, &[0x21, 1, 2, 3, 4, 5, 6, 7]),
+            (Some(0xF1), 2, 8, &[0xF1, 0x22, 1, 2, 3, 4, 5, 6]),
+            // the sequence number wraps from 15 to 0
+            (None, 16, 8, &[0x20, 1, 2, 3, 4, 5, 6, 7]),
+            (None, 3, 12, &[0x23, 1, 2, 3, 4, 5, 6, 7, 8, 9]),
+        ];
+        for (ext_address, sequence_number, frame_len, expected) in table {
+            let mut frame = [0xCC; 12];
+            let (len, consumed) =
+                encode_consecutive(&mut frame[..frame_len], ext_address, sequence_number, &data)
+                    .unwrap();
+            assert_eq!(&frame[..len], expected, "{ext_address:?} {sequence_number}");
+            assert_eq!(consumed, len - 1 - usize::from(ext_address.is_some()));
+        }
+        assert_eq!(
+            encode_consecutive(&mut [0xF1], Some(0xF1), 1, &data),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn encode_flow_control_frames() {
+        let table: [(Option<u8>, FlowStatus, &[u8]); 3] = [
+            (None, FlowStatus::ContinueToSend, &[0x30, 0x08, 0x14]),
+            (Some(0xF1), FlowStatus::Wait, &[0xF1, 0x31, 0x08, 0x14]),
+            (None, FlowStatus::Overflow, &[0x32, 0x08, 0x14]),
+        ];
+        for (ext_address, status, expected) in table {
+            let mut frame = [0xCC; 8];
+            let len = encode_flow_control(&mut frame, ext_address, status, 8, 0x14).unwrap();
+            assert_eq!(&frame[..len], expected, "{ext_address:?} {status:?}");
+        }
+        assert_eq!(
+            encode_flow_control(&mut [0; 3], Some(0xF1), FlowStatus::Wait, 0, 0),
+            Err(CodecError::BufferTooSmall)
+        );
+    }
+
+    #[test]
+    fn padded_frame_lengths() {
+        let table = [
+            (3, false, false, 3),
+            (3, false, true, 8),
+            (3, true, false, 3),
+            (3, true, true, 8),
+            (9, true, false, 12),
+            (13, true, true, 16),
+            (21, true, false, 24),
+            (33, true, false, 48),
+            (49, true, false, 64),
+            (64, true, true, 64),
+        ];
+        for (len, fd, padding, expected) in table {
+            assert_eq!(
+                padded_len(len, fd, padding),
+                expected,
+                "{len} {fd} {padding}"
+            );
+        }
+    }
+}
@@ -0,0 +1,33 @@
+//! ISO-TP over CAN FD with bit rate switching.
+//!
+//! Requires a CAN FD capable interface, e.g. a virtual one:
+//!
+//! ```sh
+//! sudo ip link add dev vcan0 type vcan
+//! sudo ip link set vcan0 mtu 72
+//! sudo ip link set up vcan0
+//! candump -x vcan0
+//! ```
+use socketcan_isotp::{self, IsoTpSocketBuilder, StandardId, TxDataLength, TxFlags};
+use std::time::Duration;
+
+fn main() -> Result<(), socketcan_isotp::Error> {
+    let tp_socket = IsoTpSocketBuilder::new()
+        .canfd_if_supported(TxDataLength::Dl64, TxFlags::empty())
+        .bit_rate_switch(true)
+        .open(
+            "vcan0",
+            StandardId::new(0x321).expect("Invalid rx id"),
+            StandardId::new(0x123).expect("Invalid tx id"),
+        )?;
+
+    let link_layer_options = tp_socket.get_link_layer_options()?;
+    println!("Link layer: {}", link_layer_options);
+
+    let payload: Vec<u8> = (0..=255).collect();
+    loop {
+        tp_socket.write(&payload)?;
+        println!("Sent {} bytes", payload.len());
+        std::thread::sleep(Duration::from_millis(1000));
+    }
+}
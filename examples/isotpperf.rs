@@ -0,0 +1,43 @@
+//! Measure ISO-TP round-trip latency and throughput on an interface.
+//!
+//! ```sh
+//! cargo run --example isotpperf -- vcan0 4095 8 0 64
+//! ```
+//!
+//! Arguments: interface, payload length, block size, STmin (raw byte) and optionally the
+//! CAN FD data length, which requires `ip link set vcan0 mtu 72`.
+use socketcan_isotp::bench::Benchmark;
+use socketcan_isotp::{
+    self, FlowControlOptions, LinkLayerOptions, SeparationTime, StandardId, TxDataLength, TxFlags,
+};
+
+fn main() -> Result<(), socketcan_isotp::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg = |index: usize, default: &str| -> u16 {
+        args.get(index)
+            .map(String::as_str)
+            .unwrap_or(default)
+            .parse()
+            .expect("Invalid numeric argument")
+    };
+    let ifname = args.first().map(String::as_str).unwrap_or("vcan0");
+    let payload_len = usize::from(arg(1, "4095"));
+    let bs = u8::try_from(arg(2, "0")).expect("Invalid block size");
+    let stmin = SeparationTime::from_raw(u8::try_from(arg(3, "0")).expect("Invalid STmin"))?;
+
+    let mut benchmark = Benchmark::new(payload_len)
+        .iterations(100)
+        .flow_control_options(FlowControlOptions::new(bs, stmin, 0));
+    if let Some(tx_dl) = args.get(4) {
+        let tx_dl = TxDataLength::try_from(tx_dl.parse::<u8>().expect("Invalid tx_dl"))?;
+        benchmark = benchmark.link_layer_options(LinkLayerOptions::canfd(tx_dl, TxFlags::empty()));
+    }
+
+    let report = benchmark.run(
+        ifname,
+        StandardId::new(0x700).expect("Invalid tx id"),
+        StandardId::new(0x708).expect("Invalid rx id"),
+    )?;
+    println!("{}", report);
+    Ok(())
+}
@@ -14,7 +14,7 @@ fn main() -> Result<(), socketcan_isotp::Error> {
         print!("{:X?} ", x);
     }
 
-    println!("");
+    println!();
 
     Ok(())
 }
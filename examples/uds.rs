@@ -1,44 +1,27 @@
-//! UDS (unified diagnostic protocol) example for reading a data by identifer.
-//! Run the following server for testing.
-//! https://github.com/zombieCraig/uds-server
+//! UDS (unified diagnostic protocol) client reading a data by identifer.
+//! Answers the request with a scripted responder simulating the ECU.
 
+use socketcan_isotp::uds::{Nrc, ScriptedResponder, UdsClient, UdsError};
 use socketcan_isotp::{self, IsoTpSocket, StandardId};
-use std::sync::mpsc;
 
-fn main() -> Result<(), socketcan_isotp::Error> {
-    let (tx, rx) = mpsc::channel();
-
-    // Reader
-    let mut reader_tp_socket = IsoTpSocket::open(
+fn main() -> Result<(), UdsError> {
+    let (tp_socket, ecu_tp_socket) = IsoTpSocket::pair(
         "vcan0",
-        StandardId::new(0x7E8).expect("Invalid rx CAN ID"),
-        StandardId::new(0x77A).expect("Invalid tx CAN ID"),
+        StandardId::new(0x7E0).expect("Invalid tester CAN ID"),
+        StandardId::new(0x7E8).expect("Invalid ECU CAN ID"),
     )?;
-    std::thread::spawn(move || loop {
-        let buffer = reader_tp_socket.read().expect("Failed to read from socket");
-        tx.send(buffer.to_vec()).expect("Receiver deallocated");
-    });
 
-    let tp_socket = IsoTpSocket::open(
-        "vcan0",
-        StandardId::new(0x77A).expect("Invalid rx CAN ID"),
-        StandardId::new(0x7E0).expect("Invalid tx CAN ID"),
-    )?;
+    // ECU
+    let responder = ScriptedResponder::new()
+        .reply(&[0x22, 0xF1, 0x89], b"\x62\xF1\x89SW 1.0.0")
+        .otherwise(Nrc::ServiceNotSupported)
+        .spawn(ecu_tp_socket);
 
-    // 0x22 - Service Identifier for "Read data by identifier" request
-    // 0xF189 - Data identifer - VehicleManufacturerECUSoftwareVersionNumberDataIdentifier
-    tp_socket.write(&[0x22, 0xF1, 0x89])?;
+    let mut client = UdsClient::new(tp_socket);
 
-    println!("Sent read data by identifier 0xF189 - VehicleManufacturerECUSoftwareVersionNumberDataIdentifier");
+    // 0xF189 - Data identifer - VehicleManufacturerECUSoftwareVersionNumberDataIdentifier
+    let response = client.read_data_by_identifier(0xF189)?;
+    println!("Response: {:X?}", response);
 
-    loop {
-        let recv_buffer = rx.recv().expect("Failed to receive");
-        // 0x62 - Service Identifier for "Read data by identifier" response
-        // 0xF189 - Data identifer - VehicleManufacturerECUSoftwareVersionNumberDataIdentifier
-        if recv_buffer[0..=2] != [0x62, 0xF1, 0x89] {
-            println!("Skipping: {:X?}", recv_buffer);
-        } else {
-            println!("Response: {:X?}", &recv_buffer[3..]);
-        }
-    }
+    Ok(responder.stop()?)
 }
@@ -0,0 +1,373 @@
+//! ISO 15765-4 / SAE J1939-21 style identifiers for normal fixed addressing.
+//!
+//! With normal fixed addressing the target and source address are encoded in a
+//! 29 bit identifier: `0x18DA<target><source>` for physical and
+//! `0x18DB<target><source>` for functional addressing.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::{addressing::NormalFixedId, IsoTpSocket};
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     // tester 0xF1 talking to ECU 0x10
+//!     let request = NormalFixedId::physical(0x10, 0xF1);
+//!     let tp_socket = IsoTpSocket::open("vcan0", request.response().id(), request.id())?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Extended and mixed addressing prefix the payload with an address extension byte
+//! (N_AE) instead, see [`ExtAddress`].
+//!
+//! [`IsoTpAddress`] bundles everything needed to open a socket in one value with a
+//! canonical string representation.
+
+use crate::Error;
+use embedded_can::{ExtendedId, Id, StandardId};
+use std::fmt;
+use std::str::FromStr;
+
+/// PDU format of physically addressed frames
+pub const PF_PHYSICAL: u8 = 0xDA;
+
+/// PDU format of functionally addressed frames
+pub const PF_FUNCTIONAL: u8 = 0xDB;
+
+/// Default priority as used by ISO 15765-4
+pub const DEFAULT_PRIORITY: u8 = 6;
+
+/// Target address type (N_TAtype)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TargetAddressType {
+    /// 1-to-1 communication, PDU format 0xDA
+    Physical,
+    /// 1-to-N communication, PDU format 0xDB
+    Functional,
+}
+
+/// A 29 bit identifier using normal fixed addressing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NormalFixedId {
+    priority: u8,
+    target_address_type: TargetAddressType,
+    target_address: u8,
+    source_address: u8,
+}
+
+impl NormalFixedId {
+    /// Physically addressed identifier `0x18DA<target><source>`
+    pub fn physical(target_address: u8, source_address: u8) -> Self {
+        Self {
+            priority: DEFAULT_PRIORITY,
+            target_address_type: TargetAddressType::Physical,
+            target_address,
+            source_address,
+        }
+    }
+
+    /// Functionally addressed identifier `0x18DB<target><source>`
+    pub fn functional(target_address: u8, source_address: u8) -> Self {
+        Self {
+            priority: DEFAULT_PRIORITY,
+            target_address_type: TargetAddressType::Functional,
+            target_address,
+            source_address,
+        }
+    }
+
+    /// Set the 3 bit priority, higher bits are ignored
+    pub fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority & 0x07;
+        self
+    }
+
+    /// The physically addressed identifier of responses, swapping target and source address
+    pub fn response(&self) -> Self {
+        Self {
+            priority: self.priority,
+            target_address_type: TargetAddressType::Physical,
+            target_address: self.source_address,
+            source_address: self.target_address,
+        }
+    }
+
+    /// Decode a normal fixed addressing identifier, `None` if the PDU format doesn't match
+    pub fn from_id(id: ExtendedId) -> Option<Self> {
+        let raw = id.as_raw();
+        // reserved and data page bits are zero
+        if raw & 0x0300_0000 != 0 {
+            return None;
+        }
+        let target_address_type = match (raw >> 16) as u8 {
+            PF_PHYSICAL => TargetAddressType::Physical,
+            PF_FUNCTIONAL => TargetAddressType::Functional,
+            _ => return None,
+        };
+        Some(Self {
+            priority: (raw >> 26) as u8 & 0x07,
+            target_address_type,
+            target_address: (raw >> 8) as u8,
+            source_address: raw as u8,
+        })
+    }
+
+    /// The encoded 29 bit identifier
+    pub fn id(&self) -> ExtendedId {
+        let pdu_format = match self.target_address_type {
+            TargetAddressType::Physical => PF_PHYSICAL,
+            TargetAddressType::Functional => PF_FUNCTIONAL,
+        };
+        let raw = (self.priority as u32 & 0x07) << 26
+            | (pdu_format as u32) << 16
+            | (self.target_address as u32) << 8
+            | self.source_address as u32;
+        // at most 29 bit are set
+        ExtendedId::new(raw).unwrap_or(ExtendedId::ZERO)
+    }
+
+    /// get the 3 bit priority
+    pub fn get_priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// get the target address type
+    pub fn get_target_address_type(&self) -> TargetAddressType {
+        self.target_address_type
+    }
+
+    /// get the target address (N_TA)
+    pub fn get_target_address(&self) -> u8 {
+        self.target_address
+    }
+
+    /// get the source address (N_SA)
+    pub fn get_source_address(&self) -> u8 {
+        self.source_address
+    }
+}
+
+impl From<NormalFixedId> for ExtendedId {
+    fn from(id: NormalFixedId) -> Self {
+        id.id()
+    }
+}
+
+impl From<NormalFixedId> for Id {
+    fn from(id: NormalFixedId) -> Self {
+        Id::Extended(id.id())
+    }
+}
+
+impl fmt::Display for NormalFixedId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08X}", self.id().as_raw())
+    }
+}
+
+/// Address extension bytes (N_AE) of extended and mixed addressing
+///
+/// Usually both directions use the same address byte. Mixed setups, e.g. gateways
+/// answering with their own address, use distinct tx and rx bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtAddress {
+    tx: u8,
+    rx: u8,
+}
+
+impl ExtAddress {
+    /// Use `ext_address` for transmitted and received frames
+    pub fn new(ext_address: u8) -> Self {
+        Self {
+            tx: ext_address,
+            rx: ext_address,
+        }
+    }
+
+    /// Prefix transmitted frames with `tx` and only accept received frames prefixed with `rx`
+    pub fn mixed(tx: u8, rx: u8) -> Self {
+        Self { tx, rx }
+    }
+
+    /// get the address byte of transmitted frames
+    pub fn get_tx(&self) -> u8 {
+        self.tx
+    }
+
+    /// get the address byte of received frames
+    pub fn get_rx(&self) -> u8 {
+        self.rx
+    }
+
+    /// Whether tx and rx address bytes differ, requiring `CAN_ISOTP_RX_EXT_ADDR`
+    pub fn is_mixed(&self) -> bool {
+        self.tx != self.rx
+    }
+}
+
+impl From<u8> for ExtAddress {
+    fn from(ext_address: u8) -> Self {
+        Self::new(ext_address)
+    }
+}
+
+impl fmt::Display for ExtAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_mixed() {
+            write!(f, "{:02X}/{:02X}", self.tx, self.rx)
+        } else {
+            write!(f, "{:02X}", self.tx)
+        }
+    }
+}
+
+impl FromStr for ExtAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |s| {
+            u8::from_str_radix(s, 16)
+                .map_err(|_| Error::InvalidInput("extended address is not a hex byte"))
+        };
+        match s.split_once('/') {
+            Some((tx, rx)) => Ok(Self::mixed(parse(tx)?, parse(rx)?)),
+            None => parse(s).map(Self::new),
+        }
+    }
+}
+
+/// Interface, identifiers and optional address extension of an ISO-TP connection
+///
+/// The string representation is `<interface>:<tx_id>:<rx_id>[:<ext_address>]` with
+/// hexadecimal identifiers, e.g. `can0:7E0:7E8`. Like `candump`, 3 digits denote a
+/// standard and 8 digits an extended identifier, e.g. `can0:18DA10F1:18DAF110`. The
+/// address extension is a hex byte, or `<tx>/<rx>` for mixed addressing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "String", into = "String"))]
+pub struct IsoTpAddress {
+    interface: String,
+    tx_id: Id,
+    rx_id: Id,
+    ext_address: Option<ExtAddress>,
+}
+
+impl IsoTpAddress {
+    /// Address of a connection using normal or normal fixed addressing
+    pub fn new(interface: impl Into<String>, tx_id: impl Into<Id>, rx_id: impl Into<Id>) -> Self {
+        Self {
+            interface: interface.into(),
+            tx_id: tx_id.into(),
+            rx_id: rx_id.into(),
+            ext_address: None,
+        }
+    }
+
+    /// Use extended or mixed addressing
+    pub fn with_ext_address(mut self, ext_address: impl Into<ExtAddress>) -> Self {
+        self.ext_address = Some(ext_address.into());
+        self
+    }
+
+    /// get the CAN interface name
+    pub fn get_interface(&self) -> &str {
+        &self.interface
+    }
+
+    /// get the identifier of transmitted frames
+    pub fn get_tx_id(&self) -> Id {
+        self.tx_id
+    }
+
+    /// get the identifier of received frames
+    pub fn get_rx_id(&self) -> Id {
+        self.rx_id
+    }
+
+    /// get the address extension, `None` without extended addressing
+    pub fn get_ext_address(&self) -> Option<ExtAddress> {
+        self.ext_address
+    }
+}
+
+fn fmt_id(f: &mut fmt::Formatter<'_>, id: Id) -> fmt::Result {
+    match id {
+        Id::Standard(id) => write!(f, "{:03X}", id.as_raw()),
+        Id::Extended(id) => write!(f, "{:08X}", id.as_raw()),
+    }
+}
+
+fn parse_id(s: &str) -> Result<Id, Error> {
+    let raw = u32::from_str_radix(s, 16)
+        .map_err(|_| Error::InvalidInput("identifier is not a hex number"))?;
+    match s.len() {
+        3 => u16::try_from(raw)
+            .ok()
+            .and_then(StandardId::new)
+            .map(Id::Standard)
+            .ok_or(Error::InvalidInput("standard identifier exceeds 11 bit")),
+        8 => ExtendedId::new(raw)
+            .map(Id::Extended)
+            .ok_or(Error::InvalidInput("extended identifier exceeds 29 bit")),
+        _ => Err(Error::InvalidInput(
+            "identifier must have 3 (standard) or 8 (extended) hex digits",
+        )),
+    }
+}
+
+impl fmt::Display for IsoTpAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.interface)?;
+        fmt_id(f, self.tx_id)?;
+        write!(f, ":")?;
+        fmt_id(f, self.rx_id)?;
+        if let Some(ext_address) = &self.ext_address {
+            write!(f, ":{}", ext_address)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for IsoTpAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let (Some(interface), Some(tx_id), Some(rx_id)) =
+            (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(Error::InvalidInput(
+                "address must be <interface>:<tx_id>:<rx_id>[:<ext_address>]",
+            ));
+        };
+        if interface.is_empty() {
+            return Err(Error::InvalidInput("interface name is empty"));
+        }
+        let ext_address = parts.next().map(str::parse).transpose()?;
+        if parts.next().is_some() {
+            return Err(Error::InvalidInput("address has trailing fields"));
+        }
+
+        Ok(Self {
+            interface: interface.to_owned(),
+            tx_id: parse_id(tx_id)?,
+            rx_id: parse_id(rx_id)?,
+            ext_address,
+        })
+    }
+}
+
+impl TryFrom<String> for IsoTpAddress {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<IsoTpAddress> for String {
+    fn from(address: IsoTpAddress) -> Self {
+        address.to_string()
+    }
+}
@@ -0,0 +1,246 @@
+//! Round-trip latency and throughput measurement, similar to `isotpperf` of can-utils.
+//!
+//! Opens two cross-wired sockets on an interface, one of them echoing each PDU back from a
+//! background thread. Useful to tune the flow control parameters BS and STmin and the CAN
+//! FD data length empirically.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::bench::Benchmark;
+//! use socketcan_isotp::{FlowControlOptions, SeparationTime, StandardId};
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     let report = Benchmark::new(512)
+//!         .iterations(100)
+//!         .flow_control_options(FlowControlOptions::new(8, SeparationTime::ZERO, 0))
+//!         .run(
+//!             "vcan0",
+//!             StandardId::new(0x700).expect("Invalid id"),
+//!             StandardId::new(0x708).expect("Invalid id"),
+//!         )?;
+//!     println!("{}", report);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    interface_index, poll, Error, FlowControlOptions, Id, IsoTpOptions, IsoTpSocket,
+    LinkLayerOptions,
+};
+use std::fmt;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default number of PDUs per measurement
+pub const DEFAULT_ITERATIONS: usize = 100;
+
+/// Default time to wait for each PDU before the benchmark fails with [`Error::Timeout`]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Configuration of a benchmark run, see the [module documentation](self)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Benchmark {
+    payload_len: usize,
+    iterations: usize,
+    timeout: Duration,
+    isotp_options: IsoTpOptions,
+    flow_control_options: FlowControlOptions,
+    link_layer_options: LinkLayerOptions,
+}
+
+impl Benchmark {
+    /// Measure PDUs of `payload_len` bytes with the kernel default options
+    pub fn new(payload_len: usize) -> Self {
+        Self {
+            payload_len,
+            iterations: DEFAULT_ITERATIONS,
+            timeout: DEFAULT_TIMEOUT,
+            isotp_options: IsoTpOptions::default(),
+            flow_control_options: FlowControlOptions::default(),
+            link_layer_options: LinkLayerOptions::default(),
+        }
+    }
+
+    /// Number of PDUs sent for each of latency and throughput, at least 1
+    pub fn iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+
+    /// Time to wait for each PDU
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// ISO-TP options of both sockets
+    pub fn isotp_options(mut self, isotp_options: IsoTpOptions) -> Self {
+        self.isotp_options = isotp_options;
+        self
+    }
+
+    /// Flow control options, i.e. BS and STmin, of both sockets
+    pub fn flow_control_options(mut self, flow_control_options: FlowControlOptions) -> Self {
+        self.flow_control_options = flow_control_options;
+        self
+    }
+
+    /// Link layer options, e.g. the CAN FD `tx_dl`, of both sockets
+    pub fn link_layer_options(mut self, link_layer_options: LinkLayerOptions) -> Self {
+        self.link_layer_options = link_layer_options;
+        self
+    }
+
+    /// Run the benchmark on `ifname`, sending on `tx_id` and echoing on `rx_id`
+    pub fn run(
+        &self,
+        ifname: &str,
+        tx_id: impl Into<Id>,
+        rx_id: impl Into<Id>,
+    ) -> Result<BenchmarkReport, Error> {
+        let if_index = interface_index(ifname)?;
+        let (tx_id, rx_id) = (tx_id.into(), rx_id.into());
+        let open = |rx_id, tx_id| {
+            IsoTpSocket::open_if_with_opts(
+                if_index,
+                rx_id,
+                tx_id,
+                Some(self.isotp_options),
+                Some(self.flow_control_options),
+                Some(self.link_layer_options),
+            )
+        };
+        let mut tester = open(rx_id, tx_id)?;
+        let mut echo = open(tx_id, rx_id)?;
+
+        let iterations = self.iterations;
+        let timeout = self.timeout;
+        // echo the latency PDUs, then receive the throughput PDUs
+        let echo = thread::spawn(move || -> Result<Instant, Error> {
+            for _ in 0..iterations {
+                read_timeout(&mut echo, timeout)?;
+                let pdu = echo.read()?.to_vec();
+                echo.write(&pdu)?;
+            }
+            for _ in 0..iterations {
+                read_timeout(&mut echo, timeout)?;
+                echo.read()?;
+            }
+            Ok(Instant::now())
+        });
+
+        let payload: Vec<u8> = (0..self.payload_len).map(|i| i as u8).collect();
+        let mut round_trips = Vec::with_capacity(iterations);
+        let latency = (|| {
+            for _ in 0..iterations {
+                let sent = Instant::now();
+                tester.write(&payload)?;
+                read_timeout(&mut tester, timeout)?;
+                tester.read()?;
+                round_trips.push(sent.elapsed());
+            }
+            Ok::<_, Error>(())
+        })();
+        let started = Instant::now();
+        let throughput = latency.and_then(|()| {
+            for _ in 0..iterations {
+                tester.write(&payload)?;
+            }
+            Ok(())
+        });
+        let finished = echo
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        throughput?;
+        let elapsed = finished?.saturating_duration_since(started);
+
+        Ok(BenchmarkReport {
+            payload_len: self.payload_len,
+            iterations,
+            latency: LatencyStats::from_samples(round_trips),
+            throughput: (self.payload_len * iterations) as f64 / elapsed.as_secs_f64(),
+        })
+    }
+}
+
+/// Distribution of the measured round-trip times
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyStats {
+    /// Fastest round trip
+    pub min: Duration,
+    /// Arithmetic mean
+    pub mean: Duration,
+    /// Median
+    pub p50: Duration,
+    /// 90th percentile
+    pub p90: Duration,
+    /// 99th percentile
+    pub p99: Duration,
+    /// Slowest round trip
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Compute the statistics of `samples`, all zero if empty
+    pub fn from_samples(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let percentile = |percent: usize| {
+            let index = (samples.len() * percent).div_ceil(100).max(1) - 1;
+            samples.get(index).copied().unwrap_or_default()
+        };
+        let total: Duration = samples.iter().sum();
+        Self {
+            min: samples.first().copied().unwrap_or_default(),
+            mean: total.checked_div(samples.len() as u32).unwrap_or_default(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: samples.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Result of a [`Benchmark`] run
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BenchmarkReport {
+    /// PDU size in bytes
+    pub payload_len: usize,
+    /// Number of PDUs per measurement
+    pub iterations: usize,
+    /// PDU round-trip times
+    pub latency: LatencyStats,
+    /// Sustained one-way payload throughput in bytes per second
+    pub throughput: f64,
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let LatencyStats {
+            min,
+            mean,
+            p50,
+            p90,
+            p99,
+            max,
+        } = self.latency;
+        writeln!(f, "{} PDUs of {} bytes", self.iterations, self.payload_len)?;
+        writeln!(
+            f,
+            "round trip: min {:?}, mean {:?}, p50 {:?}, p90 {:?}, p99 {:?}, max {:?}",
+            min, mean, p50, p90, p99, max
+        )?;
+        write!(f, "throughput: {:.1} kB/s", self.throughput / 1000.0)
+    }
+}
+
+/// Wait up to `timeout` for a PDU, failing with [`Error::Timeout`]
+fn read_timeout(socket: &mut IsoTpSocket, timeout: Duration) -> Result<(), Error> {
+    if poll::wait_readable(socket.as_raw_fd(), Some(timeout))? {
+        Ok(())
+    } else {
+        Err(Error::from(io::Error::from(io::ErrorKind::TimedOut)))
+    }
+}
@@ -0,0 +1,72 @@
+//! Experimental CAN XL link layer definitions.
+//!
+//! CAN XL frames carry up to 2048 bytes of payload. The kernel ISO-TP implementation does
+//! not support CAN XL yet: `can_isotp_ll_options.mtu` is a `__u8` and can't hold
+//! [`CANXL_MTU`]. The definitions here allow validating XL configurations ahead of
+//! kernel support, they are not applied to sockets.
+
+use crate::Error;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Size of `struct canxl_frame` with the maximum payload
+pub const CANXL_MTU: u16 = CANXL_HDR_SIZE + CANXL_MAX_DLEN;
+
+/// Size of the `struct canxl_frame` header
+pub const CANXL_HDR_SIZE: u16 = 12;
+
+/// Minimum CAN XL payload length
+pub const CANXL_MIN_DLEN: u16 = 1;
+
+/// Maximum CAN XL payload length
+pub const CANXL_MAX_DLEN: u16 = 2048;
+
+/// Link layer data length used for transmitted CAN XL frames
+///
+/// Unlike CAN FD, any length between [`CANXL_MIN_DLEN`] and [`CANXL_MAX_DLEN`] is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u16", into = "u16"))]
+pub struct XlDataLength(u16);
+
+impl XlDataLength {
+    /// The maximum CAN XL payload length
+    pub const MAX: Self = Self(CANXL_MAX_DLEN);
+
+    /// Data length in bytes
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+impl Default for XlDataLength {
+    fn default() -> Self {
+        Self::MAX
+    }
+}
+
+impl TryFrom<u16> for XlDataLength {
+    type Error = Error;
+
+    fn try_from(tx_dl: u16) -> Result<Self, Self::Error> {
+        if (CANXL_MIN_DLEN..=CANXL_MAX_DLEN).contains(&tx_dl) {
+            Ok(Self(tx_dl))
+        } else {
+            Err(Error::InvalidLinkLayerOptions(
+                "CAN XL tx_dl must be between 1 and 2048 bytes",
+            ))
+        }
+    }
+}
+
+impl From<XlDataLength> for u16 {
+    fn from(tx_dl: XlDataLength) -> Self {
+        tx_dl.as_u16()
+    }
+}
+
+impl fmt::Display for XlDataLength {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
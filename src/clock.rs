@@ -0,0 +1,114 @@
+//! Time source of timers, replaceable to test timing deterministically.
+//!
+//! [`SystemClock`] reads the monotonic system time and sleeps the calling thread,
+//! [`SimulatedClock`] only advances when slept on or advanced explicitly, so timeouts
+//! such as the UDS P2/P2* expire without waiting for them.
+//!
+//! ```rust
+//! use socketcan_isotp::clock::{Clock, SimulatedClock};
+//! use std::time::Duration;
+//!
+//! let clock = SimulatedClock::new();
+//! let start = clock.now();
+//! clock.sleep(Duration::from_secs(5));
+//! assert_eq!(clock.now() - start, Duration::from_secs(5));
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A monotonic time source
+pub trait Clock: Send + Sync {
+    /// Get the current time
+    fn now(&self) -> Instant;
+
+    /// Block until `duration` passed
+    fn sleep(&self, duration: Duration);
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+}
+
+/// The monotonic system time, the default clock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// A clock standing still until it is slept on or advanced, sleeping returns immediately
+///
+/// Clones share the same time, e.g. a test keeps one to inspect the time the code under
+/// test spent waiting.
+#[derive(Clone)]
+pub struct SimulatedClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl SimulatedClock {
+    /// Start the clock at the current system time
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    /// Move the time forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        // a poisoned lock only means another thread panicked while advancing
+        *self
+            .elapsed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) += duration;
+    }
+
+    /// Get the time passed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        *self
+            .elapsed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for SimulatedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SimulatedClock")
+            .field("elapsed", &self.elapsed())
+            .finish()
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
@@ -0,0 +1,39 @@
+//! Kernel default values as defined in `linux/can/isotp.h`.
+//!
+//! These are the values the kernel uses when the corresponding socket option is not set,
+//! and the values used by the `Default` implementations of the option structs.
+
+use crate::{CAN_MAX_DLEN, CAN_MTU};
+
+/// Default isotp behaviour flags
+pub const CAN_ISOTP_DEFAULT_FLAGS: u32 = 0;
+
+/// Default address for extended addressing
+pub const CAN_ISOTP_DEFAULT_EXT_ADDRESS: u8 = 0x00;
+
+/// Default content of padding bytes (tx and rx)
+pub const CAN_ISOTP_DEFAULT_PAD_CONTENT: u8 = 0xCC;
+
+/// Default frame transmission time in nano secs
+///
+/// Kernels before Linux 5.18 default to 0. Newer kernels replace a configured
+/// `frame_txtime` of 0 by this value.
+pub const CAN_ISOTP_DEFAULT_FRAME_TXTIME: u32 = 50_000;
+
+/// Default blocksize provided in FC frame (off)
+pub const CAN_ISOTP_DEFAULT_RECV_BS: u8 = 0;
+
+/// Default separation time provided in FC frame
+pub const CAN_ISOTP_DEFAULT_RECV_STMIN: u8 = 0x00;
+
+/// Default max. number of wait frame transmissions (omit FC N_PDU WT)
+pub const CAN_ISOTP_DEFAULT_RECV_WFTMAX: u8 = 0;
+
+/// Default link layer MTU (classic CAN)
+pub const CAN_ISOTP_DEFAULT_LL_MTU: u8 = CAN_MTU;
+
+/// Default tx link layer data length
+pub const CAN_ISOTP_DEFAULT_LL_TX_DL: u8 = CAN_MAX_DLEN;
+
+/// Default flags set into struct canfd_frame.flags
+pub const CAN_ISOTP_DEFAULT_LL_TX_FLAGS: u8 = 0;
@@ -0,0 +1,277 @@
+//! Companion CAN error frame monitor.
+//!
+//! ISO-TP sockets never deliver CAN error frames, so a bus-off controller or a missing
+//! acknowledge only shows up as ISO-TP timeout. The [`ErrorMonitor`] opens a `CAN_RAW`
+//! socket on the same interface that receives nothing but error frames.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::{IsoTpSocket, StandardId};
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     let tp_socket = IsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x7E8).expect("Invalid rx id"),
+//!         StandardId::new(0x7E0).expect("Invalid tx id"),
+//!     )?;
+//!     let error_monitor = tp_socket.error_monitor()?;
+//!
+//!     std::thread::spawn(move || loop {
+//!         match error_monitor.read() {
+//!             Ok(error_frame) => println!("CAN error: {:?}", error_frame.class()),
+//!             Err(e) => break eprintln!("error monitor failed: {}", e),
+//!         }
+//!     });
+//!
+//!     tp_socket.write(&[0x3E, 0x00])?;
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{
+    close_fd, interface_index, Error, AF_CAN, ERR_MASK, ERR_MASK_ALL, PF_CAN, SOL_CAN_BASE,
+};
+use bitflags::bitflags;
+use libc::{bind, c_int, c_short, c_void, read, setsockopt, sockaddr, socket, socklen_t, SOCK_RAW};
+use std::io;
+use std::mem::size_of;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+
+/// Raw CAN protocol
+pub const CAN_RAW: c_int = 1;
+
+/// Socket option level of raw CAN sockets
+pub const SOL_CAN_RAW: c_int = SOL_CAN_BASE + CAN_RAW;
+
+/// set 0 .. n can_filter(s)
+pub const CAN_RAW_FILTER: c_int = 1;
+
+/// set filter for error frames
+pub const CAN_RAW_ERR_FILTER: c_int = 2;
+
+bitflags! {
+    /// Error class of a CAN error frame, as found in its CAN identifier
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct CanErrorClass: u32 {
+        /// TX timeout (by netdevice driver)
+        const TX_TIMEOUT = 0x001;
+        /// lost arbitration, see data\[0\]
+        const LOSTARB = 0x002;
+        /// controller problems, see data\[1\]
+        const CRTL = 0x004;
+        /// protocol violations, see data\[2..3\]
+        const PROT = 0x008;
+        /// transceiver status, see data\[4\]
+        const TRX = 0x010;
+        /// received no ACK on transmission
+        const ACK = 0x020;
+        /// bus off
+        const BUSOFF = 0x040;
+        /// bus error (may flood!)
+        const BUSERROR = 0x080;
+        /// controller restarted
+        const RESTARTED = 0x100;
+        /// TX error counter / data\[6\], RX error counter / data\[7\]
+        const CNT = 0x200;
+    }
+}
+
+bitflags! {
+    /// Controller problems, data\[1\] of error frames with [`CanErrorClass::CRTL`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct CanControllerState: u8 {
+        /// RX buffer overflow
+        const RX_OVERFLOW = 0x01;
+        /// TX buffer overflow
+        const TX_OVERFLOW = 0x02;
+        /// reached warning level for RX errors
+        const RX_WARNING = 0x04;
+        /// reached warning level for TX errors
+        const TX_WARNING = 0x08;
+        /// reached error passive status RX
+        const RX_PASSIVE = 0x10;
+        /// reached error passive status TX
+        const TX_PASSIVE = 0x20;
+        /// recovered to error active state
+        const ACTIVE = 0x40;
+    }
+}
+
+/// A CAN error frame as reported by the CAN driver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanErrorFrame {
+    can_id: u32,
+    data: [u8; 8],
+}
+
+impl CanErrorFrame {
+    /// Error classes reported by this frame
+    pub fn class(&self) -> CanErrorClass {
+        CanErrorClass::from_bits_truncate(self.can_id & ERR_MASK)
+    }
+
+    /// Controller state, if the frame reports controller problems
+    pub fn controller_state(&self) -> Option<CanControllerState> {
+        if self.class().contains(CanErrorClass::CRTL) {
+            Some(CanControllerState::from_bits_truncate(self.data[1]))
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the controller went bus-off
+    pub fn is_bus_off(&self) -> bool {
+        self.class().contains(CanErrorClass::BUSOFF)
+    }
+
+    /// TX and RX error counters, if reported by the driver
+    pub fn error_counters(&self) -> Option<(u8, u8)> {
+        if self.class().contains(CanErrorClass::CNT) {
+            Some((self.data[6], self.data[7]))
+        } else {
+            None
+        }
+    }
+
+    /// Raw error frame payload, see `linux/can/error.h`
+    pub fn data(&self) -> &[u8; 8] {
+        &self.data
+    }
+}
+
+/// `struct can_frame`
+#[repr(C)]
+struct CanFrame {
+    can_id: u32,
+    len: u8,
+    _pad: u8,
+    _res0: u8,
+    _len8_dlc: u8,
+    data: [u8; 8],
+}
+
+/// `struct sockaddr_can` without transport protocol address
+#[repr(C)]
+pub(crate) struct RawCanAddr {
+    _af_can: c_short,
+    if_index: c_int,
+    _addr: [u8; 16],
+}
+
+impl RawCanAddr {
+    /// Address of the interface `if_index`
+    pub(crate) fn new(if_index: c_int) -> Self {
+        Self {
+            _af_can: AF_CAN,
+            if_index,
+            _addr: [0; 16],
+        }
+    }
+}
+
+/// A raw CAN socket receiving only the error frames of an interface.
+pub struct ErrorMonitor {
+    fd: OwnedFd,
+}
+
+impl ErrorMonitor {
+    /// Open an error monitor on a named CAN device.
+    pub fn open(ifname: &str) -> Result<Self, Error> {
+        Self::open_if(interface_index(ifname)?)
+    }
+
+    /// Open an error monitor on a CAN device by interface number, 0 monitors all CAN devices.
+    pub fn open_if(if_index: c_int) -> Result<Self, Error> {
+        if if_index < 0 {
+            return Err(Error::InvalidInput("interface index must not be negative"));
+        }
+        let sock_fd = unsafe { socket(PF_CAN, SOCK_RAW, CAN_RAW) };
+        if sock_fd == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(sock_fd) };
+
+        // no filters, no data frames
+        let err = unsafe { setsockopt(sock_fd, SOL_CAN_RAW, CAN_RAW_FILTER, std::ptr::null(), 0) };
+        if err == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+
+        let err_mask = ERR_MASK_ALL;
+        let err = unsafe {
+            setsockopt(
+                sock_fd,
+                SOL_CAN_RAW,
+                CAN_RAW_ERR_FILTER,
+                &err_mask as *const _ as *const c_void,
+                size_of::<u32>() as socklen_t,
+            )
+        };
+        if err == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+
+        let addr = RawCanAddr::new(if_index);
+        let bind_rv = unsafe {
+            bind(
+                sock_fd,
+                &addr as *const _ as *const sockaddr,
+                size_of::<RawCanAddr>() as socklen_t,
+            )
+        };
+        if bind_rv == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+
+        Ok(Self { fd })
+    }
+
+    /// Blocking read of the next error frame
+    pub fn read(&self) -> io::Result<CanErrorFrame> {
+        let mut frame = CanFrame {
+            can_id: 0,
+            len: 0,
+            _pad: 0,
+            _res0: 0,
+            _len8_dlc: 0,
+            data: [0; 8],
+        };
+
+        let read_rv = unsafe {
+            read(
+                self.fd.as_raw_fd(),
+                &mut frame as *mut _ as *mut c_void,
+                size_of::<CanFrame>(),
+            )
+        };
+        if read_rv < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if read_rv as usize != size_of::<CanFrame>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected CAN frame size",
+            ));
+        }
+
+        Ok(CanErrorFrame {
+            can_id: frame.can_id,
+            data: frame.data,
+        })
+    }
+
+    /// Change socket to non-blocking mode
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        crate::set_nonblocking_fd(self.fd.as_raw_fd(), nonblocking)
+    }
+
+    /// Close the socket, reporting errors that `Drop` would silently ignore.
+    pub fn close(self) -> io::Result<()> {
+        close_fd(self.fd.into_raw_fd())
+    }
+}
+
+impl AsRawFd for ErrorMonitor {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
@@ -0,0 +1,137 @@
+//! Entry points of the cargo-fuzz targets in `fuzz/`.
+//!
+//! Each function feeds arbitrary bytes to the parsers of untrusted bus data and log files
+//! and ignores the result, the fuzzer only looks for panics and hangs. The frame codec
+//! additionally asserts that encoded frames decode to their input. Timeouts pass on a
+//! [`SimulatedClock`](crate::clock::SimulatedClock), so responses skipped as unrelated
+//! don't slow the fuzzer down.
+//!
+//! ```sh
+//! cargo +nightly fuzz run uds_response
+//! ```
+
+#[cfg(feature = "uds")]
+use crate::uds::{
+    decode_negative_response, DiagnosticSession, DidRegistry, Dtc, DtcStatus, MockTransport,
+    Recording, ResetType, SecurityAccess, UdsClient,
+};
+#[cfg(feature = "uds")]
+use std::sync::Arc;
+#[cfg(feature = "uds")]
+use std::time::Duration;
+
+/// Answer a UDS request selected by the first byte with the remaining bytes
+#[cfg(feature = "uds")]
+pub fn uds_response(data: &[u8]) {
+    let Some((&selector, response)) = data.split_first() else {
+        return;
+    };
+    let _ = decode_negative_response(response);
+
+    let clock = Arc::new(crate::clock::SimulatedClock::new());
+    let mock = MockTransport::new()
+        .with_clock(clock.clone())
+        .on_request(&[], response);
+    let mut client = UdsClient::new(mock);
+    client.set_clock(clock);
+    // bound the pending responses a single response can't produce anyway
+    client.set_max_pending_responses(Some(1));
+
+    let dtc = Dtc::new(0x123456);
+    match selector % 12 {
+        0 => drop(client.read_data_by_identifier(0xF190)),
+        1 => drop(client.diagnostic_session_control(DiagnosticSession::Extended)),
+        2 => drop(client.ecu_reset(ResetType::Hard, Duration::ZERO)),
+        3 => drop(client.read_dtc_by_status_mask(DtcStatus::all())),
+        4 => drop(client.read_dtc_extended_data(dtc, 0xFF)),
+        5 => drop(client.read_dtc_snapshot_records(dtc, 0xFF, &DidRegistry::new())),
+        6 => {
+            if let Ok(security_access) = SecurityAccess::new(0x01) {
+                drop(security_access.request_seed(&mut client));
+            }
+        }
+        7 => drop(client.start_routine(0xFF00, &[])),
+        8 => drop(client.request_routine_results(0xFF00)),
+        9 => drop(client.read_timing_parameters()),
+        #[cfg(feature = "obd")]
+        10 => drop(client.read_wwh_obd_dtcs(DtcStatus::all(), 0xFF)),
+        #[cfg(feature = "obd")]
+        11 => drop(client.read_wwh_obd_test_results(0x01)),
+        _ => drop(client.request(response.first().copied().unwrap_or_default(), &[])),
+    }
+}
+
+/// Decode a frame, and encode the remaining bytes with the CAN FD data length and address
+/// extension selected by the first byte, asserting they decode unchanged
+pub fn codec(data: &[u8]) {
+    use crate::codec::{
+        decode, encode_consecutive, encode_first, encode_single, max_single_frame_len, Frame,
+    };
+
+    let _ = decode(data);
+    let Some((&selector, pdu)) = data.split_first() else {
+        return;
+    };
+    let tx_dl = [8, 12, 16, 20, 24, 32, 48, 64][usize::from(selector & 0x07)];
+    let ext_address = (selector & 0x08 != 0).then_some(selector);
+    let ext_len = usize::from(ext_address.is_some());
+    let mut buf = [0; 64];
+    let frame = &mut buf[..tx_dl];
+
+    if !pdu.is_empty() && pdu.len() <= max_single_frame_len(tx_dl, ext_address.is_some()) {
+        let len = encode_single(frame, ext_address, pdu).expect("single frame fits");
+        assert_eq!(
+            decode(&frame[ext_len..len]),
+            Ok(Frame::Single { data: pdu })
+        );
+    } else if let Ok(consumed) = encode_first(frame, ext_address, pdu) {
+        match decode(&frame[ext_len..]) {
+            Ok(Frame::First { len, data }) => {
+                assert_eq!(len as usize, pdu.len());
+                assert_eq!(data, &pdu[..consumed]);
+            }
+            decoded => panic!("first frame decoded as {:?}", decoded),
+        }
+        let (len, consumed) = encode_consecutive(frame, ext_address, selector, &pdu[consumed..])
+            .expect("consecutive frame fits");
+        assert!(matches!(
+            decode(&frame[ext_len..len]),
+            Ok(Frame::Consecutive { sequence_number, data })
+                if sequence_number == selector & 0x0F && data.len() == consumed
+        ));
+    }
+}
+
+/// Parse a [`Recording`] text file
+#[cfg(feature = "uds")]
+pub fn recording(data: &[u8]) {
+    let _ = Recording::read_from(data);
+}
+
+/// Decode the OBD-II PID selected by the first byte, Mode 06 test records and DTC strings
+#[cfg(feature = "obd")]
+pub fn obd_response(data: &[u8]) {
+    use crate::obd::{decode_pid, ObdDtc, TestResult};
+
+    if let Some((&pid, pid_data)) = data.split_first() {
+        let _ = decode_pid(pid, pid_data);
+    }
+    for record in data.chunks_exact(9) {
+        let mut bytes = [0; 9];
+        bytes.copy_from_slice(record);
+        let _ = TestResult::from_bytes(bytes).passed();
+    }
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = text.parse::<ObdDtc>();
+    }
+}
+
+/// Load an ODX document or PDX archive
+#[cfg(feature = "odx")]
+pub fn odx(data: &[u8]) {
+    let mut database = crate::uds::OdxDatabase::new();
+    match std::str::from_utf8(data) {
+        Ok(document) => drop(database.add_odx(document)),
+        Err(_) => drop(database.add_pdx(std::io::Cursor::new(data))),
+    }
+}
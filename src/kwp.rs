@@ -0,0 +1,654 @@
+//! Keyword Protocol 2000 (ISO 14230-3) client on top of ISO-TP.
+//!
+//! KWP2000 on CAN shares the response framing of UDS: positive responses carry the
+//! service identifier + 0x40, negative responses `[0x7F, sid, code]` and pending responses
+//! (code 0x78) extend the response timeout. [`KwpClient`] therefore wraps a
+//! [`UdsClient`] and reuses its transport, response timeouts, retry policy and tracing.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::{kwp::{KwpClient, KwpSession}, IsoTpSocket, StandardId};
+//!
+//! fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+//!     let tp_socket = IsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x7E8).expect("Invalid rx id"),
+//!         StandardId::new(0x7E0).expect("Invalid tx id"),
+//!     )?;
+//!     let mut client = KwpClient::new(tp_socket);
+//!
+//!     client.start_diagnostic_session(KwpSession::Standard)?;
+//!     let identification = client.read_ecu_identification(0x80)?;
+//!     println!("ECU identification: {:X?}", identification);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::uds::{Request, UdsClient, UdsError, UdsTransport};
+use crate::IsoTpSocket;
+use std::time::Duration;
+
+/// Service identifiers of ISO 14230-3 requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum KwpServiceId {
+    /// startDiagnosticSession
+    StartDiagnosticSession = 0x10,
+    /// ecuReset
+    EcuReset = 0x11,
+    /// clearDiagnosticInformation
+    ClearDiagnosticInformation = 0x14,
+    /// readStatusOfDiagnosticTroubleCodes
+    ReadStatusOfDiagnosticTroubleCodes = 0x17,
+    /// readDiagnosticTroubleCodesByStatus
+    ReadDiagnosticTroubleCodesByStatus = 0x18,
+    /// readEcuIdentification
+    ReadEcuIdentification = 0x1A,
+    /// stopDiagnosticSession
+    StopDiagnosticSession = 0x20,
+    /// readDataByLocalIdentifier
+    ReadDataByLocalIdentifier = 0x21,
+    /// readDataByCommonIdentifier
+    ReadDataByCommonIdentifier = 0x22,
+    /// readMemoryByAddress
+    ReadMemoryByAddress = 0x23,
+    /// securityAccess
+    SecurityAccess = 0x27,
+    /// writeDataByCommonIdentifier
+    WriteDataByCommonIdentifier = 0x2E,
+    /// startRoutineByLocalIdentifier
+    StartRoutineByLocalIdentifier = 0x31,
+    /// stopRoutineByLocalIdentifier
+    StopRoutineByLocalIdentifier = 0x32,
+    /// requestRoutineResultsByLocalIdentifier
+    RequestRoutineResultsByLocalIdentifier = 0x33,
+    /// writeDataByLocalIdentifier
+    WriteDataByLocalIdentifier = 0x3B,
+    /// writeMemoryByAddress
+    WriteMemoryByAddress = 0x3D,
+    /// testerPresent
+    TesterPresent = 0x3E,
+}
+
+impl KwpServiceId {
+    /// The encoded service identifier
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl From<KwpServiceId> for u8 {
+    fn from(service: KwpServiceId) -> Self {
+        service.as_u8()
+    }
+}
+
+/// Diagnostic modes of startDiagnosticSession (0x10)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KwpSession {
+    /// standardDiagnosticMode (0x81)
+    Standard,
+    /// ecuProgrammingMode (0x85)
+    Programming,
+    /// ecuDevelopmentMode (0x86)
+    Development,
+    /// ecuAdjustmentMode (0x87)
+    Adjustment,
+    /// Vehicle manufacturer specific diagnostic mode
+    Other(u8),
+}
+
+impl KwpSession {
+    /// The encoded diagnosticMode
+    pub fn as_u8(self) -> u8 {
+        match self {
+            KwpSession::Standard => 0x81,
+            KwpSession::Programming => 0x85,
+            KwpSession::Development => 0x86,
+            KwpSession::Adjustment => 0x87,
+            KwpSession::Other(session) => session,
+        }
+    }
+}
+
+impl From<u8> for KwpSession {
+    fn from(session: u8) -> Self {
+        match session {
+            0x81 => KwpSession::Standard,
+            0x85 => KwpSession::Programming,
+            0x86 => KwpSession::Development,
+            0x87 => KwpSession::Adjustment,
+            session => KwpSession::Other(session),
+        }
+    }
+}
+
+/// Reset modes of ecuReset (0x11)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KwpResetMode {
+    /// powerOn (0x01)
+    PowerOn,
+    /// Vehicle manufacturer specific reset
+    Other(u8),
+}
+
+impl KwpResetMode {
+    /// The encoded resetMode
+    pub fn as_u8(self) -> u8 {
+        match self {
+            KwpResetMode::PowerOn => 0x01,
+            KwpResetMode::Other(mode) => mode,
+        }
+    }
+}
+
+/// A 2 byte KWP2000 diagnostic trouble code with its status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KwpDtc {
+    /// The trouble code
+    pub code: u16,
+    /// statusOfDTC
+    pub status: u8,
+}
+
+/// groupOfDTC selecting all DTCs
+pub const ALL_DTC_GROUPS: u16 = 0xFF00;
+
+/// Largest memoryAddress of readMemoryByAddress and writeMemoryByAddress
+pub const MAX_MEMORY_ADDRESS: u32 = 0x00FF_FFFF;
+
+/// A KWP2000 client sending requests to a single server over an [`IsoTpSocket`] or
+/// another [`UdsTransport`].
+///
+/// Response timeouts, pending responses, retries and tracing are handled by the wrapped
+/// [`UdsClient`], accessible with [`KwpClient::get_uds`] and [`KwpClient::get_uds_mut`].
+/// securityAccess (0x27) is identical to UDS, unlock levels with
+/// [`SecurityAccess`](crate::uds::SecurityAccess) on the wrapped client.
+pub struct KwpClient<T = IsoTpSocket> {
+    client: UdsClient<T>,
+    session: KwpSession,
+}
+
+impl<T: UdsTransport> KwpClient<T> {
+    /// Create a client using the default response timeouts
+    pub fn new(transport: T) -> Self {
+        Self::from_uds(UdsClient::new(transport))
+    }
+
+    /// Send KWP2000 requests with a configured [`UdsClient`]
+    pub fn from_uds(client: UdsClient<T>) -> Self {
+        Self {
+            client,
+            session: KwpSession::Standard,
+        }
+    }
+
+    /// Send a request and wait for the response of the server.
+    ///
+    /// Returns the response parameters following the positive response service identifier.
+    pub fn request(&mut self, service: impl Into<u8>, data: &[u8]) -> Result<Vec<u8>, UdsError> {
+        self.client.request(service, data)
+    }
+
+    /// startDiagnosticSession (0x10), switching to `session`
+    pub fn start_diagnostic_session(&mut self, session: KwpSession) -> Result<(), UdsError> {
+        let response = self.request(KwpServiceId::StartDiagnosticSession, &[session.as_u8()])?;
+        // the diagnosticMode echo is optional
+        if response
+            .first()
+            .is_some_and(|echo| *echo != session.as_u8())
+        {
+            return Err(UdsError::InvalidResponse(
+                "response does not echo the diagnostic mode",
+            ));
+        }
+        self.session = session;
+        Ok(())
+    }
+
+    /// stopDiagnosticSession (0x20), returning to the standard session
+    pub fn stop_diagnostic_session(&mut self) -> Result<(), UdsError> {
+        self.request(KwpServiceId::StopDiagnosticSession, &[])?;
+        self.session = KwpSession::Standard;
+        Ok(())
+    }
+
+    /// Get the diagnostic session last confirmed by the server
+    pub fn get_session(&self) -> KwpSession {
+        self.session
+    }
+
+    /// ecuReset (0x11), delaying the next request by `reset_delay`, the time the server
+    /// needs to restart
+    pub fn ecu_reset(&mut self, mode: KwpResetMode, reset_delay: Duration) -> Result<(), UdsError> {
+        self.request(KwpServiceId::EcuReset, &[mode.as_u8()])?;
+        self.session = KwpSession::Standard;
+        self.client.delay_next_request(reset_delay);
+        Ok(())
+    }
+
+    /// testerPresent (0x3E), waiting for a negative response only unless
+    /// `response_required`
+    pub fn tester_present(&mut self, response_required: bool) -> Result<(), UdsError> {
+        if response_required {
+            self.request(KwpServiceId::TesterPresent, &[0x01])?;
+        } else {
+            let request = Request::new(KwpServiceId::TesterPresent)
+                .data(&[0x02])
+                .positive_response_not_required();
+            self.client.send(&request)?;
+        }
+        Ok(())
+    }
+
+    /// readEcuIdentification (0x1A), returning the identification record of
+    /// `identification_option`
+    pub fn read_ecu_identification(
+        &mut self,
+        identification_option: u8,
+    ) -> Result<Vec<u8>, UdsError> {
+        let response = self.request(
+            KwpServiceId::ReadEcuIdentification,
+            &[identification_option],
+        )?;
+        strip_echo(
+            response,
+            &[identification_option],
+            "response does not echo the identification option",
+        )
+    }
+
+    /// readDataByLocalIdentifier (0x21), returning the record of `local_identifier`
+    pub fn read_data_by_local_identifier(
+        &mut self,
+        local_identifier: u8,
+    ) -> Result<Vec<u8>, UdsError> {
+        let response =
+            self.request(KwpServiceId::ReadDataByLocalIdentifier, &[local_identifier])?;
+        strip_echo(
+            response,
+            &[local_identifier],
+            "response does not echo the local identifier",
+        )
+    }
+
+    /// writeDataByLocalIdentifier (0x3B)
+    pub fn write_data_by_local_identifier(
+        &mut self,
+        local_identifier: u8,
+        record: &[u8],
+    ) -> Result<(), UdsError> {
+        let mut request = Vec::with_capacity(1 + record.len());
+        request.push(local_identifier);
+        request.extend_from_slice(record);
+        let response = self.request(KwpServiceId::WriteDataByLocalIdentifier, &request)?;
+        strip_echo(
+            response,
+            &[local_identifier],
+            "response does not echo the local identifier",
+        )
+        .map(drop)
+    }
+
+    /// readDataByCommonIdentifier (0x22), returning the record of `common_identifier`
+    pub fn read_data_by_common_identifier(
+        &mut self,
+        common_identifier: u16,
+    ) -> Result<Vec<u8>, UdsError> {
+        let identifier = common_identifier.to_be_bytes();
+        let response = self.request(KwpServiceId::ReadDataByCommonIdentifier, &identifier)?;
+        strip_echo(
+            response,
+            &identifier,
+            "response does not echo the common identifier",
+        )
+    }
+
+    /// writeDataByCommonIdentifier (0x2E)
+    pub fn write_data_by_common_identifier(
+        &mut self,
+        common_identifier: u16,
+        record: &[u8],
+    ) -> Result<(), UdsError> {
+        let identifier = common_identifier.to_be_bytes();
+        let mut request = Vec::with_capacity(2 + record.len());
+        request.extend_from_slice(&identifier);
+        request.extend_from_slice(record);
+        let response = self.request(KwpServiceId::WriteDataByCommonIdentifier, &request)?;
+        strip_echo(
+            response,
+            &identifier,
+            "response does not echo the common identifier",
+        )
+        .map(drop)
+    }
+
+    /// readMemoryByAddress (0x23) of `size` bytes at the 24 bit `address`
+    pub fn read_memory_by_address(&mut self, address: u32, size: u8) -> Result<Vec<u8>, UdsError> {
+        let mut request = encode_address(address)?.to_vec();
+        request.push(size);
+        let response = self.request(KwpServiceId::ReadMemoryByAddress, &request)?;
+        if response.len() != usize::from(size) {
+            return Err(UdsError::InvalidResponse(
+                "response length does not match the memory size",
+            ));
+        }
+        Ok(response)
+    }
+
+    /// writeMemoryByAddress (0x3D) of `data`, at most 255 bytes, at the 24 bit `address`
+    pub fn write_memory_by_address(&mut self, address: u32, data: &[u8]) -> Result<(), UdsError> {
+        let encoded_address = encode_address(address)?;
+        let size = u8::try_from(data.len())
+            .map_err(|_| UdsError::InvalidRequest("memory size exceeds 255 bytes"))?;
+        let mut request = Vec::with_capacity(4 + data.len());
+        request.extend_from_slice(&encoded_address);
+        request.push(size);
+        request.extend_from_slice(data);
+        let response = self.request(KwpServiceId::WriteMemoryByAddress, &request)?;
+        strip_echo(
+            response,
+            &encoded_address,
+            "response does not echo the memory address",
+        )
+        .map(drop)
+    }
+
+    /// clearDiagnosticInformation (0x14) of `group`, e.g. [`ALL_DTC_GROUPS`]
+    pub fn clear_diagnostic_information(&mut self, group: u16) -> Result<(), UdsError> {
+        let group = group.to_be_bytes();
+        let response = self.request(KwpServiceId::ClearDiagnosticInformation, &group)?;
+        strip_echo(response, &group, "response does not echo the group of DTC").map(drop)
+    }
+
+    /// readDiagnosticTroubleCodesByStatus (0x18) of the DTCs in `group` matching `status`
+    pub fn read_dtcs_by_status(&mut self, status: u8, group: u16) -> Result<Vec<KwpDtc>, UdsError> {
+        let [group_high, group_low] = group.to_be_bytes();
+        let response = self.request(
+            KwpServiceId::ReadDiagnosticTroubleCodesByStatus,
+            &[status, group_high, group_low],
+        )?;
+        let Some((&count, records)) = response.split_first() else {
+            return Err(UdsError::InvalidResponse(
+                "response lacks the number of DTCs",
+            ));
+        };
+        if records.len() != 3 * usize::from(count) {
+            return Err(UdsError::InvalidResponse(
+                "response length does not match the number of DTCs",
+            ));
+        }
+        Ok(records
+            .chunks_exact(3)
+            .map(|record| KwpDtc {
+                code: u16::from_be_bytes([record[0], record[1]]),
+                status: record[2],
+            })
+            .collect())
+    }
+
+    /// startRoutineByLocalIdentifier (0x31), returning the routineEntryStatus
+    pub fn start_routine(
+        &mut self,
+        local_identifier: u8,
+        option: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        self.routine(
+            KwpServiceId::StartRoutineByLocalIdentifier,
+            local_identifier,
+            option,
+        )
+    }
+
+    /// stopRoutineByLocalIdentifier (0x32), returning the routineExitStatus
+    pub fn stop_routine(
+        &mut self,
+        local_identifier: u8,
+        option: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        self.routine(
+            KwpServiceId::StopRoutineByLocalIdentifier,
+            local_identifier,
+            option,
+        )
+    }
+
+    /// requestRoutineResultsByLocalIdentifier (0x33), returning the routineResults
+    pub fn request_routine_results(&mut self, local_identifier: u8) -> Result<Vec<u8>, UdsError> {
+        self.routine(
+            KwpServiceId::RequestRoutineResultsByLocalIdentifier,
+            local_identifier,
+            &[],
+        )
+    }
+
+    fn routine(
+        &mut self,
+        service: KwpServiceId,
+        local_identifier: u8,
+        option: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = Vec::with_capacity(1 + option.len());
+        request.push(local_identifier);
+        request.extend_from_slice(option);
+        let response = self.request(service, &request)?;
+        strip_echo(
+            response,
+            &[local_identifier],
+            "response does not echo the routine local identifier",
+        )
+    }
+
+    /// Get the wrapped UDS client
+    pub fn get_uds(&self) -> &UdsClient<T> {
+        &self.client
+    }
+
+    /// Get the wrapped UDS client mutably, e.g. to change the response timeouts
+    pub fn get_uds_mut(&mut self) -> &mut UdsClient<T> {
+        &mut self.client
+    }
+
+    /// Unwrap the UDS client
+    pub fn into_uds(self) -> UdsClient<T> {
+        self.client
+    }
+}
+
+/// Encode a 24 bit memoryAddress
+fn encode_address(address: u32) -> Result<[u8; 3], UdsError> {
+    if address > MAX_MEMORY_ADDRESS {
+        return Err(UdsError::InvalidRequest("memory address exceeds 24 bits"));
+    }
+    let [_, high, middle, low] = address.to_be_bytes();
+    Ok([high, middle, low])
+}
+
+/// Remove the echoed request parameters from the response, failing with `error` if the
+/// response doesn't start with them
+fn strip_echo(
+    mut response: Vec<u8>,
+    echo: &[u8],
+    error: &'static str,
+) -> Result<Vec<u8>, UdsError> {
+    if !response.starts_with(echo) {
+        return Err(UdsError::InvalidResponse(error));
+    }
+    response.drain(..echo.len());
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::uds::{MockReply, MockTransport, Nrc};
+    use std::sync::Arc;
+
+    /// A server and client waiting on `clock`
+    fn ecu(clock: &SimulatedClock) -> MockTransport {
+        MockTransport::new().with_clock(Arc::new(clock.clone()))
+    }
+
+    fn client<'a>(
+        mock: &'a mut MockTransport,
+        clock: &SimulatedClock,
+    ) -> KwpClient<&'a mut MockTransport> {
+        let mut client = KwpClient::new(mock);
+        client.get_uds_mut().set_clock(Arc::new(clock.clone()));
+        client
+    }
+
+    #[test]
+    fn start_diagnostic_session_checks_optional_echo() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock)
+            .on_request(&[0x10, 0x85], &[0x50, 0x85])
+            .on_request(&[0x10, 0x86], &[0x50])
+            .on_request(&[0x10, 0x87], &[0x50, 0x81]);
+        let mut client = client(&mut mock, &clock);
+        client
+            .start_diagnostic_session(KwpSession::Programming)
+            .unwrap();
+        assert_eq!(client.get_session(), KwpSession::Programming);
+        client
+            .start_diagnostic_session(KwpSession::Development)
+            .unwrap();
+        assert_eq!(client.get_session(), KwpSession::Development);
+
+        let error = client
+            .start_diagnostic_session(KwpSession::Adjustment)
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        assert_eq!(client.get_session(), KwpSession::Development);
+    }
+
+    #[test]
+    fn negative_and_pending_responses() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock)
+            .on_request(
+                &[0x31, 0x01],
+                MockReply::new()
+                    .response(&[0x7F, 0x31, 0x78])
+                    .delayed_response(Duration::from_secs(2), &[0x71, 0x01, 0xAA]),
+            )
+            .on_request(&[0x31, 0x02], &[0x7F, 0x31, 0x22]);
+        let mut client = client(&mut mock, &clock);
+        assert_eq!(client.start_routine(0x01, &[]).unwrap(), [0xAA]);
+        assert_eq!(clock.elapsed(), Duration::from_secs(2));
+        let error = client.start_routine(0x02, &[]).unwrap_err();
+        assert!(
+            matches!(error, UdsError::Negative(Nrc::ConditionsNotCorrect)),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn tester_present_without_response_is_traced() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock).on_request(&[0x3E, 0x01], &[0x7E]);
+        let mut client = client(&mut mock, &clock);
+        client.get_uds_mut().enable_trace();
+        client.tester_present(false).unwrap();
+        client.tester_present(true).unwrap();
+        let requests: Vec<_> = client
+            .get_uds()
+            .get_trace()
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|entry| entry.request.clone())
+            .collect();
+        assert_eq!(requests, [vec![0x3E, 0x02], vec![0x3E, 0x01]]);
+    }
+
+    #[test]
+    fn tester_present_without_response_reports_negative_response() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock).on_request(&[0x3E], &[0x7F, 0x3E, 0x12]);
+        let mut client = client(&mut mock, &clock);
+        let error = client.tester_present(false).unwrap_err();
+        assert!(
+            matches!(error, UdsError::Negative(Nrc::SubFunctionNotSupported)),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn ecu_reset_delays_next_request() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock)
+            .on_request(&[0x10, 0x85], &[0x50, 0x85])
+            .on_request(&[0x11, 0x01], &[0x51])
+            .on_request(&[0x1A, 0x90], b"\x5A\x90VIN");
+        let mut client = client(&mut mock, &clock);
+        client
+            .start_diagnostic_session(KwpSession::Programming)
+            .unwrap();
+        client
+            .ecu_reset(KwpResetMode::PowerOn, Duration::from_secs(3))
+            .unwrap();
+        assert_eq!(client.get_session(), KwpSession::Standard);
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+
+        assert_eq!(client.read_ecu_identification(0x90).unwrap(), b"VIN");
+        assert_eq!(clock.elapsed(), Duration::from_secs(3));
+    }
+
+    #[test]
+    fn read_dtcs_by_status_parses_records() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock)
+            .on_request(
+                &[0x18, 0x00, 0xFF, 0x00],
+                &[0x58, 0x02, 0x01, 0x23, 0xE0, 0x45, 0x67, 0x20],
+            )
+            .on_request(&[0x18, 0x02], &[0x58, 0x02, 0x01, 0x23, 0xE0]);
+        let mut client = client(&mut mock, &clock);
+        assert_eq!(
+            client.read_dtcs_by_status(0x00, ALL_DTC_GROUPS).unwrap(),
+            [
+                KwpDtc {
+                    code: 0x0123,
+                    status: 0xE0
+                },
+                KwpDtc {
+                    code: 0x4567,
+                    status: 0x20
+                },
+            ]
+        );
+        // fewer records than announced
+        let error = client
+            .read_dtcs_by_status(0x02, ALL_DTC_GROUPS)
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+    }
+
+    #[test]
+    fn memory_access_checks_address_and_echo() {
+        let clock = SimulatedClock::new();
+        let mut mock = ecu(&clock)
+            .on_request(&[0x23, 0x12, 0x34, 0x56, 0x02], &[0x63, 0xAB, 0xCD])
+            .on_request(&[0x23, 0x12, 0x34, 0x57], &[0x63, 0xAB])
+            .on_request(&[0x3D, 0x12, 0x34, 0x56], &[0x7D, 0x12, 0x34, 0x00]);
+        let mut client = client(&mut mock, &clock);
+        assert_eq!(
+            client.read_memory_by_address(0x12_3456, 2).unwrap(),
+            [0xAB, 0xCD]
+        );
+        let error = client.read_memory_by_address(0x12_3457, 2).unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        let error = client
+            .write_memory_by_address(0x12_3456, &[0x01])
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        let error = client.read_memory_by_address(0x0100_0000, 1).unwrap_err();
+        assert!(matches!(error, UdsError::InvalidRequest(_)), "{error:?}");
+        assert_eq!(mock.sent().len(), 3);
+    }
+}
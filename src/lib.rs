@@ -15,13 +15,13 @@
 //! at [https://github.com/hartkopp/can-isotp](https://github.com/hartkopp/can-isotp) .
 //!
 //! ```rust,no_run
-//! use socketcan_isotp::IsoTpSocket;
+//! use socketcan_isotp::{IsoTpSocket, StandardId};
 //!
 //! fn main() -> Result<(), socketcan_isotp::Error> {
 //!     let mut tp_socket = IsoTpSocket::open(
 //!         "vcan0",
-//!         0x123,
-//!         0x321
+//!         StandardId::new(0x123).expect("Invalid rx id"),
+//!         StandardId::new(0x321).expect("Invalid tx id"),
 //!     )?;
 //!
 //!     loop {
@@ -33,10 +33,8 @@
 //!             print!("{:X?} ", x);
 //!         }
 //!
-//!         println!("");
+//!         println!();
 //!     }
-//!
-//!     Ok(())
 //! }
 //! ```
 //!
@@ -44,19 +42,56 @@
 use bitflags::bitflags;
 pub use embedded_can::{ExtendedId, Id, StandardId};
 use libc::{
-    bind, c_int, c_short, c_void, close, fcntl, read, setsockopt, sockaddr, socket, write, F_GETFL,
-    F_SETFL, O_NONBLOCK, SOCK_DGRAM,
+    bind, c_int, c_short, c_void, close, fcntl, getsockname, getsockopt, if_indextoname, ifreq,
+    ioctl, iovec, msghdr, recvmsg, setsockopt, sockaddr, sockaddr_storage, socket, socklen_t,
+    write, CMSG_DATA, CMSG_FIRSTHDR, CMSG_NXTHDR, F_GETFL, F_SETFL, O_NONBLOCK, SIOCGIFMTU,
+    SOCK_DGRAM, SOL_SOCKET, SO_ERROR, SO_RCVBUF, SO_RXQ_OVFL, SO_SNDBUF,
 };
 use nix::net::if_::if_nametoindex;
 use std::convert::TryFrom;
-use std::convert::TryInto;
+use std::fmt;
 use std::io;
-use std::mem::size_of;
+use std::mem::{size_of, size_of_val};
 use std::num::TryFromIntError;
-use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd, RawFd};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::Duration;
 use thiserror::Error;
 
+pub mod addressing;
+#[cfg(feature = "async-std")]
+pub mod async_std;
+pub mod bench;
+#[cfg(feature = "canxl")]
+pub mod canxl;
+pub mod clock;
+pub use socketcan_isotp_codec as codec;
+pub mod defaults;
+pub mod error_monitor;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+#[cfg(feature = "kwp")]
+pub mod kwp;
+#[cfg(any(feature = "uds", feature = "vcan"))]
+mod netlink;
+#[cfg(feature = "obd")]
+pub mod obd;
+mod poll;
+#[cfg(feature = "serde")]
+mod serde_support;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+pub mod transport;
+#[cfg(feature = "uds")]
+pub mod uds;
+#[cfg(feature = "userspace")]
+pub mod userspace;
+#[cfg(feature = "vcan")]
+pub mod vcan;
+
+use addressing::{ExtAddress, IsoTpAddress};
+use defaults::*;
+
 /// CAN address family
 pub const AF_CAN: c_short = 29;
 
@@ -91,29 +126,28 @@ pub const CAN_ISOTP_RX_STMIN: c_int = 4;
 /// pass struct `LinkLayerOptions`
 pub const CAN_ISOTP_LL_OPTS: c_int = 5;
 
+/// `frame_txtime` sentinel forcing a frame transmission time of zero
+pub const CAN_ISOTP_FRAME_TXTIME_ZERO: u32 = 0xFFFF_FFFF;
+
 /// `CAN_MAX_DLEN` According to ISO 11898-1
 pub const CAN_MAX_DLEN: u8 = 8;
 
 /// Size of buffer allocated for reading TP data
 const RECV_BUFFER_SIZE: usize = 4096;
 
-/// Size of a canframe, constant to reduce crate dependencies
-/// `std::mem::size_of::<socketcan::CANFrame>())`
-const SIZE_OF_CAN_FRAME: u8 = 16;
-
-const FLOW_CONTROL_OPTIONS_SIZE: usize = size_of::<FlowControlOptions>();
+/// Largest PDU length encodable in a classic first frame without escape sequence
+pub const MAX_PDU_LENGTH: usize = 4095;
 
-const ISOTP_OPTIONS_SIZE: usize = size_of::<IsoTpOptions>();
+/// MTU of classic CAN 2.0 frames aka `sizeof(struct can_frame)`
+pub const CAN_MTU: u8 = 16;
 
-const LINK_LAYER_OPTIONS_SIZE: usize = size_of::<LinkLayerOptions>();
-
-const CAN_ISOTP_DEFAULT_RECV_BS: u8 = 0;
-
-const CAN_ISOTP_DEFAULT_RECV_STMIN: u8 = 0x00;
-
-const CAN_ISOTP_DEFAULT_RECV_WFTMAX: u8 = 0;
+/// MTU of CAN FD frames aka `sizeof(struct canfd_frame)`
+pub const CANFD_MTU: u8 = 72;
 
 bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(transparent))]
     pub struct IsoTpBehaviour: u32 {
         /// listen only (do not send FC)
         const CAN_ISOTP_LISTEN_MODE = 0x001;
@@ -135,6 +169,12 @@ bitflags! {
         const CAN_ISOTP_FORCE_RXSTMIN = 0x100;
         /// different rx extended addressing
         const CAN_ISOTP_RX_EXT_ADDR = 0x200;
+        /// wait for tx completion
+        const CAN_ISOTP_WAIT_TX_DONE = 0x400;
+        /// 1-to-N functional addressing
+        const CAN_ISOTP_SF_BROADCAST = 0x800;
+        /// 1-to-N transmission w/o FC
+        const CAN_ISOTP_CF_BROADCAST = 0x1000;
     }
 }
 
@@ -162,9 +202,14 @@ pub const ERR_MASK_ALL: u32 = ERR_MASK;
 /// an error mask that will cause Socketcan to silently drop all errors
 pub const ERR_MASK_NONE: u32 = 0;
 
-#[derive(Debug)]
+/// Socket address of an ISO-TP socket aka `struct sockaddr_can`
+///
+/// Holds the interface and the identifiers an ISO-TP socket is bound to, see
+/// [`IsoTpSocket::local_addr`]. Converts to and from `sockaddr_storage` for use with
+/// raw `bind`/`getsockname` calls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(C)]
-struct CanAddr {
+pub struct CanAddr {
     _af_can: c_short,
     if_index: c_int, // address familiy,
     /// transport protocol class address information
@@ -175,10 +220,148 @@ struct CanAddr {
     _addr: u8,
 }
 
+impl CanAddr {
+    /// ISO-TP address receiving `rx_id` and transmitting `tx_id` on interface `if_index`
+    pub fn new(if_index: c_int, rx_id: impl Into<Id>, tx_id: impl Into<Id>) -> Self {
+        Self::from_raw_ids(if_index, id_to_raw(rx_id), id_to_raw(tx_id))
+    }
+
+    fn from_raw_ids(if_index: c_int, rx_id: u32, tx_id: u32) -> Self {
+        Self {
+            _af_can: AF_CAN,
+            if_index,
+            rx_id,
+            tx_id,
+            _pgn: 0,
+            _addr: 0,
+        }
+    }
+
+    /// get the kernel interface number
+    pub fn get_if_index(&self) -> c_int {
+        self.if_index
+    }
+
+    /// get the receive identifier, `None` if the raw value is no valid identifier
+    pub fn get_rx_id(&self) -> Option<Id> {
+        id_from_raw(self.rx_id)
+    }
+
+    /// get the transmit identifier, `None` if the raw value is no valid identifier
+    pub fn get_tx_id(&self) -> Option<Id> {
+        id_from_raw(self.tx_id)
+    }
+
+    /// get the receive identifier as kernel `canid_t`
+    pub fn get_raw_rx_id(&self) -> u32 {
+        self.rx_id
+    }
+
+    /// get the transmit identifier as kernel `canid_t`
+    pub fn get_raw_tx_id(&self) -> u32 {
+        self.tx_id
+    }
+}
+
+impl From<CanAddr> for sockaddr_storage {
+    fn from(addr: CanAddr) -> Self {
+        let mut storage: sockaddr_storage = unsafe { std::mem::zeroed() };
+        unsafe {
+            std::ptr::write(&mut storage as *mut _ as *mut CanAddr, addr);
+        }
+        storage
+    }
+}
+
+impl TryFrom<&sockaddr_storage> for CanAddr {
+    type Error = Error;
+
+    /// Fails with [`Error::InvalidInput`] unless the address family is `AF_CAN`
+    fn try_from(storage: &sockaddr_storage) -> Result<Self, Self::Error> {
+        if storage.ss_family as c_int != AF_CAN as c_int {
+            return Err(Error::InvalidInput("address family is not AF_CAN"));
+        }
+        Ok(unsafe { std::ptr::read(storage as *const _ as *const CanAddr) })
+    }
+}
+
+/// Frame transmission time (N_As/N_Ar), the gap between two transmitted CAN frames
+///
+/// Kernels before Linux 5.18 use `frame_txtime` as is, so `Off` means no gap. Newer kernels
+/// replace `Off` by [`CAN_ISOTP_DEFAULT_FRAME_TXTIME`] and need `Zero` to send without gap,
+/// which older kernels in turn misinterpret as a gap of ~4.3 s. [`FrameTxTime::no_gap`]
+/// picks the right variant for the running kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FrameTxTime {
+    /// No frame transmission time configured, the kernel default applies
+    #[default]
+    Off,
+    /// Force a frame transmission time of zero ([`CAN_ISOTP_FRAME_TXTIME_ZERO`])
+    Zero,
+    /// Frame transmission time, must be less than `u32::MAX` nano secs
+    ///
+    /// A zero duration is encoded like `Zero`, never like `Off`, so it forces a gap of zero
+    /// instead of falling back to the kernel default. Use [`FrameTxTime::no_gap`] to also
+    /// support kernels before Linux 5.18.
+    Duration(Duration),
+}
+
+impl FrameTxTime {
+    /// Send frames without gap on the running kernel, `Zero` on Linux 5.18 and later,
+    /// `Off` otherwise.
+    pub fn no_gap() -> Self {
+        let release = nix::sys::utsname::uname()
+            .map(|uname| uname.release().to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut version = release
+            .split(|c: char| !c.is_ascii_digit())
+            .map(|n| n.parse::<u32>().unwrap_or(0));
+        let major = version.next().unwrap_or(0);
+        let minor = version.next().unwrap_or(0);
+
+        if (major, minor) >= (5, 18) {
+            Self::Zero
+        } else {
+            Self::Off
+        }
+    }
+
+    /// Raw `frame_txtime` value in nano secs, a zero duration is encoded as `Zero`
+    ///
+    /// Decoding the raw value with [`FrameTxTime::from_raw`] therefore returns `Zero` for
+    /// `Duration(Duration::ZERO)`.
+    pub fn as_raw(self) -> Result<u32, TryFromIntError> {
+        match self {
+            Self::Off => Ok(0),
+            Self::Zero => Ok(CAN_ISOTP_FRAME_TXTIME_ZERO),
+            Self::Duration(frame_txtime) if frame_txtime.is_zero() => {
+                Ok(CAN_ISOTP_FRAME_TXTIME_ZERO)
+            }
+            // u32::MAX itself is the ZERO sentinel and therefore not a valid duration
+            Self::Duration(frame_txtime) => {
+                u32::try_from(frame_txtime.as_nanos() + 1).map(|nanos| nanos - 1)
+            }
+        }
+    }
+
+    /// Decode a raw `frame_txtime` value in nano secs
+    pub fn from_raw(frame_txtime: u32) -> Self {
+        match frame_txtime {
+            0 => Self::Off,
+            CAN_ISOTP_FRAME_TXTIME_ZERO => Self::Zero,
+            nanos => Self::Duration(Duration::from_nanos(nanos.into())),
+        }
+    }
+}
+
 /// ISO-TP otions aka `can_isotp_options`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[repr(C)]
 pub struct IsoTpOptions {
     /// set flags for isotp behaviour.
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::isotp_behaviour"))]
     flags: u32,
     /// frame transmission time (N_As/N_Ar)
     /// time in nano secs
@@ -196,23 +379,117 @@ pub struct IsoTpOptions {
 impl IsoTpOptions {
     pub fn new(
         flags: IsoTpBehaviour,
-        frame_txtime: Duration,
+        frame_txtime: FrameTxTime,
         ext_address: u8,
         txpad_content: u8,
         rxpad_content: u8,
         rx_ext_address: u8,
-    ) -> Result<Self, TryFromIntError> {
-        let flags = flags.bits();
-        let frame_txtime = u32::try_from(frame_txtime.as_nanos())?;
-
-        Ok(Self {
-            flags,
-            frame_txtime,
+    ) -> Result<Self, Error> {
+        let options = Self {
+            flags: flags.bits(),
+            frame_txtime: frame_txtime
+                .as_raw()
+                .map_err(|_| Error::InvalidFrameTxTime(frame_txtime))?,
             ext_address,
             txpad_content,
             rxpad_content,
             rx_ext_address,
-        })
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Check for flag combinations the kernel rejects or silently ignores
+    pub fn validate(&self) -> Result<(), Error> {
+        let flags = IsoTpBehaviour::from_bits(self.flags)
+            .ok_or(Error::InvalidIsoTpOptions("unknown flags set"))?;
+
+        if flags.intersects(
+            IsoTpBehaviour::CAN_ISOTP_CHK_PAD_LEN | IsoTpBehaviour::CAN_ISOTP_CHK_PAD_DATA,
+        ) && !flags.contains(IsoTpBehaviour::CAN_ISOTP_RX_PADDING)
+        {
+            return Err(Error::InvalidIsoTpOptions(
+                "rx padding checks require CAN_ISOTP_RX_PADDING",
+            ));
+        }
+
+        if flags.contains(IsoTpBehaviour::CAN_ISOTP_RX_EXT_ADDR)
+            && !flags.contains(IsoTpBehaviour::CAN_ISOTP_EXTEND_ADDR)
+        {
+            return Err(Error::InvalidIsoTpOptions(
+                "CAN_ISOTP_RX_EXT_ADDR requires CAN_ISOTP_EXTEND_ADDR",
+            ));
+        }
+
+        if flags.contains(
+            IsoTpBehaviour::CAN_ISOTP_SF_BROADCAST | IsoTpBehaviour::CAN_ISOTP_CF_BROADCAST,
+        ) {
+            return Err(Error::InvalidIsoTpOptions(
+                "CAN_ISOTP_SF_BROADCAST and CAN_ISOTP_CF_BROADCAST are mutually exclusive",
+            ));
+        }
+
+        if flags.contains(IsoTpBehaviour::CAN_ISOTP_LISTEN_MODE)
+            && flags.intersects(
+                IsoTpBehaviour::CAN_ISOTP_SF_BROADCAST | IsoTpBehaviour::CAN_ISOTP_CF_BROADCAST,
+            )
+        {
+            return Err(Error::InvalidIsoTpOptions(
+                "CAN_ISOTP_LISTEN_MODE can not be combined with broadcast transmission",
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// UDS (ISO 14229) preset for physical addressing.
+    ///
+    /// Pads transmitted frames with 0xAA and accepts received frames with any or no
+    /// padding, which works with the majority of ECUs. ECUs expecting a different padding
+    /// byte, e.g. 0x00, can be served by [`IsoTpOptions::with_tx_padding`].
+    pub fn uds_defaults() -> Self {
+        Self::with_tx_padding(0xAA)
+    }
+
+    /// OBD-II (ISO 15765-4) preset.
+    ///
+    /// ISO 15765-4 demands all frames to be sent with a DLC of 8, so transmitted frames are
+    /// padded with 0x00. Receivers must not evaluate the padding of received frames.
+    pub fn obd_defaults() -> Self {
+        Self::with_tx_padding(0x00)
+    }
+
+    /// Default options with padding of transmitted frames enabled, received frames are
+    /// accepted regardless of their padding.
+    pub fn with_tx_padding(padding: u8) -> Self {
+        let mut options = Self::default();
+        options.flags |= IsoTpBehaviour::CAN_ISOTP_TX_PADDING.bits();
+        options.txpad_content = padding;
+        options
+    }
+
+    /// Default options with padding enabled and checked on both tx and rx path.
+    ///
+    /// See [`IsoTpOptions::set_padding`].
+    pub fn with_padding(padding: u8) -> Self {
+        let mut options = Self::default();
+        options.set_padding(padding);
+        options
+    }
+
+    /// Pad tx frames with `padding` and only accept rx frames padded the same way.
+    ///
+    /// Enables `CAN_ISOTP_TX_PADDING`, `CAN_ISOTP_RX_PADDING`, `CAN_ISOTP_CHK_PAD_LEN` and
+    /// `CAN_ISOTP_CHK_PAD_DATA` and sets tx and rx padding content, as usual for
+    /// ISO 15765-4 style communication.
+    pub fn set_padding(&mut self, padding: u8) {
+        self.flags |= (IsoTpBehaviour::CAN_ISOTP_TX_PADDING
+            | IsoTpBehaviour::CAN_ISOTP_RX_PADDING
+            | IsoTpBehaviour::CAN_ISOTP_CHK_PAD_LEN
+            | IsoTpBehaviour::CAN_ISOTP_CHK_PAD_DATA)
+            .bits();
+        self.txpad_content = padding;
+        self.rxpad_content = padding;
     }
 
     /// get flags for isotp behaviour.
@@ -226,13 +503,13 @@ impl IsoTpOptions {
     }
 
     /// get frame transmission time (N_As/N_Ar)
-    pub fn get_frame_txtime(&self) -> Duration {
-        Duration::from_nanos(self.frame_txtime.into())
+    pub fn get_frame_txtime(&self) -> FrameTxTime {
+        FrameTxTime::from_raw(self.frame_txtime)
     }
 
     /// set frame transmission time (N_As/N_Ar)
-    pub fn set_frame_txtime(&mut self, frame_txtime: Duration) -> Result<(), TryFromIntError> {
-        self.frame_txtime = u32::try_from(frame_txtime.as_nanos())?;
+    pub fn set_frame_txtime(&mut self, frame_txtime: FrameTxTime) -> Result<(), TryFromIntError> {
+        self.frame_txtime = frame_txtime.as_raw()?;
         Ok(())
     }
 
@@ -275,23 +552,70 @@ impl IsoTpOptions {
     pub fn set_rx_ext_address(&mut self, rx_ext_address: u8) {
         self.rx_ext_address = rx_ext_address;
     }
+
+    /// get the extended addressing configuration, `None` if `CAN_ISOTP_EXTEND_ADDR` is unset
+    pub fn get_ext_addressing(&self) -> Option<ExtAddress> {
+        let flags = IsoTpBehaviour::from_bits_retain(self.flags);
+        if !flags.contains(IsoTpBehaviour::CAN_ISOTP_EXTEND_ADDR) {
+            None
+        } else if flags.contains(IsoTpBehaviour::CAN_ISOTP_RX_EXT_ADDR) {
+            Some(ExtAddress::mixed(self.ext_address, self.rx_ext_address))
+        } else {
+            Some(ExtAddress::new(self.ext_address))
+        }
+    }
+
+    /// Enable extended addressing with matching flags and address bytes.
+    ///
+    /// Sets `CAN_ISOTP_EXTEND_ADDR` and, for mixed addresses, `CAN_ISOTP_RX_EXT_ADDR`.
+    pub fn set_ext_addressing(&mut self, ext_address: ExtAddress) {
+        let mut flags = IsoTpBehaviour::from_bits_retain(self.flags);
+        flags.insert(IsoTpBehaviour::CAN_ISOTP_EXTEND_ADDR);
+        flags.set(
+            IsoTpBehaviour::CAN_ISOTP_RX_EXT_ADDR,
+            ext_address.is_mixed(),
+        );
+        self.flags = flags.bits();
+        self.ext_address = ext_address.get_tx();
+        self.rx_ext_address = ext_address.get_rx();
+    }
 }
 
 impl Default for IsoTpOptions {
     fn default() -> Self {
-        // Defaults defined in linux/can/isotp.h
+        // Defaults defined in linux/can/isotp.h, frame_txtime 0 selects the kernel default
         Self {
-            flags: 0x00,
+            flags: CAN_ISOTP_DEFAULT_FLAGS,
             frame_txtime: 0x00,
-            ext_address: 0x00,
-            txpad_content: 0xCC,
-            rxpad_content: 0xCC,
-            rx_ext_address: 0x00,
+            ext_address: CAN_ISOTP_DEFAULT_EXT_ADDRESS,
+            txpad_content: CAN_ISOTP_DEFAULT_PAD_CONTENT,
+            rxpad_content: CAN_ISOTP_DEFAULT_PAD_CONTENT,
+            rx_ext_address: CAN_ISOTP_DEFAULT_EXT_ADDRESS,
         }
     }
 }
 
+impl fmt::Display for IsoTpOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "flags: ")?;
+        write_flags(f, &IsoTpBehaviour::from_bits_retain(self.flags))?;
+        write!(
+            f,
+            ", frame_txtime: {:?}, ext_address: {:#04X}, txpad_content: {:#04X}, \
+             rxpad_content: {:#04X}, rx_ext_address: {:#04X}",
+            self.get_frame_txtime(),
+            self.ext_address,
+            self.txpad_content,
+            self.rxpad_content,
+            self.rx_ext_address
+        )
+    }
+}
+
 /// Flow control options aka `can_isotp_fc_options`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[repr(C)]
 pub struct FlowControlOptions {
     /// blocksize provided in FC frame
@@ -303,6 +627,7 @@ pub struct FlowControlOptions {
     /// 0x80 - 0xF0 : reserved
     /// 0xF1 - 0xF9 : 100 us - 900 us
     /// 0xFA - 0xFF : reserved
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::separation_time"))]
     stmin: u8,
     /// max. number of wait frame transmiss.
     /// 0 = omit FC N_PDU WT
@@ -323,17 +648,166 @@ impl FlowControlOptions {
     /// Creates new flow control options
     /// # Parameters
     /// * bs - Blocksize for the ISO-TP frame, set 0 for off (Sending ECU shall send all frames in 1 block)
-    /// * stmin - Separation time between consecutive frames. The following values are allowed:
-    ///     * 0x00 - 0x7F : 0 - 127 ms
-    ///     * 0xF1 - 0xF9 : 100 - 900 us
+    /// * stmin - Separation time between consecutive frames
     /// * wftmax - Maximum number of wait frame transmiss
     ///     * Default value is 0 (omit)
-    pub fn new(bs: u8, stmin: u8, wftmax: u8) -> Self {
-        Self { bs, stmin, wftmax }
+    pub fn new(bs: u8, stmin: SeparationTime, wftmax: u8) -> Self {
+        Self {
+            bs,
+            stmin: stmin.as_raw(),
+            wftmax,
+        }
+    }
+
+    /// OBD-II (ISO 15765-4) preset, the ECU may send all consecutive frames without
+    /// delay in a single block.
+    pub fn obd_defaults() -> Self {
+        Self::new(0, SeparationTime::ZERO, 0)
+    }
+
+    /// get blocksize provided in FC frame
+    pub fn get_bs(&self) -> u8 {
+        self.bs
+    }
+
+    /// get separation time provided in FC frame
+    ///
+    /// Reserved encodings are interpreted as 127 ms as demanded by ISO 15765-2.
+    pub fn get_stmin(&self) -> SeparationTime {
+        SeparationTime::from_raw(self.stmin).unwrap_or(SeparationTime::MAX)
+    }
+
+    /// get max. number of wait frame transmiss.
+    pub fn get_wftmax(&self) -> u8 {
+        self.wftmax
+    }
+}
+
+impl fmt::Display for FlowControlOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bs: {}, stmin: ", self.bs)?;
+        match SeparationTime::from_raw(self.stmin) {
+            Ok(stmin) => write!(f, "{}", stmin)?,
+            Err(_) => write!(f, "reserved ({:#04X})", self.stmin)?,
+        }
+        write!(f, ", wftmax: {}", self.wftmax)
+    }
+}
+
+/// Separation time (STmin) between consecutive frames as encoded in a flow control frame
+///
+/// * 0x00 - 0x7F : 0 - 127 ms
+/// * 0xF1 - 0xF9 : 100 us - 900 us
+///
+/// All other encodings are reserved and rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+pub struct SeparationTime(u8);
+
+impl SeparationTime {
+    /// No separation time between consecutive frames
+    pub const ZERO: Self = Self(0x00);
+
+    /// Largest separation time of 127 ms
+    pub const MAX: Self = Self(0x7F);
+
+    /// Separation time in milliseconds, valid range is 0 - 127 ms
+    pub fn from_millis(millis: u8) -> Result<Self, Error> {
+        if millis <= 0x7F {
+            Ok(Self(millis))
+        } else {
+            Err(Error::InvalidSeparationTime(Duration::from_millis(
+                millis.into(),
+            )))
+        }
+    }
+
+    /// Separation time in 100 us steps, valid range is 100 - 900 us
+    pub fn from_micros(micros: u16) -> Result<Self, Error> {
+        match micros {
+            100..=900 if micros % 100 == 0 => Ok(Self(0xF0 + (micros / 100) as u8)),
+            _ => Err(Error::InvalidSeparationTime(Duration::from_micros(
+                micros.into(),
+            ))),
+        }
+    }
+
+    /// Separation time from its raw STmin encoding, reserved encodings are rejected
+    pub fn from_raw(stmin: u8) -> Result<Self, Error> {
+        match stmin {
+            0x00..=0x7F | 0xF1..=0xF9 => Ok(Self(stmin)),
+            _ => Err(Error::ReservedSeparationTime(stmin)),
+        }
+    }
+
+    /// Raw STmin encoding
+    pub fn as_raw(self) -> u8 {
+        self.0
+    }
+
+    /// Separation time as duration
+    pub fn as_duration(self) -> Duration {
+        match self.0 {
+            0xF1..=0xF9 => Duration::from_micros(u64::from(self.0 - 0xF0) * 100),
+            millis => Duration::from_millis(millis.into()),
+        }
+    }
+}
+
+impl Default for SeparationTime {
+    fn default() -> Self {
+        Self(CAN_ISOTP_DEFAULT_RECV_STMIN)
+    }
+}
+
+impl TryFrom<Duration> for SeparationTime {
+    type Error = Error;
+
+    /// Encode a duration, only whole milliseconds up to 127 ms and 100 - 900 us in
+    /// 100 us steps can be represented.
+    fn try_from(stmin: Duration) -> Result<Self, Self::Error> {
+        let micros = stmin.as_micros();
+        if stmin.subsec_nanos() % 100_000 == 0 && (100..=900).contains(&micros) {
+            Self::from_micros(micros as u16)
+        } else if stmin.subsec_nanos() % 1_000_000 == 0 && micros <= 127_000 {
+            Self::from_millis((micros / 1000) as u8)
+        } else {
+            Err(Error::InvalidSeparationTime(stmin))
+        }
+    }
+}
+
+impl fmt::Display for SeparationTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.as_duration())
+    }
+}
+
+impl TryFrom<u8> for SeparationTime {
+    type Error = Error;
+
+    fn try_from(stmin: u8) -> Result<Self, Self::Error> {
+        Self::from_raw(stmin)
+    }
+}
+
+impl From<SeparationTime> for u8 {
+    fn from(stmin: SeparationTime) -> Self {
+        stmin.as_raw()
+    }
+}
+
+impl From<SeparationTime> for Duration {
+    fn from(stmin: SeparationTime) -> Self {
+        stmin.as_duration()
     }
 }
 
 bitflags! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(transparent))]
     pub struct TxFlags: u8 {
         /// bit rate switch (second bitrate for payload data)
         const CANFD_BRS = 0x01;
@@ -342,51 +816,262 @@ bitflags! {
     }
 }
 
+/// CAN frame type generated and accepted by the link layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+#[repr(u8)]
+pub enum Mtu {
+    /// [`CAN_MTU`], classic CAN 2.0 frames
+    #[default]
+    Classic = CAN_MTU,
+    /// [`CANFD_MTU`], CAN FD frames
+    Fd = CANFD_MTU,
+}
+
+impl Mtu {
+    /// MTU in bytes
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for Mtu {
+    type Error = Error;
+
+    fn try_from(mtu: u8) -> Result<Self, Self::Error> {
+        match mtu {
+            CAN_MTU => Ok(Self::Classic),
+            CANFD_MTU => Ok(Self::Fd),
+            _ => Err(Error::InvalidMtu(mtu)),
+        }
+    }
+}
+
+impl From<Mtu> for u8 {
+    fn from(mtu: Mtu) -> Self {
+        mtu.as_u8()
+    }
+}
+
+/// Link layer data length used for transmitted CAN frames
+///
+/// Classic CAN frames carry 8 bytes, CAN FD frames additionally 12 - 64 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "u8", into = "u8"))]
+#[repr(u8)]
+pub enum TxDataLength {
+    /// 8 bytes, classic CAN and CAN FD
+    #[default]
+    Dl8 = 8,
+    /// 12 bytes, CAN FD only
+    Dl12 = 12,
+    /// 16 bytes, CAN FD only
+    Dl16 = 16,
+    /// 20 bytes, CAN FD only
+    Dl20 = 20,
+    /// 24 bytes, CAN FD only
+    Dl24 = 24,
+    /// 32 bytes, CAN FD only
+    Dl32 = 32,
+    /// 48 bytes, CAN FD only
+    Dl48 = 48,
+    /// 64 bytes, CAN FD only
+    Dl64 = 64,
+}
+
+impl TxDataLength {
+    /// Data length in bytes
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+}
+
+impl TryFrom<u8> for TxDataLength {
+    type Error = Error;
+
+    fn try_from(tx_dl: u8) -> Result<Self, Self::Error> {
+        match tx_dl {
+            8 => Ok(Self::Dl8),
+            12 => Ok(Self::Dl12),
+            16 => Ok(Self::Dl16),
+            20 => Ok(Self::Dl20),
+            24 => Ok(Self::Dl24),
+            32 => Ok(Self::Dl32),
+            48 => Ok(Self::Dl48),
+            64 => Ok(Self::Dl64),
+            _ => Err(Error::InvalidTxDataLength(tx_dl)),
+        }
+    }
+}
+
+impl From<TxDataLength> for u8 {
+    fn from(tx_dl: TxDataLength) -> Self {
+        tx_dl.as_u8()
+    }
+}
+
 /// Link layer options aka `can_isotp_ll_options`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 #[repr(C)]
 pub struct LinkLayerOptions {
     /// generated & accepted CAN frame type
-    /// CAN_MTU   (16) -> standard CAN 2.0
-    /// CANFD_MTU (72) -> CAN FD frame
+    /// [`CAN_MTU`]   (16) -> standard CAN 2.0
+    /// [`CANFD_MTU`] (72) -> CAN FD frame
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::mtu"))]
     mtu: u8,
     /// tx link layer data length in bytes
     /// (configured maximum payload length)
     /// __u8 value : 8,12,16,20,24,32,48,64
     /// => rx path supports all LL_DL values
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::tx_data_length"))]
     tx_dl: u8,
     /// set into struct canfd_frame.flags
     /// at frame creation: e.g. CANFD_BRS
     /// Obsolete when the BRS flag is fixed
     /// by the CAN netdriver configuration
+    #[cfg_attr(feature = "serde", serde(with = "serde_support::tx_flags"))]
     tx_flags: u8,
 }
 
 impl LinkLayerOptions {
-    pub fn new(mtu: u8, tx_dl: u8, tx_flags: TxFlags) -> Self {
-        let tx_flags = tx_flags.bits();
+    pub fn new(mtu: Mtu, tx_dl: TxDataLength, tx_flags: TxFlags) -> Result<Self, Error> {
+        let options = Self {
+            mtu: mtu.as_u8(),
+            tx_dl: tx_dl.as_u8(),
+            tx_flags: tx_flags.bits(),
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Check for combinations the kernel rejects with `EINVAL` or that classic CAN can't transmit
+    pub fn validate(&self) -> Result<(), Error> {
+        TxDataLength::try_from(self.tx_dl)?;
+
+        match Mtu::try_from(self.mtu)? {
+            Mtu::Classic if self.tx_dl > CAN_MAX_DLEN => Err(Error::InvalidLinkLayerOptions(
+                "tx_dl above 8 bytes requires CANFD_MTU",
+            )),
+            Mtu::Classic if self.tx_flags != 0 => Err(Error::InvalidLinkLayerOptions(
+                "tx_flags BRS and ESI require CANFD_MTU",
+            )),
+            Mtu::Classic | Mtu::Fd => Ok(()),
+        }
+    }
+
+    /// CAN FD link layer options transmitting frames with `tx_dl` bytes of payload.
+    ///
+    /// Frames are generated with [`CANFD_MTU`], `tx_flags` can enable bit rate switching
+    /// ([`TxFlags::CANFD_BRS`]) for the data phase. Received frames may use any data length.
+    pub fn canfd(tx_dl: TxDataLength, tx_flags: TxFlags) -> Self {
         Self {
-            mtu,
-            tx_dl,
-            tx_flags,
+            mtu: CANFD_MTU,
+            tx_dl: tx_dl.as_u8(),
+            tx_flags: tx_flags.bits(),
         }
     }
+
+    /// get generated & accepted CAN frame type
+    pub fn get_mtu(&self) -> Mtu {
+        Mtu::try_from(self.mtu).unwrap_or_default()
+    }
+
+    /// get tx link layer data length in bytes
+    pub fn get_tx_dl(&self) -> TxDataLength {
+        TxDataLength::try_from(self.tx_dl).unwrap_or_default()
+    }
+
+    /// get flags set into struct canfd_frame.flags
+    pub fn get_tx_flags(&self) -> Option<TxFlags> {
+        TxFlags::from_bits(self.tx_flags)
+    }
+
+    /// get whether transmitted frames switch to the data bit rate ([`TxFlags::CANFD_BRS`])
+    pub fn get_bit_rate_switch(&self) -> bool {
+        TxFlags::from_bits_retain(self.tx_flags).contains(TxFlags::CANFD_BRS)
+    }
+
+    /// get whether transmitted frames signal error passive state ([`TxFlags::CANFD_ESI`])
+    pub fn get_error_state_indicator(&self) -> bool {
+        TxFlags::from_bits_retain(self.tx_flags).contains(TxFlags::CANFD_ESI)
+    }
+}
+
+impl fmt::Display for LinkLayerOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frame_type = match self.mtu {
+            CAN_MTU => "CAN 2.0",
+            CANFD_MTU => "CAN FD",
+            _ => "unknown",
+        };
+        write!(
+            f,
+            "mtu: {} ({}), tx_dl: {}, tx_flags: ",
+            self.mtu, frame_type, self.tx_dl
+        )?;
+        write_flags(f, &TxFlags::from_bits_retain(self.tx_flags))
+    }
+}
+
+/// Write flag names separated by `|`, or `empty` if no flag is set
+fn write_flags<B: bitflags::Flags>(f: &mut fmt::Formatter<'_>, flags: &B) -> fmt::Result
+where
+    B::Bits: bitflags::parser::WriteHex,
+{
+    if flags.is_empty() {
+        write!(f, "empty")
+    } else {
+        bitflags::parser::to_writer(flags, f)
+    }
 }
 
 impl Default for LinkLayerOptions {
     fn default() -> Self {
         Self {
-            // CAN_ISOTP_DEFAULT_LL_MTU
-            mtu: SIZE_OF_CAN_FRAME,
-            // CAN_ISOTP_DEFAULT_LL_TX_DL
-            tx_dl: CAN_MAX_DLEN,
-            // CAN_ISOTP_DEFAULT_LL_TX_FLAGS
-            tx_flags: 0x00,
+            mtu: CAN_ISOTP_DEFAULT_LL_MTU,
+            tx_dl: CAN_ISOTP_DEFAULT_LL_TX_DL,
+            tx_flags: CAN_ISOTP_DEFAULT_LL_TX_FLAGS,
+        }
+    }
+}
+
+/// ISO 15765-2 network layer timers aborting a transfer when they expire
+///
+/// The kernel supervises both with the fixed [`NetworkTimer::KERNEL_TIMEOUT`] and reports
+/// the expired timer by errno only, see [`Error::network_timer`]. The userspace stack
+/// enforces a configurable timeout per timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NetworkTimer {
+    /// N_Bs, reception of the next flow control frame by the sender
+    Bs,
+    /// N_Cr, reception of the next consecutive frame by the receiver
+    Cr,
+}
+
+impl NetworkTimer {
+    /// Timeout the kernel applies to both timers, not configurable
+    pub const KERNEL_TIMEOUT: Duration = Duration::from_secs(1);
+}
+
+impl fmt::Display for NetworkTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkTimer::Bs => write!(f, "N_Bs"),
+            NetworkTimer::Cr => write!(f, "N_Cr"),
         }
     }
 }
 
 #[derive(Error, Debug)]
 /// Possible errors
+///
+/// IO errors are classified by their errno when converted, so `?` on the
+/// `io::Result` of socket operations yields the typed variants below.
 pub enum Error {
     /// CAN device could not be found
     #[error("Failed to find can device: {source:?}")]
@@ -397,18 +1082,209 @@ pub enum Error {
 
     /// IO Error
     #[error("IO error: {source:?}")]
-    Io {
-        #[from]
-        source: io::Error,
-    },
+    Io { source: io::Error },
+
+    /// Timeout waiting for flow control or consecutive frames (`ETIMEDOUT`)
+    #[error("ISO-TP timeout: {source:?}")]
+    Timeout { source: io::Error },
+
+    /// Non-blocking operation could not complete immediately (`EAGAIN`)
+    #[error("Operation would block: {source:?}")]
+    WouldBlock { source: io::Error },
+
+    /// Multi-frame transmission aborted, e.g. flow control timeout (`ECOMM`)
+    #[error("ISO-TP transfer aborted: {source:?}")]
+    TransferAborted { source: io::Error },
+
+    /// Received consecutive frame with wrong sequence number (`EILSEQ`)
+    #[error("ISO-TP sequence error: {source:?}")]
+    SequenceError { source: io::Error },
+
+    /// Receiver signalled a flow control overflow (`EMSGSIZE`)
+    #[error("ISO-TP overflow: {source:?}")]
+    Overflow { source: io::Error },
+
+    /// Received frame failed the padding checks (`EBADMSG`)
+    #[error("Malformed ISO-TP frame: {source:?}")]
+    MalformedFrame { source: io::Error },
+
+    /// Kernel send queue is full (`ENOBUFS`)
+    #[error("No buffer space available: {source:?}")]
+    NoBufferSpace { source: io::Error },
+
+    /// CAN interface is down or vanished (`ENETDOWN`, `ENODEV`)
+    #[error("CAN interface down: {source:?}")]
+    InterfaceDown { source: io::Error },
+
+    /// CAN ISO-TP protocol not available (`EPROTONOSUPPORT`, `EAFNOSUPPORT`)
+    #[error(
+        "CAN ISO-TP protocol not supported, load the can-isotp kernel module with \
+         `modprobe can-isotp` (built in-tree since Linux 5.10, out-of-tree module at \
+         https://github.com/hartkopp/can-isotp): {source:?}"
+    )]
+    ProtocolNotSupported { source: io::Error },
+
+    /// Separation time can not be encoded in a flow control frame
+    #[error("Separation time {0:?} is neither 0 - 127 ms nor 100 - 900 us in 100 us steps")]
+    InvalidSeparationTime(Duration),
+
+    /// Raw separation time uses a reserved encoding
+    #[error("Separation time encoding {0:#04X} is reserved")]
+    ReservedSeparationTime(u8),
+
+    /// Frame transmission time exceeds the kernel range
+    #[error("Frame transmission time {0:?} does not fit into u32 nano secs")]
+    InvalidFrameTxTime(FrameTxTime),
+
+    /// Conflicting ISO-TP option flags
+    #[error("Invalid ISO-TP options: {0}")]
+    InvalidIsoTpOptions(&'static str),
+
+    /// Conflicting link layer options
+    #[error("Invalid link layer options: {0}")]
+    InvalidLinkLayerOptions(&'static str),
+
+    /// Argument out of the supported range
+    #[error("Invalid input: {0}")]
+    InvalidInput(&'static str),
+
+    /// Link layer MTU neither CAN nor CAN FD
+    #[error("MTU {0} is neither CAN_MTU (16) nor CANFD_MTU (72)")]
+    InvalidMtu(u8),
+
+    /// Link layer data length not supported by CAN (FD)
+    #[error("Tx data length {0} is not one of 8, 12, 16, 20, 24, 32, 48, 64")]
+    InvalidTxDataLength(u8),
+}
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        match source.raw_os_error() {
+            Some(libc::ETIMEDOUT) => Error::Timeout { source },
+            Some(libc::EAGAIN) => Error::WouldBlock { source },
+            Some(libc::ECOMM) => Error::TransferAborted { source },
+            Some(libc::EILSEQ) => Error::SequenceError { source },
+            Some(libc::EMSGSIZE) => Error::Overflow { source },
+            Some(libc::EBADMSG) => Error::MalformedFrame { source },
+            Some(libc::ENOBUFS) => Error::NoBufferSpace { source },
+            Some(libc::ENETDOWN) | Some(libc::ENODEV) => Error::InterfaceDown { source },
+            Some(libc::EPROTONOSUPPORT) | Some(libc::EAFNOSUPPORT) => {
+                Error::ProtocolNotSupported { source }
+            }
+            _ => match source.kind() {
+                io::ErrorKind::TimedOut => Error::Timeout { source },
+                io::ErrorKind::WouldBlock => Error::WouldBlock { source },
+                _ => Error::Io { source },
+            },
+        }
+    }
+}
+
+impl Error {
+    /// The network layer timer that expired, derived from the error variant, `None` for
+    /// other errors.
+    ///
+    /// The kernel also aborts a transfer if the echo of a sent frame is missing (N_As),
+    /// which is indistinguishable from a missing flow control frame and reported as N_Bs.
+    pub fn network_timer(&self) -> Option<NetworkTimer> {
+        match self {
+            Error::Timeout { .. } => Some(NetworkTimer::Cr),
+            Error::TransferAborted { .. } => Some(NetworkTimer::Bs),
+            _ => None,
+        }
+    }
+
+    /// Stable mapping onto `io::ErrorKind`
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Error::Lookup { .. } => io::ErrorKind::NotFound,
+            Error::Io { source } => source.kind(),
+            Error::Timeout { .. } => io::ErrorKind::TimedOut,
+            Error::WouldBlock { .. } => io::ErrorKind::WouldBlock,
+            Error::TransferAborted { .. } => io::ErrorKind::ConnectionAborted,
+            Error::SequenceError { .. } | Error::Overflow { .. } | Error::MalformedFrame { .. } => {
+                io::ErrorKind::InvalidData
+            }
+            Error::NoBufferSpace { .. } => io::ErrorKind::Other,
+            Error::InterfaceDown { .. } => io::ErrorKind::NetworkDown,
+            Error::ProtocolNotSupported { .. } => io::ErrorKind::Unsupported,
+            Error::InvalidSeparationTime(_)
+            | Error::ReservedSeparationTime(_)
+            | Error::InvalidFrameTxTime(_)
+            | Error::InvalidIsoTpOptions(_)
+            | Error::InvalidLinkLayerOptions(_)
+            | Error::InvalidMtu(_)
+            | Error::InvalidTxDataLength(_)
+            | Error::InvalidInput(_) => io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl From<Error> for io::Error {
+    /// Unwraps the underlying OS error where there is one, so the errno is preserved
+    fn from(error: Error) -> Self {
+        match error {
+            Error::Lookup { source } => io::Error::from(source),
+            Error::Io { source }
+            | Error::Timeout { source }
+            | Error::WouldBlock { source }
+            | Error::TransferAborted { source }
+            | Error::SequenceError { source }
+            | Error::Overflow { source }
+            | Error::MalformedFrame { source }
+            | Error::NoBufferSpace { source }
+            | Error::InterfaceDown { source }
+            | Error::ProtocolNotSupported { source } => source,
+            error => io::Error::new(error.kind(), error),
+        }
+    }
+}
+
+/// Encode an identifier as kernel `canid_t`, setting [`EFF_FLAG`] for extended identifiers.
+pub fn id_to_raw(id: impl Into<Id>) -> u32 {
+    match id.into() {
+        Id::Standard(standard_id) => standard_id.as_raw() as u32,
+        Id::Extended(extended_id) => extended_id.as_raw() | EFF_FLAG,
+    }
+}
+
+/// Decode a kernel `canid_t`, [`EFF_FLAG`] selects an extended identifier.
+///
+/// Returns `None` for [`RTR_FLAG`] or [`ERR_FLAG`] identifiers and for standard
+/// identifiers exceeding [`SFF_MASK`].
+pub fn id_from_raw(raw: u32) -> Option<Id> {
+    if raw & (RTR_FLAG | ERR_FLAG) != 0 {
+        None
+    } else if raw & EFF_FLAG != 0 {
+        ExtendedId::new(raw & EFF_MASK).map(Id::Extended)
+    } else {
+        StandardId::new(u16::try_from(raw).ok()?).map(Id::Standard)
+    }
+}
+
+/// Probe whether the running kernel supports CAN ISO-TP sockets.
+///
+/// Opens and closes an unbound ISO-TP socket, no CAN interface is required.
+/// Depending on the system configuration the probe may trigger loading the
+/// can-isotp kernel module on demand.
+pub fn isotp_supported() -> bool {
+    let sock_fd = unsafe { socket(PF_CAN, SOCK_DGRAM, CAN_ISOTP) };
+    if sock_fd == -1 {
+        return false;
+    }
+    close_fd(sock_fd).ok();
+    true
 }
+
 /// An ISO-TP socketcan socket.
 ///
-/// Will be closed upon deallocation. To close manually, use `std::drop::Drop`.
-/// Internally this is just a wrapped file-descriptor.
+/// Will be closed upon deallocation on a best-effort basis, errors on close are ignored.
+/// Use [`IsoTpSocket::close`] to observe them. Internally this is just a wrapped
+/// file-descriptor.
 pub struct IsoTpSocket {
     fd: c_int,
     recv_buffer: [u8; RECV_BUFFER_SIZE],
+    dropped_messages: AtomicU32,
+    retry_on_interrupt: bool,
 }
 
 impl IsoTpSocket {
@@ -427,21 +1303,114 @@ impl IsoTpSocket {
         )
     }
 
-    /// Open a named CAN ISO-TP device, passing additional options.
+    /// Open the CAN ISO-TP connection described by `address`.
+    pub fn open_address(address: &IsoTpAddress) -> Result<Self, Error> {
+        IsoTpSocketBuilder::new().open_address(address)
+    }
+
+    /// Open a named CAN ISO-TP device using 11 bit identifiers.
     ///
-    /// Usually the more common case, opens a socket can device by name, such
-    /// as "vcan0" or "socan0".
-    pub fn open_with_opts(
-        ifname: &str,
-        rx_id: impl Into<Id>,
-        tx_id: impl Into<Id>,
-        isotp_options: Option<IsoTpOptions>,
+    /// Fails with [`Error::InvalidInput`] if an identifier exceeds [`SFF_MASK`], use
+    /// [`IsoTpSocket::open_extended`] for 29 bit identifiers.
+    pub fn open_standard(ifname: &str, rx_id: u16, tx_id: u16) -> Result<Self, Error> {
+        let rx_id = StandardId::new(rx_id).ok_or(Error::InvalidInput("rx_id exceeds 11 bit"))?;
+        let tx_id = StandardId::new(tx_id).ok_or(Error::InvalidInput("tx_id exceeds 11 bit"))?;
+        Self::open(ifname, rx_id, tx_id)
+    }
+
+    /// Open a named CAN ISO-TP device using 29 bit identifiers.
+    ///
+    /// Identifiers are sent as extended frames even if they fit into 11 bit. Fails with
+    /// [`Error::InvalidInput`] if an identifier exceeds [`EFF_MASK`].
+    pub fn open_extended(ifname: &str, rx_id: u32, tx_id: u32) -> Result<Self, Error> {
+        let rx_id = ExtendedId::new(rx_id).ok_or(Error::InvalidInput("rx_id exceeds 29 bit"))?;
+        let tx_id = ExtendedId::new(tx_id).ok_or(Error::InvalidInput("tx_id exceeds 29 bit"))?;
+        Self::open(ifname, rx_id, tx_id)
+    }
+
+    /// Open a named CAN ISO-TP device using extended addressing.
+    ///
+    /// Every frame is prefixed with `ext_address` (N_AE), received frames are only
+    /// accepted if they carry the same address byte. Use
+    /// [`IsoTpSocketBuilder::ext_address`] for distinct tx and rx addresses.
+    pub fn open_with_ext_address(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        ext_address: u8,
+    ) -> Result<Self, Error> {
+        IsoTpSocketBuilder::new()
+            .ext_address(ext_address)
+            .open(ifname, rx_id, tx_id)
+    }
+
+    /// Open a named CAN ISO-TP device for functionally addressed single frames.
+    ///
+    /// See [`IsoTpFunctionalSocket`].
+    pub fn open_functional(
+        ifname: &str,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpFunctionalSocket, Error> {
+        IsoTpSocketBuilder::new().open_functional(ifname, tx_id)
+    }
+
+    /// Open a named CAN ISO-TP device for passive monitoring.
+    ///
+    /// See [`IsoTpListenSocket`].
+    pub fn open_listen(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpListenSocket, Error> {
+        IsoTpSocketBuilder::new().open_listen(ifname, rx_id, tx_id)
+    }
+
+    /// Open two cross-wired sockets on a named CAN ISO-TP device.
+    ///
+    /// The first socket transmits on `a_id` and receives on `b_id`, the second one
+    /// transmits on `b_id` and receives on `a_id`, e.g. a tester and a simulated ECU.
+    ///
+    /// ```rust,no_run
+    /// use socketcan_isotp::{IsoTpSocket, StandardId};
+    ///
+    /// fn main() -> Result<(), socketcan_isotp::Error> {
+    ///     let (tester, mut ecu) = IsoTpSocket::pair(
+    ///         "vcan0",
+    ///         StandardId::new(0x7E0).expect("Invalid id"),
+    ///         StandardId::new(0x7E8).expect("Invalid id"),
+    ///     )?;
+    ///     tester.write(&[0x3E, 0x00])?;
+    ///     assert_eq!(ecu.read()?, [0x3E, 0x00]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn pair(
+        ifname: &str,
+        a_id: impl Into<Id>,
+        b_id: impl Into<Id>,
+    ) -> Result<(Self, Self), Error> {
+        let if_index = interface_index(ifname)?;
+        let (a_id, b_id) = (a_id.into(), b_id.into());
+        let a = Self::open_if(if_index, b_id, a_id)?;
+        let b = Self::open_if(if_index, a_id, b_id)?;
+        Ok((a, b))
+    }
+
+    /// Open a named CAN ISO-TP device, passing additional options.
+    ///
+    /// Usually the more common case, opens a socket can device by name, such
+    /// as "vcan0" or "socan0".
+    pub fn open_with_opts(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        isotp_options: Option<IsoTpOptions>,
         rx_flow_control_options: Option<FlowControlOptions>,
         link_layer_options: Option<LinkLayerOptions>,
     ) -> Result<Self, Error> {
-        let if_index = if_nametoindex(ifname)?;
+        let if_index = interface_index(ifname)?;
         Self::open_if_with_opts(
-            if_index.try_into().unwrap(),
+            if_index,
             rx_id,
             tx_id,
             isotp_options,
@@ -450,6 +1419,28 @@ impl IsoTpSocket {
         )
     }
 
+    /// Open a named CAN FD ISO-TP device.
+    ///
+    /// Transmits CAN FD frames with `tx_dl` bytes of payload, see [`LinkLayerOptions::canfd`].
+    /// Pass [`TxFlags::CANFD_BRS`] in `tx_flags` to switch to the data bit rate for the payload.
+    /// The interface has to be configured for CAN FD, e.g. `ip link set vcan0 mtu 72`.
+    pub fn open_canfd(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        tx_dl: TxDataLength,
+        tx_flags: TxFlags,
+    ) -> Result<Self, Error> {
+        Self::open_with_opts(
+            ifname,
+            rx_id,
+            tx_id,
+            Some(IsoTpOptions::default()),
+            Some(FlowControlOptions::default()),
+            Some(LinkLayerOptions::canfd(tx_dl, tx_flags)),
+        )
+    }
+
     /// Open CAN ISO-TP device device by interface number.
     ///
     /// Opens a CAN device by kernel interface number.
@@ -479,23 +1470,727 @@ impl IsoTpSocket {
         rx_flow_control_options: Option<FlowControlOptions>,
         link_layer_options: Option<LinkLayerOptions>,
     ) -> Result<Self, Error> {
-        let rx_id = match rx_id.into() {
-            Id::Standard(standard_id) => standard_id.as_raw() as u32,
-            Id::Extended(extended_id) => extended_id.as_raw() | EFF_FLAG,
-        };
-        let tx_id = match tx_id.into() {
-            Id::Standard(standard_id) => standard_id.as_raw() as u32,
-            Id::Extended(extended_id) => extended_id.as_raw() | EFF_FLAG,
-        };
-        let addr = CanAddr {
-            _af_can: AF_CAN,
-            if_index,
-            rx_id,
-            tx_id,
-            _pgn: 0,
-            _addr: 0,
+        IsoTpSocketBuilder {
+            isotp_options,
+            rx_flow_control_options,
+            link_layer_options,
+            rx_stmin: None,
+            flags: IsoTpBehaviour::empty(),
+            allow_identical_ids: false,
+            canfd_if_supported: None,
+            tx_flags: TxFlags::empty(),
+        }
+        .open_if(if_index, rx_id, tx_id)
+    }
+
+    /// Close the socket, reporting errors that `Drop` would silently ignore.
+    ///
+    /// The kernel waits for a PDU currently being transmitted to complete before the
+    /// socket is released, so closing drains a pending transmission.
+    pub fn close(self) -> io::Result<()> {
+        let fd = self.into_raw_fd();
+        close_fd(fd)
+    }
+
+    /// Change socket to non-blocking mode
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        set_nonblocking_fd(self.fd, nonblocking)
+    }
+
+    /// Open an [`ErrorMonitor`](error_monitor::ErrorMonitor) receiving the CAN error frames
+    /// of the interface this socket is bound to.
+    pub fn error_monitor(&self) -> Result<error_monitor::ErrorMonitor, Error> {
+        let addr = self.local_addr()?;
+        error_monitor::ErrorMonitor::open_if(addr.if_index)
+    }
+
+    /// Get the address the socket is bound to
+    pub fn local_addr(&self) -> io::Result<CanAddr> {
+        let mut addr = CanAddr::from_raw_ids(0, 0, 0);
+        let mut len = size_of::<CanAddr>() as socklen_t;
+        let rv = unsafe { getsockname(self.fd, &mut addr as *mut _ as *mut sockaddr, &mut len) };
+
+        if rv == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(addr)
+    }
+
+    /// Read back the ISO-TP options currently in effect on the socket
+    pub fn get_isotp_options(&self) -> io::Result<IsoTpOptions> {
+        self.get_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_OPTS)
+    }
+
+    /// Read back the receive flow control options currently in effect on the socket
+    pub fn get_flow_control_options(&self) -> io::Result<FlowControlOptions> {
+        self.get_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_RECV_FC)
+    }
+
+    /// Read back the link layer options currently in effect on the socket
+    pub fn get_link_layer_options(&self) -> io::Result<LinkLayerOptions> {
+        self.get_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_LL_OPTS)
+    }
+
+    /// Change the ISO-TP options of the open socket.
+    ///
+    /// Mainline kernels (Linux 5.11 and later) reject all ISO-TP socket options once the
+    /// socket is bound and fail with `EISCONN`, only the out-of-tree can-isotp module
+    /// applies changes to an open connection. Portable code reopens the socket instead.
+    pub fn set_isotp_options(&self, isotp_options: &IsoTpOptions) -> io::Result<()> {
+        self.set_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_OPTS, isotp_options)
+    }
+
+    /// Change the receive flow control options of the open socket.
+    ///
+    /// Subject to the same kernel restrictions as [`IsoTpSocket::set_isotp_options`].
+    pub fn set_flow_control_options(
+        &self,
+        rx_flow_control_options: &FlowControlOptions,
+    ) -> io::Result<()> {
+        self.set_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_RECV_FC, rx_flow_control_options)
+    }
+
+    /// Change the link layer options of the open socket.
+    ///
+    /// Subject to the same kernel restrictions as [`IsoTpSocket::set_isotp_options`].
+    pub fn set_link_layer_options(&self, link_layer_options: &LinkLayerOptions) -> io::Result<()> {
+        self.set_sockopt(SOL_CAN_ISOTP, CAN_ISOTP_LL_OPTS, link_layer_options)
+    }
+
+    /// Get and clear the pending error of the socket (`SO_ERROR`)
+    ///
+    /// The kernel records asynchronous failures, e.g. a timed out transmission, as pending
+    /// error which is otherwise reported by the next read or write.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        let errno: c_int = self.get_sockopt(SOL_SOCKET, SO_ERROR)?;
+        if errno == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(io::Error::from_raw_os_error(errno)))
+        }
+    }
+
+    /// Set the kernel send buffer size in bytes (`SO_SNDBUF`)
+    ///
+    /// The kernel doubles the value to allow for bookkeeping overhead and caps it at
+    /// `net.core.wmem_max`.
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        let size =
+            c_int::try_from(size).map_err(|_| Error::InvalidInput("buffer size out of range"))?;
+        self.set_sockopt(SOL_SOCKET, SO_SNDBUF, &size)
+    }
+
+    /// Get the kernel send buffer size in bytes (`SO_SNDBUF`)
+    pub fn get_send_buffer_size(&self) -> io::Result<usize> {
+        let size: c_int = self.get_sockopt(SOL_SOCKET, SO_SNDBUF)?;
+        Ok(size as usize)
+    }
+
+    /// Set the kernel receive buffer size in bytes (`SO_RCVBUF`)
+    ///
+    /// The kernel doubles the value to allow for bookkeeping overhead and caps it at
+    /// `net.core.rmem_max`. A larger receive queue avoids dropped PDUs during bursts.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        let size =
+            c_int::try_from(size).map_err(|_| Error::InvalidInput("buffer size out of range"))?;
+        self.set_sockopt(SOL_SOCKET, SO_RCVBUF, &size)
+    }
+
+    /// Get the kernel receive buffer size in bytes (`SO_RCVBUF`)
+    pub fn get_recv_buffer_size(&self) -> io::Result<usize> {
+        let size: c_int = self.get_sockopt(SOL_SOCKET, SO_RCVBUF)?;
+        Ok(size as usize)
+    }
+
+    fn set_sockopt<T>(&self, level: c_int, optname: c_int, value: &T) -> io::Result<()> {
+        set_sockopt_fd(self.fd, level, optname, value)
+    }
+
+    fn get_sockopt<T: Default>(&self, level: c_int, optname: c_int) -> io::Result<T> {
+        let mut value = T::default();
+        let mut len = size_of::<T>() as socklen_t;
+        let rv = unsafe {
+            getsockopt(
+                self.fd,
+                level,
+                optname,
+                &mut value as *mut _ as *mut c_void,
+                &mut len,
+            )
         };
 
+        if rv == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if len as usize != size_of::<T>() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected socket option size",
+            ));
+        }
+
+        Ok(value)
+    }
+
+    /// Blocking read data
+    pub fn read(&mut self) -> io::Result<&[u8]> {
+        let Self {
+            fd,
+            recv_buffer,
+            dropped_messages,
+            retry_on_interrupt,
+        } = self;
+        let len = retry(*retry_on_interrupt, || {
+            recv_fd(*fd, recv_buffer, dropped_messages)
+        })?;
+        Ok(&recv_buffer[0..len])
+    }
+
+    /// Blocking read data into the provided buffer, returning the number of bytes read.
+    ///
+    /// PDUs exceeding the buffer length are truncated.
+    pub fn read_to_buf(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        retry(self.retry_on_interrupt, || {
+            recv_fd(self.fd, buffer, &self.dropped_messages)
+        })
+    }
+
+    /// Retry blocking reads and writes interrupted by a signal (`EINTR`), enabled by default.
+    ///
+    /// Disable to let signal handlers interrupt a blocking call, which then fails with
+    /// `io::ErrorKind::Interrupted`.
+    pub fn set_retry_on_interrupt(&mut self, retry_on_interrupt: bool) {
+        self.retry_on_interrupt = retry_on_interrupt;
+    }
+
+    /// Enable reporting of PDUs dropped due to a full receive queue (`SO_RXQ_OVFL`)
+    ///
+    /// Once enabled, every read updates the counter returned by
+    /// [`IsoTpSocket::dropped_messages`].
+    pub fn set_rxq_overflow(&self, enable: bool) -> io::Result<()> {
+        self.set_sockopt(SOL_SOCKET, SO_RXQ_OVFL, &c_int::from(enable))
+    }
+
+    /// Number of PDUs the kernel dropped on this socket so far, as reported by the last read
+    ///
+    /// Stays 0 unless reporting is enabled with [`IsoTpSocket::set_rxq_overflow`].
+    pub fn dropped_messages(&self) -> u32 {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
+    /// Blocking write a slice of data
+    ///
+    /// Returns once the PDU is queued for transmission unless the socket was opened with
+    /// [`IsoTpSocketBuilder::wait_tx_done`].
+    pub fn write(&self, buffer: &[u8]) -> io::Result<()> {
+        retry(self.retry_on_interrupt, || {
+            let write_rv = unsafe {
+                let buffer_ptr = buffer as *const _ as *const c_void;
+                write(self.fd, buffer_ptr, buffer.len())
+            };
+
+            if write_rv < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            if write_rv as usize != buffer.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "PDU was only partially written",
+                ));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// Repeat `f` as long as it fails with `EINTR`, if enabled
+fn retry<T>(enabled: bool, mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    loop {
+        match f() {
+            Err(e) if enabled && e.kind() == io::ErrorKind::Interrupted => continue,
+            result => return result,
+        }
+    }
+}
+
+fn recv_fd(fd: c_int, buffer: &mut [u8], dropped_messages: &AtomicU32) -> io::Result<usize> {
+    // room for a single SO_RXQ_OVFL control message
+    let mut control = [0u64; 4];
+    let mut iov = iovec {
+        iov_base: buffer as *mut _ as *mut c_void,
+        iov_len: buffer.len(),
+    };
+    let mut msg: msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = control.as_mut_ptr() as *mut c_void;
+    msg.msg_controllen = size_of_val(&control) as _;
+
+    let recv_rv = unsafe { recvmsg(fd, &mut msg, 0) };
+
+    if recv_rv < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    unsafe {
+        let mut cmsg = CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == SOL_SOCKET && (*cmsg).cmsg_type == SO_RXQ_OVFL {
+                let dropped = std::ptr::read_unaligned(CMSG_DATA(cmsg) as *const u32);
+                dropped_messages.store(dropped, Ordering::Relaxed);
+            }
+            cmsg = CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+
+    Ok(recv_rv as usize)
+}
+
+impl AsFd for IsoTpSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+impl AsRawFd for IsoTpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl FromRawFd for IsoTpSocket {
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Self {
+            fd,
+            recv_buffer: [0x00; RECV_BUFFER_SIZE],
+            dropped_messages: AtomicU32::new(0),
+            retry_on_interrupt: true,
+        }
+    }
+}
+
+impl IntoRawFd for IsoTpSocket {
+    fn into_raw_fd(self) -> RawFd {
+        // the caller takes over ownership, do not close on drop
+        let fd = self.fd;
+        std::mem::forget(self);
+        fd
+    }
+}
+
+impl Drop for IsoTpSocket {
+    fn drop(&mut self) {
+        close_fd(self.fd).ok(); // ignore result
+    }
+}
+
+/// Look up the kernel interface number of a named CAN device
+pub(crate) fn interface_index(ifname: &str) -> Result<c_int, Error> {
+    if ifname.len() >= libc::IFNAMSIZ {
+        return Err(Error::InvalidInput("interface name exceeds IFNAMSIZ"));
+    }
+    let if_index = if_nametoindex(ifname)?;
+    c_int::try_from(if_index).map_err(|_| Error::InvalidInput("interface index out of range"))
+}
+
+/// Query the MTU of the interface `if_index` using the socket `fd`
+fn interface_mtu(fd: c_int, if_index: c_int) -> Result<c_int, Error> {
+    let mut ifreq: ifreq = unsafe { std::mem::zeroed() };
+    if unsafe { if_indextoname(if_index as u32, ifreq.ifr_name.as_mut_ptr()) }.is_null() {
+        return Err(Error::from(io::Error::last_os_error()));
+    }
+
+    let rv = unsafe { ioctl(fd, SIOCGIFMTU as _, &mut ifreq as *mut ifreq) };
+    if rv == -1 {
+        return Err(Error::from(io::Error::last_os_error()));
+    }
+    Ok(unsafe { ifreq.ifr_ifru.ifru_mtu })
+}
+
+/// Run `configure` on the freshly opened socket `sock`, closing it if `configure` fails
+fn configure_socket(
+    sock: OwnedFd,
+    configure: impl FnOnce(c_int) -> Result<(), Error>,
+) -> Result<OwnedFd, Error> {
+    configure(sock.as_raw_fd())?;
+    Ok(sock)
+}
+
+fn set_sockopt_fd<T>(fd: c_int, level: c_int, optname: c_int, value: &T) -> io::Result<()> {
+    let rv = unsafe {
+        setsockopt(
+            fd,
+            level,
+            optname,
+            value as *const _ as *const c_void,
+            size_of::<T>() as socklen_t,
+        )
+    };
+
+    if rv == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+fn set_nonblocking_fd(fd: c_int, nonblocking: bool) -> io::Result<()> {
+    // retrieve current flags
+    let oldfl = unsafe { fcntl(fd, F_GETFL) };
+
+    if oldfl == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let newfl = if nonblocking {
+        oldfl | O_NONBLOCK
+    } else {
+        oldfl & !O_NONBLOCK
+    };
+
+    let rv = unsafe { fcntl(fd, F_SETFL, newfl) };
+
+    if rv != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn close_fd(fd: c_int) -> io::Result<()> {
+    let rv = unsafe { close(fd) };
+    if rv == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Builder for an [`IsoTpSocket`] configured beyond the plain option structs.
+///
+/// The setters only store their arguments, validation is deferred to opening the socket.
+/// Flags such as [`IsoTpSocketBuilder::wait_tx_done`] or the broadcast and listen modes of
+/// the `open_*` methods are merged into the ISO-TP options at that point, so only the final
+/// options are checked with [`IsoTpOptions::validate`] and [`LinkLayerOptions::validate`].
+/// Invalid combinations fail before a socket is created.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::{IsoTpSocketBuilder, StandardId};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), socketcan_isotp::Error> {
+///     let tp_socket = IsoTpSocketBuilder::new()
+///         .rx_stmin(Duration::from_millis(5))
+///         .open(
+///             "vcan0",
+///             StandardId::new(0x123).expect("Invalid rx id"),
+///             StandardId::new(0x321).expect("Invalid tx id"),
+///         )?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct IsoTpSocketBuilder {
+    isotp_options: Option<IsoTpOptions>,
+    rx_flow_control_options: Option<FlowControlOptions>,
+    link_layer_options: Option<LinkLayerOptions>,
+    rx_stmin: Option<Duration>,
+    /// flags added to the ISO-TP options on open
+    flags: IsoTpBehaviour,
+    allow_identical_ids: bool,
+    /// CAN FD settings used if the interface supports CAN FD
+    canfd_if_supported: Option<(TxDataLength, TxFlags)>,
+    /// flags added to CAN FD link layer options on open
+    tx_flags: TxFlags,
+}
+
+impl Default for IsoTpSocketBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsoTpSocketBuilder {
+    /// Creates a builder using the kernel default options
+    pub fn new() -> Self {
+        Self {
+            isotp_options: Some(IsoTpOptions::default()),
+            rx_flow_control_options: Some(FlowControlOptions::default()),
+            link_layer_options: Some(LinkLayerOptions::default()),
+            rx_stmin: None,
+            flags: IsoTpBehaviour::empty(),
+            allow_identical_ids: false,
+            canfd_if_supported: None,
+            tx_flags: TxFlags::empty(),
+        }
+    }
+
+    /// Set the ISO-TP options, `None` keeps the kernel defaults
+    ///
+    /// Validated together with the flags added by the builder on open.
+    pub fn isotp_options(mut self, isotp_options: Option<IsoTpOptions>) -> Self {
+        self.isotp_options = isotp_options;
+        self
+    }
+
+    /// Set the flow control options sent to the peer, `None` keeps the kernel defaults
+    pub fn rx_flow_control_options(
+        mut self,
+        rx_flow_control_options: Option<FlowControlOptions>,
+    ) -> Self {
+        self.rx_flow_control_options = rx_flow_control_options;
+        self
+    }
+
+    /// Set the link layer options, `None` keeps the kernel defaults
+    ///
+    /// Validated on open.
+    pub fn link_layer_options(mut self, link_layer_options: Option<LinkLayerOptions>) -> Self {
+        self.link_layer_options = link_layer_options;
+        self
+    }
+
+    /// Enable padding of tx frames and padding checks of rx frames, see [`IsoTpOptions::set_padding`].
+    ///
+    /// Applies to the ISO-TP options set so far, a later call to
+    /// [`IsoTpSocketBuilder::isotp_options`] replaces them.
+    pub fn padding(mut self, padding: u8) -> Self {
+        self.isotp_options
+            .get_or_insert_with(IsoTpOptions::default)
+            .set_padding(padding);
+        self
+    }
+
+    /// Use extended addressing, prefixing every frame with an address byte (N_AE).
+    ///
+    /// See [`IsoTpOptions::set_ext_addressing`]. Applies to the ISO-TP options set so far,
+    /// a later call to [`IsoTpSocketBuilder::isotp_options`] replaces them.
+    pub fn ext_address(mut self, ext_address: impl Into<ExtAddress>) -> Self {
+        self.isotp_options
+            .get_or_insert_with(IsoTpOptions::default)
+            .set_ext_addressing(ext_address.into());
+        self
+    }
+
+    /// Ignore received consecutive frames whose timestamps differ less than `rx_stmin`.
+    ///
+    /// Enables `CAN_ISOTP_FORCE_RXSTMIN` on the ISO-TP options, the kernel otherwise
+    /// ignores the value. Fails on open if `rx_stmin` does not fit into `u32` nanoseconds.
+    pub fn rx_stmin(mut self, rx_stmin: Duration) -> Self {
+        self.rx_stmin = Some(rx_stmin);
+        self
+    }
+
+    /// Let `write` block until the complete PDU has been transmitted on the bus.
+    ///
+    /// Enables `CAN_ISOTP_WAIT_TX_DONE` on the ISO-TP options, so transmission errors of
+    /// multi-frame transfers are reported by the `write` call that caused them.
+    ///
+    /// Requires Linux 5.10 or a recent out-of-tree can-isotp module, older
+    /// implementations silently ignore the flag and return as soon as the PDU is queued.
+    pub fn wait_tx_done(mut self, wait_tx_done: bool) -> Self {
+        self.flags
+            .set(IsoTpBehaviour::CAN_ISOTP_WAIT_TX_DONE, wait_tx_done);
+        self
+    }
+
+    /// Accept the same CAN identifier for reception and transmission.
+    ///
+    /// Opening a unicast socket with `rx_id == tx_id` is rejected by default, as the
+    /// socket would consume its own flow control frames. Broadcast sockets are exempt.
+    pub fn allow_identical_ids(mut self, allow_identical_ids: bool) -> Self {
+        self.allow_identical_ids = allow_identical_ids;
+        self
+    }
+
+    /// Switch to the data bit rate for the payload of transmitted CAN FD frames.
+    ///
+    /// Sets [`TxFlags::CANFD_BRS`] on CAN FD link layer options, classic CAN link layer
+    /// options are left untouched.
+    pub fn bit_rate_switch(mut self, bit_rate_switch: bool) -> Self {
+        self.tx_flags.set(TxFlags::CANFD_BRS, bit_rate_switch);
+        self
+    }
+
+    /// Signal error passive state in transmitted CAN FD frames.
+    ///
+    /// Sets [`TxFlags::CANFD_ESI`] on CAN FD link layer options, classic CAN link layer
+    /// options are left untouched.
+    pub fn error_state_indicator(mut self, error_state_indicator: bool) -> Self {
+        self.tx_flags.set(TxFlags::CANFD_ESI, error_state_indicator);
+        self
+    }
+
+    /// Choose the link layer options from the interface MTU on open.
+    ///
+    /// Uses [`LinkLayerOptions::canfd`] with `tx_dl` and `tx_flags` if the interface is
+    /// configured for CAN FD and the classic CAN defaults otherwise. Replaces the link
+    /// layer options set by [`IsoTpSocketBuilder::link_layer_options`].
+    pub fn canfd_if_supported(mut self, tx_dl: TxDataLength, tx_flags: TxFlags) -> Self {
+        self.canfd_if_supported = Some((tx_dl, tx_flags));
+        self
+    }
+
+    /// Open a named CAN ISO-TP device.
+    pub fn open(
+        self,
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpSocket, Error> {
+        let if_index = interface_index(ifname)?;
+        self.open_if(if_index, rx_id, tx_id)
+    }
+
+    /// Open the CAN ISO-TP connection described by `address`.
+    ///
+    /// Enables extended addressing if the address carries an address extension.
+    pub fn open_address(mut self, address: &IsoTpAddress) -> Result<IsoTpSocket, Error> {
+        if let Some(ext_address) = address.get_ext_address() {
+            self = self.ext_address(ext_address);
+        }
+        self.open(
+            address.get_interface(),
+            address.get_rx_id(),
+            address.get_tx_id(),
+        )
+    }
+
+    /// Open a named CAN ISO-TP device for unconfirmed 1-to-N transmission.
+    ///
+    /// See [`IsoTpBroadcastSocket`].
+    pub fn open_broadcast(
+        self,
+        ifname: &str,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpBroadcastSocket, Error> {
+        let if_index = interface_index(ifname)?;
+        self.open_broadcast_if(if_index, tx_id)
+    }
+
+    /// Open CAN ISO-TP device by interface number for unconfirmed 1-to-N transmission.
+    ///
+    /// See [`IsoTpBroadcastSocket`].
+    pub fn open_broadcast_if(
+        mut self,
+        if_index: c_int,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpBroadcastSocket, Error> {
+        let tx_id = tx_id.into();
+        self.flags |= IsoTpBehaviour::CAN_ISOTP_CF_BROADCAST;
+        // the receive identifier is not evaluated by the kernel in broadcast mode
+        self.open_if(if_index, tx_id, tx_id)
+            .map(IsoTpBroadcastSocket)
+    }
+
+    /// Open a named CAN ISO-TP device for functionally addressed single frames.
+    ///
+    /// See [`IsoTpFunctionalSocket`].
+    pub fn open_functional(
+        self,
+        ifname: &str,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpFunctionalSocket, Error> {
+        let if_index = interface_index(ifname)?;
+        self.open_functional_if(if_index, tx_id)
+    }
+
+    /// Open CAN ISO-TP device by interface number for functionally addressed single frames.
+    ///
+    /// See [`IsoTpFunctionalSocket`].
+    pub fn open_functional_if(
+        mut self,
+        if_index: c_int,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpFunctionalSocket, Error> {
+        let tx_id = tx_id.into();
+        self.flags |= IsoTpBehaviour::CAN_ISOTP_SF_BROADCAST;
+        // the receive identifier is not evaluated by the kernel in broadcast mode
+        let socket = self.open_if(if_index, tx_id, tx_id)?;
+
+        let isotp_options = socket.get_isotp_options()?;
+        let link_layer_options = socket.get_link_layer_options()?;
+        let ext_address_len = usize::from(isotp_options.get_ext_addressing().is_some());
+        let max_payload_len = match link_layer_options.get_tx_dl() {
+            TxDataLength::Dl8 => CAN_MAX_DLEN as usize - 1,
+            // CAN FD single frames with escape sequence carry the length in a second byte
+            tx_dl => tx_dl.as_u8() as usize - 2,
+        } - ext_address_len;
+
+        Ok(IsoTpFunctionalSocket {
+            socket,
+            max_payload_len,
+        })
+    }
+
+    /// Open a named CAN ISO-TP device for passive monitoring.
+    ///
+    /// See [`IsoTpListenSocket`].
+    pub fn open_listen(
+        self,
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpListenSocket, Error> {
+        let if_index = interface_index(ifname)?;
+        self.open_listen_if(if_index, rx_id, tx_id)
+    }
+
+    /// Open CAN ISO-TP device by interface number for passive monitoring.
+    ///
+    /// See [`IsoTpListenSocket`].
+    pub fn open_listen_if(
+        mut self,
+        if_index: c_int,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpListenSocket, Error> {
+        self.flags |= IsoTpBehaviour::CAN_ISOTP_LISTEN_MODE;
+        self.open_if(if_index, rx_id, tx_id).map(IsoTpListenSocket)
+    }
+
+    /// Open CAN ISO-TP device device by interface number.
+    pub fn open_if(
+        self,
+        if_index: c_int,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<IsoTpSocket, Error> {
+        let rx_id = id_to_raw(rx_id);
+        let tx_id = id_to_raw(tx_id);
+        let rx_stmin = self
+            .rx_stmin
+            .map(|rx_stmin| u32::try_from(rx_stmin.as_nanos()))
+            .transpose()
+            .map_err(|_| Error::InvalidInput("rx_stmin does not fit into u32 nano secs"))?;
+
+        if if_index <= 0 {
+            return Err(Error::InvalidInput("interface index must be positive"));
+        }
+        let isotp_options = self.isotp_options_with_flags();
+        let broadcast = isotp_options
+            .and_then(|isotp_options| isotp_options.get_flags())
+            .is_some_and(|flags| {
+                flags.intersects(
+                    IsoTpBehaviour::CAN_ISOTP_SF_BROADCAST | IsoTpBehaviour::CAN_ISOTP_CF_BROADCAST,
+                )
+            });
+        if rx_id == tx_id && !broadcast && !self.allow_identical_ids {
+            return Err(Error::InvalidInput(
+                "rx_id and tx_id must differ, see IsoTpSocketBuilder::allow_identical_ids",
+            ));
+        }
+        if let Some(isotp_options) = &isotp_options {
+            isotp_options.validate()?;
+        }
+        if let Some(link_layer_options) = &self.link_layer_options {
+            link_layer_options.validate()?;
+        }
+
+        let addr = CanAddr::from_raw_ids(if_index, rx_id, tx_id);
+
         // open socket
         let sock_fd;
         unsafe {
@@ -506,169 +2201,437 @@ impl IsoTpSocket {
             return Err(Error::from(io::Error::last_os_error()));
         }
 
-        // Set IsoTpOptions
-        if let Some(isotp_options) = isotp_options {
-            let isotp_options_ptr: *const c_void = &isotp_options as *const _ as *const c_void;
-            let err = unsafe {
-                setsockopt(
-                    sock_fd,
-                    SOL_CAN_ISOTP,
-                    CAN_ISOTP_OPTS,
-                    isotp_options_ptr,
-                    ISOTP_OPTIONS_SIZE.try_into().unwrap(),
-                )
-            };
-            if err == -1 {
-                return Err(Error::from(io::Error::last_os_error()));
+        let sock = configure_socket(unsafe { OwnedFd::from_raw_fd(sock_fd) }, |sock_fd| {
+            let link_layer_options = self.link_layer_options_for(sock_fd, if_index)?;
+            if let Some(link_layer_options) = &link_layer_options {
+                if link_layer_options.mtu == CANFD_MTU
+                    && interface_mtu(sock_fd, if_index)? < CANFD_MTU.into()
+                {
+                    return Err(Error::InvalidLinkLayerOptions(
+                        "interface does not support CAN FD, set its mtu to 72",
+                    ));
+                }
             }
-        }
 
-        // Set FlowControlOptions
-        if let Some(rx_flow_control_options) = rx_flow_control_options {
-            let rx_flow_control_options_ptr: *const c_void =
-                &rx_flow_control_options as *const _ as *const c_void;
-            let err = unsafe {
-                setsockopt(
+            if let Some(isotp_options) = isotp_options {
+                set_sockopt_fd(sock_fd, SOL_CAN_ISOTP, CAN_ISOTP_OPTS, &isotp_options)?;
+            }
+            if let Some(rx_flow_control_options) = self.rx_flow_control_options {
+                set_sockopt_fd(
                     sock_fd,
                     SOL_CAN_ISOTP,
                     CAN_ISOTP_RECV_FC,
-                    rx_flow_control_options_ptr,
-                    FLOW_CONTROL_OPTIONS_SIZE.try_into().unwrap(),
-                )
-            };
-            if err == -1 {
-                return Err(Error::from(io::Error::last_os_error()));
+                    &rx_flow_control_options,
+                )?;
             }
-        }
-
-        // Set LinkLayerOptions
-        if let Some(link_layer_options) = link_layer_options {
-            let link_layer_options_ptr: *const c_void =
-                &link_layer_options as *const _ as *const c_void;
-            let err = unsafe {
-                setsockopt(
+            if let Some(link_layer_options) = link_layer_options {
+                set_sockopt_fd(
                     sock_fd,
                     SOL_CAN_ISOTP,
                     CAN_ISOTP_LL_OPTS,
-                    link_layer_options_ptr,
-                    LINK_LAYER_OPTIONS_SIZE.try_into().unwrap(),
-                )
-            };
-            if err == -1 {
-                return Err(Error::from(io::Error::last_os_error()));
+                    &link_layer_options,
+                )?;
+            }
+            if let Some(rx_stmin) = rx_stmin {
+                set_sockopt_fd(sock_fd, SOL_CAN_ISOTP, CAN_ISOTP_RX_STMIN, &rx_stmin)?;
             }
-        }
-
-        // bind it
-        let bind_rv;
-        unsafe {
-            let sockaddr_ptr = &addr as *const CanAddr;
-            bind_rv = bind(
-                sock_fd,
-                sockaddr_ptr as *const sockaddr,
-                size_of::<CanAddr>().try_into().unwrap(),
-            );
-        }
 
-        // FIXME: on fail, close socket (do not leak socketfds)
-        if bind_rv == -1 {
-            let e = io::Error::last_os_error();
+            // bind it
+            let bind_rv;
             unsafe {
-                close(sock_fd);
+                let sockaddr_ptr = &addr as *const CanAddr;
+                bind_rv = bind(
+                    sock_fd,
+                    sockaddr_ptr as *const sockaddr,
+                    size_of::<CanAddr>() as socklen_t,
+                );
             }
-            return Err(Error::from(e));
-        }
 
-        Ok(Self {
-            fd: sock_fd,
+            if bind_rv == -1 {
+                return Err(Error::from(io::Error::last_os_error()));
+            }
+            Ok(())
+        })?;
+
+        Ok(IsoTpSocket {
+            fd: sock.into_raw_fd(),
             recv_buffer: [0x00; RECV_BUFFER_SIZE],
+            dropped_messages: AtomicU32::new(0),
+            retry_on_interrupt: true,
         })
     }
 
-    fn close(&mut self) -> io::Result<()> {
-        unsafe {
-            let rv = close(self.fd);
-            if rv != -1 {
-                return Err(io::Error::last_os_error());
+    /// The link layer options applied on open, `fd` is used to query the interface MTU
+    fn link_layer_options_for(
+        &self,
+        fd: c_int,
+        if_index: c_int,
+    ) -> Result<Option<LinkLayerOptions>, Error> {
+        let link_layer_options = match self.canfd_if_supported {
+            Some((tx_dl, tx_flags)) => {
+                if interface_mtu(fd, if_index)? >= CANFD_MTU.into() {
+                    Some(LinkLayerOptions::canfd(tx_dl, tx_flags))
+                } else {
+                    Some(LinkLayerOptions::default())
+                }
             }
-        }
-        Ok(())
+            None => self.link_layer_options,
+        };
+        Ok(
+            link_layer_options.map(|link_layer_options| match link_layer_options.get_mtu() {
+                Mtu::Fd => LinkLayerOptions {
+                    tx_flags: link_layer_options.tx_flags | self.tx_flags.bits(),
+                    ..link_layer_options
+                },
+                Mtu::Classic => link_layer_options,
+            }),
+        )
     }
 
-    /// Change socket to non-blocking mode
-    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
-        // retrieve current flags
-        let oldfl = unsafe { fcntl(self.fd, F_GETFL) };
-
-        if oldfl == -1 {
-            return Err(io::Error::last_os_error());
+    /// ISO-TP options including the flags implied by other builder settings
+    fn isotp_options_with_flags(&self) -> Option<IsoTpOptions> {
+        let mut flags = self.flags;
+        if self.rx_stmin.is_some() {
+            flags |= IsoTpBehaviour::CAN_ISOTP_FORCE_RXSTMIN;
         }
 
-        let newfl = if nonblocking {
-            oldfl | O_NONBLOCK
-        } else {
-            oldfl & !O_NONBLOCK
-        };
-
-        let rv = unsafe { fcntl(self.fd, F_SETFL, newfl) };
-
-        if rv != 0 {
-            return Err(io::Error::last_os_error());
+        match &self.isotp_options {
+            Some(isotp_options) => Some(IsoTpOptions {
+                flags: isotp_options.flags | flags.bits(),
+                ..*isotp_options
+            }),
+            None if flags.is_empty() => None,
+            None => Some(IsoTpOptions {
+                flags: flags.bits(),
+                ..IsoTpOptions::default()
+            }),
         }
-        Ok(())
     }
+}
+
+/// A receive-only ISO-TP socket that never sends flow control frames.
+///
+/// Opened with `CAN_ISOTP_LISTEN_MODE`, PDUs sent to `rx_id` are reassembled while the
+/// flow control frames are left to the actual receiver, like `isotpdump` does. Open a
+/// second socket with swapped identifiers to observe both directions of a conversation.
+pub struct IsoTpListenSocket(IsoTpSocket);
 
+impl IsoTpListenSocket {
     /// Blocking read data
     pub fn read(&mut self) -> io::Result<&[u8]> {
-        let buffer_ptr = &mut self.recv_buffer as *mut _ as *mut c_void;
+        self.0.read()
+    }
 
-        let read_rv = unsafe { read(self.fd, buffer_ptr, RECV_BUFFER_SIZE) };
+    /// Blocking read data into the provided buffer, returning the number of bytes read.
+    pub fn read_to_buf(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.0.read_to_buf(buffer)
+    }
 
-        if read_rv < 0 {
-            return Err(io::Error::last_os_error());
-        }
+    /// Get a reference to the underlying socket
+    pub fn get_ref(&self) -> &IsoTpSocket {
+        &self.0
+    }
+}
 
-        Ok(&self.recv_buffer[0..read_rv.try_into().unwrap()])
+impl AsRawFd for IsoTpListenSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
     }
+}
 
+/// A transmit-only ISO-TP socket sending multi-frame PDUs without flow control.
+///
+/// Opened with `CAN_ISOTP_CF_BROADCAST`, consecutive frames are sent right after the
+/// first frame using the configured `frame_txtime` as gap, without waiting for a flow
+/// control frame of a receiver. Requires Linux 6.0 or later, older kernels ignore the flag
+/// and wait for flow control like a regular socket.
+pub struct IsoTpBroadcastSocket(IsoTpSocket);
+
+impl IsoTpBroadcastSocket {
     /// Blocking write a slice of data
+    ///
+    /// Payloads must not be empty and at most [`MAX_PDU_LENGTH`] bytes long.
     pub fn write(&self, buffer: &[u8]) -> io::Result<()> {
-        let write_rv = unsafe {
-            let buffer_ptr = buffer as *const _ as *const c_void;
-            write(self.fd, buffer_ptr, buffer.len())
-        };
-
-        if write_rv != buffer.len().try_into().unwrap() {
-            return Err(io::Error::last_os_error());
+        if buffer.is_empty() || buffer.len() > MAX_PDU_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "broadcast payload length {} is not within 1 - {} bytes",
+                    buffer.len(),
+                    MAX_PDU_LENGTH
+                ),
+            ));
         }
+        self.0.write(buffer)
+    }
 
-        Ok(())
+    /// Get a reference to the underlying socket
+    pub fn get_ref(&self) -> &IsoTpSocket {
+        &self.0
     }
 }
 
-impl AsRawFd for IsoTpSocket {
+impl AsRawFd for IsoTpBroadcastSocket {
     fn as_raw_fd(&self) -> RawFd {
-        self.fd
+        self.0.as_raw_fd()
     }
 }
 
-impl FromRawFd for IsoTpSocket {
-    unsafe fn from_raw_fd(fd: RawFd) -> Self {
-        Self {
-            fd,
-            recv_buffer: [0x00; RECV_BUFFER_SIZE],
+/// A transmit-only ISO-TP socket sending functionally addressed single frames.
+///
+/// Opened with `CAN_ISOTP_SF_BROADCAST`, PDUs are sent as single frames without waiting
+/// for flow control, so any number of receivers can listen on the identifier. Responses
+/// are physically addressed and received on separate sockets, one per responding node.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::Error> {
+///     // OBD-II functional request to all emission related ECUs
+///     let request = IsoTpSocket::open_functional("vcan0", StandardId::new(0x7DF).unwrap())?;
+///     let responses = (0..8)
+///         .map(|ecu| {
+///             IsoTpSocket::open(
+///                 "vcan0",
+///                 StandardId::new(0x7E8 + ecu).unwrap(),
+///                 StandardId::new(0x7E0 + ecu).unwrap(),
+///             )
+///         })
+///         .collect::<Result<Vec<_>, _>>()?;
+///     request.write(&[0x01, 0x00])?;
+///     Ok(())
+/// }
+/// ```
+pub struct IsoTpFunctionalSocket {
+    socket: IsoTpSocket,
+    max_payload_len: usize,
+}
+
+impl IsoTpFunctionalSocket {
+    /// Blocking write a slice of data
+    ///
+    /// Payloads must fit into a single frame, see [`IsoTpFunctionalSocket::max_payload_len`].
+    pub fn write(&self, buffer: &[u8]) -> io::Result<()> {
+        if buffer.is_empty() || buffer.len() > self.max_payload_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "functional payload length {} is not within 1 - {} bytes",
+                    buffer.len(),
+                    self.max_payload_len
+                ),
+            ));
         }
+        self.socket.write(buffer)
+    }
+
+    /// Maximum payload length of a single frame with the configured link layer and addressing
+    ///
+    /// 7 bytes on classic CAN, up to 62 bytes on CAN FD, one byte less with extended
+    /// addressing.
+    pub fn max_payload_len(&self) -> usize {
+        self.max_payload_len
+    }
+
+    /// Get a reference to the underlying socket
+    pub fn get_ref(&self) -> &IsoTpSocket {
+        &self.socket
     }
 }
 
-impl IntoRawFd for IsoTpSocket {
-    fn into_raw_fd(self) -> RawFd {
-        self.fd
+impl AsRawFd for IsoTpFunctionalSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
     }
 }
 
-impl Drop for IsoTpSocket {
-    fn drop(&mut self) {
-        self.close().ok(); // ignore result
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open a socket expected to fail
+    fn open_error(open: impl FnOnce() -> Result<IsoTpSocket, Error>) -> Error {
+        open().err().expect("open succeeded")
+    }
+
+    fn open_if(builder: IsoTpSocketBuilder, if_index: c_int) -> Result<IsoTpSocket, Error> {
+        builder.open_if(
+            if_index,
+            StandardId::new(0x7E8).unwrap(),
+            StandardId::new(0x7E0).unwrap(),
+        )
+    }
+
+    #[test]
+    fn open_if_rejects_non_positive_interface_index() {
+        for if_index in [0, -1, c_int::MIN] {
+            let error = open_error(|| open_if(IsoTpSocketBuilder::new(), if_index));
+            assert!(matches!(error, Error::InvalidInput(_)), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn open_validates_options_combined_with_builder_flags() {
+        let mut isotp_options = IsoTpOptions::default();
+        isotp_options.set_flags(IsoTpBehaviour::CAN_ISOTP_LISTEN_MODE);
+        let error = IsoTpSocketBuilder::new()
+            .isotp_options(Some(isotp_options))
+            .open_broadcast_if(1, StandardId::new(0x7DF).unwrap())
+            .err()
+            .expect("open succeeded");
+        assert!(matches!(error, Error::InvalidIsoTpOptions(_)), "{error:?}");
+    }
+
+    #[test]
+    fn interface_index_rejects_names_beyond_ifnamsiz() {
+        let error = interface_index(&"c".repeat(libc::IFNAMSIZ)).unwrap_err();
+        assert!(matches!(error, Error::InvalidInput(_)), "{error:?}");
+    }
+
+    #[test]
+    fn interface_index_fails_on_unknown_names() {
+        for ifname in ["", "can\0x", "nosuchcan0"] {
+            assert!(interface_index(ifname).is_err(), "{ifname:?}");
+        }
+    }
+
+    #[test]
+    fn constructors_reject_non_positive_interface_index() {
+        let rx_id = StandardId::new(0x7E8).unwrap();
+        let tx_id = StandardId::new(0x7E0).unwrap();
+        for if_index in [0, -1, c_int::MIN] {
+            let errors = [
+                IsoTpSocket::open_if(if_index, rx_id, tx_id).err(),
+                IsoTpSocket::open_if_with_opts(if_index, rx_id, tx_id, None, None, None).err(),
+                IsoTpSocketBuilder::new()
+                    .open_broadcast_if(if_index, tx_id)
+                    .err(),
+                IsoTpSocketBuilder::new()
+                    .open_functional_if(if_index, tx_id)
+                    .err(),
+                IsoTpSocketBuilder::new()
+                    .open_listen_if(if_index, rx_id, tx_id)
+                    .err(),
+            ];
+            for error in errors {
+                assert!(matches!(error, Some(Error::InvalidInput(_))), "{error:?}");
+            }
+        }
+        let error = error_monitor::ErrorMonitor::open_if(-1).err();
+        assert!(matches!(error, Some(Error::InvalidInput(_))), "{error:?}");
+    }
+
+    #[test]
+    fn open_rejects_rx_stmin_beyond_u32_nanos() {
+        let builder = IsoTpSocketBuilder::new().rx_stmin(Duration::from_secs(5));
+        let error = open_error(|| open_if(builder, 1));
+        assert!(matches!(error, Error::InvalidInput(_)), "{error:?}");
+    }
+
+    #[test]
+    fn buffer_sizes_beyond_c_int_are_rejected() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let socket = unsafe { IsoTpSocket::from_raw_fd(file.into_raw_fd()) };
+        for size in [c_int::MAX as usize + 1, usize::MAX] {
+            let error = socket.set_recv_buffer_size(size).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+            let error = socket.set_send_buffer_size(size).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn open_if_reports_missing_protocol() {
+        if isotp_supported() {
+            return;
+        }
+        let error = open_error(|| open_if(IsoTpSocketBuilder::new(), 1));
+        assert!(
+            matches!(error, Error::ProtocolNotSupported { .. }),
+            "{error:?}"
+        );
+    }
+
+    /// A pipe, the write end handed to [`configure_socket`] in place of a socket
+    #[test]
+    fn frame_txtime_zero_duration_is_forced_zero() {
+        let zero = FrameTxTime::Duration(Duration::ZERO);
+        assert_eq!(zero.as_raw().unwrap(), CAN_ISOTP_FRAME_TXTIME_ZERO);
+        assert_ne!(zero.as_raw().unwrap(), FrameTxTime::Off.as_raw().unwrap());
+        assert_eq!(
+            FrameTxTime::from_raw(zero.as_raw().unwrap()),
+            FrameTxTime::Zero
+        );
+    }
+
+    #[test]
+    fn frame_txtime_raw_round_trip() {
+        for frame_txtime in [
+            FrameTxTime::Off,
+            FrameTxTime::Zero,
+            FrameTxTime::Duration(Duration::from_nanos(1)),
+            FrameTxTime::Duration(Duration::from_micros(50)),
+            FrameTxTime::Duration(Duration::from_nanos(u64::from(u32::MAX) - 1)),
+        ] {
+            let raw = frame_txtime.as_raw().unwrap();
+            assert_eq!(FrameTxTime::from_raw(raw), frame_txtime);
+        }
+        assert!(FrameTxTime::Duration(Duration::from_nanos(u32::MAX.into()))
+            .as_raw()
+            .is_err());
+    }
+
+    fn pipe() -> (std::fs::File, OwnedFd) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe {
+            (
+                std::fs::File::from_raw_fd(fds[0]),
+                OwnedFd::from_raw_fd(fds[1]),
+            )
+        }
+    }
+
+    #[test]
+    fn configure_socket_closes_socket_when_configure_fails() {
+        let (mut rx, tx) = pipe();
+        let error = configure_socket(tx, |fd| {
+            assert!(fd >= 0);
+            Err(Error::from(io::Error::from_raw_os_error(libc::EINVAL)))
+        })
+        .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{error:?}");
+        // end of file once the only write end is closed
+        let mut buffer = [0; 1];
+        assert_eq!(io::Read::read(&mut rx, &mut buffer).unwrap(), 0);
+    }
+
+    #[test]
+    fn configure_socket_returns_configured_socket() {
+        let (mut rx, tx) = pipe();
+        let tx = configure_socket(tx, |_| Ok(())).unwrap();
+        let written = unsafe { libc::write(tx.as_raw_fd(), b"x".as_ptr().cast(), 1) };
+        assert_eq!(written, 1);
+        let mut buffer = [0; 1];
+        assert_eq!(io::Read::read(&mut rx, &mut buffer).unwrap(), 1);
+    }
+
+    #[test]
+    fn open_if_fails_when_mtu_query_fails() {
+        if !isotp_supported() {
+            return;
+        }
+        let builder =
+            IsoTpSocketBuilder::new().canfd_if_supported(TxDataLength::Dl64, TxFlags::empty());
+        open_error(|| open_if(builder, c_int::MAX));
+    }
+
+    #[test]
+    fn open_if_fails_when_bind_fails() {
+        if !isotp_supported() {
+            return;
+        }
+        let error = open_error(|| open_if(IsoTpSocketBuilder::new(), c_int::MAX));
+        assert!(matches!(error, Error::InterfaceDown { .. }), "{error:?}");
     }
 }
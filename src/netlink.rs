@@ -0,0 +1,109 @@
+//! Minimal rtnetlink requests configuring network interfaces.
+
+use crate::Error;
+use libc::{
+    c_int, nlmsghdr, recv, send, socket, AF_NETLINK, AF_UNSPEC, IFF_UP, NETLINK_ROUTE, NLMSG_ERROR,
+    NLM_F_ACK, NLM_F_REQUEST, SOCK_CLOEXEC, SOCK_RAW,
+};
+use std::io;
+use std::mem::size_of;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// Offset of the interface flags in the request, following nlmsghdr and the start of
+/// ifinfomsg
+const IFI_FLAGS: usize = 24;
+
+/// An RTM_NEWLINK/RTM_DELLINK request with its attributes
+pub(crate) struct LinkRequest {
+    message: Vec<u8>,
+}
+
+impl LinkRequest {
+    /// Start a request for the interface `index`
+    pub(crate) fn new(message_type: u16, flags: c_int, index: c_int) -> Self {
+        let mut message = Vec::with_capacity(256);
+        // nlmsghdr, the length is filled in on execute
+        message.extend_from_slice(&0u32.to_ne_bytes());
+        message.extend_from_slice(&message_type.to_ne_bytes());
+        message.extend_from_slice(&((NLM_F_REQUEST | NLM_F_ACK | flags) as u16).to_ne_bytes());
+        message.extend_from_slice(&1u32.to_ne_bytes()); // sequence number
+        message.extend_from_slice(&0u32.to_ne_bytes()); // port id of the kernel
+
+        // ifinfomsg
+        message.extend_from_slice(&[AF_UNSPEC as u8, 0]);
+        message.extend_from_slice(&0u16.to_ne_bytes()); // device type
+        message.extend_from_slice(&index.to_ne_bytes());
+        message.extend_from_slice(&0u32.to_ne_bytes()); // interface flags
+        message.extend_from_slice(&0u32.to_ne_bytes()); // change mask
+        Self { message }
+    }
+
+    /// Bring the interface up or down
+    pub(crate) fn set_up(&mut self, up: bool) {
+        let flags = if up { IFF_UP as u32 } else { 0 };
+        self.message[IFI_FLAGS..IFI_FLAGS + 4].copy_from_slice(&flags.to_ne_bytes());
+        self.message[IFI_FLAGS + 4..IFI_FLAGS + 8].copy_from_slice(&(IFF_UP as u32).to_ne_bytes());
+    }
+
+    pub(crate) fn attribute(&mut self, attribute_type: u16, data: &[u8]) {
+        push_attribute(&mut self.message, attribute_type, data);
+    }
+
+    /// Send the request to the kernel and wait for the acknowledgement
+    pub(crate) fn execute(mut self) -> Result<(), Error> {
+        let len = self.message.len() as u32;
+        self.message[..4].copy_from_slice(&len.to_ne_bytes());
+
+        let fd = unsafe { socket(AF_NETLINK, SOCK_RAW | SOCK_CLOEXEC, NETLINK_ROUTE) };
+        if fd == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let rv = unsafe {
+            send(
+                fd.as_raw_fd(),
+                self.message.as_ptr().cast(),
+                self.message.len(),
+                0,
+            )
+        };
+        if rv == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+
+        let mut buffer = [0u8; 4096];
+        let rv = unsafe { recv(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len(), 0) };
+        if rv == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+        // nlmsghdr followed by the nlmsgerr errno, 0 acknowledges the request
+        let header_len = size_of::<nlmsghdr>();
+        let response = &buffer[..rv as usize];
+        let message_type = response
+            .get(4..6)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]));
+        let errno = response
+            .get(header_len..header_len + 4)
+            .map(|bytes| i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        match (message_type, errno) {
+            (Some(message_type), Some(0)) if c_int::from(message_type) == NLMSG_ERROR => Ok(()),
+            (Some(message_type), Some(errno)) if c_int::from(message_type) == NLMSG_ERROR => {
+                Err(Error::from(io::Error::from_raw_os_error(-errno)))
+            }
+            _ => Err(Error::from(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected rtnetlink response",
+            ))),
+        }
+    }
+}
+
+/// Append a 4 byte aligned rtattr
+pub(crate) fn push_attribute(buffer: &mut Vec<u8>, attribute_type: u16, data: &[u8]) {
+    let len = 4 + data.len();
+    buffer.extend_from_slice(&(len as u16).to_ne_bytes());
+    buffer.extend_from_slice(&attribute_type.to_ne_bytes());
+    buffer.extend_from_slice(data);
+    buffer.resize(buffer.len().next_multiple_of(4), 0);
+}
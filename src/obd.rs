@@ -0,0 +1,368 @@
+//! On-board diagnostics (SAE J1979 / ISO 15031-5) on top of ISO-TP (ISO 15765-4).
+//!
+//! Requests are sent functionally to all emission related ECUs on 0x7DF, responses are
+//! received physically on 0x7E8 - 0x7EF, one socket per responder, so multi-frame
+//! responses get their flow control.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::obd::{ObdClient, PID_ENGINE_SPEED};
+//!
+//! fn main() -> Result<(), socketcan_isotp::obd::ObdError> {
+//!     let mut client = ObdClient::open("vcan0")?;
+//!     for response in client.current_data(PID_ENGINE_SPEED)? {
+//!         match response.value {
+//!             Ok(value) => println!("{:?}: {}", response.ecu, value),
+//!             Err(error) => println!("{:?}: {}", response.ecu, error),
+//!         }
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+mod discovery;
+mod dtc;
+mod freeze_frame;
+mod pid;
+mod test_results;
+mod vehicle_info;
+
+pub use discovery::{discover_ecus, ObdEcu};
+pub use dtc::{aggregate_dtcs, ObdDtc};
+pub use freeze_frame::PID_FREEZE_FRAME_DTC;
+pub use pid::{
+    decode_pid, pid_len, pid_name, MonitorStatus, PidValue, Quantity, SupportedPids, Unit,
+    PID_COOLANT_TEMPERATURE, PID_ENGINE_LOAD, PID_ENGINE_SPEED, PID_INTAKE_AIR_TEMPERATURE,
+    PID_MAF_AIR_FLOW_RATE, PID_MONITOR_STATUS, PID_SUPPORTED_01_20, PID_THROTTLE_POSITION,
+    PID_VEHICLE_SPEED,
+};
+pub use test_results::{monitor_name, summarize_tests, TestResult, MID_SUPPORTED_01_20};
+pub use vehicle_info::{
+    INFO_TYPE_CALIBRATION_ID, INFO_TYPE_CVN, INFO_TYPE_ECU_NAME, INFO_TYPE_SUPPORTED, INFO_TYPE_VIN,
+};
+
+use crate::uds::{Nrc, NEGATIVE_RESPONSE_SID, POSITIVE_RESPONSE_OFFSET};
+use crate::{
+    poll, FlowControlOptions, Id, IsoTpFunctionalSocket, IsoTpOptions, IsoTpSocket,
+    IsoTpSocketBuilder, StandardId,
+};
+use libc::{c_int, pollfd, POLLIN};
+use std::io;
+use std::ops::RangeInclusive;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// Functionally addressed request identifier of 11 bit OBD
+pub const FUNCTIONAL_REQUEST_ID: u16 = 0x7DF;
+
+/// Physically addressed request identifiers of 11 bit OBD
+pub const PHYSICAL_REQUEST_IDS: RangeInclusive<u16> = 0x7E0..=0x7E7;
+
+/// Response identifiers of 11 bit OBD, request identifier + 8
+pub const RESPONSE_IDS: RangeInclusive<u16> = 0x7E8..=0x7EF;
+
+/// Response timeout P2CAN as defined by ISO 15765-4
+pub const P2_CAN: Duration = Duration::from_millis(50);
+
+/// Enhanced response timeout after a pending response as defined by ISO 15765-4
+pub const P2_CAN_EXTENDED: Duration = Duration::from_millis(5000);
+
+/// Errors of OBD requests
+#[derive(Error, Debug)]
+pub enum ObdError {
+    /// Sending the request or receiving a response failed
+    #[error(transparent)]
+    Transport(#[from] crate::Error),
+
+    /// The request parameters are out of range
+    #[error("Invalid request: {0}")]
+    InvalidRequest(&'static str),
+
+    /// The response is malformed
+    #[error("Invalid response: {0}")]
+    InvalidResponse(&'static str),
+}
+
+impl From<io::Error> for ObdError {
+    fn from(source: io::Error) -> Self {
+        ObdError::Transport(crate::Error::from(source))
+    }
+}
+
+/// A response of one ECU
+///
+/// The requests of [`ObdClient`] return the result of each ECU, so a malformed response of
+/// one ECU doesn't discard the responses of the others.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObdResponse<V> {
+    /// CAN id the ECU responded on
+    pub ecu: Id,
+    /// The decoded response
+    pub value: V,
+}
+
+/// The responses of all ECUs to a request, see [`ObdResponse`]
+pub type ObdResponses<V> = Vec<ObdResponse<Result<V, ObdError>>>;
+
+impl<V> ObdResponse<V> {
+    /// Map the value, keeping the ECU
+    pub fn map<U>(self, f: impl FnOnce(V) -> U) -> ObdResponse<U> {
+        ObdResponse {
+            ecu: self.ecu,
+            value: f(self.value),
+        }
+    }
+}
+
+impl<V> ObdResponse<Result<V, ObdError>> {
+    /// The response of the ECU if it was received and decoded, its error otherwise
+    pub fn transpose(self) -> Result<ObdResponse<V>, ObdError> {
+        Ok(ObdResponse {
+            ecu: self.ecu,
+            value: self.value?,
+        })
+    }
+
+    /// Map the value of a successful response, keeping the ECU
+    fn and_then<U>(
+        self,
+        f: impl FnOnce(V) -> Result<U, ObdError>,
+    ) -> ObdResponse<Result<U, ObdError>> {
+        self.map(|value| value.and_then(f))
+    }
+}
+
+/// An OBD client sending functionally addressed requests and collecting the responses of
+/// all ECUs
+pub struct ObdClient {
+    request: IsoTpFunctionalSocket,
+    responders: Vec<(Id, IsoTpSocket)>,
+    timeout: Duration,
+}
+
+impl ObdClient {
+    /// Open the 11 bit OBD identifiers on `ifname`, using the ISO 15765-4 socket options
+    pub fn open(ifname: &str) -> Result<Self, crate::Error> {
+        let builder = || {
+            IsoTpSocketBuilder::new()
+                .isotp_options(Some(IsoTpOptions::obd_defaults()))
+                .rx_flow_control_options(Some(FlowControlOptions::obd_defaults()))
+        };
+        let request = builder().open_functional(ifname, standard_id(FUNCTIONAL_REQUEST_ID))?;
+        let responders = PHYSICAL_REQUEST_IDS
+            .zip(RESPONSE_IDS)
+            .map(|(tx_id, rx_id)| builder().open(ifname, standard_id(rx_id), standard_id(tx_id)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(request, responders)
+    }
+
+    /// Send requests on `request` and collect the responses of the `responders`, e.g. to
+    /// use 29 bit identifiers
+    pub fn new(
+        request: IsoTpFunctionalSocket,
+        responders: Vec<IsoTpSocket>,
+    ) -> Result<Self, crate::Error> {
+        let responders = responders
+            .into_iter()
+            .map(|socket| {
+                let ecu = socket.local_addr()?.get_rx_id().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "invalid receive identifier")
+                })?;
+                Ok((ecu, socket))
+            })
+            .collect::<Result<Vec<_>, crate::Error>>()?;
+        Ok(Self {
+            request,
+            responders,
+            timeout: P2_CAN,
+        })
+    }
+
+    /// Set the time to wait for responses, by default [`P2_CAN`]
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Get the time to wait for responses
+    pub fn get_timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Send a request and collect the positive responses until the timeout expires.
+    ///
+    /// Returns the response parameters following the positive response service identifier
+    /// of each responding ECU, or the error receiving its response. ECUs not supporting the
+    /// request don't respond or respond negatively, both are left out. Pending responses
+    /// extend the wait to [`P2_CAN_EXTENDED`].
+    pub fn request(&mut self, request: &[u8]) -> Result<ObdResponses<Vec<u8>>, ObdError> {
+        let Some(&service) = request.first() else {
+            return Err(ObdError::InvalidRequest("empty request"));
+        };
+        // discard late responses and errors of previous requests
+        for (_, socket) in &mut self.responders {
+            while !wait_readable(&[socket.as_raw_fd()], Duration::ZERO)?.is_empty() {
+                if socket.read().is_err() {
+                    break;
+                }
+            }
+        }
+        self.request.write(request)?;
+
+        let mut responses = Vec::new();
+        let mut pending = vec![false; self.responders.len()];
+        let mut deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let fds = self
+                .responders
+                .iter()
+                .enumerate()
+                .filter(|(index, (ecu, _))| {
+                    pending[*index] || !responses.iter().any(|r: &ObdResponse<_>| r.ecu == *ecu)
+                })
+                .map(|(index, (_, socket))| (index, socket.as_raw_fd()))
+                .collect::<Vec<_>>();
+            if fds.is_empty() {
+                break;
+            }
+            let raw_fds = fds.iter().map(|(_, fd)| *fd).collect::<Vec<_>>();
+            let readable = wait_readable(&raw_fds, timeout)?;
+            if readable.is_empty() {
+                break;
+            }
+            for index in readable.into_iter().map(|ready| fds[ready].0) {
+                let (ecu, socket) = &mut self.responders[index];
+                let response = match socket.read() {
+                    Ok(response) => response,
+                    // e.g. a multi-frame response timed out, the other ECUs are still heard
+                    Err(error) => {
+                        pending[index] = false;
+                        responses.push(ObdResponse {
+                            ecu: *ecu,
+                            value: Err(error.into()),
+                        });
+                        continue;
+                    }
+                };
+                match *response {
+                    [NEGATIVE_RESPONSE_SID, sid, nrc, ..]
+                        if sid == service
+                            && Nrc::from(nrc) == Nrc::RequestCorrectlyReceivedResponsePending =>
+                    {
+                        pending[index] = true;
+                        deadline = deadline.max(Instant::now() + P2_CAN_EXTENDED);
+                    }
+                    [sid, ref parameters @ ..]
+                        if sid == service.wrapping_add(POSITIVE_RESPONSE_OFFSET) =>
+                    {
+                        pending[index] = false;
+                        responses.push(ObdResponse {
+                            ecu: *ecu,
+                            value: Ok(parameters.to_vec()),
+                        });
+                    }
+                    // negative or unrelated response
+                    _ => pending[index] = false,
+                }
+            }
+        }
+        Ok(responses)
+    }
+
+    /// Mode 01, request the current value of `pid` from all ECUs
+    pub fn current_data(&mut self, pid: u8) -> Result<ObdResponses<PidValue>, ObdError> {
+        Ok(self
+            .request(&[0x01, pid])?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|value| match value.split_first() {
+                    Some((&echo, data)) if echo == pid => decode_pid(pid, data),
+                    _ => Err(ObdError::InvalidResponse("response does not echo the PID")),
+                })
+            })
+            .collect())
+    }
+
+    /// Mode 01, request the supported PIDs of all ECUs, following the bitmaps of PIDs
+    /// 0x00, 0x20, ... as long as any ECU reports the next range
+    ///
+    /// Malformed responses are left out.
+    pub fn supported_pids(&mut self) -> Result<Vec<ObdResponse<SupportedPids>>, ObdError> {
+        let mut supported: Vec<ObdResponse<SupportedPids>> = Vec::new();
+        let mut base = PID_SUPPORTED_01_20;
+        loop {
+            for response in self.current_data(base)? {
+                let Ok(PidValue::SupportedPids(pids)) = response.value else {
+                    continue;
+                };
+                match supported.iter_mut().find(|known| known.ecu == response.ecu) {
+                    Some(known) => known.value.merge(&pids),
+                    None => supported.push(ObdResponse {
+                        ecu: response.ecu,
+                        value: pids,
+                    }),
+                }
+            }
+            if !supported.iter().any(|ecu| ecu.value.has_next_range(base)) {
+                return Ok(supported);
+            }
+            base += 0x20;
+        }
+    }
+
+    /// Request the bitmaps `[base, a, b, c, d]` of the supported PIDs, OBDMIDs or
+    /// InfoTypes of `mode` for `base` 0x00, 0x20, ... as long as any ECU reports the next
+    /// range, leaving out malformed responses
+    fn supported_bitmaps(&mut self, mode: u8) -> Result<Vec<ObdResponse<SupportedPids>>, ObdError> {
+        let mut supported: Vec<ObdResponse<SupportedPids>> = Vec::new();
+        let mut base = 0x00;
+        loop {
+            for response in self.request(&[mode, base])? {
+                let bitmap = match response.value.as_deref() {
+                    Ok(&[echo, a, b, c, d, ..]) if echo == base => u32::from_be_bytes([a, b, c, d]),
+                    _ => continue,
+                };
+                match supported.iter_mut().find(|known| known.ecu == response.ecu) {
+                    Some(known) => known.value.insert_bitmap(base, bitmap),
+                    None => {
+                        let mut supported_ids = SupportedPids::new();
+                        supported_ids.insert_bitmap(base, bitmap);
+                        supported.push(ObdResponse {
+                            ecu: response.ecu,
+                            value: supported_ids,
+                        });
+                    }
+                }
+            }
+            if !supported.iter().any(|ecu| ecu.value.has_next_range(base)) {
+                return Ok(supported);
+            }
+            base += 0x20;
+        }
+    }
+}
+
+fn standard_id(id: u16) -> StandardId {
+    // the OBD identifiers are valid 11 bit identifiers
+    StandardId::new(id).unwrap_or(StandardId::ZERO)
+}
+
+/// Wait up to `timeout` for any of `fds` to become readable or report an error, returning
+/// their indices
+fn wait_readable(fds: &[c_int], timeout: Duration) -> io::Result<Vec<usize>> {
+    let mut pollfds = fds
+        .iter()
+        .map(|fd| pollfd {
+            fd: *fd,
+            events: POLLIN,
+            revents: 0,
+        })
+        .collect::<Vec<_>>();
+    poll::poll(&mut pollfds, Some(timeout))?;
+    Ok(pollfds
+        .iter()
+        .enumerate()
+        .filter(|(_, pollfd)| pollfd.revents != 0)
+        .map(|(index, _)| index)
+        .collect())
+}
@@ -0,0 +1,62 @@
+use super::{ObdClient, ObdError, SupportedPids};
+use crate::Id;
+
+/// An emission related ECU answering Mode 01 PID 0x00, see [`discover_ecus`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ObdEcu {
+    /// CAN id the ECU receives physically addressed requests on
+    pub request_id: Id,
+    /// CAN id the ECU sends responses on
+    pub response_id: Id,
+    /// Mode 01 PIDs supported by the ECU
+    pub supported_pids: SupportedPids,
+}
+
+/// Discover the emission related ECUs on `ifname`
+///
+/// Sends Mode 01 PID 0x00 functionally to 0x7DF, listening for responses on
+/// 0x7E8 - 0x7EF, and follows the supported PID bitmaps of all responders.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::obd::discover_ecus;
+///
+/// fn main() -> Result<(), socketcan_isotp::obd::ObdError> {
+///     for ecu in discover_ecus("vcan0")? {
+///         println!(
+///             "{:?} -> {:?} supports {:02X?}",
+///             ecu.request_id,
+///             ecu.response_id,
+///             ecu.supported_pids.iter().collect::<Vec<_>>()
+///         );
+///     }
+///     Ok(())
+/// }
+/// ```
+pub fn discover_ecus(ifname: &str) -> Result<Vec<ObdEcu>, ObdError> {
+    ObdClient::open(ifname)?.discover()
+}
+
+impl ObdClient {
+    /// Discover the ECUs answering Mode 01 PID 0x00 on the responder sockets
+    pub fn discover(&mut self) -> Result<Vec<ObdEcu>, ObdError> {
+        let supported = self.supported_pids()?;
+        supported
+            .into_iter()
+            .map(|response| {
+                let request_id = self
+                    .responders
+                    .iter()
+                    .find(|(ecu, _)| *ecu == response.ecu)
+                    .and_then(|(_, socket)| socket.local_addr().ok()?.get_tx_id())
+                    .ok_or(ObdError::InvalidResponse(
+                        "response from an unknown responder",
+                    ))?;
+                Ok(ObdEcu {
+                    request_id,
+                    response_id: response.ecu,
+                    supported_pids: response.value,
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,188 @@
+use super::{ObdClient, ObdError, ObdResponse, ObdResponses};
+use crate::{Error, Id};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A 2 byte emission related diagnostic trouble code
+///
+/// Displayed and parsed in SAE J2012 notation, e.g. `P0123`.
+///
+/// ```rust
+/// use socketcan_isotp::obd::ObdDtc;
+///
+/// let dtc = ObdDtc::from_bytes([0x01, 0x23]);
+/// assert_eq!(dtc.to_string(), "P0123");
+/// assert_eq!("U0100".parse::<ObdDtc>().unwrap().as_u16(), 0xC100);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ObdDtc(u16);
+
+impl ObdDtc {
+    /// A DTC from its 16 bit value
+    pub fn new(dtc: u16) -> Self {
+        Self(dtc)
+    }
+
+    /// Decode a DTC from 2 big endian bytes
+    pub fn from_bytes(bytes: [u8; 2]) -> Self {
+        Self(u16::from_be_bytes(bytes))
+    }
+
+    /// The DTC as 2 big endian bytes
+    pub fn to_bytes(self) -> [u8; 2] {
+        self.0.to_be_bytes()
+    }
+
+    /// The 16 bit value
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+}
+
+impl fmt::Display for ObdDtc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let system = ['P', 'C', 'B', 'U'][usize::from(self.0 >> 14)];
+        write!(f, "{}{:04X}", system, self.0 & 0x3FFF)
+    }
+}
+
+impl FromStr for ObdDtc {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidInput("DTC is not in SAE J2012 notation, e.g. P0123");
+        let mut chars = s.chars();
+        let system = match chars.next().map(|system| system.to_ascii_uppercase()) {
+            Some('P') => 0,
+            Some('C') => 1,
+            Some('B') => 2,
+            Some('U') => 3,
+            _ => return Err(invalid()),
+        };
+        let code = chars.as_str();
+        if code.len() != 4 || !code.starts_with(['0', '1', '2', '3']) {
+            return Err(invalid());
+        }
+        let code = u16::from_str_radix(code, 16).map_err(|_| invalid())?;
+        Ok(Self(system << 14 | code))
+    }
+}
+
+/// Group the DTCs reported by several ECUs by code, listing the reporting ECUs of each DTC
+///
+/// Malformed responses are left out.
+pub fn aggregate_dtcs(
+    responses: &[ObdResponse<Result<Vec<ObdDtc>, ObdError>>],
+) -> BTreeMap<ObdDtc, Vec<Id>> {
+    let mut dtcs: BTreeMap<ObdDtc, Vec<Id>> = BTreeMap::new();
+    for response in responses {
+        let Ok(response_dtcs) = &response.value else {
+            continue;
+        };
+        for dtc in response_dtcs {
+            let ecus = dtcs.entry(*dtc).or_default();
+            if !ecus.contains(&response.ecu) {
+                ecus.push(response.ecu);
+            }
+        }
+    }
+    dtcs
+}
+
+/// Decode the DTCs of a Mode 03, 07 or 0A response `[count, dtc_high, dtc_low, ...]`
+///
+/// `P0000` is used as padding by some ECUs and skipped.
+fn parse_dtcs(response: &[u8]) -> Result<Vec<ObdDtc>, ObdError> {
+    let Some((&count, dtcs)) = response.split_first() else {
+        return Err(ObdError::InvalidResponse(
+            "response lacks the number of DTCs",
+        ));
+    };
+    let dtcs = dtcs
+        .get(..2 * usize::from(count))
+        .ok_or(ObdError::InvalidResponse(
+            "response is shorter than the number of DTCs",
+        ))?;
+    Ok(dtcs
+        .chunks_exact(2)
+        .map(|dtc| ObdDtc::from_bytes([dtc[0], dtc[1]]))
+        .filter(|dtc| dtc.as_u16() != 0)
+        .collect())
+}
+
+impl ObdClient {
+    /// Mode 03, request the confirmed emission related DTCs of all ECUs
+    pub fn stored_dtcs(&mut self) -> Result<ObdResponses<Vec<ObdDtc>>, ObdError> {
+        self.request_dtcs(0x03)
+    }
+
+    /// Mode 07, request the pending emission related DTCs detected during the current or
+    /// last driving cycle of all ECUs
+    pub fn pending_dtcs(&mut self) -> Result<ObdResponses<Vec<ObdDtc>>, ObdError> {
+        self.request_dtcs(0x07)
+    }
+
+    /// Mode 0A, request the permanent emission related DTCs of all ECUs
+    ///
+    /// Permanent DTCs are not erased by Mode 04, only by the ECU after the monitor passed
+    /// again.
+    pub fn permanent_dtcs(&mut self) -> Result<ObdResponses<Vec<ObdDtc>>, ObdError> {
+        self.request_dtcs(0x0A)
+    }
+
+    /// Mode 04, clear the emission related diagnostic information of all ECUs, returning
+    /// the ECUs confirming the request or the error receiving their response
+    ///
+    /// Stored and pending DTCs are cleared, permanent DTCs remain.
+    ///
+    /// ECUs reject the request with a negative response if the engine is running.
+    pub fn clear_dtcs(&mut self) -> Result<ObdResponses<()>, ObdError> {
+        Ok(self
+            .request(&[0x04])?
+            .into_iter()
+            .map(|response| response.map(|value| value.map(drop)))
+            .collect())
+    }
+
+    fn request_dtcs(&mut self, mode: u8) -> Result<ObdResponses<Vec<ObdDtc>>, ObdError> {
+        Ok(self
+            .request(&[mode])?
+            .into_iter()
+            .map(|response| response.and_then(|value| parse_dtcs(&value)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StandardId;
+
+    fn ecu(id: u16) -> Id {
+        Id::Standard(StandardId::new(id).unwrap())
+    }
+
+    #[test]
+    fn aggregate_dtcs_skips_malformed_responses() {
+        let responses = [
+            ObdResponse {
+                ecu: ecu(0x7E8),
+                value: parse_dtcs(&[0x02, 0x01, 0x23, 0xC1, 0x00]),
+            },
+            ObdResponse {
+                ecu: ecu(0x7E9),
+                value: parse_dtcs(&[0x02, 0x01, 0x23]),
+            },
+            ObdResponse {
+                ecu: ecu(0x7EA),
+                value: parse_dtcs(&[0x01, 0x01, 0x23]),
+            },
+        ];
+        assert!(responses[1].value.is_err());
+        let dtcs = aggregate_dtcs(&responses);
+        assert_eq!(dtcs[&ObdDtc::new(0x0123)], [ecu(0x7E8), ecu(0x7EA)]);
+        assert_eq!(dtcs[&ObdDtc::new(0xC100)], [ecu(0x7E8)]);
+    }
+}
@@ -0,0 +1,62 @@
+use super::{decode_pid, ObdClient, ObdDtc, ObdError, ObdResponses, PidValue};
+
+/// Mode 02 PID 0x02, the DTC that caused the freeze frame to be stored
+pub const PID_FREEZE_FRAME_DTC: u8 = 0x02;
+
+impl ObdClient {
+    /// Mode 02, request the value of `pid` captured in freeze frame `frame` from all ECUs
+    ///
+    /// PIDs are decoded like their Mode 01 counterparts. Frame 0 is the freeze frame
+    /// mandated by SAE J1979, further frames are manufacturer specific.
+    pub fn freeze_frame_data(
+        &mut self,
+        pid: u8,
+        frame: u8,
+    ) -> Result<ObdResponses<PidValue>, ObdError> {
+        Ok(self
+            .freeze_frame(pid, frame)?
+            .into_iter()
+            .map(|response| response.and_then(|value| decode_pid(pid, &value)))
+            .collect())
+    }
+
+    /// Mode 02 PID 0x02, request the DTC that caused freeze frame `frame` to be stored,
+    /// `None` if an ECU has no freeze frame stored
+    pub fn freeze_frame_dtc(
+        &mut self,
+        frame: u8,
+    ) -> Result<ObdResponses<Option<ObdDtc>>, ObdError> {
+        Ok(self
+            .freeze_frame(PID_FREEZE_FRAME_DTC, frame)?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|value| match *value {
+                    [high, low, ..] => {
+                        let dtc = ObdDtc::from_bytes([high, low]);
+                        Ok((dtc.as_u16() != 0).then_some(dtc))
+                    }
+                    _ => Err(ObdError::InvalidResponse("response lacks the DTC")),
+                })
+            })
+            .collect())
+    }
+
+    /// Request `pid` of `frame`, returning the data following the echoed PID and frame
+    fn freeze_frame(&mut self, pid: u8, frame: u8) -> Result<ObdResponses<Vec<u8>>, ObdError> {
+        Ok(self
+            .request(&[0x02, pid, frame])?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|mut value| match *value {
+                    [echo_pid, echo_frame, ..] if echo_pid == pid && echo_frame == frame => {
+                        value.drain(..2);
+                        Ok(value)
+                    }
+                    _ => Err(ObdError::InvalidResponse(
+                        "response does not echo the PID and frame number",
+                    )),
+                })
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,415 @@
+use super::ObdError;
+use std::fmt;
+
+/// PID 0x00, PIDs 0x01 - 0x20 supported
+pub const PID_SUPPORTED_01_20: u8 = 0x00;
+/// PID 0x01, monitor status since DTCs cleared
+pub const PID_MONITOR_STATUS: u8 = 0x01;
+/// PID 0x04, calculated engine load
+pub const PID_ENGINE_LOAD: u8 = 0x04;
+/// PID 0x05, engine coolant temperature
+pub const PID_COOLANT_TEMPERATURE: u8 = 0x05;
+/// PID 0x0C, engine speed
+pub const PID_ENGINE_SPEED: u8 = 0x0C;
+/// PID 0x0D, vehicle speed
+pub const PID_VEHICLE_SPEED: u8 = 0x0D;
+/// PID 0x0F, intake air temperature
+pub const PID_INTAKE_AIR_TEMPERATURE: u8 = 0x0F;
+/// PID 0x10, mass air flow sensor air flow rate
+pub const PID_MAF_AIR_FLOW_RATE: u8 = 0x10;
+/// PID 0x11, throttle position
+pub const PID_THROTTLE_POSITION: u8 = 0x11;
+
+/// Physical unit of a [`Quantity`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unit {
+    /// %
+    Percent,
+    /// °C
+    Celsius,
+    /// rpm
+    Rpm,
+    /// km/h
+    KilometersPerHour,
+    /// g/s
+    GramsPerSecond,
+    /// kPa
+    Kilopascal,
+    /// Pa
+    Pascal,
+    /// °, e.g. timing advance relative to top dead center
+    Degrees,
+    /// V
+    Volts,
+    /// s
+    Seconds,
+    /// min
+    Minutes,
+    /// km
+    Kilometers,
+    /// L/h
+    LitersPerHour,
+    /// A
+    Amperes,
+    /// Ω
+    Ohms,
+    /// Hz
+    Hertz,
+    /// g
+    Grams,
+    /// L
+    Liters,
+    /// ppm
+    PartsPerMillion,
+    /// dimensionless ratio, e.g. the commanded equivalence ratio λ
+    Ratio,
+    /// number of events
+    Count,
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Unit::Percent => "%",
+            Unit::Celsius => "°C",
+            Unit::Rpm => "rpm",
+            Unit::KilometersPerHour => "km/h",
+            Unit::GramsPerSecond => "g/s",
+            Unit::Kilopascal => "kPa",
+            Unit::Pascal => "Pa",
+            Unit::Degrees => "°",
+            Unit::Volts => "V",
+            Unit::Seconds => "s",
+            Unit::Minutes => "min",
+            Unit::Kilometers => "km",
+            Unit::LitersPerHour => "L/h",
+            Unit::Amperes => "A",
+            Unit::Ohms => "Ω",
+            Unit::Hertz => "Hz",
+            Unit::Grams => "g",
+            Unit::Liters => "L",
+            Unit::PartsPerMillion => "ppm",
+            Unit::Ratio | Unit::Count => "",
+        })
+    }
+}
+
+/// A scaled value with its unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quantity {
+    /// The scaled value
+    pub value: f64,
+    /// The unit of `value`
+    pub unit: Unit,
+}
+
+impl Quantity {
+    /// A value in `unit`
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            Unit::Ratio | Unit::Count => write!(f, "{}", self.value),
+            unit => write!(f, "{} {}", self.value, unit),
+        }
+    }
+}
+
+/// Set of PIDs a server supports, decoded from the bitmaps of PIDs 0x00, 0x20, ... 0xE0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SupportedPids([u32; 8]);
+
+impl SupportedPids {
+    /// An empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the PIDs `base + 1` - `base + 32` set in the bitmap reported for `base`
+    pub fn insert_bitmap(&mut self, base: u8, bitmap: u32) {
+        self.0[usize::from(base / 0x20)] |= bitmap;
+    }
+
+    /// Add the PIDs of `other`
+    pub fn merge(&mut self, other: &SupportedPids) {
+        for (bitmap, other) in self.0.iter_mut().zip(other.0) {
+            *bitmap |= other;
+        }
+    }
+
+    /// Whether `pid` is supported
+    pub fn contains(&self, pid: u8) -> bool {
+        match pid.checked_sub(1) {
+            Some(index) => self.0[usize::from(index / 0x20)] & (0x8000_0000 >> (index % 0x20)) != 0,
+            None => false,
+        }
+    }
+
+    /// Whether the server reports support for the next bitmap following `base`
+    pub fn has_next_range(&self, base: u8) -> bool {
+        base.checked_add(0x20)
+            .is_some_and(|next| self.contains(next))
+    }
+
+    /// The supported PIDs in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..=u8::MAX).filter(|pid| self.contains(*pid))
+    }
+}
+
+/// Decoded monitor status of PID 0x01
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitorStatus {
+    /// The malfunction indicator lamp is on
+    pub mil: bool,
+    /// Number of emission related DTCs
+    pub dtc_count: u8,
+    /// Availability and completeness bytes B, C and D of the monitors
+    pub monitors: [u8; 3],
+}
+
+/// A decoded Mode 01 / Mode 02 PID
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PidValue {
+    /// A single scaled value
+    Quantity(Quantity),
+    /// Several scaled values, e.g. voltage and fuel trim of an oxygen sensor
+    Quantities(Vec<Quantity>),
+    /// Bitmap of supported PIDs
+    SupportedPids(SupportedPids),
+    /// Monitor status of PID 0x01
+    MonitorStatus(MonitorStatus),
+    /// PIDs without decoder
+    Raw(Vec<u8>),
+}
+
+impl fmt::Display for PidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PidValue::Quantity(quantity) => write!(f, "{}", quantity),
+            PidValue::Quantities(quantities) => {
+                for (index, quantity) in quantities.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", quantity)?;
+                }
+                Ok(())
+            }
+            PidValue::SupportedPids(pids) => {
+                write!(f, "{:02X?}", pids.iter().collect::<Vec<_>>())
+            }
+            PidValue::MonitorStatus(status) => write!(
+                f,
+                "MIL {}, {} DTCs",
+                if status.mil { "on" } else { "off" },
+                status.dtc_count
+            ),
+            PidValue::Raw(data) => write!(f, "{:02X?}", data),
+        }
+    }
+}
+
+type Decoder = fn(&[u8]) -> PidValue;
+
+struct PidDefinition {
+    name: &'static str,
+    len: usize,
+    decode: Decoder,
+}
+
+fn a(data: &[u8]) -> f64 {
+    f64::from(data[0])
+}
+
+fn ab(data: &[u8]) -> f64 {
+    f64::from(u16::from_be_bytes([data[0], data[1]]))
+}
+
+fn quantity(value: f64, unit: Unit) -> PidValue {
+    PidValue::Quantity(Quantity::new(value, unit))
+}
+
+fn percent(data: &[u8]) -> PidValue {
+    quantity(a(data) * 100.0 / 255.0, Unit::Percent)
+}
+
+fn temperature(data: &[u8]) -> PidValue {
+    quantity(a(data) - 40.0, Unit::Celsius)
+}
+
+fn fuel_trim(data: &[u8]) -> PidValue {
+    quantity(a(data) * 100.0 / 128.0 - 100.0, Unit::Percent)
+}
+
+fn oxygen_sensor(data: &[u8]) -> PidValue {
+    PidValue::Quantities(vec![
+        Quantity::new(a(data) / 200.0, Unit::Volts),
+        Quantity::new(f64::from(data[1]) * 100.0 / 128.0 - 100.0, Unit::Percent),
+    ])
+}
+
+fn supported_pids(base: u8) -> impl Fn(&[u8]) -> PidValue {
+    move |data| {
+        let mut pids = SupportedPids::new();
+        pids.insert_bitmap(
+            base,
+            u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+        );
+        PidValue::SupportedPids(pids)
+    }
+}
+
+fn definition(pid: u8) -> Option<PidDefinition> {
+    let (name, len, decode): (&'static str, usize, Decoder) = match pid {
+        0x00 => ("PIDs supported [01 - 20]", 4, |d| supported_pids(0x00)(d)),
+        0x20 => ("PIDs supported [21 - 40]", 4, |d| supported_pids(0x20)(d)),
+        0x40 => ("PIDs supported [41 - 60]", 4, |d| supported_pids(0x40)(d)),
+        0x60 => ("PIDs supported [61 - 80]", 4, |d| supported_pids(0x60)(d)),
+        0x80 => ("PIDs supported [81 - A0]", 4, |d| supported_pids(0x80)(d)),
+        0xA0 => ("PIDs supported [A1 - C0]", 4, |d| supported_pids(0xA0)(d)),
+        0xC0 => ("PIDs supported [C1 - E0]", 4, |d| supported_pids(0xC0)(d)),
+        0xE0 => ("PIDs supported [E1 - FF]", 4, |d| supported_pids(0xE0)(d)),
+        0x01 => ("Monitor status since DTCs cleared", 4, |d| {
+            PidValue::MonitorStatus(MonitorStatus {
+                mil: d[0] & 0x80 != 0,
+                dtc_count: d[0] & 0x7F,
+                monitors: [d[1], d[2], d[3]],
+            })
+        }),
+        0x04 => ("Calculated engine load", 1, percent),
+        0x05 => ("Engine coolant temperature", 1, temperature),
+        0x06 => ("Short term fuel trim - bank 1", 1, fuel_trim),
+        0x07 => ("Long term fuel trim - bank 1", 1, fuel_trim),
+        0x08 => ("Short term fuel trim - bank 2", 1, fuel_trim),
+        0x09 => ("Long term fuel trim - bank 2", 1, fuel_trim),
+        0x0A => ("Fuel pressure", 1, |d| {
+            quantity(3.0 * a(d), Unit::Kilopascal)
+        }),
+        0x0B => ("Intake manifold absolute pressure", 1, |d| {
+            quantity(a(d), Unit::Kilopascal)
+        }),
+        0x0C => ("Engine speed", 2, |d| quantity(ab(d) / 4.0, Unit::Rpm)),
+        0x0D => ("Vehicle speed", 1, |d| {
+            quantity(a(d), Unit::KilometersPerHour)
+        }),
+        0x0E => ("Timing advance", 1, |d| {
+            quantity(a(d) / 2.0 - 64.0, Unit::Degrees)
+        }),
+        0x0F => ("Intake air temperature", 1, temperature),
+        0x10 => ("Mass air flow sensor air flow rate", 2, |d| {
+            quantity(ab(d) / 100.0, Unit::GramsPerSecond)
+        }),
+        0x11 => ("Throttle position", 1, percent),
+        0x14 => ("Oxygen sensor 1", 2, oxygen_sensor),
+        0x15 => ("Oxygen sensor 2", 2, oxygen_sensor),
+        0x16 => ("Oxygen sensor 3", 2, oxygen_sensor),
+        0x17 => ("Oxygen sensor 4", 2, oxygen_sensor),
+        0x18 => ("Oxygen sensor 5", 2, oxygen_sensor),
+        0x19 => ("Oxygen sensor 6", 2, oxygen_sensor),
+        0x1A => ("Oxygen sensor 7", 2, oxygen_sensor),
+        0x1B => ("Oxygen sensor 8", 2, oxygen_sensor),
+        0x1F => ("Run time since engine start", 2, |d| {
+            quantity(ab(d), Unit::Seconds)
+        }),
+        0x21 => ("Distance traveled with MIL on", 2, |d| {
+            quantity(ab(d), Unit::Kilometers)
+        }),
+        0x22 => ("Fuel rail pressure relative to manifold vacuum", 2, |d| {
+            quantity(ab(d) * 0.079, Unit::Kilopascal)
+        }),
+        0x23 => ("Fuel rail gauge pressure", 2, |d| {
+            quantity(ab(d) * 10.0, Unit::Kilopascal)
+        }),
+        0x2C => ("Commanded EGR", 1, percent),
+        0x2D => ("EGR error", 1, fuel_trim),
+        0x2E => ("Commanded evaporative purge", 1, percent),
+        0x2F => ("Fuel tank level input", 1, percent),
+        0x30 => ("Warm-ups since codes cleared", 1, |d| {
+            quantity(a(d), Unit::Count)
+        }),
+        0x31 => ("Distance traveled since codes cleared", 2, |d| {
+            quantity(ab(d), Unit::Kilometers)
+        }),
+        0x32 => ("Evap. system vapor pressure", 2, |d| {
+            let raw = i16::from_be_bytes([d[0], d[1]]);
+            quantity(f64::from(raw) / 4.0, Unit::Pascal)
+        }),
+        0x33 => ("Absolute barometric pressure", 1, |d| {
+            quantity(a(d), Unit::Kilopascal)
+        }),
+        0x42 => ("Control module voltage", 2, |d| {
+            quantity(ab(d) / 1000.0, Unit::Volts)
+        }),
+        0x43 => ("Absolute load value", 2, |d| {
+            quantity(ab(d) * 100.0 / 255.0, Unit::Percent)
+        }),
+        0x44 => ("Commanded air-fuel equivalence ratio", 2, |d| {
+            quantity(ab(d) * 2.0 / 65536.0, Unit::Ratio)
+        }),
+        0x45 => ("Relative throttle position", 1, percent),
+        0x46 => ("Ambient air temperature", 1, temperature),
+        0x47 => ("Absolute throttle position B", 1, percent),
+        0x48 => ("Absolute throttle position C", 1, percent),
+        0x49 => ("Accelerator pedal position D", 1, percent),
+        0x4A => ("Accelerator pedal position E", 1, percent),
+        0x4B => ("Accelerator pedal position F", 1, percent),
+        0x4C => ("Commanded throttle actuator", 1, percent),
+        0x4D => ("Time run with MIL on", 2, |d| {
+            quantity(ab(d), Unit::Minutes)
+        }),
+        0x4E => ("Time since trouble codes cleared", 2, |d| {
+            quantity(ab(d), Unit::Minutes)
+        }),
+        0x52 => ("Ethanol fuel", 1, percent),
+        0x5A => ("Relative accelerator pedal position", 1, percent),
+        0x5B => ("Hybrid battery pack remaining life", 1, percent),
+        0x5C => ("Engine oil temperature", 1, temperature),
+        0x5E => ("Engine fuel rate", 2, |d| {
+            quantity(ab(d) / 20.0, Unit::LitersPerHour)
+        }),
+        _ => return None,
+    };
+    Some(PidDefinition { name, len, decode })
+}
+
+/// Name of a standard Mode 01 PID, `None` for PIDs without decoder
+pub fn pid_name(pid: u8) -> Option<&'static str> {
+    definition(pid).map(|definition| definition.name)
+}
+
+/// Data length of a standard Mode 01 PID, `None` for PIDs without decoder
+pub fn pid_len(pid: u8) -> Option<usize> {
+    definition(pid).map(|definition| definition.len)
+}
+
+/// Decode the data of a Mode 01 / Mode 02 PID as defined by SAE J1979
+///
+/// PIDs without decoder are returned as [`PidValue::Raw`], trailing bytes beyond the PID
+/// length are ignored.
+///
+/// ```rust
+/// use socketcan_isotp::obd::{decode_pid, PidValue, Quantity, Unit, PID_ENGINE_SPEED};
+///
+/// let rpm = decode_pid(PID_ENGINE_SPEED, &[0x1A, 0xF8]).unwrap();
+/// assert_eq!(rpm, PidValue::Quantity(Quantity::new(1726.0, Unit::Rpm)));
+/// assert_eq!(rpm.to_string(), "1726 rpm");
+/// ```
+pub fn decode_pid(pid: u8, data: &[u8]) -> Result<PidValue, ObdError> {
+    let Some(definition) = definition(pid) else {
+        return Ok(PidValue::Raw(data.to_vec()));
+    };
+    let data = data.get(..definition.len).ok_or(ObdError::InvalidResponse(
+        "PID data is shorter than the PID length",
+    ))?;
+    Ok((definition.decode)(data))
+}
@@ -0,0 +1,388 @@
+use super::{ObdClient, ObdError, ObdResponse, ObdResponses, Quantity, SupportedPids, Unit};
+use std::collections::BTreeMap;
+
+/// Mode 06 OBDMID 0x00, OBDMIDs 0x01 - 0x20 supported
+pub const MID_SUPPORTED_01_20: u8 = 0x00;
+
+/// Result of an on-board test reported by Mode 06
+///
+/// Values, limits and unit are scaled according to the unit and scaling identifier.
+/// Unknown identifiers are reported unscaled as [`Unit::Count`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TestResult {
+    /// On-board diagnostic monitor identifier (OBDMID)
+    pub mid: u8,
+    /// Test identifier (TID), 0x01 - 0x7F standardized, 0x80 - 0xFE manufacturer specific
+    pub tid: u8,
+    /// Unit and scaling identifier (UASID)
+    pub uas_id: u8,
+    /// Test value
+    pub value: Quantity,
+    /// Minimum test limit
+    pub min: Quantity,
+    /// Maximum test limit
+    pub max: Quantity,
+}
+
+impl TestResult {
+    /// Decode a 9 byte test record `[mid, tid, uas_id, value, min, max]`
+    pub fn from_bytes(record: [u8; 9]) -> Self {
+        let [mid, tid, uas_id, value_high, value_low, min_high, min_low, max_high, max_low] =
+            record;
+        let scale = |high, low| scale(uas_id, u16::from_be_bytes([high, low]));
+        Self {
+            mid,
+            tid,
+            uas_id,
+            value: scale(value_high, value_low),
+            min: scale(min_high, min_low),
+            max: scale(max_high, max_low),
+        }
+    }
+
+    /// Whether the test value is within the limits
+    pub fn passed(&self) -> bool {
+        self.min.value <= self.value.value && self.value.value <= self.max.value
+    }
+}
+
+/// Name of a standardized OBDMID as defined by SAE J1979, `None` for reserved or
+/// manufacturer specific monitors
+pub fn monitor_name(mid: u8) -> Option<&'static str> {
+    const OXYGEN_SENSORS: [&str; 16] = [
+        "Oxygen sensor monitor bank 1 sensor 1",
+        "Oxygen sensor monitor bank 1 sensor 2",
+        "Oxygen sensor monitor bank 1 sensor 3",
+        "Oxygen sensor monitor bank 1 sensor 4",
+        "Oxygen sensor monitor bank 2 sensor 1",
+        "Oxygen sensor monitor bank 2 sensor 2",
+        "Oxygen sensor monitor bank 2 sensor 3",
+        "Oxygen sensor monitor bank 2 sensor 4",
+        "Oxygen sensor monitor bank 3 sensor 1",
+        "Oxygen sensor monitor bank 3 sensor 2",
+        "Oxygen sensor monitor bank 3 sensor 3",
+        "Oxygen sensor monitor bank 3 sensor 4",
+        "Oxygen sensor monitor bank 4 sensor 1",
+        "Oxygen sensor monitor bank 4 sensor 2",
+        "Oxygen sensor monitor bank 4 sensor 3",
+        "Oxygen sensor monitor bank 4 sensor 4",
+    ];
+    const OXYGEN_SENSOR_HEATERS: [&str; 16] = [
+        "Oxygen sensor heater monitor bank 1 sensor 1",
+        "Oxygen sensor heater monitor bank 1 sensor 2",
+        "Oxygen sensor heater monitor bank 1 sensor 3",
+        "Oxygen sensor heater monitor bank 1 sensor 4",
+        "Oxygen sensor heater monitor bank 2 sensor 1",
+        "Oxygen sensor heater monitor bank 2 sensor 2",
+        "Oxygen sensor heater monitor bank 2 sensor 3",
+        "Oxygen sensor heater monitor bank 2 sensor 4",
+        "Oxygen sensor heater monitor bank 3 sensor 1",
+        "Oxygen sensor heater monitor bank 3 sensor 2",
+        "Oxygen sensor heater monitor bank 3 sensor 3",
+        "Oxygen sensor heater monitor bank 3 sensor 4",
+        "Oxygen sensor heater monitor bank 4 sensor 1",
+        "Oxygen sensor heater monitor bank 4 sensor 2",
+        "Oxygen sensor heater monitor bank 4 sensor 3",
+        "Oxygen sensor heater monitor bank 4 sensor 4",
+    ];
+    const MISFIRE_CYLINDERS: [&str; 12] = [
+        "Misfire cylinder 1 data",
+        "Misfire cylinder 2 data",
+        "Misfire cylinder 3 data",
+        "Misfire cylinder 4 data",
+        "Misfire cylinder 5 data",
+        "Misfire cylinder 6 data",
+        "Misfire cylinder 7 data",
+        "Misfire cylinder 8 data",
+        "Misfire cylinder 9 data",
+        "Misfire cylinder 10 data",
+        "Misfire cylinder 11 data",
+        "Misfire cylinder 12 data",
+    ];
+    let bank = |first: u8, names: [&'static str; 4]| names[usize::from(mid - first)];
+    Some(match mid {
+        0x01..=0x10 => OXYGEN_SENSORS[usize::from(mid - 0x01)],
+        0x21..=0x24 => bank(
+            0x21,
+            [
+                "Catalyst monitor bank 1",
+                "Catalyst monitor bank 2",
+                "Catalyst monitor bank 3",
+                "Catalyst monitor bank 4",
+            ],
+        ),
+        0x31..=0x34 => bank(
+            0x31,
+            [
+                "EGR monitor bank 1",
+                "EGR monitor bank 2",
+                "EGR monitor bank 3",
+                "EGR monitor bank 4",
+            ],
+        ),
+        0x35..=0x38 => bank(
+            0x35,
+            [
+                "VVT monitor bank 1",
+                "VVT monitor bank 2",
+                "VVT monitor bank 3",
+                "VVT monitor bank 4",
+            ],
+        ),
+        0x39 => "EVAP monitor (cap off / 0.150\")",
+        0x3A => "EVAP monitor (0.090\")",
+        0x3B => "EVAP monitor (0.040\")",
+        0x3C => "EVAP monitor (0.020\")",
+        0x3D => "Purge flow monitor",
+        0x41..=0x50 => OXYGEN_SENSOR_HEATERS[usize::from(mid - 0x41)],
+        0x61..=0x64 => bank(
+            0x61,
+            [
+                "Heated catalyst monitor bank 1",
+                "Heated catalyst monitor bank 2",
+                "Heated catalyst monitor bank 3",
+                "Heated catalyst monitor bank 4",
+            ],
+        ),
+        0x71..=0x74 => bank(
+            0x71,
+            [
+                "Secondary air monitor 1",
+                "Secondary air monitor 2",
+                "Secondary air monitor 3",
+                "Secondary air monitor 4",
+            ],
+        ),
+        0x81..=0x84 => bank(
+            0x81,
+            [
+                "Fuel system monitor bank 1",
+                "Fuel system monitor bank 2",
+                "Fuel system monitor bank 3",
+                "Fuel system monitor bank 4",
+            ],
+        ),
+        0x85 => "Boost pressure control monitor bank 1",
+        0x86 => "Boost pressure control monitor bank 2",
+        0x90 => "NOx absorber monitor bank 1",
+        0x91 => "NOx absorber monitor bank 2",
+        0x98 => "NOx catalyst monitor bank 1",
+        0x99 => "NOx catalyst monitor bank 2",
+        0xA1 => "Misfire monitor general data",
+        0xA2..=0xAD => MISFIRE_CYLINDERS[usize::from(mid - 0xA2)],
+        0xB0 => "PM filter monitor bank 1",
+        0xB1 => "PM filter monitor bank 2",
+        _ => return None,
+    })
+}
+
+/// Pass/fail per monitor, a monitor passes if all of its tests passed
+pub fn summarize_tests(results: &[TestResult]) -> BTreeMap<u8, bool> {
+    let mut monitors = BTreeMap::new();
+    for result in results {
+        *monitors.entry(result.mid).or_insert(true) &= result.passed();
+    }
+    monitors
+}
+
+/// Scale a raw test value according to the unit and scaling identifier of SAE J1979
+/// Appendix E
+fn scale(uas_id: u8, raw: u16) -> Quantity {
+    let (factor, offset, unit) = match uas_id & 0x7F {
+        0x01 => (1.0, 0.0, Unit::Count),
+        0x02 => (0.1, 0.0, Unit::Count),
+        0x03 => (0.01, 0.0, Unit::Count),
+        0x04 => (0.001, 0.0, Unit::Count),
+        0x05 => (0.0000305, 0.0, Unit::Count),
+        0x06 => (0.000305, 0.0, Unit::Count),
+        0x07 => (0.25, 0.0, Unit::Rpm),
+        0x08 => (0.01, 0.0, Unit::KilometersPerHour),
+        0x09 => (1.0, 0.0, Unit::KilometersPerHour),
+        0x0A => (0.000122, 0.0, Unit::Volts),
+        0x0B => (0.001, 0.0, Unit::Volts),
+        0x0C => (0.01, 0.0, Unit::Volts),
+        0x0D => (0.00000390625, 0.0, Unit::Amperes),
+        0x0E => (0.001, 0.0, Unit::Amperes),
+        0x0F => (0.01, 0.0, Unit::Amperes),
+        0x10 => (0.001, 0.0, Unit::Seconds),
+        0x11 => (0.1, 0.0, Unit::Seconds),
+        0x12 => (1.0, 0.0, Unit::Seconds),
+        0x13 => (0.001, 0.0, Unit::Ohms),
+        0x14 => (1.0, 0.0, Unit::Ohms),
+        0x15 => (1000.0, 0.0, Unit::Ohms),
+        0x16 => (0.1, -40.0, Unit::Celsius),
+        0x17 => (0.01, 0.0, Unit::Kilopascal),
+        0x18 => (0.0117, 0.0, Unit::Kilopascal),
+        0x19 => (0.079, 0.0, Unit::Kilopascal),
+        0x1A => (1.0, 0.0, Unit::Kilopascal),
+        0x1B => (10.0, 0.0, Unit::Kilopascal),
+        0x1C => (0.01, 0.0, Unit::Degrees),
+        0x1D => (0.5, 0.0, Unit::Degrees),
+        0x1E => (0.0000305, 0.0, Unit::Ratio),
+        0x1F => (0.05, 0.0, Unit::Ratio),
+        0x20 => (0.0039062, 0.0, Unit::Ratio),
+        0x21 => (0.001, 0.0, Unit::Hertz),
+        0x22 => (1.0, 0.0, Unit::Hertz),
+        0x23 => (1000.0, 0.0, Unit::Hertz),
+        0x24 => (1.0, 0.0, Unit::Count),
+        0x25 => (1.0, 0.0, Unit::Kilometers),
+        0x27 => (0.01, 0.0, Unit::GramsPerSecond),
+        0x28 => (1.0, 0.0, Unit::GramsPerSecond),
+        0x2F => (0.01, 0.0, Unit::Percent),
+        0x30 => (0.001526, 0.0, Unit::Percent),
+        0x31 => (0.001, 0.0, Unit::Liters),
+        0x34 => (1.0, 0.0, Unit::Minutes),
+        0x35 => (0.01, 0.0, Unit::Seconds),
+        0x36 => (0.01, 0.0, Unit::Grams),
+        0x37 => (0.1, 0.0, Unit::Grams),
+        0x38 => (1.0, 0.0, Unit::Grams),
+        0x3A => (0.001, 0.0, Unit::Grams),
+        0x3B => (0.0001, 0.0, Unit::Grams),
+        0x3F => (0.01, 0.0, Unit::Liters),
+        0x40 => (1.0, 0.0, Unit::PartsPerMillion),
+        _ => return Quantity::new(f64::from(raw), Unit::Count),
+    };
+    // identifiers 0x81 - 0xFE are the signed variants of 0x01 - 0x7E, without offset
+    if uas_id & 0x80 != 0 {
+        Quantity::new(f64::from(raw as i16) * factor, unit)
+    } else {
+        Quantity::new(f64::from(raw) * factor + offset, unit)
+    }
+}
+
+impl ObdClient {
+    /// Mode 06, request the test results of the monitor `mid` from all ECUs
+    pub fn test_results(&mut self, mid: u8) -> Result<ObdResponses<Vec<TestResult>>, ObdError> {
+        Ok(self
+            .request(&[0x06, mid])?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|value| {
+                    let records = value.chunks_exact(9);
+                    if !records.remainder().is_empty() {
+                        return Err(ObdError::InvalidResponse(
+                            "response is not a sequence of 9 byte test records",
+                        ));
+                    }
+                    Ok(records
+                        .map(|record| {
+                            let mut bytes = [0; 9];
+                            bytes.copy_from_slice(record);
+                            TestResult::from_bytes(bytes)
+                        })
+                        .collect())
+                })
+            })
+            .collect())
+    }
+
+    /// Mode 06, request the supported monitors of all ECUs, following the bitmaps of
+    /// OBDMIDs 0x00, 0x20, ... as long as any ECU reports the next range
+    ///
+    /// Malformed responses are left out.
+    pub fn supported_monitors(&mut self) -> Result<Vec<ObdResponse<SupportedPids>>, ObdError> {
+        self.supported_bitmaps(0x06)
+    }
+
+    /// Mode 06, request the test results of all supported monitors and summarize them
+    /// per ECU and monitor
+    ///
+    /// Malformed responses are left out.
+    pub fn monitor_summary(&mut self) -> Result<Vec<ObdResponse<BTreeMap<u8, bool>>>, ObdError> {
+        let supported = self.supported_monitors()?;
+        let mut mids = SupportedPids::new();
+        let mut results = Vec::with_capacity(supported.len());
+        for ecu in supported {
+            mids.merge(&ecu.value);
+            results.push((ecu.ecu, Vec::new()));
+        }
+        // the bitmap MIDs 0x20, 0x40, ... don't report test results
+        for mid in mids.iter().filter(|mid| mid % 0x20 != 0) {
+            for response in self.test_results(mid)? {
+                let Ok(value) = response.value else {
+                    continue;
+                };
+                if let Some((_, tests)) = results.iter_mut().find(|(ecu, _)| *ecu == response.ecu) {
+                    tests.extend(value);
+                }
+            }
+        }
+        Ok(results
+            .into_iter()
+            .map(|(ecu, tests)| ObdResponse {
+                ecu,
+                value: summarize_tests(&tests),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_uas_ids() {
+        let cases: [(u8, u16, f64, Unit); 10] = [
+            (0x01, 0x1234, 4660.0, Unit::Count),
+            (0x07, 0x0FA0, 1000.0, Unit::Rpm),
+            (0x0B, 0x3039, 12.345, Unit::Volts),
+            (0x16, 0x0000, -40.0, Unit::Celsius),
+            (0x16, 0x0FA0, 360.0, Unit::Celsius),
+            (0x1D, 0xFFFF, 32767.5, Unit::Degrees),
+            (0x81, 0xFFFF, -1.0, Unit::Count),
+            (0x81, 0x7FFF, 32767.0, Unit::Count),
+            (0x8B, 0x8000, -32.768, Unit::Volts),
+            (0x96, 0xFF9C, -10.0, Unit::Celsius),
+        ];
+        for (uas_id, raw, value, unit) in cases {
+            let quantity = scale(uas_id, raw);
+            assert!(
+                (quantity.value - value).abs() < 1e-9,
+                "UASID {uas_id:#04X} raw {raw:#06X}: {} != {value}",
+                quantity.value
+            );
+            assert_eq!(quantity.unit, unit, "UASID {uas_id:#04X}");
+        }
+    }
+
+    #[test]
+    fn scale_unknown_uas_id_is_unscaled_count() {
+        for uas_id in [0x00, 0x26, 0x7F, 0xA6, 0xFF] {
+            assert_eq!(scale(uas_id, 0xFFFF), Quantity::new(65535.0, Unit::Count));
+        }
+    }
+
+    #[test]
+    fn from_bytes_scales_value_and_limits() {
+        let result = TestResult::from_bytes([0x01, 0x85, 0x16, 0x02, 0x58, 0x01, 0x90, 0x03, 0x20]);
+        assert_eq!((result.mid, result.tid, result.uas_id), (0x01, 0x85, 0x16));
+        assert_eq!(result.value.unit, Unit::Celsius);
+        assert!((result.value.value - 20.0).abs() < 1e-9);
+        assert!((result.min.value - 0.0).abs() < 1e-9);
+        assert!((result.max.value - 40.0).abs() < 1e-9);
+        assert!(result.passed());
+    }
+
+    #[test]
+    fn from_bytes_compares_signed_limits() {
+        // value -5, limits -10 and -1 in signed counts
+        let result = TestResult::from_bytes([0x21, 0x01, 0x81, 0xFF, 0xFB, 0xFF, 0xF6, 0xFF, 0xFF]);
+        assert_eq!(result.value.value, -5.0);
+        assert_eq!(result.min.value, -10.0);
+        assert_eq!(result.max.value, -1.0);
+        assert!(result.passed());
+        let failed = TestResult::from_bytes([0x21, 0x01, 0x81, 0x00, 0x01, 0xFF, 0xF6, 0xFF, 0xFF]);
+        assert!(!failed.passed());
+    }
+
+    #[test]
+    fn summarize_tests_fails_monitor_with_any_failed_test() {
+        let passed = TestResult::from_bytes([0x01, 0x01, 0x01, 0, 5, 0, 0, 0, 10]);
+        let failed = TestResult::from_bytes([0x01, 0x02, 0x01, 0, 11, 0, 0, 0, 10]);
+        let other = TestResult::from_bytes([0x21, 0x01, 0x01, 0, 5, 0, 0, 0, 10]);
+        let summary = summarize_tests(&[passed, failed, other]);
+        assert_eq!(summary, BTreeMap::from([(0x01, false), (0x21, true)]));
+    }
+}
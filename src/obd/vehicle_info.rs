@@ -0,0 +1,220 @@
+use super::{ObdClient, ObdError, ObdResponse, ObdResponses, SupportedPids};
+
+/// Mode 09 InfoType 0x00, InfoTypes 0x01 - 0x20 supported
+pub const INFO_TYPE_SUPPORTED: u8 = 0x00;
+/// Mode 09 InfoType 0x02, vehicle identification number
+pub const INFO_TYPE_VIN: u8 = 0x02;
+/// Mode 09 InfoType 0x04, calibration identifications
+pub const INFO_TYPE_CALIBRATION_ID: u8 = 0x04;
+/// Mode 09 InfoType 0x06, calibration verification numbers
+pub const INFO_TYPE_CVN: u8 = 0x06;
+/// Mode 09 InfoType 0x0A, ECU name
+pub const INFO_TYPE_ECU_NAME: u8 = 0x0A;
+
+impl ObdClient {
+    /// Mode 09, request the data items of `info_type` from all ECUs
+    ///
+    /// On CAN all items are sent in one multi-frame response `[info_type, count, items...]`,
+    /// which is split into `count` items of `item_len` bytes.
+    pub fn vehicle_information(
+        &mut self,
+        info_type: u8,
+        item_len: usize,
+    ) -> Result<ObdResponses<Vec<Vec<u8>>>, ObdError> {
+        Ok(self
+            .request(&[0x09, info_type])?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|value| split_data_items(&value, info_type, item_len))
+            })
+            .collect())
+    }
+
+    /// Mode 09 InfoType 0x00, request the supported InfoTypes of all ECUs, following the
+    /// bitmaps of InfoTypes 0x00, 0x20, ... as long as any ECU reports the next range
+    ///
+    /// Unlike the data items of the other InfoTypes, the bitmaps are not preceded by the
+    /// number of data items. Malformed responses are left out.
+    pub fn supported_info_types(&mut self) -> Result<Vec<ObdResponse<SupportedPids>>, ObdError> {
+        self.supported_bitmaps(0x09)
+    }
+
+    /// Mode 09 InfoType 0x02, request the 17 character vehicle identification number
+    pub fn vin(&mut self) -> Result<ObdResponses<String>, ObdError> {
+        self.text(INFO_TYPE_VIN, 17)
+    }
+
+    /// Mode 09 InfoType 0x04, request the calibration identifications of all ECUs
+    pub fn calibration_ids(&mut self) -> Result<ObdResponses<Vec<String>>, ObdError> {
+        self.text_items(INFO_TYPE_CALIBRATION_ID, 16)
+    }
+
+    /// Mode 09 InfoType 0x06, request the calibration verification numbers of all ECUs,
+    /// one per calibration identification
+    pub fn cvns(&mut self) -> Result<ObdResponses<Vec<u32>>, ObdError> {
+        Ok(self
+            .vehicle_information(INFO_TYPE_CVN, 4)?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|items| {
+                    Ok(items
+                        .iter()
+                        .map(|item| u32::from_be_bytes([item[0], item[1], item[2], item[3]]))
+                        .collect())
+                })
+            })
+            .collect())
+    }
+
+    /// Mode 09 InfoType 0x0A, request the 20 character names of all ECUs
+    pub fn ecu_names(&mut self) -> Result<ObdResponses<String>, ObdError> {
+        self.text(INFO_TYPE_ECU_NAME, 20)
+    }
+
+    /// Request a text split into ASCII data items, removing the padding
+    fn text(&mut self, info_type: u8, item_len: usize) -> Result<ObdResponses<String>, ObdError> {
+        Ok(self
+            .text_items(info_type, item_len)?
+            .into_iter()
+            .map(|response| response.and_then(|items| Ok(items.concat())))
+            .collect())
+    }
+
+    /// Request ASCII data items, removing the padding
+    fn text_items(
+        &mut self,
+        info_type: u8,
+        item_len: usize,
+    ) -> Result<ObdResponses<Vec<String>>, ObdError> {
+        Ok(self
+            .vehicle_information(info_type, item_len)?
+            .into_iter()
+            .map(|response| {
+                response.and_then(|items| Ok(items.iter().map(|item| text_item(item)).collect()))
+            })
+            .collect())
+    }
+}
+
+/// Split a Mode 09 response `[info_type, count, items...]` into `count` items of
+/// `item_len` bytes, ignoring trailing padding
+fn split_data_items(
+    response: &[u8],
+    info_type: u8,
+    item_len: usize,
+) -> Result<Vec<Vec<u8>>, ObdError> {
+    let items = match *response {
+        [echo, count, ref items @ ..] if echo == info_type => items
+            .get(..item_len * usize::from(count))
+            .ok_or(ObdError::InvalidResponse(
+                "response is shorter than the number of data items",
+            ))?,
+        _ => {
+            return Err(ObdError::InvalidResponse(
+                "response does not echo the InfoType",
+            ))
+        }
+    };
+    Ok(items.chunks(item_len.max(1)).map(<[u8]>::to_vec).collect())
+}
+
+/// Decode an ASCII data item, removing the padding
+fn text_item(item: &[u8]) -> String {
+    String::from_utf8_lossy(item)
+        .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_single_vin_item() {
+        let mut response = vec![INFO_TYPE_VIN, 0x01];
+        response.extend_from_slice(b"1G1JC5444R7252367");
+        let items = split_data_items(&response, INFO_TYPE_VIN, 17).unwrap();
+        assert_eq!(items, [b"1G1JC5444R7252367".to_vec()]);
+    }
+
+    #[test]
+    fn split_multiple_calibration_ids() {
+        let mut response = vec![INFO_TYPE_CALIBRATION_ID, 0x02];
+        response.extend_from_slice(b"JMB*36761500\0\0\0\0");
+        response.extend_from_slice(b"JMB*47872611\0\0\0\0");
+        let items = split_data_items(&response, INFO_TYPE_CALIBRATION_ID, 16).unwrap();
+        assert_eq!(items.len(), 2);
+        let ids = items.iter().map(|item| text_item(item)).collect::<Vec<_>>();
+        assert_eq!(ids, ["JMB*36761500", "JMB*47872611"]);
+    }
+
+    #[test]
+    fn split_multiple_cvns() {
+        let response = [
+            INFO_TYPE_CVN,
+            0x03,
+            0x17,
+            0x91,
+            0xBC,
+            0x82,
+            0x16,
+            0xE0,
+            0x62,
+            0xBE,
+            0x00,
+            0x00,
+            0x12,
+            0x34,
+        ];
+        let items = split_data_items(&response, INFO_TYPE_CVN, 4).unwrap();
+        assert_eq!(
+            items,
+            [
+                vec![0x17, 0x91, 0xBC, 0x82],
+                vec![0x16, 0xE0, 0x62, 0xBE],
+                vec![0x00, 0x00, 0x12, 0x34],
+            ]
+        );
+    }
+
+    #[test]
+    fn split_ignores_trailing_padding() {
+        let response = [INFO_TYPE_CVN, 0x01, 0x17, 0x91, 0xBC, 0x82, 0xAA, 0xAA];
+        let items = split_data_items(&response, INFO_TYPE_CVN, 4).unwrap();
+        assert_eq!(items, [vec![0x17, 0x91, 0xBC, 0x82]]);
+    }
+
+    #[test]
+    fn split_without_items() {
+        let items = split_data_items(&[INFO_TYPE_CVN, 0x00], INFO_TYPE_CVN, 4).unwrap();
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn split_rejects_fewer_items_than_counted() {
+        let response = [INFO_TYPE_CVN, 0x02, 0x17, 0x91, 0xBC, 0x82, 0x16, 0xE0];
+        assert!(matches!(
+            split_data_items(&response, INFO_TYPE_CVN, 4),
+            Err(ObdError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn split_rejects_other_info_type() {
+        let response = [INFO_TYPE_CALIBRATION_ID, 0x01, 0x17, 0x91, 0xBC, 0x82];
+        assert!(matches!(
+            split_data_items(&response, INFO_TYPE_CVN, 4),
+            Err(ObdError::InvalidResponse(_))
+        ));
+        assert!(matches!(
+            split_data_items(&[INFO_TYPE_CVN], INFO_TYPE_CVN, 4),
+            Err(ObdError::InvalidResponse(_))
+        ));
+    }
+
+    #[test]
+    fn text_item_removes_padding() {
+        assert_eq!(text_item(b"ECM\0-EngineControl\0"), "ECM\0-EngineControl");
+        assert_eq!(text_item(b"  JMB*36761500\0\0\0\0"), "JMB*36761500");
+    }
+}
@@ -0,0 +1,60 @@
+//! `poll(2)` shared by the blocking sockets waiting with a timeout.
+
+use libc::{c_int, nfds_t, pollfd, POLLIN};
+use std::io;
+use std::os::unix::io::RawFd;
+use std::time::{Duration, Instant};
+
+/// Wait up to `timeout`, forever if `None`, until one of `fds` is readable or reports an
+/// error, returning the number of ready descriptors, 0 if the timeout expired.
+///
+/// The events of each descriptor are left in its `revents`, negative descriptors are
+/// ignored. Interrupted waits are resumed with the remaining timeout.
+pub(crate) fn poll(fds: &mut [pollfd], timeout: Option<Duration>) -> io::Result<usize> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    loop {
+        let timeout_ms = deadline.map_or(-1, |deadline| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            // round up, a timeout below 1 ms must not turn into a busy loop
+            c_int::try_from(remaining.as_nanos().div_ceil(1_000_000)).unwrap_or(c_int::MAX)
+        });
+        match unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as nfds_t, timeout_ms) } {
+            -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+            -1 => return Err(io::Error::last_os_error()),
+            ready => return Ok(ready as usize),
+        }
+    }
+}
+
+/// Wait up to `timeout`, forever if `None`, until `fd` is readable or reports an error,
+/// `false` if the timeout expired
+pub(crate) fn wait_readable(fd: RawFd, timeout: Option<Duration>) -> io::Result<bool> {
+    let mut fds = [pollfd {
+        fd,
+        events: POLLIN,
+        revents: 0,
+    }];
+    Ok(poll(&mut fds, timeout)? > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+
+    #[test]
+    fn wait_readable_reports_data_and_timeout() {
+        let mut pipe = [0; 2];
+        assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+        let (rx, tx) = unsafe { (OwnedFd::from_raw_fd(pipe[0]), OwnedFd::from_raw_fd(pipe[1])) };
+        let rx = rx.as_raw_fd();
+
+        let start = Instant::now();
+        assert!(!wait_readable(rx, Some(Duration::from_millis(20))).unwrap());
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let written = unsafe { libc::write(tx.as_raw_fd(), b"x".as_ptr().cast(), 1) };
+        assert_eq!(written, 1);
+        assert!(wait_readable(rx, None).unwrap());
+    }
+}
@@ -0,0 +1,68 @@
+//! Serde representations of the raw fields of the kernel option structs.
+//!
+//! Flags are (de)serialized by name, encoded values are validated on deserialization.
+
+pub(crate) mod isotp_behaviour {
+    use crate::IsoTpBehaviour;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(flags: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        IsoTpBehaviour::from_bits_retain(*flags).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        IsoTpBehaviour::deserialize(deserializer).map(|flags| flags.bits())
+    }
+}
+
+pub(crate) mod tx_flags {
+    use crate::TxFlags;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(flags: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        TxFlags::from_bits_retain(*flags).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        TxFlags::deserialize(deserializer).map(|flags| flags.bits())
+    }
+}
+
+pub(crate) mod separation_time {
+    use crate::SeparationTime;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(stmin: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*stmin)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        SeparationTime::deserialize(deserializer).map(SeparationTime::as_raw)
+    }
+}
+
+pub(crate) mod tx_data_length {
+    use crate::TxDataLength;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(tx_dl: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*tx_dl)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        TxDataLength::deserialize(deserializer).map(TxDataLength::as_u8)
+    }
+}
+
+pub(crate) mod mtu {
+    use crate::Mtu;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(mtu: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(*mtu)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        Mtu::deserialize(deserializer).map(Mtu::as_u8)
+    }
+}
@@ -0,0 +1,254 @@
+//! Asynchronous ISO-TP socket for the tokio runtime.
+//!
+//! The socket is registered with the tokio reactor through [`AsyncFd`], so it can be
+//! awaited from any tokio task.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::{tokio::IsoTpSocket, StandardId};
+//!
+//! async fn echo() -> Result<(), socketcan_isotp::Error> {
+//!     let mut tp_socket = IsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x123).expect("Invalid rx id"),
+//!         StandardId::new(0x321).expect("Invalid tx id"),
+//!     )?;
+//!
+//!     loop {
+//!         let buffer = tp_socket.read().await?.to_vec();
+//!         tp_socket.write(&buffer).await?;
+//!     }
+//! }
+//! ```
+
+use crate::transport::AsyncIsoTpTransport;
+use crate::{Error, FlowControlOptions, Id, IsoTpOptions, LinkLayerOptions, RECV_BUFFER_SIZE};
+use ::tokio::io::unix::AsyncFd;
+use ::tokio::io::Interest;
+use ::tokio::sync::mpsc::{self, error::TrySendError};
+use libc::c_int;
+use std::io;
+use std::num::NonZeroUsize;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// An asynchronous ISO-TP socketcan socket.
+///
+/// Wraps a blocking [`crate::IsoTpSocket`] that is switched to non-blocking mode.
+pub struct IsoTpSocket {
+    inner: Arc<AsyncFd<crate::IsoTpSocket>>,
+    recv_buffer: [u8; RECV_BUFFER_SIZE],
+}
+
+impl IsoTpSocket {
+    /// Open a named CAN ISO-TP device.
+    ///
+    /// See [`crate::IsoTpSocket::open`].
+    pub fn open(ifname: &str, rx_id: impl Into<Id>, tx_id: impl Into<Id>) -> Result<Self, Error> {
+        Self::new(crate::IsoTpSocket::open(ifname, rx_id, tx_id)?)
+    }
+
+    /// Open a named CAN ISO-TP device, passing additional options.
+    ///
+    /// See [`crate::IsoTpSocket::open_with_opts`].
+    pub fn open_with_opts(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        isotp_options: Option<IsoTpOptions>,
+        rx_flow_control_options: Option<FlowControlOptions>,
+        link_layer_options: Option<LinkLayerOptions>,
+    ) -> Result<Self, Error> {
+        Self::new(crate::IsoTpSocket::open_with_opts(
+            ifname,
+            rx_id,
+            tx_id,
+            isotp_options,
+            rx_flow_control_options,
+            link_layer_options,
+        )?)
+    }
+
+    /// Open CAN ISO-TP device device by interface number.
+    ///
+    /// See [`crate::IsoTpSocket::open_if`].
+    pub fn open_if(
+        if_index: c_int,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+    ) -> Result<Self, Error> {
+        Self::new(crate::IsoTpSocket::open_if(if_index, rx_id, tx_id)?)
+    }
+
+    /// Open CAN ISO-TP device device by interface number, passing additional options.
+    ///
+    /// See [`crate::IsoTpSocket::open_if_with_opts`].
+    pub fn open_if_with_opts(
+        if_index: c_int,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        isotp_options: Option<IsoTpOptions>,
+        rx_flow_control_options: Option<FlowControlOptions>,
+        link_layer_options: Option<LinkLayerOptions>,
+    ) -> Result<Self, Error> {
+        Self::new(crate::IsoTpSocket::open_if_with_opts(
+            if_index,
+            rx_id,
+            tx_id,
+            isotp_options,
+            rx_flow_control_options,
+            link_layer_options,
+        )?)
+    }
+
+    /// Wrap an already opened blocking socket, switching it to non-blocking mode.
+    ///
+    /// Must be called within a tokio runtime.
+    pub fn new(socket: crate::IsoTpSocket) -> Result<Self, Error> {
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            inner: Arc::new(AsyncFd::with_interest(
+                socket,
+                Interest::READABLE | Interest::WRITABLE,
+            )?),
+            recv_buffer: [0x00; RECV_BUFFER_SIZE],
+        })
+    }
+
+    /// Read data, waiting until a complete PDU has been received
+    ///
+    /// Cancel safe, no PDU is lost if the future is dropped before it completes.
+    pub async fn read(&mut self) -> io::Result<&[u8]> {
+        let Self { inner, recv_buffer } = self;
+        let len = inner
+            .async_io(Interest::READABLE, |socket| socket.read_to_buf(recv_buffer))
+            .await?;
+        Ok(&recv_buffer[0..len])
+    }
+
+    /// Write a slice of data, waiting until the kernel accepted the PDU
+    pub async fn write(&self, buffer: &[u8]) -> io::Result<()> {
+        self.inner
+            .async_io(Interest::WRITABLE, |socket| socket.write(buffer))
+            .await
+    }
+
+    /// Read a PDU without waiting, fails with `WouldBlock` if none was received
+    pub fn try_read(&mut self) -> io::Result<&[u8]> {
+        let len = self.inner.get_ref().read_to_buf(&mut self.recv_buffer)?;
+        Ok(&self.recv_buffer[0..len])
+    }
+
+    /// Get a reference to the underlying blocking socket
+    pub fn get_ref(&self) -> &crate::IsoTpSocket {
+        self.inner.get_ref()
+    }
+
+    /// Unwrap the underlying blocking socket, leaving it in non-blocking mode
+    ///
+    /// Fails if a [`SendQueueWorker`] of this socket is still alive.
+    pub fn into_inner(self) -> io::Result<crate::IsoTpSocket> {
+        Ok(Arc::try_unwrap(self.inner)
+            .map_err(|_| io::Error::other("socket is used by a send queue"))?
+            .into_inner())
+    }
+
+    /// Create a bounded outgoing queue holding up to `depth` PDUs.
+    ///
+    /// [`SendQueue::send`] waits while the queue is full, the returned [`SendQueueWorker`]
+    /// has to be spawned on the runtime to drain the queue into the socket.
+    /// Since the worker only takes PDUs off the queue once the kernel accepted the previous
+    /// one, a full kernel send buffer propagates back to the producers.
+    pub fn send_queue(&self, depth: NonZeroUsize) -> (SendQueue, SendQueueWorker) {
+        let (tx, rx) = mpsc::channel(depth.get());
+        let worker = SendQueueWorker {
+            socket: Arc::clone(&self.inner),
+            rx,
+        };
+        (SendQueue { tx }, worker)
+    }
+}
+
+/// Producer side of a bounded send queue, see [`IsoTpSocket::send_queue`].
+///
+/// Can be cloned to feed the queue from multiple tasks.
+#[derive(Clone)]
+pub struct SendQueue {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl SendQueue {
+    /// Enqueue a PDU, waiting while the queue is full
+    pub async fn send(&self, buffer: &[u8]) -> io::Result<()> {
+        self.tx
+            .send(buffer.to_vec())
+            .await
+            .map_err(|_| worker_stopped())
+    }
+
+    /// Enqueue a PDU without waiting, fails with `WouldBlock` if the queue is full
+    pub fn try_send(&self, buffer: &[u8]) -> io::Result<()> {
+        self.tx.try_send(buffer.to_vec()).map_err(|e| match e {
+            TrySendError::Full(_) => io::Error::from(io::ErrorKind::WouldBlock),
+            TrySendError::Closed(_) => worker_stopped(),
+        })
+    }
+
+    /// Number of PDUs currently waiting in the queue
+    pub fn len(&self) -> usize {
+        self.tx.max_capacity() - self.tx.capacity()
+    }
+
+    /// Returns `true` if no PDUs are waiting in the queue
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Maximum number of PDUs the queue holds
+    pub fn capacity(&self) -> usize {
+        self.tx.max_capacity()
+    }
+}
+
+/// Consumer side of a bounded send queue, see [`IsoTpSocket::send_queue`].
+pub struct SendQueueWorker {
+    socket: Arc<AsyncFd<crate::IsoTpSocket>>,
+    rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl SendQueueWorker {
+    /// Write queued PDUs to the socket until all [`SendQueue`] handles are dropped.
+    ///
+    /// Stops at the first write error, PDUs still queued at that point are discarded.
+    pub async fn run(mut self) -> io::Result<()> {
+        while let Some(buffer) = self.rx.recv().await {
+            self.socket
+                .async_io(Interest::WRITABLE, |socket| socket.write(&buffer))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn worker_stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "send queue worker stopped")
+}
+
+impl AsyncIsoTpTransport for IsoTpSocket {
+    async fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        Ok(self.write(pdu).await?)
+    }
+
+    async fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        match ::tokio::time::timeout(timeout, self.read()).await {
+            Ok(pdu) => Ok(Some(pdu?)),
+            Err(_elapsed) => Ok(None),
+        }
+    }
+}
+
+impl AsRawFd for IsoTpSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.as_raw_fd()
+    }
+}
@@ -0,0 +1,202 @@
+//! Backend independent exchange of ISO-TP PDUs.
+//!
+//! [`IsoTpTransport`] is implemented by the kernel [`IsoTpSocket`], the userspace stack
+//! and the UDS test doubles, [`AsyncIsoTpTransport`] by the tokio and async-std sockets.
+//! Code written against the traits, such as the UDS client, runs unchanged on any of them,
+//! so the backend can be chosen per deployment.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::transport::IsoTpTransport;
+//! use socketcan_isotp::{IsoTpSocket, StandardId};
+//! use std::time::Duration;
+//!
+//! /// Send a tester present and wait for the response on any backend
+//! fn ping(transport: &mut impl IsoTpTransport) -> Result<bool, socketcan_isotp::Error> {
+//!     transport.send(&[0x3E, 0x00])?;
+//!     Ok(transport.receive(Duration::from_millis(50))?.is_some())
+//! }
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     let mut tp_socket = IsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x7E8).expect("Invalid rx id"),
+//!         StandardId::new(0x7E0).expect("Invalid tx id"),
+//!     )?;
+//!     println!("ECU alive: {}", ping(&mut tp_socket)?);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::{poll, Error, IsoTpBehaviour, IsoTpSocket};
+use std::future::Future;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+/// Blocking exchange of PDUs, e.g. over an [`IsoTpSocket`], the userspace stack, DoIP or a
+/// mock server in tests
+///
+/// ```rust
+/// use socketcan_isotp::transport::IsoTpTransport;
+/// use std::collections::VecDeque;
+/// use std::time::Duration;
+///
+/// /// Answers every request with the queued responses
+/// struct Mock {
+///     responses: VecDeque<Vec<u8>>,
+///     response: Vec<u8>,
+/// }
+///
+/// impl IsoTpTransport for Mock {
+///     fn send(&mut self, _pdu: &[u8]) -> Result<(), socketcan_isotp::Error> {
+///         Ok(())
+///     }
+///
+///     fn receive(&mut self, _timeout: Duration) -> Result<Option<&[u8]>, socketcan_isotp::Error> {
+///         Ok(self.responses.pop_front().map(|response| {
+///             self.response = response;
+///             self.response.as_slice()
+///         }))
+///     }
+/// }
+///
+/// let mut mock = Mock {
+///     responses: VecDeque::from([vec![0x62, 0xF1, 0x90, 0x57]]),
+///     response: Vec::new(),
+/// };
+/// mock.send(&[0x22, 0xF1, 0x90]).unwrap();
+/// let timeout = Duration::from_millis(50);
+/// assert_eq!(mock.receive(timeout).unwrap(), Some(&[0x62, 0xF1, 0x90, 0x57][..]));
+/// assert_eq!(mock.receive(timeout).unwrap(), None);
+/// ```
+pub trait IsoTpTransport {
+    /// Send a PDU
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error>;
+
+    /// Wait up to `timeout` for a PDU, `None` if none arrived in time
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error>;
+
+    /// Send a PDU and return once it was transmitted, e.g. before the interface is
+    /// reconfigured.
+    ///
+    /// Defaults to [`IsoTpTransport::send`] for backends without transmission
+    /// confirmation.
+    fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        self.send(pdu)
+    }
+}
+
+impl IsoTpTransport for IsoTpSocket {
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        Ok(self.write(pdu)?)
+    }
+
+    /// Fails with [`Error::InvalidInput`] unless the socket was opened with
+    /// [`IsoTpSocketBuilder::wait_tx_done`](crate::IsoTpSocketBuilder::wait_tx_done), the
+    /// kernel doesn't allow changing the option of a bound socket.
+    fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        let flags = self.get_isotp_options()?.get_flags();
+        if !flags.is_some_and(|flags| flags.contains(IsoTpBehaviour::CAN_ISOTP_WAIT_TX_DONE)) {
+            return Err(Error::InvalidInput(
+                "socket must be opened with CAN_ISOTP_WAIT_TX_DONE to confirm transmission",
+            ));
+        }
+        self.send(pdu)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        if !poll::wait_readable(self.as_raw_fd(), Some(timeout))? {
+            return Ok(None);
+        }
+        Ok(Some(self.read()?))
+    }
+}
+
+#[cfg(feature = "userspace")]
+impl<B: crate::userspace::CanBackend> IsoTpTransport for crate::userspace::UserspaceIsoTpSocket<B> {
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        self.write(pdu)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        self.read_timeout(timeout)
+    }
+}
+
+#[cfg(feature = "userspace")]
+impl IsoTpTransport for crate::userspace::FallbackIsoTpSocket {
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        self.write(pdu)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        match self {
+            crate::userspace::FallbackIsoTpSocket::Kernel(socket) => socket.receive(timeout),
+            crate::userspace::FallbackIsoTpSocket::Userspace(socket) => socket.receive(timeout),
+        }
+    }
+
+    fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        match self {
+            crate::userspace::FallbackIsoTpSocket::Kernel(socket) => socket.send_wait_tx_done(pdu),
+            crate::userspace::FallbackIsoTpSocket::Userspace(socket) => {
+                socket.send_wait_tx_done(pdu)
+            }
+        }
+    }
+}
+
+impl<T: IsoTpTransport + ?Sized> IsoTpTransport for &mut T {
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        (**self).send(pdu)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        (**self).receive(timeout)
+    }
+
+    fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        (**self).send_wait_tx_done(pdu)
+    }
+}
+
+impl<T: IsoTpTransport + ?Sized> IsoTpTransport for Box<T> {
+    fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        (**self).send(pdu)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        (**self).receive(timeout)
+    }
+
+    fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        (**self).send_wait_tx_done(pdu)
+    }
+}
+
+/// Asynchronous exchange of PDUs, e.g. over a tokio or async-std `IsoTpSocket`
+///
+/// Both futures must be cancel safe, a PDU received while the future is dropped stays
+/// available for the next call.
+pub trait AsyncIsoTpTransport {
+    /// Send a PDU
+    fn send(&mut self, pdu: &[u8]) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Wait up to `timeout` for a PDU, `None` if none arrived in time
+    fn receive(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Option<&[u8]>, Error>> + Send;
+}
+
+impl<T: AsyncIsoTpTransport + Send + ?Sized> AsyncIsoTpTransport for &mut T {
+    fn send(&mut self, pdu: &[u8]) -> impl Future<Output = Result<(), Error>> + Send {
+        (**self).send(pdu)
+    }
+
+    fn receive(
+        &mut self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<Option<&[u8]>, Error>> + Send {
+        (**self).receive(timeout)
+    }
+}
@@ -0,0 +1,116 @@
+//! Unified Diagnostic Services (ISO 14229) client on top of ISO-TP.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::{uds::UdsClient, IsoTpSocket, StandardId};
+//!
+//! fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+//!     let tp_socket = IsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x7E8).expect("Invalid rx id"),
+//!         StandardId::new(0x7E0).expect("Invalid tx id"),
+//!     )?;
+//!     let mut client = UdsClient::new(tp_socket);
+//!
+//!     // ReadDataByIdentifier 0xF189 - VehicleManufacturerECUSoftwareVersionNumber
+//!     let response = client.request(0x22, &[0xF1, 0x89])?;
+//!     println!("Response: {:X?}", response);
+//!     Ok(())
+//! }
+//! ```
+
+#[cfg(feature = "tokio")]
+mod async_client;
+mod authentication;
+mod client;
+mod communication_control;
+mod did;
+mod did_codec;
+mod download;
+mod dtc;
+mod dtc_setting;
+mod dynamic_did;
+mod error;
+mod fault;
+mod fuzz;
+mod io_control;
+mod link_control;
+mod memory;
+mod mock;
+mod nrc;
+#[cfg(feature = "odx")]
+mod odx;
+mod periodic;
+mod replay;
+mod responder;
+mod response_matcher;
+mod response_on_event;
+mod retry;
+mod routine;
+mod scanner;
+mod security_access;
+mod sequence;
+mod server;
+mod service;
+mod session;
+mod tester_present;
+mod timing_parameter;
+mod trace;
+#[cfg(feature = "obd")]
+mod wwh_obd;
+
+/// The transport of [`UdsClient`], alias of [`IsoTpTransport`](crate::transport::IsoTpTransport)
+pub use crate::transport::IsoTpTransport as UdsTransport;
+#[cfg(feature = "tokio")]
+pub use async_client::AsyncUdsClient;
+pub use authentication::{
+    AuthenticationReturn, CertificateAuthenticator, ChallengeResponseAuthenticator,
+    ALGORITHM_INDICATOR_LEN,
+};
+pub use client::{UdsClient, DEFAULT_P2, DEFAULT_P2_EXTENDED};
+pub use communication_control::{
+    CommunicationControlType, CommunicationMessages, CommunicationType,
+};
+pub use did::{Did, DidCodec, DidRegistry, DidValue};
+pub use did_codec::{DidDecode, DidEncode, Scaled, ScaledRaw};
+pub use download::{Download, DownloadProgress};
+pub use dtc::{Dtc, DtcRecord, DtcStatus, SnapshotRecord};
+pub use dtc_setting::DtcSettingType;
+pub use dynamic_did::SourceDid;
+pub use error::{decode_negative_response, UdsError};
+pub use fault::{FaultInjector, FaultPolicy, FaultStats};
+pub use fuzz::{FuzzCase, FuzzHook, FuzzOutcome, Fuzzer, Mutator, ResponseClass};
+pub use io_control::IoControl;
+pub use link_control::{BitrateSwitch, LinkBaudrate, LinkControl, NetlinkBitrate};
+pub use memory::AddressAndLengthFormat;
+pub use mock::{MockReply, MockTransport};
+pub use nrc::Nrc;
+#[cfg(feature = "odx")]
+pub use odx::{OdxDatabase, OdxError, OdxService};
+pub use periodic::{PeriodicStream, TransmissionMode};
+pub use replay::{PduDirection, RecordedPdu, Recorder, Recording, ReplayError, Replayer};
+pub use responder::{ResponderHandle, ScriptedResponder};
+pub use response_on_event::{Events, ResponseOnEvent};
+pub use retry::RetryPolicy;
+pub use routine::{Rid, RoutineControlType};
+pub use scanner::{DiscoveredEcu, Scanner};
+pub use security_access::{AsyncSeedKeyAlgorithm, SecurityAccess, SeedKeyAlgorithm, SeedKeyError};
+pub use sequence::{
+    Sequence, SequenceError, SequenceHooks, SequenceProgress, Step, ROUTINE_POLL_INTERVAL,
+};
+pub use server::{RoutineHandler, ServerState, ServiceHandler, UdsServer};
+pub use service::{Request, ServiceId, SUPPRESS_POSITIVE_RESPONSE};
+pub use session::{DiagnosticSession, ResetType, SessionTiming};
+pub use tester_present::TesterPresentKeeper;
+pub use timing_parameter::TimingParameterAccessType;
+pub use trace::{SessionTrace, TraceEntry, TraceOutcome};
+#[cfg(feature = "obd")]
+pub use wwh_obd::{
+    WwhObdDtcRecord, WWH_OBD_EMISSIONS_GROUP, WWH_OBD_INFO_TYPE_DIDS, WWH_OBD_MONITOR_DIDS,
+    WWH_OBD_PID_DIDS,
+};
+
+/// Service identifier of negative responses
+pub const NEGATIVE_RESPONSE_SID: u8 = 0x7F;
+
+/// Offset added to the request service identifier in positive responses
+pub const POSITIVE_RESPONSE_OFFSET: u8 = 0x40;
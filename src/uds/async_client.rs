@@ -0,0 +1,173 @@
+use super::response_matcher::{Matched, ResponseMatcher};
+use super::{Request, UdsError, DEFAULT_P2, DEFAULT_P2_EXTENDED};
+use crate::tokio::IsoTpSocket;
+use crate::transport::AsyncIsoTpTransport;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// An asynchronous UDS client on a tokio [`IsoTpSocket`] or any other
+/// [`AsyncIsoTpTransport`], see [`super::UdsClient`].
+///
+/// Responses are matched like by the blocking client, ResponseOnEvent and periodic
+/// identifiers are only supported by the blocking client.
+///
+/// Dropping a request future before it completes leaves the client usable: responses of
+/// the cancelled request already received are discarded before the next request is sent.
+/// Responses arriving later, e.g. the final response after a pending response, are taken
+/// as the response of the next request of the same service. Wait P2* after cancelling such
+/// a request before sending it again.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::{tokio::IsoTpSocket, uds::AsyncUdsClient, StandardId};
+///
+/// async fn read_vin() -> Result<Vec<u8>, socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = AsyncUdsClient::new(tp_socket);
+///     client.request(0x22, &[0xF1, 0x90]).await
+/// }
+/// ```
+pub struct AsyncUdsClient<T = IsoTpSocket> {
+    socket: T,
+    p2: Duration,
+    p2_extended: Duration,
+    max_pending_responses: Option<u32>,
+    /// set while a request is in progress, still set if it was cancelled
+    in_flight: bool,
+}
+
+impl<T: AsyncIsoTpTransport> AsyncUdsClient<T> {
+    /// Create a client using the default response timeout [`DEFAULT_P2`]
+    pub fn new(socket: T) -> Self {
+        Self {
+            socket,
+            p2: DEFAULT_P2,
+            p2_extended: DEFAULT_P2_EXTENDED,
+            max_pending_responses: None,
+            in_flight: false,
+        }
+    }
+
+    /// Send a request and wait for the response of the server.
+    ///
+    /// See [`super::UdsClient::request`].
+    pub async fn request(
+        &mut self,
+        service: impl Into<u8>,
+        data: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let request = Request::new(service).data(data);
+        self.send(&request).await?.ok_or(UdsError::Timeout)
+    }
+
+    /// Send an encoded request and wait for the response of the server.
+    ///
+    /// See [`super::UdsClient::send`].
+    pub async fn send(&mut self, request: &Request) -> Result<Option<Vec<u8>>, UdsError> {
+        if self.in_flight {
+            self.discard_received().await?;
+        }
+        self.in_flight = true;
+        let response = self.exchange(request).await;
+        self.in_flight = false;
+        response
+    }
+
+    async fn exchange(&mut self, request: &Request) -> Result<Option<Vec<u8>>, UdsError> {
+        self.socket.send(request.as_bytes()).await?;
+
+        let mut matcher = ResponseMatcher::new(request, self.max_pending_responses, &[]);
+        let mut deadline = Instant::now() + self.p2;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let Some(response) = self.socket.receive(timeout).await? else {
+                return matcher.on_timeout();
+            };
+            match matcher.on_response(response) {
+                Matched::Done(result) => return result,
+                Matched::Pending => deadline = Instant::now() + self.p2_extended,
+                Matched::Event | Matched::Skip => (),
+            }
+        }
+    }
+
+    /// Drop responses of a cancelled request
+    async fn discard_received(&mut self) -> Result<(), crate::Error> {
+        while self.socket.receive(Duration::ZERO).await?.is_some() {}
+        Ok(())
+    }
+
+    /// Set the time to wait for a response (P2)
+    pub fn set_p2(&mut self, p2: Duration) {
+        self.p2 = p2;
+    }
+
+    /// Get the time to wait for a response (P2)
+    pub fn get_p2(&self) -> Duration {
+        self.p2
+    }
+
+    /// Set the time to wait for a response after a pending response (P2*)
+    pub fn set_p2_extended(&mut self, p2_extended: Duration) {
+        self.p2_extended = p2_extended;
+    }
+
+    /// Get the time to wait for a response after a pending response (P2*)
+    pub fn get_p2_extended(&self) -> Duration {
+        self.p2_extended
+    }
+
+    /// Limit the number of pending responses accepted per request, `None` for no limit
+    pub fn set_max_pending_responses(&mut self, max_pending_responses: Option<u32>) {
+        self.max_pending_responses = max_pending_responses;
+    }
+
+    /// Get the number of pending responses accepted per request, `None` for no limit
+    pub fn get_max_pending_responses(&self) -> Option<u32> {
+        self.max_pending_responses
+    }
+
+    /// Get a reference to the underlying socket
+    pub fn get_ref(&self) -> &T {
+        &self.socket
+    }
+
+    /// Consume the client, returning the underlying socket
+    pub fn into_inner(self) -> T {
+        self.socket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::{MockReply, MockTransport};
+
+    #[tokio::test]
+    async fn request_waits_for_final_response() {
+        let mut mock = MockTransport::new().on_request(
+            &[0x31, 0x01, 0xFF, 0x00],
+            MockReply::new()
+                .response(&[0x7F, 0x31, 0x78])
+                .response(&[0x71, 0x01, 0xFF, 0x00]),
+        );
+        let mut client = AsyncUdsClient::new(&mut mock);
+        let response = client.request(0x31, &[0x01, 0xFF, 0x00]).await.unwrap();
+        assert_eq!(response, [0x01, 0xFF, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn responses_of_cancelled_request_are_discarded() {
+        let mut mock =
+            MockTransport::new().on_request(&[0x22, 0xF1, 0x90], &[0x62, 0xF1, 0x90, 0x02]);
+        // response of a request whose future was dropped
+        mock.push_response(Duration::ZERO, &[0x62, 0xF1, 0x90, 0x01]);
+        let mut client = AsyncUdsClient::new(&mut mock);
+        client.in_flight = true;
+        let response = client.request(0x22, &[0xF1, 0x90]).await.unwrap();
+        assert_eq!(response, [0xF1, 0x90, 0x02]);
+    }
+}
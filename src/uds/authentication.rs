@@ -0,0 +1,617 @@
+use super::{SeedKeyError, ServiceId, UdsClient, UdsError, UdsTransport};
+use std::fmt;
+
+/// Length of the algorithmIndicator, an ASN.1 object identifier padded to 16 bytes
+pub const ALGORITHM_INDICATOR_LEN: usize = 16;
+
+/// authenticationReturnParameter of Authentication (0x29) responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AuthenticationReturn {
+    /// RequestAccepted (0x00)
+    RequestAccepted,
+    /// GeneralReject (0x01)
+    GeneralReject,
+    /// AuthenticationConfiguration APCE (0x02), authentication with PKI certificate
+    /// exchange
+    ConfigurationApce,
+    /// AuthenticationConfiguration ACR with asymmetric cryptography (0x03)
+    ConfigurationAcrAsymmetric,
+    /// AuthenticationConfiguration ACR with symmetric cryptography (0x04)
+    ConfigurationAcrSymmetric,
+    /// DeAuthentication successful (0x10)
+    DeAuthenticationSuccessful,
+    /// CertificateVerified, OwnershipVerificationNecessary (0x11)
+    CertificateVerifiedOwnershipVerificationNecessary,
+    /// OwnershipVerified, AuthenticationComplete (0x12)
+    OwnershipVerified,
+    /// CertificateVerified (0x13)
+    CertificateVerified,
+    /// Reserved or vehicle manufacturer specific return value
+    Other(u8),
+}
+
+impl AuthenticationReturn {
+    /// The encoded return value
+    pub fn as_u8(self) -> u8 {
+        match self {
+            AuthenticationReturn::RequestAccepted => 0x00,
+            AuthenticationReturn::GeneralReject => 0x01,
+            AuthenticationReturn::ConfigurationApce => 0x02,
+            AuthenticationReturn::ConfigurationAcrAsymmetric => 0x03,
+            AuthenticationReturn::ConfigurationAcrSymmetric => 0x04,
+            AuthenticationReturn::DeAuthenticationSuccessful => 0x10,
+            AuthenticationReturn::CertificateVerifiedOwnershipVerificationNecessary => 0x11,
+            AuthenticationReturn::OwnershipVerified => 0x12,
+            AuthenticationReturn::CertificateVerified => 0x13,
+            AuthenticationReturn::Other(value) => value,
+        }
+    }
+}
+
+impl From<u8> for AuthenticationReturn {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => AuthenticationReturn::RequestAccepted,
+            0x01 => AuthenticationReturn::GeneralReject,
+            0x02 => AuthenticationReturn::ConfigurationApce,
+            0x03 => AuthenticationReturn::ConfigurationAcrAsymmetric,
+            0x04 => AuthenticationReturn::ConfigurationAcrSymmetric,
+            0x10 => AuthenticationReturn::DeAuthenticationSuccessful,
+            0x11 => AuthenticationReturn::CertificateVerifiedOwnershipVerificationNecessary,
+            0x12 => AuthenticationReturn::OwnershipVerified,
+            0x13 => AuthenticationReturn::CertificateVerified,
+            value => AuthenticationReturn::Other(value),
+        }
+    }
+}
+
+impl fmt::Display for AuthenticationReturn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({:#04X})", self, self.as_u8())
+    }
+}
+
+/// Crypto hooks of the authentication with PKI certificate exchange (APCE)
+pub trait CertificateAuthenticator {
+    /// The certificate of the client
+    fn certificate(&mut self) -> Result<Vec<u8>, SeedKeyError>;
+
+    /// Prove the ownership of the certificate's private key by signing `challenge_server`
+    fn prove_ownership(&mut self, challenge_server: &[u8]) -> Result<Vec<u8>, SeedKeyError>;
+
+    /// The challenge for the server to sign, only sent in bidirectional authentication
+    fn challenge_client(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+        Ok(Vec::new())
+    }
+
+    /// The ephemeral public key of the client for the session key agreement
+    fn ephemeral_public_key(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+        Ok(Vec::new())
+    }
+
+    /// Verify the server's certificate and its proof of ownership of
+    /// [`CertificateAuthenticator::challenge_client`] in bidirectional authentication
+    fn verify_server(
+        &mut self,
+        _certificate_server: &[u8],
+        _proof_of_ownership_server: &[u8],
+    ) -> Result<(), SeedKeyError> {
+        Ok(())
+    }
+}
+
+/// Crypto hooks of the authentication with challenge-response (ACR)
+pub trait ChallengeResponseAuthenticator {
+    /// Prove the ownership of the shared or private key by answering `challenge_server`
+    fn prove_ownership(
+        &mut self,
+        algorithm_indicator: &[u8; ALGORITHM_INDICATOR_LEN],
+        challenge_server: &[u8],
+    ) -> Result<Vec<u8>, SeedKeyError>;
+
+    /// The challenge for the server to answer, only sent in bidirectional authentication
+    fn challenge_client(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+        Ok(Vec::new())
+    }
+
+    /// Verify the server's proof of ownership of
+    /// [`ChallengeResponseAuthenticator::challenge_client`] in bidirectional authentication
+    fn verify_server(&mut self, _proof_of_ownership_server: &[u8]) -> Result<(), SeedKeyError> {
+        Ok(())
+    }
+}
+
+/// Reads the fields of an authentication response
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], UdsError> {
+        if self.0.len() < len {
+            return Err(UdsError::InvalidResponse(
+                "authentication response is truncated",
+            ));
+        }
+        let (field, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(field)
+    }
+
+    /// A field preceded by its 2 byte length
+    fn take_prefixed(&mut self) -> Result<&'a [u8], UdsError> {
+        let len = self.take(2)?;
+        self.take(usize::from(u16::from_be_bytes([len[0], len[1]])))
+    }
+
+    fn take_return(&mut self, expected: &[AuthenticationReturn]) -> Result<(), UdsError> {
+        let value = AuthenticationReturn::from(self.take(1)?[0]);
+        if expected.contains(&value) {
+            Ok(())
+        } else {
+            Err(UdsError::AuthenticationRejected(value))
+        }
+    }
+}
+
+/// Append `field` preceded by its 2 byte length
+fn push_prefixed(request: &mut Vec<u8>, field: &[u8]) -> Result<(), UdsError> {
+    let len = u16::try_from(field.len())
+        .map_err(|_| UdsError::InvalidRequest("authentication parameter exceeds 65535 bytes"))?;
+    request.extend_from_slice(&len.to_be_bytes());
+    request.extend_from_slice(field);
+    Ok(())
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// Authentication (0x29) with the raw request parameters following the sub-function
+    /// `task`, returning the response parameters after the echoed task
+    pub fn authentication(&mut self, task: u8, parameters: &[u8]) -> Result<Vec<u8>, UdsError> {
+        let mut request = Vec::with_capacity(1 + parameters.len());
+        request.push(task);
+        request.extend_from_slice(parameters);
+        let response = self.request(ServiceId::Authentication, &request)?;
+        match response.split_first() {
+            Some((&echo, parameters)) if echo == task => Ok(parameters.to_vec()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the authentication task",
+            )),
+        }
+    }
+
+    /// authenticationConfiguration (0x08), the authentication scheme of the server
+    pub fn authentication_configuration(&mut self) -> Result<AuthenticationReturn, UdsError> {
+        let response = self.authentication(0x08, &[])?;
+        Ok(AuthenticationReturn::from(Fields(&response).take(1)?[0]))
+    }
+
+    /// deAuthenticate (0x00), returning to the unauthenticated state
+    pub fn deauthenticate(&mut self) -> Result<(), UdsError> {
+        let response = self.authentication(0x00, &[])?;
+        Fields(&response).take_return(&[AuthenticationReturn::DeAuthenticationSuccessful])
+    }
+
+    /// transmitCertificate (0x04), e.g. to install a certificate with evaluation id
+    /// `evaluation_id` in an authenticated session
+    pub fn transmit_certificate(
+        &mut self,
+        evaluation_id: u16,
+        certificate: &[u8],
+    ) -> Result<(), UdsError> {
+        let mut request = evaluation_id.to_be_bytes().to_vec();
+        push_prefixed(&mut request, certificate)?;
+        let response = self.authentication(0x04, &request)?;
+        Fields(&response).take_return(&[AuthenticationReturn::CertificateVerified])
+    }
+
+    /// Authenticate with PKI certificate exchange (APCE), verifyCertificateUnidirectional
+    /// or verifyCertificateBidirectional followed by proofOfOwnership.
+    ///
+    /// Returns the sessionKeyInfo of the server.
+    pub fn authenticate_with_certificate(
+        &mut self,
+        communication_configuration: u8,
+        bidirectional: bool,
+        authenticator: &mut impl CertificateAuthenticator,
+    ) -> Result<Vec<u8>, UdsError> {
+        let certificate = authenticator
+            .certificate()
+            .map_err(UdsError::Authentication)?;
+        let challenge_client = if bidirectional {
+            authenticator
+                .challenge_client()
+                .map_err(UdsError::Authentication)?
+        } else {
+            Vec::new()
+        };
+        let mut request = vec![communication_configuration];
+        push_prefixed(&mut request, &certificate)?;
+        push_prefixed(&mut request, &challenge_client)?;
+        let task = if bidirectional { 0x02 } else { 0x01 };
+        let response = self.authentication(task, &request)?;
+
+        let mut fields = Fields(&response);
+        fields.take_return(&[
+            AuthenticationReturn::CertificateVerifiedOwnershipVerificationNecessary,
+        ])?;
+        let challenge_server = fields.take_prefixed()?;
+        if bidirectional {
+            let certificate_server = fields.take_prefixed()?;
+            let proof_of_ownership_server = fields.take_prefixed()?;
+            authenticator
+                .verify_server(certificate_server, proof_of_ownership_server)
+                .map_err(UdsError::Authentication)?;
+        }
+
+        let proof_of_ownership = authenticator
+            .prove_ownership(challenge_server)
+            .map_err(UdsError::Authentication)?;
+        let ephemeral_public_key = authenticator
+            .ephemeral_public_key()
+            .map_err(UdsError::Authentication)?;
+        let mut request = Vec::new();
+        push_prefixed(&mut request, &proof_of_ownership)?;
+        push_prefixed(&mut request, &ephemeral_public_key)?;
+        let response = self.authentication(0x03, &request)?;
+
+        let mut fields = Fields(&response);
+        fields.take_return(&[AuthenticationReturn::OwnershipVerified])?;
+        Ok(fields.take_prefixed()?.to_vec())
+    }
+
+    /// Authenticate with challenge-response (ACR), requestChallengeForAuthentication followed
+    /// by verifyProofOfOwnershipUnidirectional or verifyProofOfOwnershipBidirectional.
+    ///
+    /// Returns the sessionKeyInfo of the server.
+    pub fn authenticate_with_challenge(
+        &mut self,
+        communication_configuration: u8,
+        algorithm_indicator: [u8; ALGORITHM_INDICATOR_LEN],
+        bidirectional: bool,
+        authenticator: &mut impl ChallengeResponseAuthenticator,
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = vec![communication_configuration];
+        request.extend_from_slice(&algorithm_indicator);
+        let response = self.authentication(0x05, &request)?;
+
+        let mut fields = Fields(&response);
+        fields.take_return(&[AuthenticationReturn::RequestAccepted])?;
+        let algorithm_indicator: [u8; ALGORITHM_INDICATOR_LEN] = fields
+            .take(ALGORITHM_INDICATOR_LEN)?
+            .try_into()
+            .expect("field has the algorithm indicator length");
+        let challenge_server = fields.take_prefixed()?;
+        let additional_parameter = fields.take_prefixed()?;
+
+        let proof_of_ownership = authenticator
+            .prove_ownership(&algorithm_indicator, challenge_server)
+            .map_err(UdsError::Authentication)?;
+        let challenge_client = if bidirectional {
+            authenticator
+                .challenge_client()
+                .map_err(UdsError::Authentication)?
+        } else {
+            Vec::new()
+        };
+        let mut request = algorithm_indicator.to_vec();
+        push_prefixed(&mut request, &proof_of_ownership)?;
+        push_prefixed(&mut request, &challenge_client)?;
+        push_prefixed(&mut request, additional_parameter)?;
+        let task = if bidirectional { 0x07 } else { 0x06 };
+        let response = self.authentication(task, &request)?;
+
+        let mut fields = Fields(&response);
+        fields.take_return(&[AuthenticationReturn::OwnershipVerified])?;
+        fields.take(ALGORITHM_INDICATOR_LEN)?;
+        if bidirectional {
+            let proof_of_ownership_server = fields.take_prefixed()?;
+            authenticator
+                .verify_server(proof_of_ownership_server)
+                .map_err(UdsError::Authentication)?;
+        }
+        Ok(fields.take_prefixed()?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::MockTransport;
+
+    const AI: [u8; ALGORITHM_INDICATOR_LEN] = [0xA1; ALGORITHM_INDICATOR_LEN];
+
+    /// Records the server fields passed to the verification hooks, proves ownership by
+    /// reversing the challenge
+    #[derive(Default)]
+    struct Authenticator {
+        verified: Vec<Vec<u8>>,
+    }
+
+    impl CertificateAuthenticator for Authenticator {
+        fn certificate(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+            Ok(vec![0xCE, 0x47])
+        }
+
+        fn prove_ownership(&mut self, challenge_server: &[u8]) -> Result<Vec<u8>, SeedKeyError> {
+            Ok(challenge_server.iter().rev().copied().collect())
+        }
+
+        fn challenge_client(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+            Ok(vec![0xC1])
+        }
+
+        fn ephemeral_public_key(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+            Ok(vec![0xE0])
+        }
+
+        fn verify_server(
+            &mut self,
+            certificate_server: &[u8],
+            proof_of_ownership_server: &[u8],
+        ) -> Result<(), SeedKeyError> {
+            self.verified.push(certificate_server.to_vec());
+            self.verified.push(proof_of_ownership_server.to_vec());
+            Ok(())
+        }
+    }
+
+    impl ChallengeResponseAuthenticator for Authenticator {
+        fn prove_ownership(
+            &mut self,
+            algorithm_indicator: &[u8; ALGORITHM_INDICATOR_LEN],
+            challenge_server: &[u8],
+        ) -> Result<Vec<u8>, SeedKeyError> {
+            assert_eq!(algorithm_indicator, &AI);
+            Ok(challenge_server.iter().rev().copied().collect())
+        }
+
+        fn challenge_client(&mut self) -> Result<Vec<u8>, SeedKeyError> {
+            Ok(vec![0xC1])
+        }
+
+        fn verify_server(&mut self, proof_of_ownership_server: &[u8]) -> Result<(), SeedKeyError> {
+            self.verified.push(proof_of_ownership_server.to_vec());
+            Ok(())
+        }
+    }
+
+    fn certificate_exchange(
+        mock: &mut MockTransport,
+        bidirectional: bool,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>), UdsError> {
+        let mut authenticator = Authenticator::default();
+        let mut client = UdsClient::new(mock);
+        let session_key_info =
+            client.authenticate_with_certificate(0x00, bidirectional, &mut authenticator)?;
+        Ok((session_key_info, authenticator.verified))
+    }
+
+    fn challenge_response(
+        mock: &mut MockTransport,
+        bidirectional: bool,
+    ) -> Result<(Vec<u8>, Vec<Vec<u8>>), UdsError> {
+        let mut authenticator = Authenticator::default();
+        let mut client = UdsClient::new(mock);
+        let session_key_info =
+            client.authenticate_with_challenge(0x00, AI, bidirectional, &mut authenticator)?;
+        Ok((session_key_info, authenticator.verified))
+    }
+
+    #[test]
+    fn configuration_and_deauthentication() {
+        let mut mock = MockTransport::new()
+            .on_request(&[0x29, 0x08], &[0x69, 0x08, 0x02])
+            .on_request_times(&[0x29, 0x00], 1, &[0x69, 0x00, 0x10])
+            .on_request(&[0x29, 0x00], &[0x69, 0x00, 0x01]);
+        let mut client = UdsClient::new(&mut mock);
+        assert_eq!(
+            client.authentication_configuration().unwrap(),
+            AuthenticationReturn::ConfigurationApce
+        );
+        client.deauthenticate().unwrap();
+        let error = client.deauthenticate().unwrap_err();
+        assert!(
+            matches!(
+                error,
+                UdsError::AuthenticationRejected(AuthenticationReturn::GeneralReject)
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn transmit_certificate_prefixes_length() {
+        let mut mock = MockTransport::new()
+            .on_request(&[0x29, 0x04, 0x00, 0x01], &[0x69, 0x04, 0x13])
+            .on_request(&[0x29, 0x04], &[0x69, 0x04]);
+        let mut client = UdsClient::new(&mut mock);
+        client.transmit_certificate(0x0001, &[0xCE, 0x47]).unwrap();
+        let error = client
+            .transmit_certificate(0x0002, &[0xCE, 0x47])
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        assert_eq!(
+            mock.sent()[0],
+            [0x29, 0x04, 0x00, 0x01, 0x00, 0x02, 0xCE, 0x47]
+        );
+    }
+
+    #[test]
+    fn certificate_exchange_unidirectional() {
+        let mut mock = MockTransport::new()
+            .on_request(
+                &[0x29, 0x01, 0x00, 0x00, 0x02, 0xCE, 0x47, 0x00, 0x00],
+                &[0x69, 0x01, 0x11, 0x00, 0x02, 0xAA, 0xBB],
+            )
+            .on_request(
+                &[0x29, 0x03, 0x00, 0x02, 0xBB, 0xAA, 0x00, 0x01, 0xE0],
+                &[0x69, 0x03, 0x12, 0x00, 0x01, 0x5E],
+            );
+        let (session_key_info, verified) = certificate_exchange(&mut mock, false).unwrap();
+        assert_eq!(session_key_info, [0x5E]);
+        assert!(verified.is_empty());
+        assert_eq!(mock.sent().len(), 2);
+    }
+
+    #[test]
+    fn certificate_exchange_bidirectional() {
+        let mut mock = MockTransport::new()
+            .on_request(
+                &[0x29, 0x02, 0x00, 0x00, 0x02, 0xCE, 0x47, 0x00, 0x01, 0xC1],
+                &[
+                    0x69, 0x02, 0x11, 0x00, 0x02, 0xAA, 0xBB, 0x00, 0x01, 0x5C, 0x00, 0x01, 0x50,
+                ],
+            )
+            .on_request(
+                &[0x29, 0x03, 0x00, 0x02, 0xBB, 0xAA],
+                &[0x69, 0x03, 0x12, 0x00, 0x00],
+            );
+        let (session_key_info, verified) = certificate_exchange(&mut mock, true).unwrap();
+        assert!(session_key_info.is_empty());
+        assert_eq!(verified, [vec![0x5C], vec![0x50]]);
+    }
+
+    #[test]
+    fn certificate_exchange_rejects_truncated_responses() {
+        let responses: [&[u8]; 4] = [
+            // challengeServer shorter than its length
+            &[0x69, 0x02, 0x11, 0x00, 0x05, 0xAA],
+            // length of the challengeServer cut off
+            &[0x69, 0x02, 0x11, 0x00],
+            // proofOfOwnershipServer missing
+            &[0x69, 0x02, 0x11, 0x00, 0x02, 0xAA, 0xBB, 0x00, 0x01, 0x5C],
+            &[0x69, 0x02],
+        ];
+        for response in responses {
+            let mut mock = MockTransport::new().on_request(&[0x29, 0x02], response);
+            let error = certificate_exchange(&mut mock, true).unwrap_err();
+            assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        }
+
+        let mut mock = MockTransport::new()
+            .on_request(&[0x29, 0x01], &[0x69, 0x01, 0x11, 0x00, 0x00])
+            .on_request(&[0x29, 0x03], &[0x69, 0x03, 0x12, 0x00, 0x02, 0x5E]);
+        let error = certificate_exchange(&mut mock, false).unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+    }
+
+    #[test]
+    fn certificate_exchange_reports_rejection() {
+        let mut mock = MockTransport::new()
+            .on_request(&[0x29, 0x01], &[0x69, 0x01, 0x11, 0x00, 0x00])
+            .on_request(&[0x29, 0x03], &[0x69, 0x03, 0x01]);
+        let error = certificate_exchange(&mut mock, false).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                UdsError::AuthenticationRejected(AuthenticationReturn::GeneralReject)
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test]
+    fn challenge_response_unidirectional() {
+        let mut mock = MockTransport::new()
+            .on_request(
+                &[[0x29, 0x05, 0x00].as_slice(), &AI].concat(),
+                [
+                    [0x69, 0x05, 0x00].as_slice(),
+                    &AI,
+                    &[0x00, 0x02, 0xCC, 0xDD, 0x00, 0x01, 0xAD],
+                ]
+                .concat(),
+            )
+            .on_request(
+                &[
+                    [0x29, 0x06].as_slice(),
+                    &AI,
+                    &[0x00, 0x02, 0xDD, 0xCC, 0x00, 0x00, 0x00, 0x01, 0xAD],
+                ]
+                .concat(),
+                [[0x69, 0x06, 0x12].as_slice(), &AI, &[0x00, 0x01, 0x5E]].concat(),
+            );
+        let (session_key_info, verified) = challenge_response(&mut mock, false).unwrap();
+        assert_eq!(session_key_info, [0x5E]);
+        assert!(verified.is_empty());
+        assert_eq!(mock.sent().len(), 2);
+    }
+
+    #[test]
+    fn challenge_response_bidirectional() {
+        let mut mock = MockTransport::new()
+            .on_request(
+                &[0x29, 0x05],
+                [
+                    [0x69, 0x05, 0x00].as_slice(),
+                    &AI,
+                    &[0x00, 0x02, 0xCC, 0xDD, 0x00, 0x00],
+                ]
+                .concat(),
+            )
+            .on_request(
+                &[
+                    [0x29, 0x07].as_slice(),
+                    &AI,
+                    &[0x00, 0x02, 0xDD, 0xCC, 0x00, 0x01, 0xC1, 0x00, 0x00],
+                ]
+                .concat(),
+                [
+                    [0x69, 0x07, 0x12].as_slice(),
+                    &AI,
+                    &[0x00, 0x01, 0x50, 0x00, 0x01, 0x5E],
+                ]
+                .concat(),
+            );
+        let (session_key_info, verified) = challenge_response(&mut mock, true).unwrap();
+        assert_eq!(session_key_info, [0x5E]);
+        assert_eq!(verified, [vec![0x50]]);
+    }
+
+    #[test]
+    fn challenge_response_rejects_truncated_responses() {
+        let challenges = [
+            // algorithmIndicator cut off
+            [[0x69, 0x05, 0x00].as_slice(), &AI[..10]].concat(),
+            // additionalParameter missing
+            [
+                [0x69, 0x05, 0x00].as_slice(),
+                &AI,
+                &[0x00, 0x02, 0xCC, 0xDD],
+            ]
+            .concat(),
+            [
+                [0x69, 0x05, 0x00].as_slice(),
+                &AI,
+                &[0x00, 0x03, 0xCC, 0xDD],
+            ]
+            .concat(),
+        ];
+        for challenge in challenges {
+            let mut mock = MockTransport::new().on_request(&[0x29, 0x05], challenge);
+            let error = challenge_response(&mut mock, false).unwrap_err();
+            assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        }
+
+        let verifications = [
+            // proofOfOwnershipServer missing
+            [[0x69, 0x07, 0x12].as_slice(), &AI].concat(),
+            // sessionKeyInfo missing
+            [[0x69, 0x07, 0x12].as_slice(), &AI, &[0x00, 0x01, 0x50]].concat(),
+        ];
+        for verification in verifications {
+            let mut mock = MockTransport::new()
+                .on_request(
+                    &[0x29, 0x05],
+                    [
+                        [0x69, 0x05, 0x00].as_slice(),
+                        &AI,
+                        &[0x00, 0x00, 0x00, 0x00],
+                    ]
+                    .concat(),
+                )
+                .on_request(&[0x29, 0x07], verification);
+            let error = challenge_response(&mut mock, true).unwrap_err();
+            assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use super::response_matcher::{is_event, Matched, ResponseMatcher};
+use super::{
+    DiagnosticSession, Request, RetryPolicy, SessionTrace, TesterPresentKeeper, UdsError,
+    UdsTransport,
+};
+use crate::clock::{Clock, SystemClock};
+use crate::IsoTpSocket;
+use std::collections::VecDeque;
+use std::io;
+use std::os::unix::io::AsFd;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default server response timeout (P2) as defined by ISO 14229-2
+pub const DEFAULT_P2: Duration = Duration::from_millis(50);
+
+/// Event responses queued while waiting for responses, older events are dropped
+const MAX_QUEUED_EVENTS: usize = 256;
+
+/// Default enhanced server response timeout after a pending response (P2*) as defined by
+/// ISO 14229-2
+pub const DEFAULT_P2_EXTENDED: Duration = Duration::from_millis(5000);
+
+/// A UDS client sending requests to a single server over an [`IsoTpSocket`] or another
+/// [`UdsTransport`].
+///
+/// Responses to other services, e.g. late responses of timed out requests, are skipped.
+/// Pending responses (NRC 0x78) restart the wait with the enhanced timeout P2*.
+pub struct UdsClient<T = IsoTpSocket> {
+    transport: T,
+    p2: Duration,
+    p2_extended: Duration,
+    max_pending_responses: Option<u32>,
+    retry_policy: RetryPolicy,
+    trace: Option<SessionTrace>,
+    /// time source of the timeouts and delays
+    pub(super) clock: Arc<dyn Clock>,
+    /// held while a request is in progress, contains the time of the last request
+    activity: Arc<Mutex<Instant>>,
+    /// active diagnostic session as last confirmed by the server
+    pub(super) session: DiagnosticSession,
+    /// earliest time to send the next request, e.g. while the server resets
+    ready_at: Option<Instant>,
+    /// DTC setting switched off by ControlDTCSetting in the current session
+    pub(super) dtc_setting_off: bool,
+    /// prefixes of ResponseOnEvent and periodic responses, see [`UdsClient::next_event`]
+    pub(super) event_responses: Vec<Vec<u8>>,
+    /// event responses received while waiting for a response
+    events: VecDeque<Vec<u8>>,
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// Create a client using the default response timeout [`DEFAULT_P2`]
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            p2: DEFAULT_P2,
+            p2_extended: DEFAULT_P2_EXTENDED,
+            max_pending_responses: None,
+            retry_policy: RetryPolicy::never(),
+            trace: None,
+            clock: Arc::new(SystemClock),
+            activity: Arc::new(Mutex::new(Instant::now())),
+            session: DiagnosticSession::Default,
+            ready_at: None,
+            dtc_setting_off: false,
+            event_responses: Vec::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Send a request and wait for the response of the server.
+    ///
+    /// Returns the response parameters following the positive response service identifier
+    /// `service + 0x40`. Negative responses fail with [`UdsError::Negative`].
+    pub fn request(&mut self, service: impl Into<u8>, data: &[u8]) -> Result<Vec<u8>, UdsError> {
+        let request = Request::new(service).data(data);
+        self.send(&request)?.ok_or(UdsError::Timeout)
+    }
+
+    /// Send an encoded request and wait for the response of the server.
+    ///
+    /// If the positive response is suppressed, waits for a negative response only and
+    /// returns `None` if the server didn't answer in time. After a pending response the
+    /// server always sends a final response.
+    ///
+    /// Failed requests are repeated according to the [`RetryPolicy`].
+    pub fn send(&mut self, request: &Request) -> Result<Option<Vec<u8>>, UdsError> {
+        let mut attempt = 1;
+        loop {
+            match self.send_once(request) {
+                Err(e)
+                    if attempt < self.retry_policy.get_max_attempts()
+                        && self.retry_policy.is_retryable(&e) =>
+                {
+                    self.clock.sleep(self.retry_policy.backoff(attempt));
+                    attempt += 1;
+                }
+                response => return response,
+            }
+        }
+    }
+
+    fn send_once(&mut self, request: &Request) -> Result<Option<Vec<u8>>, UdsError> {
+        if let Some(trace) = &mut self.trace {
+            trace.begin(request.as_bytes());
+        }
+        let response = self.exchange(request);
+        if let Some(trace) = &mut self.trace {
+            trace.end(&response);
+        }
+        response
+    }
+
+    /// Send the next request no earlier than `delay` from now, e.g. while the server resets
+    pub(crate) fn delay_next_request(&mut self, delay: Duration) {
+        self.ready_at = Some(self.clock.now() + delay);
+    }
+
+    fn exchange(&mut self, request: &Request) -> Result<Option<Vec<u8>>, UdsError> {
+        if let Some(ready_at) = self.ready_at.take() {
+            self.clock
+                .sleep(ready_at.saturating_duration_since(self.clock.now()));
+        }
+        // a poisoned lock only means a keeper thread panicked
+        let mut last_request = self
+            .activity
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        *last_request = Instant::now();
+        if request.get_wait_tx_done() {
+            self.transport.send_wait_tx_done(request.as_bytes())?;
+        } else {
+            self.transport.send(request.as_bytes())?;
+        }
+
+        let mut matcher =
+            ResponseMatcher::new(request, self.max_pending_responses, &self.event_responses);
+        let mut deadline = self.clock.now() + self.p2;
+        loop {
+            let timeout = deadline.saturating_duration_since(self.clock.now());
+            let Some(response) = self.transport.receive(timeout)? else {
+                return matcher.on_timeout();
+            };
+            if let Some(trace) = &mut self.trace {
+                trace.record_response(response);
+            }
+            match matcher.on_response(response) {
+                Matched::Done(result) => return result,
+                Matched::Pending => deadline = self.clock.now() + self.p2_extended,
+                Matched::Event => {
+                    if self.events.len() == MAX_QUEUED_EVENTS {
+                        self.events.pop_front();
+                    }
+                    self.events.push_back(response.to_vec());
+                }
+                Matched::Skip => (),
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for a response sent by the server on a ResponseOnEvent event.
+    ///
+    /// Event responses received while waiting for the response to a request are queued and
+    /// returned first, up to the latest 256. Other responses are skipped.
+    pub fn next_event(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, UdsError> {
+        if let Some(event) = self.events.pop_front() {
+            return Ok(Some(event));
+        }
+        let deadline = self.clock.now() + timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(self.clock.now());
+            let Some(response) = self.transport.receive(timeout)? else {
+                return Ok(None);
+            };
+            if is_event(&self.event_responses, response) {
+                return Ok(Some(response.to_vec()));
+            }
+        }
+    }
+
+    /// Set the time to wait for a response (P2)
+    pub fn set_p2(&mut self, p2: Duration) {
+        self.p2 = p2;
+    }
+
+    /// Get the time to wait for a response (P2)
+    pub fn get_p2(&self) -> Duration {
+        self.p2
+    }
+
+    /// Set the time to wait for a response after a pending response (P2*)
+    pub fn set_p2_extended(&mut self, p2_extended: Duration) {
+        self.p2_extended = p2_extended;
+    }
+
+    /// Get the time to wait for a response after a pending response (P2*)
+    pub fn get_p2_extended(&self) -> Duration {
+        self.p2_extended
+    }
+
+    /// Limit the number of pending responses accepted per request, `None` for no limit
+    pub fn set_max_pending_responses(&mut self, max_pending_responses: Option<u32>) {
+        self.max_pending_responses = max_pending_responses;
+    }
+
+    /// Get the number of pending responses accepted per request, `None` for no limit
+    pub fn get_max_pending_responses(&self) -> Option<u32> {
+        self.max_pending_responses
+    }
+
+    /// Set the policy repeating failed requests, by default requests are sent once
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Get the policy repeating failed requests
+    pub fn get_retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Set the time source of the response timeouts and delays, e.g. a
+    /// [`SimulatedClock`](crate::clock::SimulatedClock) in tests
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// Get the time source of the response timeouts and delays
+    pub fn get_clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Record all requests and responses from now on, replacing a previous trace
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(SessionTrace::new());
+    }
+
+    /// Stop recording, returning the trace
+    pub fn take_trace(&mut self) -> Option<SessionTrace> {
+        self.trace.take()
+    }
+
+    /// Get the trace recorded since [`UdsClient::enable_trace`]
+    pub fn get_trace(&self) -> Option<&SessionTrace> {
+        self.trace.as_ref()
+    }
+
+    /// Get a reference to the underlying transport
+    pub fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    /// Get a mutable reference to the underlying transport
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Consume the client, returning the underlying transport
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+}
+
+impl UdsClient<IsoTpSocket> {
+    /// Keep the diagnostic session alive by sending TesterPresent every `interval`.
+    ///
+    /// The keep-alive is skipped while a request is in progress and if the last request
+    /// was sent less than `interval` ago. See [`TesterPresentKeeper`].
+    pub fn tester_present_keeper(&self, interval: Duration) -> io::Result<TesterPresentKeeper> {
+        let fd = self.transport.as_fd().try_clone_to_owned()?;
+        Ok(TesterPresentKeeper::spawn(
+            fd,
+            Arc::clone(&self.activity),
+            interval,
+        ))
+    }
+}
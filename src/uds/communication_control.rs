@@ -0,0 +1,122 @@
+use super::{ServiceId, UdsClient, UdsError, UdsTransport};
+use bitflags::bitflags;
+
+/// Sub-functions of CommunicationControl (0x28)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CommunicationControlType {
+    /// enableRxAndTx (0x00)
+    EnableRxAndTx,
+    /// enableRxAndDisableTx (0x01)
+    EnableRxAndDisableTx,
+    /// disableRxAndEnableTx (0x02)
+    DisableRxAndEnableTx,
+    /// disableRxAndTx (0x03)
+    DisableRxAndTx,
+    /// enableRxAndDisableTxWithEnhancedAddressInformation (0x04) of the node with the
+    /// given nodeIdentificationNumber
+    EnableRxAndDisableTxWithEnhancedAddress(u16),
+    /// enableRxAndTxWithEnhancedAddressInformation (0x05) of the node with the given
+    /// nodeIdentificationNumber
+    EnableRxAndTxWithEnhancedAddress(u16),
+    /// Vehicle manufacturer or system supplier specific control type
+    Other(u8),
+}
+
+impl CommunicationControlType {
+    /// The encoded sub-function
+    pub fn as_u8(self) -> u8 {
+        match self {
+            CommunicationControlType::EnableRxAndTx => 0x00,
+            CommunicationControlType::EnableRxAndDisableTx => 0x01,
+            CommunicationControlType::DisableRxAndEnableTx => 0x02,
+            CommunicationControlType::DisableRxAndTx => 0x03,
+            CommunicationControlType::EnableRxAndDisableTxWithEnhancedAddress(_) => 0x04,
+            CommunicationControlType::EnableRxAndTxWithEnhancedAddress(_) => 0x05,
+            CommunicationControlType::Other(control_type) => control_type,
+        }
+    }
+}
+
+bitflags! {
+    /// Message groups affected by CommunicationControl
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(transparent))]
+    pub struct CommunicationMessages: u8 {
+        /// normal communication messages
+        const NORMAL = 0x01;
+        /// network management communication messages
+        const NETWORK_MANAGEMENT = 0x02;
+    }
+}
+
+/// communicationType parameter of CommunicationControl
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CommunicationType {
+    messages: CommunicationMessages,
+    subnet: u8,
+}
+
+impl CommunicationType {
+    /// Subnet number addressing all networks
+    pub const ALL_NETWORKS: u8 = 0x0;
+
+    /// Subnet number addressing the network the request is received on
+    pub const RECEIVING_NETWORK: u8 = 0xF;
+
+    /// Control `messages` on all networks
+    pub fn new(messages: CommunicationMessages) -> Self {
+        Self {
+            messages,
+            subnet: Self::ALL_NETWORKS,
+        }
+    }
+
+    /// Restrict control to subnet `subnet` (1 - 14), see also [`Self::RECEIVING_NETWORK`]
+    pub fn subnet(mut self, subnet: u8) -> Self {
+        self.subnet = subnet & 0x0F;
+        self
+    }
+
+    /// The encoded communicationType byte
+    pub fn as_u8(&self) -> u8 {
+        self.subnet << 4 | self.messages.bits()
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// CommunicationControl (0x28), e.g. disabling normal messages before flashing
+    ///
+    /// ```rust,no_run
+    /// # fn example(client: &mut socketcan_isotp::uds::UdsClient) -> Result<(), socketcan_isotp::uds::UdsError> {
+    /// use socketcan_isotp::uds::{CommunicationControlType, CommunicationMessages, CommunicationType};
+    ///
+    /// client.communication_control(
+    ///     CommunicationControlType::DisableRxAndTx,
+    ///     CommunicationType::new(CommunicationMessages::all()),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn communication_control(
+        &mut self,
+        control_type: CommunicationControlType,
+        communication_type: CommunicationType,
+    ) -> Result<(), UdsError> {
+        let mut request = vec![control_type.as_u8(), communication_type.as_u8()];
+        if let CommunicationControlType::EnableRxAndDisableTxWithEnhancedAddress(node)
+        | CommunicationControlType::EnableRxAndTxWithEnhancedAddress(node) = control_type
+        {
+            request.extend_from_slice(&node.to_be_bytes());
+        }
+        let response = self.request(ServiceId::CommunicationControl, &request)?;
+        match response.first() {
+            Some(&echo) if echo == control_type.as_u8() => Ok(()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the control type",
+            )),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use super::{ServiceId, UdsClient, UdsError, UdsTransport};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Data identifier (DID)
+pub type Did = u16;
+
+/// Encoding of a data record registered in a [`DidRegistry`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DidCodec {
+    /// Raw bytes, `None` if the record length is variable
+    Raw(Option<usize>),
+    /// ASCII/UTF-8 string of fixed length, trailing `0x00` and `0xFF` padding is trimmed
+    Ascii(usize),
+    /// Big endian unsigned integer of 1 - 8 bytes
+    Unsigned(usize),
+    /// Big endian two's complement integer of 1 - 8 bytes
+    Signed(usize),
+}
+
+impl DidCodec {
+    /// Length of the data record in bytes, `None` if variable
+    pub fn record_len(&self) -> Option<usize> {
+        match *self {
+            DidCodec::Raw(len) => len,
+            DidCodec::Ascii(len) | DidCodec::Unsigned(len) | DidCodec::Signed(len) => Some(len),
+        }
+    }
+
+    /// Decode a data record
+    pub fn decode(&self, data: &[u8]) -> Result<DidValue, UdsError> {
+        if self.record_len().is_some_and(|len| len != data.len()) {
+            return Err(UdsError::InvalidResponse(
+                "data record length does not match the registered length",
+            ));
+        }
+        match self {
+            DidCodec::Raw(_) => Ok(DidValue::Raw(data.to_vec())),
+            DidCodec::Ascii(_) => {
+                let end = data
+                    .iter()
+                    .rposition(|byte| *byte != 0x00 && *byte != 0xFF)
+                    .map_or(0, |position| position + 1);
+                Ok(DidValue::String(
+                    String::from_utf8_lossy(&data[..end]).into_owned(),
+                ))
+            }
+            DidCodec::Unsigned(len) | DidCodec::Signed(len) if !(1..=8).contains(len) => Err(
+                UdsError::InvalidRequest("integer data records must be 1 - 8 bytes long"),
+            ),
+            DidCodec::Unsigned(_) => Ok(DidValue::Unsigned(
+                data.iter()
+                    .fold(0, |value, byte| value << 8 | u64::from(*byte)),
+            )),
+            DidCodec::Signed(len) => {
+                let value = data
+                    .iter()
+                    .fold(0, |value, byte| value << 8 | u64::from(*byte));
+                // sign extend the most significant bit of the record
+                let shift = 64 - 8 * len;
+                Ok(DidValue::Signed((value << shift) as i64 >> shift))
+            }
+        }
+    }
+}
+
+/// A decoded data record
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DidValue {
+    /// Raw bytes
+    Raw(Vec<u8>),
+    /// Text
+    String(String),
+    /// Unsigned integer
+    Unsigned(u64),
+    /// Signed integer
+    Signed(i64),
+}
+
+impl fmt::Display for DidValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DidValue::Raw(data) => {
+                for (i, byte) in data.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{:02X}", byte)?;
+                }
+                Ok(())
+            }
+            DidValue::String(text) => write!(f, "{}", text),
+            DidValue::Unsigned(value) => write!(f, "{}", value),
+            DidValue::Signed(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Data record encodings by data identifier
+///
+/// The lengths are required to split responses to requests reading several DIDs.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{DidCodec, DidRegistry, UdsClient};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = UdsClient::new(tp_socket);
+///
+///     let mut registry = DidRegistry::new();
+///     registry.register(0xF190, DidCodec::Ascii(17));
+///     registry.register(0xF18C, DidCodec::Raw(Some(8)));
+///     for (did, value) in registry.read(&mut client, &[0xF190, 0xF18C])? {
+///         println!("{:04X}: {}", did, value);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DidRegistry {
+    codecs: HashMap<Did, DidCodec>,
+}
+
+impl DidRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the encoding of `did`, replacing a previous registration
+    pub fn register(&mut self, did: Did, codec: DidCodec) -> &mut Self {
+        self.codecs.insert(did, codec);
+        self
+    }
+
+    /// get the registered encoding of `did`
+    pub fn get(&self, did: Did) -> Option<DidCodec> {
+        self.codecs.get(&did).copied()
+    }
+
+    /// Decode the data record of `did`, unregistered DIDs are returned raw
+    pub fn decode(&self, did: Did, data: &[u8]) -> Result<DidValue, UdsError> {
+        self.get(did).unwrap_or(DidCodec::Raw(None)).decode(data)
+    }
+
+    /// Read and decode `dids` with a single request
+    pub fn read(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        dids: &[Did],
+    ) -> Result<Vec<(Did, DidValue)>, UdsError> {
+        client
+            .read_data_by_identifiers(dids, self)?
+            .into_iter()
+            .map(|(did, data)| Ok((did, self.decode(did, &data)?)))
+            .collect()
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ReadDataByIdentifier (0x22) of a single DID, returning its data record
+    pub fn read_data_by_identifier(&mut self, did: Did) -> Result<Vec<u8>, UdsError> {
+        let response = self.request(ServiceId::ReadDataByIdentifier, &did.to_be_bytes())?;
+        match response.split_first_chunk::<2>() {
+            Some((echo, data)) if Did::from_be_bytes(*echo) == did => Ok(data.to_vec()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the data identifier",
+            )),
+        }
+    }
+
+    /// WriteDataByIdentifier (0x2E) of a single data record
+    ///
+    /// Negative responses, e.g. [`Nrc::SecurityAccessDenied`](super::Nrc::SecurityAccessDenied)
+    /// for protected DIDs, fail with [`UdsError::Negative`].
+    pub fn write_data_by_identifier(&mut self, did: Did, data: &[u8]) -> Result<(), UdsError> {
+        let mut request = Vec::with_capacity(2 + data.len());
+        request.extend_from_slice(&did.to_be_bytes());
+        request.extend_from_slice(data);
+        let response = self.request(ServiceId::WriteDataByIdentifier, &request)?;
+        match response.as_slice() {
+            [hi, lo] if Did::from_be_bytes([*hi, *lo]) == did => Ok(()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the data identifier",
+            )),
+        }
+    }
+
+    /// ReadDataByIdentifier (0x22) of several DIDs with a single request
+    ///
+    /// The response is split into the data records using the lengths registered in
+    /// `registry`, only the last DID may have a variable length.
+    pub fn read_data_by_identifiers(
+        &mut self,
+        dids: &[Did],
+        registry: &DidRegistry,
+    ) -> Result<Vec<(Did, Vec<u8>)>, UdsError> {
+        if dids.is_empty() {
+            return Err(UdsError::InvalidRequest("no data identifier given"));
+        }
+        let request: Vec<u8> = dids.iter().flat_map(|did| did.to_be_bytes()).collect();
+        let response = self.request(ServiceId::ReadDataByIdentifier, &request)?;
+
+        let mut records = Vec::with_capacity(dids.len());
+        let mut remaining = response.as_slice();
+        for (i, did) in dids.iter().enumerate() {
+            let Some((echo, rest)) = remaining.split_first_chunk::<2>() else {
+                return Err(UdsError::InvalidResponse(
+                    "response is missing data records",
+                ));
+            };
+            if Did::from_be_bytes(*echo) != *did {
+                return Err(UdsError::InvalidResponse(
+                    "response does not echo the data identifier",
+                ));
+            }
+            let len = match registry.get(*did).and_then(|codec| codec.record_len()) {
+                Some(len) => len,
+                None if i + 1 == dids.len() => rest.len(),
+                None => {
+                    return Err(UdsError::InvalidRequest(
+                        "only the last data identifier may have an unknown length",
+                    ))
+                }
+            };
+            if rest.len() < len {
+                return Err(UdsError::InvalidResponse("data record is truncated"));
+            }
+            let (data, rest) = rest.split_at(len);
+            records.push((*did, data.to_vec()));
+            remaining = rest;
+        }
+        Ok(records)
+    }
+}
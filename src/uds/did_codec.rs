@@ -0,0 +1,236 @@
+use super::{Did, UdsClient, UdsError, UdsTransport};
+use std::convert::TryInto;
+
+/// Decodes a data record into a Rust type
+///
+/// Implemented for integers and floats (big endian, exact length), strings (trailing
+/// `0x00`/`0xFF` padding trimmed), byte vectors and arrays, and [`Scaled`] fixed-point values.
+pub trait DidDecode: Sized {
+    /// Decode the data record following the data identifier
+    fn decode(data: &[u8]) -> Result<Self, UdsError>;
+}
+
+/// Encodes a Rust type as a data record
+pub trait DidEncode {
+    /// Encode the data record following the data identifier
+    fn encode(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_did_codec_be {
+    ($($ty:ty),*) => {
+        $(
+            impl DidDecode for $ty {
+                fn decode(data: &[u8]) -> Result<Self, UdsError> {
+                    data.try_into().map(<$ty>::from_be_bytes).map_err(|_| {
+                        UdsError::InvalidResponse("data record length does not match the type")
+                    })
+                }
+            }
+
+            impl DidEncode for $ty {
+                fn encode(&self) -> Vec<u8> {
+                    self.to_be_bytes().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_did_codec_be!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl DidDecode for Vec<u8> {
+    fn decode(data: &[u8]) -> Result<Self, UdsError> {
+        Ok(data.to_vec())
+    }
+}
+
+impl DidEncode for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl<const N: usize> DidDecode for [u8; N] {
+    fn decode(data: &[u8]) -> Result<Self, UdsError> {
+        data.try_into()
+            .map_err(|_| UdsError::InvalidResponse("data record length does not match the type"))
+    }
+}
+
+impl<const N: usize> DidEncode for [u8; N] {
+    fn encode(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl DidDecode for String {
+    fn decode(data: &[u8]) -> Result<Self, UdsError> {
+        let end = data
+            .iter()
+            .rposition(|byte| *byte != 0x00 && *byte != 0xFF)
+            .map_or(0, |position| position + 1);
+        String::from_utf8(data[..end].to_vec())
+            .map_err(|_| UdsError::InvalidResponse("data record is not valid UTF-8"))
+    }
+}
+
+impl DidEncode for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl DidEncode for str {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+/// Fixed-point value with physical value `raw * MUL / DIV + OFFSET`
+///
+/// ```rust
+/// use socketcan_isotp::uds::{DidDecode, DidEncode, Scaled};
+///
+/// // coolant temperature in 0.1 °C with -40 °C offset
+/// type Temperature = Scaled<u16, 1, 10, -40>;
+/// let temperature = Temperature::decode(&[0x02, 0x8A]).unwrap();
+/// assert_eq!(temperature.value(), 25.0);
+/// assert_eq!(Temperature::new(25.0).encode(), vec![0x02, 0x8A]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Scaled<T, const MUL: i64, const DIV: i64, const OFFSET: i64> {
+    value: f64,
+    raw: std::marker::PhantomData<T>,
+}
+
+impl<T, const MUL: i64, const DIV: i64, const OFFSET: i64> Scaled<T, MUL, DIV, OFFSET> {
+    /// A physical value, rounded to the raw resolution on encoding
+    pub fn new(value: f64) -> Self {
+        Self {
+            value,
+            raw: std::marker::PhantomData,
+        }
+    }
+
+    /// The physical value
+    pub fn value(&self) -> f64 {
+        self.value
+    }
+}
+
+impl<T, const MUL: i64, const DIV: i64, const OFFSET: i64> DidDecode for Scaled<T, MUL, DIV, OFFSET>
+where
+    T: DidDecode + ScaledRaw,
+{
+    fn decode(data: &[u8]) -> Result<Self, UdsError> {
+        let raw = T::decode(data)?.to_f64();
+        Ok(Self::new(raw * MUL as f64 / DIV as f64 + OFFSET as f64))
+    }
+}
+
+impl<T, const MUL: i64, const DIV: i64, const OFFSET: i64> DidEncode for Scaled<T, MUL, DIV, OFFSET>
+where
+    T: DidEncode + ScaledRaw,
+{
+    fn encode(&self) -> Vec<u8> {
+        let raw = ((self.value - OFFSET as f64) * DIV as f64 / MUL as f64).round();
+        T::from_f64(raw).encode()
+    }
+}
+
+/// Raw type of a [`Scaled`] value, implemented for the integer and float types
+///
+/// The trait is sealed, the conversions are only defined for the primitive types.
+pub trait ScaledRaw: sealed::Sealed + Sized {
+    /// The raw value as `f64`, 64 bit integers beyond 2^53 are rounded
+    fn to_f64(self) -> f64;
+
+    /// Saturating conversion of a rounded physical value into the raw type
+    fn from_f64(value: f64) -> Self;
+}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_scaled_raw {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl ScaledRaw for $ty {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+
+                fn from_f64(value: f64) -> Self {
+                    value as $ty
+                }
+            }
+        )*
+    };
+}
+
+impl_scaled_raw!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ReadDataByIdentifier (0x22) of `did`, decoding the data record as `T`
+    pub fn read_did<V: DidDecode>(&mut self, did: Did) -> Result<V, UdsError> {
+        V::decode(&self.read_data_by_identifier(did)?)
+    }
+
+    /// WriteDataByIdentifier (0x2E) of `did` with `value` encoded as data record
+    pub fn write_did<V: DidEncode + ?Sized>(
+        &mut self,
+        did: Did,
+        value: &V,
+    ) -> Result<(), UdsError> {
+        self.write_data_by_identifier(did, &value.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_round_trips_every_raw_type() {
+        fn round_trip<T: DidDecode + DidEncode + ScaledRaw>(value: f64, encoded: &[u8]) {
+            assert_eq!(Scaled::<T, 1, 10, -40>::new(value).encode(), encoded);
+            let decoded = Scaled::<T, 1, 10, -40>::decode(encoded).unwrap();
+            assert!(
+                (decoded.value() - value).abs() < 1e-3,
+                "{}",
+                decoded.value()
+            );
+        }
+
+        round_trip::<u8>(-15.0, &[0xFA]);
+        round_trip::<i8>(-45.0, &[0xCE]);
+        round_trip::<u16>(25.0, &[0x02, 0x8A]);
+        round_trip::<i16>(-45.0, &[0xFF, 0xCE]);
+        round_trip::<u32>(25.0, &[0x00, 0x00, 0x02, 0x8A]);
+        round_trip::<i32>(-45.0, &[0xFF, 0xFF, 0xFF, 0xCE]);
+        round_trip::<u64>(25.0, &[0, 0, 0, 0, 0, 0, 0x02, 0x8A]);
+        round_trip::<i64>(-45.0, &[0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xCE]);
+        round_trip::<f32>(25.0, &650f32.to_be_bytes());
+        round_trip::<f64>(25.0, &650f64.to_be_bytes());
+    }
+
+    #[test]
+    fn scaled_encoding_saturates() {
+        assert_eq!(Scaled::<u8, 1, 1, 0>::new(300.0).encode(), [0xFF]);
+        assert_eq!(Scaled::<u8, 1, 1, 0>::new(-1.0).encode(), [0x00]);
+        assert_eq!(
+            Scaled::<i64, 1, 1, 0>::new(f64::MAX).encode(),
+            i64::MAX.to_be_bytes()
+        );
+    }
+
+    #[test]
+    fn decode_rejects_length_mismatch() {
+        assert!(u16::decode(&[0x01]).is_err());
+        assert!(<[u8; 3]>::decode(&[0x01, 0x02]).is_err());
+        assert_eq!(String::decode(b"SW\x00\xFF").unwrap(), "SW");
+    }
+}
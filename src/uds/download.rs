@@ -0,0 +1,321 @@
+use super::memory::decode_be;
+use super::{AddressAndLengthFormat, Nrc, ServiceId, UdsClient, UdsError, UdsTransport};
+use crate::MAX_PDU_LENGTH;
+
+/// Progress of a [`Download`], passed to the progress callback after every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DownloadProgress {
+    /// Bytes of the image transferred so far, including a resumed offset
+    pub transferred: usize,
+    /// Total size of the image in bytes
+    pub total: usize,
+    /// blockSequenceCounter of the last transferred block
+    pub block_sequence_counter: u8,
+}
+
+/// Download of a memory image with RequestDownload (0x34), TransferData (0x36) and
+/// RequestTransferExit (0x37)
+///
+/// The image is split into blocks of the maximum size announced by the server. Blocks are
+/// repeated with the same block sequence counter on timeouts and transient negative
+/// responses. After a failure the download can be resumed from the last reported
+/// [`DownloadProgress::transferred`] offset, if the server supports it.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{Download, UdsClient};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = UdsClient::new(tp_socket);
+///     let image = std::fs::read("firmware.bin").expect("Failed to read image");
+///
+///     Download::new(0x0800_0000)
+///         .max_retries(3)
+///         .run(&mut client, &image, |progress| {
+///             println!("{} / {} bytes", progress.transferred, progress.total)
+///         })
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Download {
+    address: u64,
+    format: Option<AddressAndLengthFormat>,
+    data_format: u8,
+    max_retries: u32,
+    offset: usize,
+}
+
+impl Download {
+    /// Download to the memory at `address`
+    pub fn new(address: u64) -> Self {
+        Self {
+            address,
+            format: None,
+            data_format: 0x00,
+            max_retries: 0,
+            offset: 0,
+        }
+    }
+
+    /// Encode address and size with `format`, by default the narrowest format is used
+    pub fn address_and_length_format(mut self, format: AddressAndLengthFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the dataFormatIdentifier announcing compression and encryption, `0x00` for none
+    pub fn data_format(mut self, data_format: u8) -> Self {
+        self.data_format = data_format;
+        self
+    }
+
+    /// Repeat a failed block up to `max_retries` times
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Skip the first `offset` bytes of the image, downloading the rest to `address + offset`
+    pub fn resume_from(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Download `image`, calling `progress` after every transferred block
+    pub fn run(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        image: &[u8],
+        mut progress: impl FnMut(DownloadProgress),
+    ) -> Result<(), UdsError> {
+        let Some(remaining) = image.get(self.offset..) else {
+            return Err(UdsError::InvalidRequest("resume offset exceeds the image"));
+        };
+        let address = u64::try_from(self.offset)
+            .ok()
+            .and_then(|offset| self.address.checked_add(offset))
+            .ok_or(UdsError::InvalidRequest(
+                "resume address exceeds the address space",
+            ))?;
+        let size = remaining.len() as u64;
+        let block_len = self.request_download(client, address, size)?;
+
+        let mut block_sequence_counter = 0u8;
+        let mut transferred = self.offset;
+        for block in remaining.chunks(block_len) {
+            block_sequence_counter = block_sequence_counter.wrapping_add(1);
+            self.transfer_block(client, block_sequence_counter, block)?;
+            transferred += block.len();
+            progress(DownloadProgress {
+                transferred,
+                total: image.len(),
+                block_sequence_counter,
+            });
+        }
+
+        client.request(ServiceId::RequestTransferExit, &[])?;
+        Ok(())
+    }
+
+    /// RequestDownload, returning the maximum payload length of TransferData blocks
+    fn request_download(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        address: u64,
+        size: u64,
+    ) -> Result<usize, UdsError> {
+        let format = self
+            .format
+            .unwrap_or_else(|| AddressAndLengthFormat::minimal(address, size));
+        let mut request = vec![self.data_format, format.as_u8()];
+        request.extend(format.encode(address, size)?);
+        let response = client.request(ServiceId::RequestDownload, &request)?;
+
+        let Some((length_format, max_block_len)) = response.split_first() else {
+            return Err(UdsError::InvalidResponse("missing lengthFormatIdentifier"));
+        };
+        let max_block_len = max_block_len
+            .get(..usize::from(length_format >> 4))
+            .and_then(decode_be)
+            .ok_or(UdsError::InvalidResponse("invalid maxNumberOfBlockLength"))?;
+        // the block length includes service identifier and block sequence counter
+        let block_len = usize::try_from(max_block_len)
+            .unwrap_or(usize::MAX)
+            .min(MAX_PDU_LENGTH)
+            .saturating_sub(2);
+        if block_len == 0 {
+            return Err(UdsError::InvalidResponse(
+                "maxNumberOfBlockLength leaves no room for data",
+            ));
+        }
+        Ok(block_len)
+    }
+
+    fn transfer_block(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        block_sequence_counter: u8,
+        block: &[u8],
+    ) -> Result<(), UdsError> {
+        let mut request = Vec::with_capacity(1 + block.len());
+        request.push(block_sequence_counter);
+        request.extend_from_slice(block);
+
+        let mut retries = 0;
+        loop {
+            match client.request(ServiceId::TransferData, &request) {
+                Ok(response) if response.first() == Some(&block_sequence_counter) => return Ok(()),
+                Ok(_) => {
+                    return Err(UdsError::InvalidResponse(
+                        "response does not echo the block sequence counter",
+                    ))
+                }
+                Err(
+                    UdsError::Timeout
+                    | UdsError::Negative(Nrc::BusyRepeatRequest | Nrc::TransferDataSuspended),
+                ) if retries < self.max_retries => retries += 1,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::uds::{MockReply, MockTransport};
+    use std::sync::Arc;
+
+    /// A server accepting blocks of up to `max_block_len` bytes including SID and counter,
+    /// echoing every block sequence counter, after the rules of `mock`
+    fn flash_server(mock: MockTransport, max_block_len: u8) -> MockTransport {
+        let mut mock = mock
+            .with_clock(Arc::new(SimulatedClock::new()))
+            .on_request(&[0x34], &[0x74, 0x10, max_block_len])
+            .on_request(&[0x37], &[0x77]);
+        for counter in 0..=u8::MAX {
+            mock.add_rule(&[0x36, counter], MockReply::from(&[0x76, counter]), None);
+        }
+        mock
+    }
+
+    fn client(mock: &mut MockTransport) -> UdsClient<&mut MockTransport> {
+        let mut client = UdsClient::new(mock);
+        client.set_clock(Arc::new(SimulatedClock::new()));
+        client
+    }
+
+    #[test]
+    fn blocks_fit_the_negotiated_length() {
+        let mut mock = flash_server(MockTransport::new(), 6);
+        let image: Vec<u8> = (0..10).collect();
+        let mut progress = Vec::new();
+        Download::new(0x1000)
+            .run(&mut client(&mut mock), &image, |p| {
+                progress.push(p.transferred)
+            })
+            .unwrap();
+        assert_eq!(
+            mock.sent(),
+            [
+                vec![0x34, 0x00, 0x12, 0x10, 0x00, 0x0A],
+                vec![0x36, 0x01, 0, 1, 2, 3],
+                vec![0x36, 0x02, 4, 5, 6, 7],
+                vec![0x36, 0x03, 8, 9],
+                vec![0x37],
+            ]
+        );
+        assert_eq!(progress, [4, 8, 10]);
+    }
+
+    #[test]
+    fn block_length_without_room_for_data_is_rejected() {
+        let mut mock = flash_server(MockTransport::new(), 2);
+        let error = Download::new(0x1000)
+            .run(&mut client(&mut mock), &[0; 4], |_| ())
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+    }
+
+    #[test]
+    fn block_sequence_counter_wraps_to_zero() {
+        let mut mock = flash_server(MockTransport::new(), 3);
+        let mut counters = Vec::new();
+        Download::new(0x1000)
+            .run(&mut client(&mut mock), &[0xA5; 257], |p| {
+                counters.push(p.block_sequence_counter)
+            })
+            .unwrap();
+        assert_eq!(counters.len(), 257);
+        assert_eq!(counters[..2], [0x01, 0x02]);
+        assert_eq!(counters[254..], [0xFF, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn failed_block_is_repeated() {
+        // busy once, then no response once
+        let failing = || {
+            MockTransport::new()
+                .on_request_times(&[0x36, 0x02], 1, &[0x7F, 0x36, 0x21])
+                .on_request_times(&[0x36, 0x02], 1, MockReply::new())
+        };
+        let mut mock = flash_server(failing(), 6);
+        Download::new(0x1000)
+            .max_retries(2)
+            .run(&mut client(&mut mock), &[0; 8], |_| ())
+            .unwrap();
+        let blocks: Vec<u8> = mock.sent()[1..5].iter().map(|request| request[1]).collect();
+        assert_eq!(blocks, [0x01, 0x02, 0x02, 0x02]);
+        assert_eq!(mock.sent().len(), 6);
+
+        // the retries are exhausted by the missing response
+        let mut mock = flash_server(failing(), 6);
+        let error = Download::new(0x1000)
+            .max_retries(1)
+            .run(&mut client(&mut mock), &[0; 8], |_| ())
+            .unwrap_err();
+        assert!(matches!(error, UdsError::Timeout), "{error:?}");
+    }
+
+    #[test]
+    fn resumes_from_offset() {
+        let mut mock = flash_server(MockTransport::new(), 6);
+        let image: Vec<u8> = (0..10).collect();
+        let mut progress = Vec::new();
+        Download::new(0x1000)
+            .resume_from(6)
+            .run(&mut client(&mut mock), &image, |p| progress.push(p))
+            .unwrap();
+        assert_eq!(mock.sent()[0], [0x34, 0x00, 0x12, 0x10, 0x06, 0x04]);
+        assert_eq!(mock.sent()[1], [0x36, 0x01, 6, 7, 8, 9]);
+        assert_eq!(
+            progress,
+            [DownloadProgress {
+                transferred: 10,
+                total: 10,
+                block_sequence_counter: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn resume_offset_beyond_image_or_address_space_is_rejected() {
+        let mut mock = flash_server(MockTransport::new(), 6);
+        for download in [
+            Download::new(0x1000).resume_from(11),
+            Download::new(u64::MAX).resume_from(1),
+        ] {
+            let error = download
+                .run(&mut client(&mut mock), &[0; 10], |_| ())
+                .unwrap_err();
+            assert!(matches!(error, UdsError::InvalidRequest(_)), "{error:?}");
+        }
+        assert!(mock.sent().is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+use super::{Did, DidRegistry, ServiceId, UdsClient, UdsError, UdsTransport};
+use bitflags::bitflags;
+use std::fmt;
+
+bitflags! {
+    /// statusOfDTC bits as defined by ISO 14229-1
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[cfg_attr(feature = "serde", serde(transparent))]
+    pub struct DtcStatus: u8 {
+        /// the most recent test failed
+        const TEST_FAILED = 0x01;
+        /// a test failed during the current operation cycle
+        const TEST_FAILED_THIS_OPERATION_CYCLE = 0x02;
+        /// a test failed during the current or last completed operation cycle
+        const PENDING_DTC = 0x04;
+        /// the malfunction was detected often enough to be stored
+        const CONFIRMED_DTC = 0x08;
+        /// no test completed since the DTC information was cleared
+        const TEST_NOT_COMPLETED_SINCE_LAST_CLEAR = 0x10;
+        /// a test failed since the DTC information was cleared
+        const TEST_FAILED_SINCE_LAST_CLEAR = 0x20;
+        /// no test completed during the current operation cycle
+        const TEST_NOT_COMPLETED_THIS_OPERATION_CYCLE = 0x40;
+        /// the server requests the warning indicator
+        const WARNING_INDICATOR_REQUESTED = 0x80;
+    }
+}
+
+/// A 3 byte diagnostic trouble code, consisting of a 2 byte code and a failure type byte
+///
+/// Displayed in SAE J2012 notation, e.g. `P0123-4A`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dtc(u32);
+
+impl Dtc {
+    /// A DTC from its 24 bit value, higher bits are ignored
+    pub fn new(dtc: u32) -> Self {
+        Self(dtc & 0x00FF_FFFF)
+    }
+
+    /// Decode a DTC from 3 big endian bytes
+    pub fn from_bytes(bytes: [u8; 3]) -> Self {
+        Self(u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]))
+    }
+
+    /// The DTC as 3 big endian bytes
+    pub fn to_bytes(self) -> [u8; 3] {
+        let [_, high, middle, low] = self.0.to_be_bytes();
+        [high, middle, low]
+    }
+
+    /// The 24 bit value
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// The SAE J2012 code without failure type, e.g. `P0123`
+    pub fn code(self) -> String {
+        let [high, middle, _] = self.to_bytes();
+        let system = match high >> 6 {
+            0 => 'P',
+            1 => 'C',
+            2 => 'B',
+            _ => 'U',
+        };
+        format!(
+            "{}{:X}{:X}{:02X}",
+            system,
+            (high >> 4) & 0x03,
+            high & 0x0F,
+            middle
+        )
+    }
+
+    /// The failure type byte (FTB)
+    pub fn failure_type(self) -> u8 {
+        self.0 as u8
+    }
+}
+
+impl fmt::Display for Dtc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{:02X}", self.code(), self.failure_type())
+    }
+}
+
+/// A DTC with its status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DtcRecord {
+    /// The trouble code
+    pub dtc: Dtc,
+    /// statusOfDTC
+    pub status: DtcStatus,
+}
+
+impl fmt::Display for DtcRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (status {:#04X})", self.dtc, self.status.bits())
+    }
+}
+
+/// A snapshot record of [`UdsClient::read_dtc_snapshot_records`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotRecord {
+    /// DTCSnapshotRecordNumber
+    pub record_number: u8,
+    /// Data records by data identifier
+    pub data: Vec<(Did, Vec<u8>)>,
+}
+
+/// Sub-functions of ReadDTCInformation (0x19) supported by the helpers
+const REPORT_DTC_BY_STATUS_MASK: u8 = 0x02;
+const REPORT_DTC_SNAPSHOT_RECORD_BY_DTC_NUMBER: u8 = 0x04;
+const REPORT_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER: u8 = 0x06;
+
+pub(super) fn parse_dtc_records(records: &[u8]) -> Result<Vec<DtcRecord>, UdsError> {
+    let chunks = records.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return Err(UdsError::InvalidResponse("truncated DTC record"));
+    }
+    Ok(chunks
+        .map(|record| DtcRecord {
+            dtc: Dtc::from_bytes([record[0], record[1], record[2]]),
+            status: DtcStatus::from_bits_retain(record[3]),
+        })
+        .collect())
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    pub(super) fn read_dtc_information(
+        &mut self,
+        sub_function: u8,
+        parameters: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = Vec::with_capacity(1 + parameters.len());
+        request.push(sub_function);
+        request.extend_from_slice(parameters);
+        let response = self.request(ServiceId::ReadDtcInformation, &request)?;
+        match response.split_first() {
+            Some((echo, records)) if *echo == sub_function => Ok(records.to_vec()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the report type",
+            )),
+        }
+    }
+
+    /// reportDTCByStatusMask (0x19 0x02), returning the status availability mask and all
+    /// DTCs matching `mask`
+    pub fn read_dtc_by_status_mask(
+        &mut self,
+        mask: DtcStatus,
+    ) -> Result<(DtcStatus, Vec<DtcRecord>), UdsError> {
+        let response = self.read_dtc_information(REPORT_DTC_BY_STATUS_MASK, &[mask.bits()])?;
+        let Some((availability, records)) = response.split_first() else {
+            return Err(UdsError::InvalidResponse(
+                "missing DTC status availability mask",
+            ));
+        };
+        Ok((
+            DtcStatus::from_bits_retain(*availability),
+            parse_dtc_records(records)?,
+        ))
+    }
+
+    /// reportDTCSnapshotRecordByDTCNumber (0x19 0x04), `0xFF` requests all records
+    ///
+    /// The data records are split using the lengths registered in `registry`.
+    pub fn read_dtc_snapshot_records(
+        &mut self,
+        dtc: Dtc,
+        record_number: u8,
+        registry: &DidRegistry,
+    ) -> Result<(DtcRecord, Vec<SnapshotRecord>), UdsError> {
+        let [high, middle, low] = dtc.to_bytes();
+        let response = self.read_dtc_information(
+            REPORT_DTC_SNAPSHOT_RECORD_BY_DTC_NUMBER,
+            &[high, middle, low, record_number],
+        )?;
+        let (dtc_record, mut remaining) = split_dtc_record(&response)?;
+
+        let mut snapshots = Vec::new();
+        while let [record_number, identifiers, rest @ ..] = remaining {
+            remaining = rest;
+            let mut data = Vec::with_capacity(usize::from(*identifiers));
+            for _ in 0..*identifiers {
+                let Some((did, rest)) = remaining.split_first_chunk::<2>() else {
+                    return Err(UdsError::InvalidResponse("truncated snapshot record"));
+                };
+                let did = Did::from_be_bytes(*did);
+                let len = registry
+                    .get(did)
+                    .and_then(|codec| codec.record_len())
+                    .ok_or(UdsError::InvalidRequest(
+                        "snapshot data identifier without registered length",
+                    ))?;
+                if rest.len() < len {
+                    return Err(UdsError::InvalidResponse("truncated snapshot record"));
+                }
+                let (record, rest) = rest.split_at(len);
+                data.push((did, record.to_vec()));
+                remaining = rest;
+            }
+            snapshots.push(SnapshotRecord {
+                record_number: *record_number,
+                data,
+            });
+        }
+        if !remaining.is_empty() {
+            return Err(UdsError::InvalidResponse("truncated snapshot record"));
+        }
+        Ok((dtc_record, snapshots))
+    }
+
+    /// reportDTCExtDataRecordByDTCNumber (0x19 0x06), `0xFF` requests all records
+    ///
+    /// Returns the raw extended data records, their layout is manufacturer specific.
+    pub fn read_dtc_extended_data(
+        &mut self,
+        dtc: Dtc,
+        record_number: u8,
+    ) -> Result<(DtcRecord, Vec<u8>), UdsError> {
+        let [high, middle, low] = dtc.to_bytes();
+        let response = self.read_dtc_information(
+            REPORT_DTC_EXT_DATA_RECORD_BY_DTC_NUMBER,
+            &[high, middle, low, record_number],
+        )?;
+        let (dtc_record, records) = split_dtc_record(&response)?;
+        Ok((dtc_record, records.to_vec()))
+    }
+}
+
+fn split_dtc_record(response: &[u8]) -> Result<(DtcRecord, &[u8]), UdsError> {
+    match response {
+        [high, middle, low, status, rest @ ..] => Ok((
+            DtcRecord {
+                dtc: Dtc::from_bytes([*high, *middle, *low]),
+                status: DtcStatus::from_bits_retain(*status),
+            },
+            rest,
+        )),
+        _ => Err(UdsError::InvalidResponse("missing DTC and status")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::{DidCodec, MockTransport};
+
+    const DTC: [u8; 3] = [0x12, 0x34, 0x56];
+
+    fn record(status: DtcStatus) -> DtcRecord {
+        DtcRecord {
+            dtc: Dtc::from_bytes(DTC),
+            status,
+        }
+    }
+
+    fn registry() -> DidRegistry {
+        let mut registry = DidRegistry::new();
+        registry
+            .register(0x0100, DidCodec::Unsigned(2))
+            .register(0x0200, DidCodec::Raw(Some(1)))
+            .register(0x0300, DidCodec::Raw(None));
+        registry
+    }
+
+    #[test]
+    fn read_dtc_by_status_mask() {
+        let mut mock = MockTransport::new().on_request(
+            &[0x19, 0x02, 0x09],
+            &[
+                0x59, 0x02, 0x7F, 0x12, 0x34, 0x56, 0x09, 0xC1, 0x00, 0x01, 0x08,
+            ],
+        );
+        let mut client = UdsClient::new(&mut mock);
+        let (availability, records) = client
+            .read_dtc_by_status_mask(DtcStatus::TEST_FAILED | DtcStatus::CONFIRMED_DTC)
+            .unwrap();
+        assert_eq!(availability.bits(), 0x7F);
+        assert_eq!(
+            records,
+            [
+                record(DtcStatus::TEST_FAILED | DtcStatus::CONFIRMED_DTC),
+                DtcRecord {
+                    dtc: Dtc::new(0xC10001),
+                    status: DtcStatus::CONFIRMED_DTC,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_dtc_by_status_mask_without_matches() {
+        let mut mock = MockTransport::new().on_request(&[0x19, 0x02, 0xFF], &[0x59, 0x02, 0xFF]);
+        let mut client = UdsClient::new(&mut mock);
+        let (availability, records) = client.read_dtc_by_status_mask(DtcStatus::all()).unwrap();
+        assert_eq!(availability, DtcStatus::all());
+        assert!(records.is_empty());
+    }
+
+    #[test]
+    fn read_dtc_by_status_mask_rejects_malformed_responses() {
+        for response in [
+            &[0x59, 0x02, 0xFF, 0x12, 0x34, 0x56][..],
+            &[0x59, 0x02][..],
+            &[0x59, 0x04, 0xFF][..],
+        ] {
+            let mut mock = MockTransport::new().on_request(&[0x19, 0x02], response);
+            let mut client = UdsClient::new(&mut mock);
+            let error = client
+                .read_dtc_by_status_mask(DtcStatus::all())
+                .unwrap_err();
+            assert!(
+                matches!(error, UdsError::InvalidResponse(_)),
+                "{response:02X?}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_dtc_snapshot_records() {
+        let mut mock = MockTransport::new().on_request(
+            &[0x19, 0x04, 0x12, 0x34, 0x56, 0xFF],
+            &[
+                0x59, 0x04, 0x12, 0x34, 0x56, 0x24, // DTC and status
+                0x01, 0x02, 0x01, 0x00, 0x0B, 0xB8, 0x02, 0x00, 0x5A, // record 1
+                0x02, 0x01, 0x01, 0x00, 0x0F, 0xA0, // record 2
+            ],
+        );
+        let mut client = UdsClient::new(&mut mock);
+        let (dtc_record, snapshots) = client
+            .read_dtc_snapshot_records(Dtc::from_bytes(DTC), 0xFF, &registry())
+            .unwrap();
+        assert_eq!(dtc_record, record(DtcStatus::from_bits_retain(0x24)));
+        assert_eq!(
+            snapshots,
+            [
+                SnapshotRecord {
+                    record_number: 0x01,
+                    data: vec![(0x0100, vec![0x0B, 0xB8]), (0x0200, vec![0x5A])],
+                },
+                SnapshotRecord {
+                    record_number: 0x02,
+                    data: vec![(0x0100, vec![0x0F, 0xA0])],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn read_dtc_snapshot_records_rejects_truncated_records() {
+        for response in [
+            // data record shorter than registered
+            &[
+                0x59, 0x04, 0x12, 0x34, 0x56, 0x24, 0x01, 0x01, 0x01, 0x00, 0x0B,
+            ][..],
+            // missing data identifier
+            &[
+                0x59, 0x04, 0x12, 0x34, 0x56, 0x24, 0x01, 0x02, 0x02, 0x00, 0x5A,
+            ][..],
+            // record number without number of identifiers
+            &[0x59, 0x04, 0x12, 0x34, 0x56, 0x24, 0x01][..],
+            // missing status
+            &[0x59, 0x04, 0x12, 0x34, 0x56][..],
+        ] {
+            let mut mock = MockTransport::new().on_request(&[0x19, 0x04], response);
+            let mut client = UdsClient::new(&mut mock);
+            let error = client
+                .read_dtc_snapshot_records(Dtc::from_bytes(DTC), 0xFF, &registry())
+                .unwrap_err();
+            assert!(
+                matches!(error, UdsError::InvalidResponse(_)),
+                "{response:02X?}: {error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn read_dtc_snapshot_records_requires_registered_lengths() {
+        for did in [[0x03, 0x00], [0x04, 0x00]] {
+            let mut response = vec![0x59, 0x04, 0x12, 0x34, 0x56, 0x24, 0x01, 0x01];
+            response.extend_from_slice(&did);
+            response.push(0x00);
+            let mut mock = MockTransport::new().on_request(&[0x19, 0x04], response);
+            let mut client = UdsClient::new(&mut mock);
+            let error = client
+                .read_dtc_snapshot_records(Dtc::from_bytes(DTC), 0x01, &registry())
+                .unwrap_err();
+            assert!(matches!(error, UdsError::InvalidRequest(_)), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn read_dtc_extended_data() {
+        let mut mock = MockTransport::new().on_request(
+            &[0x19, 0x06, 0x12, 0x34, 0x56, 0x01],
+            &[0x59, 0x06, 0x12, 0x34, 0x56, 0x2F, 0x01, 0x05, 0x02],
+        );
+        let mut client = UdsClient::new(&mut mock);
+        let (dtc_record, records) = client
+            .read_dtc_extended_data(Dtc::from_bytes(DTC), 0x01)
+            .unwrap();
+        assert_eq!(dtc_record, record(DtcStatus::from_bits_retain(0x2F)));
+        assert_eq!(records, [0x01, 0x05, 0x02]);
+    }
+
+    #[test]
+    fn read_dtc_extended_data_rejects_truncated_dtc() {
+        let mut mock = MockTransport::new().on_request(&[0x19, 0x06], &[0x59, 0x06, 0x12, 0x34]);
+        let mut client = UdsClient::new(&mut mock);
+        let error = client
+            .read_dtc_extended_data(Dtc::from_bytes(DTC), 0xFF)
+            .unwrap_err();
+        assert!(matches!(error, UdsError::InvalidResponse(_)), "{error:?}");
+    }
+
+    #[test]
+    fn parse_dtc_records_rejects_truncated_record() {
+        assert!(parse_dtc_records(&[]).unwrap().is_empty());
+        assert!(matches!(
+            parse_dtc_records(&[0x12, 0x34, 0x56, 0x09, 0xC1]),
+            Err(UdsError::InvalidResponse(_))
+        ));
+    }
+}
@@ -0,0 +1,90 @@
+use super::{ServiceId, UdsClient, UdsError, UdsTransport};
+
+/// Sub-functions of ControlDTCSetting (0x85)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DtcSettingType {
+    /// on (0x01), resume updating DTC status bits
+    On,
+    /// off (0x02), stop updating DTC status bits
+    Off,
+    /// Vehicle manufacturer or system supplier specific setting type
+    Other(u8),
+}
+
+impl DtcSettingType {
+    /// The encoded sub-function
+    pub fn as_u8(self) -> u8 {
+        match self {
+            DtcSettingType::On => 0x01,
+            DtcSettingType::Off => 0x02,
+            DtcSettingType::Other(setting_type) => setting_type,
+        }
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ControlDTCSetting (0x85) with an optional DTCSettingControlOptionRecord, e.g. a DTC
+    /// group
+    pub fn control_dtc_setting(
+        &mut self,
+        setting_type: DtcSettingType,
+        option_record: &[u8],
+    ) -> Result<(), UdsError> {
+        let mut request = Vec::with_capacity(1 + option_record.len());
+        request.push(setting_type.as_u8());
+        request.extend_from_slice(option_record);
+        let response = self.request(ServiceId::ControlDtcSetting, &request)?;
+        match response.first() {
+            Some(&echo) if echo == setting_type.as_u8() => (),
+            _ => {
+                return Err(UdsError::InvalidResponse(
+                    "response does not echo the DTC setting type",
+                ))
+            }
+        }
+        match setting_type {
+            DtcSettingType::On => self.dtc_setting_off = false,
+            DtcSettingType::Off => self.dtc_setting_off = true,
+            DtcSettingType::Other(_) => (),
+        }
+        Ok(())
+    }
+
+    /// Stop updating DTC status bits, e.g. before reprogramming
+    pub fn dtc_setting_off(&mut self) -> Result<(), UdsError> {
+        self.control_dtc_setting(DtcSettingType::Off, &[])
+    }
+
+    /// Resume updating DTC status bits
+    pub fn dtc_setting_on(&mut self) -> Result<(), UdsError> {
+        self.control_dtc_setting(DtcSettingType::On, &[])
+    }
+
+    /// Run `f` with DTC setting off, turning it on again afterwards.
+    ///
+    /// If `f` leaves the non-default session, e.g. by an ECU reset, the server restored the
+    /// setting already and no request is sent.
+    pub fn with_dtc_setting_off<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<R, UdsError>,
+    ) -> Result<R, UdsError> {
+        self.dtc_setting_off()?;
+        let result = f(self);
+        if self.dtc_setting_off {
+            let restored = self.dtc_setting_on();
+            let value = result?;
+            restored?;
+            Ok(value)
+        } else {
+            result
+        }
+    }
+
+    /// Whether DTC setting is off as last confirmed by the server.
+    ///
+    /// Returning to the default session restores the setting.
+    pub fn is_dtc_setting_off(&self) -> bool {
+        self.dtc_setting_off
+    }
+}
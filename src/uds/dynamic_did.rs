@@ -0,0 +1,117 @@
+use super::{AddressAndLengthFormat, Did, ServiceId, UdsClient, UdsError, UdsTransport};
+
+/// Part of a source data record copied into a dynamically defined data identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceDid {
+    /// sourceDataIdentifier
+    pub did: Did,
+    /// positionInSourceDataRecord, starting at 1
+    pub position: u8,
+    /// memorySize, the number of bytes copied
+    pub size: u8,
+}
+
+impl SourceDid {
+    /// Copy `size` bytes of the data record of `did` starting at byte `position` (1-based)
+    pub fn new(did: Did, position: u8, size: u8) -> Self {
+        Self {
+            did,
+            position,
+            size,
+        }
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// DynamicallyDefineDataIdentifier (0x2C) defineByIdentifier, appending the parts of
+    /// `sources` to the data record of `dddid`.
+    ///
+    /// The dynamically defined data identifier is read by
+    /// [`UdsClient::read_data_by_identifier`] or [`UdsClient::read_did`] afterwards:
+    ///
+    /// ```rust,no_run
+    /// # fn example(client: &mut socketcan_isotp::uds::UdsClient) -> Result<(), socketcan_isotp::uds::UdsError> {
+    /// use socketcan_isotp::uds::SourceDid;
+    ///
+    /// client.define_did_by_identifier(
+    ///     0xF300,
+    ///     &[SourceDid::new(0xF40C, 1, 2), SourceDid::new(0xF40D, 1, 1)],
+    /// )?;
+    /// let [rpm_high, rpm_low, speed] = client.read_did::<[u8; 3]>(0xF300)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn define_did_by_identifier(
+        &mut self,
+        dddid: Did,
+        sources: &[SourceDid],
+    ) -> Result<(), UdsError> {
+        if sources.is_empty() {
+            return Err(UdsError::InvalidRequest("no source data identifier given"));
+        }
+        let mut request = Vec::with_capacity(3 + 4 * sources.len());
+        request.push(0x01);
+        request.extend_from_slice(&dddid.to_be_bytes());
+        for source in sources {
+            request.extend_from_slice(&source.did.to_be_bytes());
+            request.push(source.position);
+            request.push(source.size);
+        }
+        self.request_dynamically_define_did(dddid, &request)
+    }
+
+    /// DynamicallyDefineDataIdentifier (0x2C) defineByMemoryAddress, appending the memory
+    /// `regions` given as `(address, size)` to the data record of `dddid`
+    pub fn define_did_by_memory_address(
+        &mut self,
+        dddid: Did,
+        format: AddressAndLengthFormat,
+        regions: &[(u64, u64)],
+    ) -> Result<(), UdsError> {
+        if regions.is_empty() {
+            return Err(UdsError::InvalidRequest("no memory region given"));
+        }
+        let mut request = vec![0x02];
+        request.extend_from_slice(&dddid.to_be_bytes());
+        request.push(format.as_u8());
+        for &(address, size) in regions {
+            request.extend(format.encode(address, size)?);
+        }
+        self.request_dynamically_define_did(dddid, &request)
+    }
+
+    /// DynamicallyDefineDataIdentifier (0x2C) clearDynamicallyDefinedDataIdentifier of
+    /// `dddid`, all dynamically defined data identifiers if `None`
+    pub fn clear_dynamically_defined_did(&mut self, dddid: Option<Did>) -> Result<(), UdsError> {
+        let mut request = vec![0x03];
+        if let Some(dddid) = dddid {
+            request.extend_from_slice(&dddid.to_be_bytes());
+        }
+        let response = self.request(ServiceId::DynamicallyDefineDataIdentifier, &request)?;
+        match response.first() {
+            Some(0x03) => Ok(()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the definition type",
+            )),
+        }
+    }
+
+    fn request_dynamically_define_did(
+        &mut self,
+        dddid: Did,
+        request: &[u8],
+    ) -> Result<(), UdsError> {
+        let response = self.request(ServiceId::DynamicallyDefineDataIdentifier, request)?;
+        match *response {
+            [definition_type, high, low, ..]
+                if definition_type == request[0] && Did::from_be_bytes([high, low]) == dddid =>
+            {
+                Ok(())
+            }
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the definition type and data identifier",
+            )),
+        }
+    }
+}
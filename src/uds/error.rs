@@ -0,0 +1,86 @@
+use super::Nrc;
+use thiserror::Error;
+
+/// Errors of UDS requests
+#[derive(Error, Debug)]
+pub enum UdsError {
+    /// Sending the request or receiving the response failed
+    #[error(transparent)]
+    Transport(crate::Error),
+
+    /// A network layer timer expired while sending the request or receiving the response,
+    /// e.g. the server stopped sending consecutive frames
+    #[error("{timer} timeout: {source}")]
+    NetworkTimeout {
+        /// The expired timer
+        timer: crate::NetworkTimer,
+        /// The error reported by the transport
+        #[source]
+        source: crate::Error,
+    },
+
+    /// No response within the response timeout (P2)
+    #[error("No response within the response timeout")]
+    Timeout,
+
+    /// The server rejected the request with a negative response code
+    #[error("Negative response: {0}")]
+    Negative(Nrc),
+
+    /// The server kept answering with pending responses (NRC 0x78)
+    #[error("Received {0} pending responses without final response")]
+    TooManyPendingResponses(u32),
+
+    /// The request parameters are out of range
+    #[error("Invalid request: {0}")]
+    InvalidRequest(&'static str),
+
+    /// The seed-key algorithm failed to compute a key
+    #[error("Seed-key algorithm failed: {0}")]
+    SeedKey(#[source] super::SeedKeyError),
+
+    /// A crypto hook of the authentication failed
+    #[error("Authentication hook failed: {0}")]
+    Authentication(#[source] super::SeedKeyError),
+
+    /// The server answered an authentication step with an unexpected return value
+    #[error("Authentication rejected: {0}")]
+    AuthenticationRejected(super::AuthenticationReturn),
+
+    /// The response does not match the request
+    #[error("Invalid response: {0}")]
+    InvalidResponse(&'static str),
+}
+
+impl From<crate::Error> for UdsError {
+    fn from(source: crate::Error) -> Self {
+        match source.network_timer() {
+            Some(timer) => UdsError::NetworkTimeout { timer, source },
+            None => UdsError::Transport(source),
+        }
+    }
+}
+
+impl From<std::io::Error> for UdsError {
+    fn from(source: std::io::Error) -> Self {
+        UdsError::from(crate::Error::from(source))
+    }
+}
+
+impl UdsError {
+    /// Negative response code if the server rejected the request
+    pub fn nrc(&self) -> Option<Nrc> {
+        match self {
+            UdsError::Negative(nrc) => Some(*nrc),
+            _ => None,
+        }
+    }
+}
+
+/// Decode a negative response `[0x7F, sid, nrc]`, `None` for any other response
+pub fn decode_negative_response(response: &[u8]) -> Option<(u8, Nrc)> {
+    match *response {
+        [super::NEGATIVE_RESPONSE_SID, service, code, ..] => Some((service, Nrc::from(code))),
+        _ => None,
+    }
+}
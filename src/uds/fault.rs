@@ -0,0 +1,344 @@
+use super::UdsTransport;
+use crate::clock::{Clock, SystemClock};
+use crate::Error;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Probabilities of the faults a [`FaultInjector`] applies to each PDU, in percent
+///
+/// ```rust
+/// use socketcan_isotp::uds::FaultPolicy;
+/// use std::time::Duration;
+///
+/// // lose every tenth response and delay every fifth one by 200ms
+/// let policy = FaultPolicy::new()
+///     .drop(10)
+///     .delay(20, Duration::from_millis(200))
+///     .tx(false);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaultPolicy {
+    drop: u8,
+    delay: u8,
+    delay_duration: Duration,
+    duplicate: u8,
+    corrupt: u8,
+    skip_tx: bool,
+    skip_rx: bool,
+}
+
+impl FaultPolicy {
+    /// A policy without faults, applied to requests and responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lose `percent` of the PDUs
+    pub fn drop(mut self, percent: u8) -> Self {
+        self.drop = percent.min(100);
+        self
+    }
+
+    /// Hold back `percent` of the PDUs for `delay`
+    pub fn delay(mut self, percent: u8, delay: Duration) -> Self {
+        self.delay = percent.min(100);
+        self.delay_duration = delay;
+        self
+    }
+
+    /// Transfer `percent` of the PDUs twice
+    pub fn duplicate(mut self, percent: u8) -> Self {
+        self.duplicate = percent.min(100);
+        self
+    }
+
+    /// Flip the bits of a random byte in `percent` of the PDUs
+    pub fn corrupt(mut self, percent: u8) -> Self {
+        self.corrupt = percent.min(100);
+        self
+    }
+
+    /// Apply the faults to sent requests, enabled by default
+    pub fn tx(mut self, enabled: bool) -> Self {
+        self.skip_tx = !enabled;
+        self
+    }
+
+    /// Apply the faults to received responses, enabled by default
+    pub fn rx(mut self, enabled: bool) -> Self {
+        self.skip_rx = !enabled;
+        self
+    }
+}
+
+/// Number of faults a [`FaultInjector`] applied so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaultStats {
+    /// PDUs lost
+    pub dropped: u64,
+    /// PDUs held back
+    pub delayed: u64,
+    /// PDUs transferred twice
+    pub duplicated: u64,
+    /// PDUs with a corrupted byte
+    pub corrupted: u64,
+}
+
+enum Fault {
+    Drop,
+    Delay,
+    Duplicate,
+    Corrupt(usize),
+    None,
+}
+
+/// [`UdsTransport`] decorator dropping, delaying, duplicating or corrupting PDUs according
+/// to a [`FaultPolicy`], e.g. to test the robustness of an application against a flaky
+/// bus
+///
+/// The faults are drawn from a pseudo random sequence, the same `seed` reproduces the same
+/// faults for the same traffic. At most one fault is applied to each PDU.
+///
+/// ```rust
+/// use socketcan_isotp::uds::{FaultInjector, FaultPolicy, MockTransport, UdsClient};
+///
+/// let mock = MockTransport::new().on_request(&[0x3E, 0x00], &[0x7E, 0x00]);
+/// let injector = FaultInjector::new(mock, FaultPolicy::new().drop(100).tx(false), 42);
+/// let mut client = UdsClient::new(injector);
+/// assert!(client.request(0x3E, &[0x00]).is_err());
+/// assert_eq!(client.get_ref().get_stats().dropped, 1);
+/// ```
+pub struct FaultInjector<T> {
+    transport: T,
+    policy: FaultPolicy,
+    state: u64,
+    stats: FaultStats,
+    /// responses held back with the time they are due
+    held: VecDeque<(Instant, Vec<u8>)>,
+    response: Vec<u8>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T: UdsTransport> FaultInjector<T> {
+    /// Wrap `transport`, reproducible for the same `seed`
+    pub fn new(transport: T, policy: FaultPolicy, seed: u64) -> Self {
+        Self {
+            transport,
+            policy,
+            // xorshift must not start at zero
+            state: seed | 1,
+            stats: FaultStats::default(),
+            held: VecDeque::new(),
+            response: Vec::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Wait for delays and timeouts on `clock` instead of the system time, e.g. the
+    /// [`SimulatedClock`](crate::clock::SimulatedClock) of the wrapped
+    /// [`MockTransport`](super::MockTransport)
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Replace the fault policy, e.g. to break the bus only during part of a test
+    pub fn set_policy(&mut self, policy: FaultPolicy) {
+        self.policy = policy;
+    }
+
+    /// Get the fault policy
+    pub fn get_policy(&self) -> &FaultPolicy {
+        &self.policy
+    }
+
+    /// Get the number of faults applied so far
+    pub fn get_stats(&self) -> FaultStats {
+        self.stats
+    }
+
+    /// Get the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    /// Get the wrapped transport mutably
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Unwrap the transport, dropping held back responses
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn draw(&mut self, len: usize) -> Fault {
+        let roll = (self.next_u64() % 100) as u8;
+        let FaultPolicy {
+            drop,
+            delay,
+            duplicate,
+            corrupt,
+            ..
+        } = self.policy;
+        let fault = if roll < drop {
+            Fault::Drop
+        } else if roll < drop.saturating_add(delay) {
+            Fault::Delay
+        } else if roll < drop.saturating_add(delay).saturating_add(duplicate) {
+            Fault::Duplicate
+        } else if len > 0
+            && roll
+                < drop
+                    .saturating_add(delay)
+                    .saturating_add(duplicate)
+                    .saturating_add(corrupt)
+        {
+            Fault::Corrupt((self.next_u64() % len as u64) as usize)
+        } else {
+            Fault::None
+        };
+        match fault {
+            Fault::Drop => self.stats.dropped += 1,
+            Fault::Delay => self.stats.delayed += 1,
+            Fault::Duplicate => self.stats.duplicated += 1,
+            Fault::Corrupt(_) => self.stats.corrupted += 1,
+            Fault::None => {}
+        }
+        fault
+    }
+
+    /// Pop the first held back response due at `now`
+    fn pop_due(&mut self, now: Instant) -> Option<Vec<u8>> {
+        let index = self.held.iter().position(|(due, _)| *due <= now)?;
+        self.held.remove(index).map(|(_, response)| response)
+    }
+}
+
+impl<T: UdsTransport> UdsTransport for FaultInjector<T> {
+    fn send(&mut self, request: &[u8]) -> Result<(), Error> {
+        if self.policy.skip_tx {
+            return self.transport.send(request);
+        }
+        match self.draw(request.len()) {
+            Fault::Drop => Ok(()),
+            Fault::Delay => {
+                self.clock.sleep(self.policy.delay_duration);
+                self.transport.send(request)
+            }
+            Fault::Duplicate => {
+                self.transport.send(request)?;
+                self.transport.send(request)
+            }
+            Fault::Corrupt(index) => {
+                let mut request = request.to_vec();
+                request[index] = !request[index];
+                self.transport.send(&request)
+            }
+            Fault::None => self.transport.send(request),
+        }
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        let deadline = self.clock.now() + timeout;
+        loop {
+            let now = self.clock.now();
+            if let Some(response) = self.pop_due(now) {
+                self.response = response;
+                return Ok(Some(&self.response));
+            }
+            if now >= deadline {
+                return Ok(None);
+            }
+            // wake up for held back responses becoming due
+            let wait = self
+                .held
+                .iter()
+                .map(|(due, _)| *due)
+                .min()
+                .map_or(deadline, |due| due.min(deadline))
+                .saturating_duration_since(now);
+            let Some(response) = self.transport.receive(wait)? else {
+                continue;
+            };
+            let mut response = response.to_vec();
+            if self.policy.skip_rx {
+                self.response = response;
+                return Ok(Some(&self.response));
+            }
+            match self.draw(response.len()) {
+                Fault::Drop => continue,
+                Fault::Delay => {
+                    let due = self.clock.now() + self.policy.delay_duration;
+                    self.held.push_back((due, response));
+                    continue;
+                }
+                Fault::Duplicate => self.held.push_back((self.clock.now(), response.clone())),
+                Fault::Corrupt(index) => response[index] = !response[index],
+                Fault::None => {}
+            }
+            self.response = response;
+            return Ok(Some(&self.response));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::uds::MockTransport;
+
+    fn injector(policy: FaultPolicy) -> (FaultInjector<MockTransport>, SimulatedClock) {
+        let clock = SimulatedClock::new();
+        let mock = MockTransport::new()
+            .with_clock(Arc::new(clock.clone()))
+            .on_request(&[0x3E, 0x00], &[0x7E, 0x00]);
+        let injector = FaultInjector::new(mock, policy, 42).with_clock(Arc::new(clock.clone()));
+        (injector, clock)
+    }
+
+    #[test]
+    fn delayed_response_is_due_after_delay() {
+        let policy = FaultPolicy::new()
+            .delay(100, Duration::from_millis(200))
+            .tx(false);
+        let (mut injector, clock) = injector(policy);
+        injector.send(&[0x3E, 0x00]).unwrap();
+        assert_eq!(
+            injector.receive(Duration::from_secs(1)).unwrap(),
+            Some(&[0x7E, 0x00][..])
+        );
+        assert_eq!(clock.elapsed(), Duration::from_millis(200));
+        assert_eq!(injector.get_stats().delayed, 1);
+    }
+
+    #[test]
+    fn delayed_request_is_sent_after_delay() {
+        let policy = FaultPolicy::new()
+            .delay(100, Duration::from_millis(200))
+            .rx(false);
+        let (mut injector, clock) = injector(policy);
+        injector.send(&[0x3E, 0x00]).unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_millis(200));
+        assert_eq!(injector.get_ref().sent(), [vec![0x3E, 0x00]]);
+    }
+
+    #[test]
+    fn dropped_response_times_out() {
+        let (mut injector, clock) = injector(FaultPolicy::new().drop(100).tx(false));
+        injector.send(&[0x3E, 0x00]).unwrap();
+        assert_eq!(injector.receive(Duration::from_secs(1)).unwrap(), None);
+        assert_eq!(clock.elapsed(), Duration::from_secs(1));
+    }
+}
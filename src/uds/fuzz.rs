@@ -0,0 +1,399 @@
+use super::{
+    decode_negative_response, Nrc, ServiceId, UdsError, UdsTransport, DEFAULT_P2,
+    DEFAULT_P2_EXTENDED, POSITIVE_RESPONSE_OFFSET,
+};
+use crate::IsoTpSocket;
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+/// Largest request of the classic ISO-TP length encoding
+const MAX_REQUEST_LEN: usize = 4095;
+
+/// A request sent by the [`Fuzzer`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzCase {
+    /// What the request probes, e.g. "reserved sub-function"
+    pub description: String,
+    /// The encoded request
+    pub request: Vec<u8>,
+}
+
+impl FuzzCase {
+    /// Create a case sending `request`
+    pub fn new(description: impl Into<String>, request: Vec<u8>) -> Self {
+        Self {
+            description: description.into(),
+            request,
+        }
+    }
+
+    /// Boundary cases of `service`: missing parameters, sub-functions reserved by
+    /// ISO 14229-1, truncated identifiers and oversized records
+    pub fn boundary(service: ServiceId) -> Vec<FuzzCase> {
+        let sid = service.as_u8();
+        let mut cases = vec![FuzzCase::new("missing parameters", vec![sid])];
+        if service.has_sub_function() {
+            for sub_function in [0x00, 0x7F, 0x80, 0xFF] {
+                cases.push(FuzzCase::new(
+                    format!("sub-function {sub_function:#04X}"),
+                    vec![sid, sub_function],
+                ));
+            }
+        }
+        if matches!(
+            service,
+            ServiceId::ReadDataByIdentifier
+                | ServiceId::WriteDataByIdentifier
+                | ServiceId::InputOutputControlByIdentifier
+                | ServiceId::ReadScalingDataByIdentifier
+        ) {
+            cases.push(FuzzCase::new("truncated data identifier", vec![sid, 0xF1]));
+            cases.push(FuzzCase::new(
+                "data identifier 0x0000",
+                vec![sid, 0x00, 0x00],
+            ));
+            cases.push(FuzzCase::new(
+                "data identifier 0xFFFF",
+                vec![sid, 0xFF, 0xFF],
+            ));
+        }
+        if matches!(
+            service,
+            ServiceId::ReadMemoryByAddress
+                | ServiceId::WriteMemoryByAddress
+                | ServiceId::RequestDownload
+                | ServiceId::RequestUpload
+        ) {
+            cases.push(FuzzCase::new(
+                "addressAndLengthFormatIdentifier 0x00",
+                vec![sid, 0x00, 0x00],
+            ));
+            cases.push(FuzzCase::new(
+                "addressAndLengthFormatIdentifier 0xFF",
+                vec![sid, 0xFF, 0xFF],
+            ));
+        }
+        let mut oversized = vec![0xFF; MAX_REQUEST_LEN];
+        oversized[0] = sid;
+        cases.push(FuzzCase::new("oversized request", oversized));
+        cases
+    }
+
+    /// Boundary cases of all services, see [`FuzzCase::boundary`]
+    pub fn boundary_all() -> Vec<FuzzCase> {
+        ServiceId::ALL
+            .iter()
+            .flat_map(|service| FuzzCase::boundary(*service))
+            .collect()
+    }
+}
+
+/// Deterministic random mutations of a valid request
+///
+/// Flips bytes, truncates or extends the request up to 4095 bytes, the service identifier
+/// is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mutator {
+    request: Vec<u8>,
+    state: u64,
+}
+
+impl Mutator {
+    /// Mutate `request`, reproducible for the same `seed`
+    pub fn new(request: &[u8], seed: u64) -> Self {
+        Self {
+            request: request.to_vec(),
+            // xorshift must not start at zero
+            state: seed | 1,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+impl Iterator for Mutator {
+    type Item = FuzzCase;
+
+    fn next(&mut self) -> Option<FuzzCase> {
+        let mut request = self.request.clone();
+        let mutation = match self.below(3) {
+            // a request at the length limit is mutated instead of extended
+            2 if request.len() >= MAX_REQUEST_LEN => 0,
+            mutation => mutation,
+        };
+        let description = match mutation {
+            0 if request.len() > 1 => {
+                let index = 1 + self.below(request.len() - 1);
+                request[index] = self.next_u64() as u8;
+                format!("byte {index} mutated")
+            }
+            1 if request.len() > 1 => {
+                let len = 1 + self.below(request.len() - 1);
+                request.truncate(len);
+                format!("truncated to {len} bytes")
+            }
+            _ => {
+                let len = 1 + self.below(MAX_REQUEST_LEN - request.len());
+                for _ in 0..len {
+                    request.push(self.next_u64() as u8);
+                }
+                format!("extended by {len} bytes")
+            }
+        };
+        Some(FuzzCase::new(description, request))
+    }
+}
+
+/// Classification of the server's reaction to a [`FuzzCase`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResponseClass {
+    /// Positive response, a malformed request was accepted
+    Positive,
+    /// Negative response with the given code
+    Negative(Nrc),
+    /// No response within the timeout
+    NoResponse,
+    /// A response not matching the request's service
+    Unexpected,
+}
+
+/// Result of one [`FuzzCase`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzOutcome {
+    /// The request sent
+    pub case: FuzzCase,
+    /// Classification of the response
+    pub class: ResponseClass,
+    /// The final response, if any
+    pub response: Option<Vec<u8>>,
+    /// Time until the final response or the timeout
+    pub elapsed: Duration,
+    /// Whether the server answered the liveness probe after an unanswered request,
+    /// `false` indicates a crash or reset
+    pub alive: bool,
+}
+
+/// Called after each [`FuzzCase`], e.g. to log findings, to check the active session or to
+/// restart a crashed ECU
+pub trait FuzzHook {
+    /// Inspect `outcome`, [`ControlFlow::Break`] stops the run
+    fn on_outcome(&mut self, outcome: &FuzzOutcome) -> ControlFlow<()>;
+}
+
+impl<F> FuzzHook for F
+where
+    F: FnMut(&FuzzOutcome) -> ControlFlow<()>,
+{
+    fn on_outcome(&mut self, outcome: &FuzzOutcome) -> ControlFlow<()> {
+        self(outcome)
+    }
+}
+
+/// Sends [`FuzzCase`]s over a [`UdsTransport`] and classifies the responses, for security
+/// testing of UDS servers
+///
+/// Only run against ECUs you are authorized to test, fuzzing may brick the ECU or trigger
+/// vehicle functions.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{FuzzCase, Fuzzer, ServiceId};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+/// use std::ops::ControlFlow;
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut fuzzer = Fuzzer::new(tp_socket);
+///     fuzzer.run(FuzzCase::boundary(ServiceId::ReadDataByIdentifier), &mut |outcome: &_| {
+///         println!("{:?}", outcome);
+///         ControlFlow::Continue(())
+///     })?;
+///     Ok(())
+/// }
+/// ```
+pub struct Fuzzer<T = IsoTpSocket> {
+    transport: T,
+    timeout: Duration,
+    liveness_probe: Vec<u8>,
+}
+
+impl<T: UdsTransport> Fuzzer<T> {
+    /// Fuzz over `transport`, waiting [`DEFAULT_P2`] for responses and probing liveness by
+    /// TesterPresent
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            timeout: DEFAULT_P2,
+            liveness_probe: vec![0x3E, 0x00],
+        }
+    }
+
+    /// Time to wait for a response
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Request checking the server is alive after an unanswered case
+    pub fn liveness_probe(mut self, request: &[u8]) -> Self {
+        self.liveness_probe = request.to_vec();
+        self
+    }
+
+    /// Send `case` and classify the response
+    pub fn run_case(&mut self, case: FuzzCase) -> Result<FuzzOutcome, UdsError> {
+        let sent_at = Instant::now();
+        let response = self.exchange(&case.request)?;
+        let elapsed = sent_at.elapsed();
+        let class = match (&response, case.request.first()) {
+            (None, _) => ResponseClass::NoResponse,
+            (Some(response), Some(&sid)) => match decode_negative_response(response) {
+                Some((service, nrc)) if service == sid => ResponseClass::Negative(nrc),
+                Some(_) => ResponseClass::Unexpected,
+                None if response.first() == Some(&sid.wrapping_add(POSITIVE_RESPONSE_OFFSET)) => {
+                    ResponseClass::Positive
+                }
+                None => ResponseClass::Unexpected,
+            },
+            (Some(_), None) => ResponseClass::Unexpected,
+        };
+        let alive = match class {
+            ResponseClass::NoResponse => {
+                let probe = self.liveness_probe.clone();
+                self.exchange(&probe)?.is_some()
+            }
+            _ => true,
+        };
+        Ok(FuzzOutcome {
+            case,
+            class,
+            response,
+            elapsed,
+            alive,
+        })
+    }
+
+    /// Run all `cases`, passing each outcome to `hook`
+    pub fn run(
+        &mut self,
+        cases: impl IntoIterator<Item = FuzzCase>,
+        hook: &mut impl FuzzHook,
+    ) -> Result<(), UdsError> {
+        for case in cases {
+            let outcome = self.run_case(case)?;
+            if hook.on_outcome(&outcome).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a mutable reference to the underlying transport
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Consume the fuzzer, returning the underlying transport
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Send `request` and return the final response, following pending responses
+    fn exchange(&mut self, request: &[u8]) -> Result<Option<Vec<u8>>, UdsError> {
+        self.transport.send(request)?;
+        let mut deadline = Instant::now() + self.timeout;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let Some(response) = self.transport.receive(timeout)? else {
+                return Ok(None);
+            };
+            match decode_negative_response(response) {
+                Some((sid, Nrc::RequestCorrectlyReceivedResponsePending))
+                    if request.first() == Some(&sid) =>
+                {
+                    deadline = Instant::now() + DEFAULT_P2_EXTENDED;
+                }
+                _ => return Ok(Some(response.to_vec())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::{MockReply, MockTransport};
+
+    #[test]
+    fn mutations_stay_within_request_limit() {
+        let short = [0x22, 0xF1, 0x90];
+        let mut limit = vec![0xAA; MAX_REQUEST_LEN];
+        limit[0] = 0x2E;
+        for request in [&short[..], &limit[..]] {
+            for seed in 0..20 {
+                for case in Mutator::new(request, seed).take(100) {
+                    assert!(!case.request.is_empty(), "{}", case.description);
+                    assert!(
+                        case.request.len() <= MAX_REQUEST_LEN,
+                        "{}",
+                        case.description
+                    );
+                    assert_eq!(case.request[0], request[0], "{}", case.description);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn mutations_are_reproducible() {
+        let request = [0x31, 0x01, 0xFF, 0x00];
+        let cases: Vec<_> = Mutator::new(&request, 7).take(20).collect();
+        assert_eq!(
+            Mutator::new(&request, 7).take(20).collect::<Vec<_>>(),
+            cases
+        );
+        assert_ne!(
+            Mutator::new(&request, 8).take(20).collect::<Vec<_>>(),
+            cases
+        );
+    }
+
+    #[test]
+    fn pending_responses_of_other_services_are_final() {
+        let mut mock = MockTransport::new()
+            .on_request(&[0x22], &[0x7F, 0x31, 0x78])
+            .on_request(
+                &[0x31],
+                MockReply::new()
+                    .response(&[0x7F, 0x31, 0x78])
+                    .response(&[0x71, 0x01, 0xFF, 0x00]),
+            );
+        let mut fuzzer = Fuzzer::new(&mut mock);
+        let outcome = fuzzer
+            .run_case(FuzzCase::new("other service", vec![0x22, 0xF1, 0x90]))
+            .unwrap();
+        assert_eq!(outcome.class, ResponseClass::Unexpected);
+        assert_eq!(outcome.response, Some(vec![0x7F, 0x31, 0x78]));
+
+        let outcome = fuzzer
+            .run_case(FuzzCase::new("pending", vec![0x31, 0x01, 0xFF, 0x00]))
+            .unwrap();
+        assert_eq!(outcome.class, ResponseClass::Positive);
+        assert!(outcome.alive);
+    }
+}
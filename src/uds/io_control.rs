@@ -0,0 +1,91 @@
+use super::{Did, DidEncode, ServiceId, UdsClient, UdsError, UdsTransport};
+
+/// inputOutputControlParameter of InputOutputControlByIdentifier (0x2F)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IoControl {
+    /// returnControlToECU (0x00)
+    ReturnControlToEcu,
+    /// resetToDefault (0x01)
+    ResetToDefault,
+    /// freezeCurrentState (0x02)
+    FreezeCurrentState,
+    /// shortTermAdjustment (0x03) to the given controlState
+    ShortTermAdjustment(Vec<u8>),
+}
+
+impl IoControl {
+    /// The encoded inputOutputControlParameter
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            IoControl::ReturnControlToEcu => 0x00,
+            IoControl::ResetToDefault => 0x01,
+            IoControl::FreezeCurrentState => 0x02,
+            IoControl::ShortTermAdjustment(_) => 0x03,
+        }
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// InputOutputControlByIdentifier (0x2F) of `did`, returning the controlStatusRecord.
+    ///
+    /// `enable_mask` is the controlEnableMaskRecord selecting the signals of a packeted
+    /// data identifier, empty if the data identifier controls a single signal.
+    pub fn io_control_by_identifier(
+        &mut self,
+        did: Did,
+        control: &IoControl,
+        enable_mask: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = did.to_be_bytes().to_vec();
+        request.push(control.as_u8());
+        if let IoControl::ShortTermAdjustment(control_state) = control {
+            request.extend_from_slice(control_state);
+        }
+        request.extend_from_slice(enable_mask);
+        let response = self.request(ServiceId::InputOutputControlByIdentifier, &request)?;
+        match *response {
+            [high, low, parameter, ref status @ ..]
+                if Did::from_be_bytes([high, low]) == did && parameter == control.as_u8() =>
+            {
+                Ok(status.to_vec())
+            }
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the data identifier and control parameter",
+            )),
+        }
+    }
+
+    /// Return control of `did` to the server
+    pub fn return_control_to_ecu(&mut self, did: Did) -> Result<Vec<u8>, UdsError> {
+        self.io_control_by_identifier(did, &IoControl::ReturnControlToEcu, &[])
+    }
+
+    /// Reset `did` to its default value
+    pub fn reset_to_default(&mut self, did: Did) -> Result<Vec<u8>, UdsError> {
+        self.io_control_by_identifier(did, &IoControl::ResetToDefault, &[])
+    }
+
+    /// Freeze the current state of `did`
+    pub fn freeze_current_state(&mut self, did: Did) -> Result<Vec<u8>, UdsError> {
+        self.io_control_by_identifier(did, &IoControl::FreezeCurrentState, &[])
+    }
+
+    /// Temporarily set `did` to `value` encoded as controlState
+    ///
+    /// ```rust,no_run
+    /// # fn example(client: &mut socketcan_isotp::uds::UdsClient) -> Result<(), socketcan_isotp::uds::UdsError> {
+    /// // drive the fan output at 80 %
+    /// client.short_term_adjustment(0x4A10, &80u8)?;
+    /// client.return_control_to_ecu(0x4A10)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn short_term_adjustment<V: DidEncode + ?Sized>(
+        &mut self,
+        did: Did,
+        value: &V,
+    ) -> Result<Vec<u8>, UdsError> {
+        self.io_control_by_identifier(did, &IoControl::ShortTermAdjustment(value.encode()), &[])
+    }
+}
@@ -0,0 +1,343 @@
+use super::{Request, ServiceId, UdsClient, UdsError, UdsTransport};
+use crate::netlink::{push_attribute, LinkRequest};
+use crate::{interface_index, Error};
+use libc::{c_int, IFLA_INFO_DATA, IFLA_INFO_KIND, IFLA_LINKINFO, RTM_NEWLINK};
+use std::io;
+use std::time::Duration;
+
+/// Link baudrate requested by LinkControl (0x87)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkBaudrate {
+    /// Fixed baudrate, see [`LinkBaudrate::CAN_125K`] and friends
+    Fixed(u8),
+    /// Specific baudrate in bit/s, encoded in 3 bytes
+    Specific(u32),
+}
+
+impl LinkBaudrate {
+    /// CAN 125 kBit/s linkControlModeIdentifier
+    pub const CAN_125K: Self = LinkBaudrate::Fixed(0x10);
+    /// CAN 250 kBit/s linkControlModeIdentifier
+    pub const CAN_250K: Self = LinkBaudrate::Fixed(0x11);
+    /// CAN 500 kBit/s linkControlModeIdentifier
+    pub const CAN_500K: Self = LinkBaudrate::Fixed(0x12);
+    /// CAN 1 MBit/s linkControlModeIdentifier
+    pub const CAN_1M: Self = LinkBaudrate::Fixed(0x13);
+
+    /// The baudrate in bit/s, `None` for vehicle manufacturer specific identifiers
+    pub fn bitrate(self) -> Option<u32> {
+        match self {
+            LinkBaudrate::Fixed(0x01) => Some(9_600),
+            LinkBaudrate::Fixed(0x02) => Some(19_200),
+            LinkBaudrate::Fixed(0x03) => Some(38_400),
+            LinkBaudrate::Fixed(0x04) => Some(57_600),
+            LinkBaudrate::Fixed(0x05) => Some(115_200),
+            LinkBaudrate::Fixed(0x10) => Some(125_000),
+            LinkBaudrate::Fixed(0x11) => Some(250_000),
+            LinkBaudrate::Fixed(0x12) => Some(500_000),
+            LinkBaudrate::Fixed(0x13) => Some(1_000_000),
+            LinkBaudrate::Fixed(_) => None,
+            LinkBaudrate::Specific(bitrate) => Some(bitrate),
+        }
+    }
+
+    /// Encode as verify sub-function and its parameters
+    fn encode_verify(self) -> Result<Vec<u8>, UdsError> {
+        match self {
+            LinkBaudrate::Fixed(identifier) => Ok(vec![0x01, identifier]),
+            LinkBaudrate::Specific(bitrate) if bitrate <= 0xFF_FFFF => {
+                let [_, high, middle, low] = bitrate.to_be_bytes();
+                Ok(vec![0x02, high, middle, low])
+            }
+            LinkBaudrate::Specific(_) => Err(UdsError::InvalidRequest(
+                "specific link baudrate must fit in 3 bytes",
+            )),
+        }
+    }
+}
+
+/// Switches the bitrate of the tester-side CAN interface during a LinkControl transition
+///
+/// Implemented for closures, e.g. to reconfigure a USB adapter instead of using
+/// [`NetlinkBitrate`].
+pub trait BitrateSwitch {
+    /// Reconfigure the interface to `bitrate` bit/s
+    fn switch_bitrate(&mut self, bitrate: u32) -> io::Result<()>;
+}
+
+impl<F> BitrateSwitch for F
+where
+    F: FnMut(u32) -> io::Result<()>,
+{
+    fn switch_bitrate(&mut self, bitrate: u32) -> io::Result<()> {
+        self(bitrate)
+    }
+}
+
+/// [`BitrateSwitch`] restarting a SocketCAN interface with the new bitrate over rtnetlink.
+///
+/// Requires `CAP_NET_ADMIN`. The ISO-TP socket stays bound while the interface is down. The
+/// kernel calculates the bit timing for the bitrate, CAN FD interfaces keep their data
+/// bitrate unless set with [`NetlinkBitrate::data_bitrate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetlinkBitrate {
+    interface: String,
+    data_bitrate: Option<u32>,
+}
+
+impl NetlinkBitrate {
+    /// Switch the bitrate of `interface`
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self {
+            interface: interface.into(),
+            data_bitrate: None,
+        }
+    }
+
+    /// Set the CAN FD data phase bitrate along with the nominal bitrate
+    pub fn data_bitrate(mut self, data_bitrate: u32) -> Self {
+        self.data_bitrate = Some(data_bitrate);
+        self
+    }
+
+    /// get the CAN FD data phase bitrate, `None` if it is left unchanged
+    pub fn get_data_bitrate(&self) -> Option<u32> {
+        self.data_bitrate
+    }
+
+    fn set_bitrate(&self, if_index: c_int, bitrate: u32) -> Result<(), Error> {
+        let mut data = Vec::new();
+        push_attribute(&mut data, IFLA_CAN_BITTIMING, &can_bittiming(bitrate));
+        if let Some(data_bitrate) = self.data_bitrate {
+            push_attribute(
+                &mut data,
+                IFLA_CAN_DATA_BITTIMING,
+                &can_bittiming(data_bitrate),
+            );
+        }
+        let mut link_info = Vec::new();
+        push_attribute(&mut link_info, IFLA_INFO_KIND, b"can");
+        push_attribute(&mut link_info, IFLA_INFO_DATA, &data);
+        let mut request = LinkRequest::new(RTM_NEWLINK, 0, if_index);
+        request.attribute(IFLA_LINKINFO, &link_info);
+        request.execute()
+    }
+}
+
+impl BitrateSwitch for NetlinkBitrate {
+    fn switch_bitrate(&mut self, bitrate: u32) -> io::Result<()> {
+        let if_index = interface_index(&self.interface)?;
+        let set_up = |up| {
+            let mut request = LinkRequest::new(RTM_NEWLINK, 0, if_index);
+            request.set_up(up);
+            request.execute()
+        };
+        set_up(false)?;
+        self.set_bitrate(if_index, bitrate)?;
+        Ok(set_up(true)?)
+    }
+}
+
+/// IFLA_CAN_BITTIMING attribute of linux/can/netlink.h
+const IFLA_CAN_BITTIMING: u16 = 1;
+/// IFLA_CAN_DATA_BITTIMING attribute of linux/can/netlink.h
+const IFLA_CAN_DATA_BITTIMING: u16 = 9;
+
+/// struct can_bittiming with only the bitrate set, the kernel calculates the rest
+fn can_bittiming(bitrate: u32) -> [u8; 32] {
+    let mut bittiming = [0; 32];
+    bittiming[..4].copy_from_slice(&bitrate.to_ne_bytes());
+    bittiming
+}
+
+/// LinkControl (0x87) verify and transition sequence, switching the tester interface in
+/// lockstep with the server
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{NetlinkBitrate, LinkBaudrate, LinkControl, UdsClient};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "can0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = UdsClient::new(tp_socket);
+///     LinkControl::new(LinkBaudrate::CAN_1M).run(&mut client, &mut NetlinkBitrate::new("can0"))
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinkControl {
+    baudrate: LinkBaudrate,
+    suppress_transition_response: bool,
+    settle_time: Duration,
+}
+
+impl LinkControl {
+    /// Transition to `baudrate`
+    pub fn new(baudrate: LinkBaudrate) -> Self {
+        Self {
+            baudrate,
+            suppress_transition_response: false,
+            settle_time: Duration::ZERO,
+        }
+    }
+
+    /// Ask the server not to send a positive response to transitionMode instead of waiting
+    /// for it before switching.
+    ///
+    /// A suppressed request is sent with [`Request::wait_tx_done`], so the interface is only
+    /// switched after the request was transmitted. Kernel sockets need
+    /// [`IsoTpSocketBuilder::wait_tx_done`](crate::IsoTpSocketBuilder::wait_tx_done) for that,
+    /// otherwise the positive response must be awaited.
+    pub fn suppress_transition_response(mut self, suppress: bool) -> Self {
+        self.suppress_transition_response = suppress;
+        self
+    }
+
+    /// Delay the next request after switching the interface
+    pub fn settle_time(mut self, settle_time: Duration) -> Self {
+        self.settle_time = settle_time;
+        self
+    }
+
+    /// get the requested baudrate
+    pub fn get_baudrate(&self) -> LinkBaudrate {
+        self.baudrate
+    }
+
+    /// Verify the transition, request it and switch the tester interface with `switch`
+    pub fn run(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        switch: &mut impl BitrateSwitch,
+    ) -> Result<(), UdsError> {
+        let bitrate = self.baudrate.bitrate().ok_or(UdsError::InvalidRequest(
+            "bitrate of vehicle manufacturer specific link baudrate is unknown",
+        ))?;
+        client.verify_link_baudrate(self.baudrate)?;
+        client.transition_link_baudrate(self.suppress_transition_response)?;
+        switch.switch_bitrate(bitrate)?;
+        client.clock.sleep(self.settle_time);
+        Ok(())
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// LinkControl (0x87) verifyModeTransition, checking the server can switch to `baudrate`
+    pub fn verify_link_baudrate(&mut self, baudrate: LinkBaudrate) -> Result<(), UdsError> {
+        let request = baudrate.encode_verify()?;
+        let response = self.request(ServiceId::LinkControl, &request)?;
+        match response.first() {
+            Some(&echo) if echo == request[0] => Ok(()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the link control type",
+            )),
+        }
+    }
+
+    /// LinkControl (0x87) transitionMode, switching the server to the verified baudrate
+    pub fn transition_link_baudrate(&mut self, suppress_response: bool) -> Result<(), UdsError> {
+        // the tester switches its bitrate next, the request must have left the interface
+        let request = Request::new(ServiceId::LinkControl)
+            .sub_function(0x03)
+            .suppress_positive_response(suppress_response)
+            .wait_tx_done(suppress_response);
+        match self.send(&request)? {
+            Some(response) if response.first() != Some(&0x03) => Err(UdsError::InvalidResponse(
+                "response does not echo the link control type",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::transport::IsoTpTransport;
+    use crate::uds::MockTransport;
+    use std::sync::Arc;
+
+    fn ecu() -> MockTransport {
+        MockTransport::new()
+            .on_request(&[0x87, 0x01, 0x13], &[0xC7, 0x01])
+            .on_request(&[0x87, 0x03], &[0xC7, 0x03])
+    }
+
+    #[test]
+    fn run_switches_after_transition_response() {
+        let mut mock = ecu();
+        let mut client = UdsClient::new(&mut mock);
+        let mut bitrates = Vec::new();
+        let mut switch = |bitrate| {
+            bitrates.push(bitrate);
+            Ok(())
+        };
+        LinkControl::new(LinkBaudrate::CAN_1M)
+            .run(&mut client, &mut switch)
+            .unwrap();
+        assert_eq!(bitrates, [1_000_000]);
+        assert_eq!(mock.sent(), [vec![0x87, 0x01, 0x13], vec![0x87, 0x03]]);
+    }
+
+    #[test]
+    fn run_waits_for_settle_time() {
+        let mut mock = ecu();
+        let mut client = UdsClient::new(&mut mock);
+        let clock = SimulatedClock::new();
+        client.set_clock(Arc::new(clock.clone()));
+        LinkControl::new(LinkBaudrate::CAN_1M)
+            .settle_time(Duration::from_secs(2))
+            .run(&mut client, &mut |_| Ok(()))
+            .unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn suppressed_transition_waits_for_transmission() {
+        /// Records the PDUs sent with transmission confirmation
+        struct Confirming {
+            mock: MockTransport,
+            confirmed: Vec<Vec<u8>>,
+        }
+
+        impl IsoTpTransport for Confirming {
+            fn send(&mut self, pdu: &[u8]) -> Result<(), Error> {
+                IsoTpTransport::send(&mut self.mock, pdu)
+            }
+
+            fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+                IsoTpTransport::receive(&mut self.mock, timeout)
+            }
+
+            fn send_wait_tx_done(&mut self, pdu: &[u8]) -> Result<(), Error> {
+                self.confirmed.push(pdu.to_vec());
+                self.send(pdu)
+            }
+        }
+
+        let mut transport = Confirming {
+            mock: ecu(),
+            confirmed: Vec::new(),
+        };
+        let mut client = UdsClient::new(&mut transport);
+        // no negative response to the suppressed transition
+        client.set_p2(Duration::from_millis(1));
+        LinkControl::new(LinkBaudrate::CAN_1M)
+            .suppress_transition_response(true)
+            .run(&mut client, &mut |_| Ok(()))
+            .unwrap();
+        assert_eq!(transport.confirmed, [vec![0x87, 0x83]]);
+    }
+
+    #[test]
+    fn netlink_bitrate_fails_on_unknown_interface() {
+        let mut switch = NetlinkBitrate::new("nosuchcan0").data_bitrate(2_000_000);
+        assert_eq!(switch.get_data_bitrate(), Some(2_000_000));
+        assert!(switch.switch_bitrate(500_000).is_err());
+    }
+}
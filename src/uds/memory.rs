@@ -0,0 +1,162 @@
+use super::{ServiceId, UdsClient, UdsError, UdsTransport};
+
+/// addressAndLengthFormatIdentifier, the byte widths of memory address and size parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddressAndLengthFormat {
+    address_len: u8,
+    size_len: u8,
+}
+
+impl AddressAndLengthFormat {
+    /// Encode addresses with `address_len` and sizes with `size_len` bytes, each 1 - 15
+    pub fn new(address_len: u8, size_len: u8) -> Result<Self, UdsError> {
+        if !(1..=15).contains(&address_len) || !(1..=15).contains(&size_len) {
+            return Err(UdsError::InvalidRequest(
+                "memory address and size must be 1 - 15 bytes wide",
+            ));
+        }
+        Ok(Self {
+            address_len,
+            size_len,
+        })
+    }
+
+    /// The narrowest format able to encode `address` and `size`
+    pub fn minimal(address: u64, size: u64) -> Self {
+        Self {
+            address_len: min_len(address),
+            size_len: min_len(size),
+        }
+    }
+
+    /// Decode an addressAndLengthFormatIdentifier byte
+    pub fn from_u8(format: u8) -> Result<Self, UdsError> {
+        Self::new(format & 0x0F, format >> 4)
+    }
+
+    /// The encoded addressAndLengthFormatIdentifier byte
+    pub fn as_u8(&self) -> u8 {
+        self.size_len << 4 | self.address_len
+    }
+
+    /// get the byte width of memory addresses
+    pub fn get_address_len(&self) -> u8 {
+        self.address_len
+    }
+
+    /// get the byte width of memory sizes
+    pub fn get_size_len(&self) -> u8 {
+        self.size_len
+    }
+
+    /// Encode `address` and `size` big endian, failing if either exceeds its width
+    pub fn encode(&self, address: u64, size: u64) -> Result<Vec<u8>, UdsError> {
+        if min_len(address) > self.address_len {
+            return Err(UdsError::InvalidRequest(
+                "memory address exceeds the address width",
+            ));
+        }
+        if min_len(size) > self.size_len {
+            return Err(UdsError::InvalidRequest(
+                "memory size exceeds the size width",
+            ));
+        }
+        let mut bytes = Vec::with_capacity(usize::from(self.address_len + self.size_len));
+        encode_be(&mut bytes, address, self.address_len);
+        encode_be(&mut bytes, size, self.size_len);
+        Ok(bytes)
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ReadMemoryByAddress (0x23) of `size` bytes at `address`
+    pub fn read_memory_by_address(
+        &mut self,
+        format: AddressAndLengthFormat,
+        address: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = vec![format.as_u8()];
+        request.extend(format.encode(address, size)?);
+        let response = self.request(ServiceId::ReadMemoryByAddress, &request)?;
+        if response.len() as u64 != size {
+            return Err(UdsError::InvalidResponse(
+                "response length does not match the memory size",
+            ));
+        }
+        Ok(response)
+    }
+
+    /// Read `size` bytes at `address` by ReadMemoryByAddress requests of at most
+    /// `max_chunk_len` bytes each, for regions exceeding a single response
+    pub fn read_memory(
+        &mut self,
+        format: AddressAndLengthFormat,
+        address: u64,
+        size: u64,
+        max_chunk_len: u64,
+    ) -> Result<Vec<u8>, UdsError> {
+        if max_chunk_len == 0 {
+            return Err(UdsError::InvalidRequest("chunk length must not be zero"));
+        }
+        // validate the whole region up front, before reading any chunk
+        format.encode(
+            address.saturating_add(size.saturating_sub(1)),
+            size.min(max_chunk_len),
+        )?;
+        let mut data = Vec::with_capacity(usize::try_from(size).unwrap_or(0));
+        let mut offset = 0;
+        while offset < size {
+            let chunk_len = max_chunk_len.min(size - offset);
+            data.extend(self.read_memory_by_address(format, address + offset, chunk_len)?);
+            offset += chunk_len;
+        }
+        Ok(data)
+    }
+
+    /// WriteMemoryByAddress (0x3D) of `data` at `address`
+    pub fn write_memory_by_address(
+        &mut self,
+        format: AddressAndLengthFormat,
+        address: u64,
+        data: &[u8],
+    ) -> Result<(), UdsError> {
+        let mut request = vec![format.as_u8()];
+        request.extend(format.encode(address, data.len() as u64)?);
+        let echo_len = request.len();
+        request.extend_from_slice(data);
+        let response = self.request(ServiceId::WriteMemoryByAddress, &request)?;
+        if response.get(..echo_len) != Some(&request[..echo_len]) {
+            return Err(UdsError::InvalidResponse(
+                "response does not echo the memory address and size",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Number of bytes needed to encode `value`, at least 1
+fn min_len(value: u64) -> u8 {
+    (8 - (value.leading_zeros() / 8) as u8).max(1)
+}
+
+/// Append `value` as `len` bytes big endian, zero padded beyond 8 bytes
+fn encode_be(bytes: &mut Vec<u8>, value: u64, len: u8) {
+    let len = usize::from(len);
+    bytes.extend(std::iter::repeat_n(0, len.saturating_sub(8)));
+    bytes.extend_from_slice(&value.to_be_bytes()[8 - len.min(8)..]);
+}
+
+/// Decode a big endian unsigned integer, `None` if it exceeds 8 bytes
+pub(crate) fn decode_be(bytes: &[u8]) -> Option<u64> {
+    let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+    if bytes.len() - leading_zeros > 8 {
+        return None;
+    }
+    Some(
+        bytes
+            .iter()
+            .fold(0, |value, byte| value << 8 | u64::from(*byte)),
+    )
+}
@@ -0,0 +1,247 @@
+use super::UdsTransport;
+use crate::clock::{Clock, SystemClock};
+use crate::transport::AsyncIsoTpTransport;
+use crate::Error;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Responses a [`MockTransport`] sends to a matching request, each after its delay
+///
+/// ```rust
+/// use socketcan_isotp::uds::MockReply;
+/// use std::time::Duration;
+///
+/// // responsePending followed by the final response 100ms later
+/// let reply = MockReply::new()
+///     .response(&[0x7F, 0x31, 0x78])
+///     .delayed_response(Duration::from_millis(100), &[0x71, 0x01, 0xFF, 0x00]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct MockReply {
+    responses: Vec<(Duration, Vec<u8>)>,
+}
+
+impl MockReply {
+    /// A reply without responses, the request times out
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a response sent immediately after the previous one
+    pub fn response(self, response: &[u8]) -> Self {
+        self.delayed_response(Duration::ZERO, response)
+    }
+
+    /// Add a response sent `delay` after the previous one
+    pub fn delayed_response(mut self, delay: Duration, response: &[u8]) -> Self {
+        self.responses.push((delay, response.to_vec()));
+        self
+    }
+
+    /// Get the responses with the delay preceding each of them
+    pub fn get_responses(&self) -> &[(Duration, Vec<u8>)] {
+        &self.responses
+    }
+}
+
+impl From<&[u8]> for MockReply {
+    fn from(response: &[u8]) -> Self {
+        Self::new().response(response)
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for MockReply {
+    fn from(response: &[u8; N]) -> Self {
+        Self::new().response(response)
+    }
+}
+
+impl From<Vec<u8>> for MockReply {
+    fn from(response: Vec<u8>) -> Self {
+        Self::new().response(&response)
+    }
+}
+
+struct Rule {
+    prefix: Vec<u8>,
+    reply: MockReply,
+    /// remaining number of matches, `None` for unlimited
+    remaining: Option<usize>,
+}
+
+/// In-memory [`UdsTransport`] answering requests from a script, e.g. to unit test
+/// diagnostic logic without a CAN interface
+///
+/// Requests are matched against the request prefixes in the order they were added, the
+/// first matching rule queues its [`MockReply`]. Requests without a matching rule are
+/// not answered. All sent requests are recorded.
+///
+/// [`receive`](UdsTransport::receive) sleeps until the next response is due or the
+/// timeout expired, on a [`SimulatedClock`](crate::clock::SimulatedClock) shared with the
+/// client delays and timeouts pass without waiting:
+///
+/// ```rust
+/// use socketcan_isotp::clock::SimulatedClock;
+/// use socketcan_isotp::uds::{MockReply, MockTransport, UdsClient, UdsError};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let clock = SimulatedClock::new();
+/// let mock = MockTransport::new()
+///     .with_clock(Arc::new(clock.clone()))
+///     .on_request(
+///         &[0x31, 0x01, 0xFF, 0x00],
+///         MockReply::new()
+///             .response(&[0x7F, 0x31, 0x78])
+///             .delayed_response(Duration::from_secs(6), &[0x71, 0x01, 0xFF, 0x00]),
+///     );
+/// let mut client = UdsClient::new(mock);
+/// client.set_clock(Arc::new(clock.clone()));
+///
+/// // the final response misses P2* of 5s
+/// let result = client.request(0x31, &[0x01, 0xFF, 0x00]);
+/// assert!(matches!(result, Err(UdsError::Timeout)));
+/// assert_eq!(clock.elapsed(), Duration::from_secs(5));
+/// ```
+///
+/// ```rust
+/// use socketcan_isotp::uds::{MockTransport, UdsClient};
+///
+/// let mut mock = MockTransport::new()
+///     .on_request(&[0x22, 0xF1, 0x90], b"\x62\xF1\x90WVWZZZ1JZXW000001")
+///     .on_request(&[0x11], &[0x7F, 0x11, 0x22]);
+/// let mut client = UdsClient::new(&mut mock);
+/// assert_eq!(
+///     client.read_data_by_identifier(0xF190).unwrap(),
+///     b"WVWZZZ1JZXW000001"
+/// );
+/// assert!(client.request(0x11, &[0x01]).is_err());
+/// assert_eq!(mock.sent(), [vec![0x22, 0xF1, 0x90], vec![0x11, 0x01]]);
+/// ```
+pub struct MockTransport {
+    rules: Vec<Rule>,
+    queued: VecDeque<(Duration, Vec<u8>)>,
+    sent: Vec<Vec<u8>>,
+    response: Vec<u8>,
+    clock: Arc<dyn Clock>,
+}
+
+impl MockTransport {
+    /// Create a mock without rules, answering no requests
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            queued: VecDeque::new(),
+            sent: Vec::new(),
+            response: Vec::new(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Wait for delays and timeouts on `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Reply to every request starting with `prefix`
+    pub fn on_request(mut self, prefix: &[u8], reply: impl Into<MockReply>) -> Self {
+        self.add_rule(prefix, reply.into(), None);
+        self
+    }
+
+    /// Reply to the next `times` requests starting with `prefix`, afterwards the rule is
+    /// skipped, e.g. to fail a request before it succeeds
+    pub fn on_request_times(
+        mut self,
+        prefix: &[u8],
+        times: usize,
+        reply: impl Into<MockReply>,
+    ) -> Self {
+        self.add_rule(prefix, reply.into(), Some(times));
+        self
+    }
+
+    /// Add a rule replying to requests starting with `prefix`, at most `times` times if
+    /// given
+    pub fn add_rule(&mut self, prefix: &[u8], reply: MockReply, times: Option<usize>) {
+        self.rules.push(Rule {
+            prefix: prefix.to_vec(),
+            reply,
+            remaining: times,
+        });
+    }
+
+    /// Queue an unsolicited response received after `delay`, e.g. a ResponseOnEvent
+    pub fn push_response(&mut self, delay: Duration, response: &[u8]) {
+        self.queued.push_back((delay, response.to_vec()));
+    }
+
+    /// Get the requests sent so far in order
+    pub fn sent(&self) -> &[Vec<u8>] {
+        &self.sent
+    }
+
+    /// Take the requests sent so far, clearing the record
+    pub fn take_sent(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.sent)
+    }
+
+    /// Number of responses queued but not yet received
+    pub fn pending_responses(&self) -> usize {
+        self.queued.len()
+    }
+}
+
+impl Default for MockTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UdsTransport for MockTransport {
+    fn send(&mut self, request: &[u8]) -> Result<(), Error> {
+        self.sent.push(request.to_vec());
+        let rule = self
+            .rules
+            .iter_mut()
+            .find(|rule| request.starts_with(&rule.prefix) && rule.remaining != Some(0));
+        if let Some(rule) = rule {
+            if let Some(remaining) = &mut rule.remaining {
+                *remaining -= 1;
+            }
+            self.queued.extend(rule.reply.responses.iter().cloned());
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        let Some((delay, _)) = self.queued.front_mut() else {
+            self.clock.sleep(timeout);
+            return Ok(None);
+        };
+        if *delay > timeout {
+            *delay -= timeout;
+            self.clock.sleep(timeout);
+            return Ok(None);
+        }
+        self.clock.sleep(*delay);
+        if let Some((_, response)) = self.queued.pop_front() {
+            self.response = response;
+        }
+        Ok(Some(&self.response))
+    }
+}
+
+/// Answers like the blocking implementation, response delays block the task unless the
+/// mock runs on a [`SimulatedClock`](crate::clock::SimulatedClock)
+impl AsyncIsoTpTransport for MockTransport {
+    async fn send(&mut self, request: &[u8]) -> Result<(), Error> {
+        UdsTransport::send(self, request)
+    }
+
+    async fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        UdsTransport::receive(self, timeout)
+    }
+}
@@ -0,0 +1,382 @@
+use std::fmt;
+
+/// Negative response code as defined by ISO 14229-1
+///
+/// Codes without a standardized meaning are kept as [`Nrc::Other`], including the
+/// vehicle manufacturer specific range `0xF0 - 0xFE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "u8", into = "u8"))]
+pub enum Nrc {
+    /// 0x10
+    GeneralReject,
+    /// 0x11
+    ServiceNotSupported,
+    /// 0x12
+    SubFunctionNotSupported,
+    /// 0x13
+    IncorrectMessageLengthOrInvalidFormat,
+    /// 0x14
+    ResponseTooLong,
+    /// 0x21
+    BusyRepeatRequest,
+    /// 0x22
+    ConditionsNotCorrect,
+    /// 0x24
+    RequestSequenceError,
+    /// 0x25
+    NoResponseFromSubnetComponent,
+    /// 0x26
+    FailurePreventsExecutionOfRequestedAction,
+    /// 0x31
+    RequestOutOfRange,
+    /// 0x33
+    SecurityAccessDenied,
+    /// 0x34
+    AuthenticationRequired,
+    /// 0x35
+    InvalidKey,
+    /// 0x36
+    ExceedNumberOfAttempts,
+    /// 0x37
+    RequiredTimeDelayNotExpired,
+    /// 0x38
+    SecureDataTransmissionRequired,
+    /// 0x39
+    SecureDataTransmissionNotAllowed,
+    /// 0x3A
+    SecureDataVerificationFailed,
+    /// 0x50
+    CertificateVerificationFailedInvalidTimePeriod,
+    /// 0x51
+    CertificateVerificationFailedInvalidSignature,
+    /// 0x52
+    CertificateVerificationFailedInvalidChainOfTrust,
+    /// 0x53
+    CertificateVerificationFailedInvalidType,
+    /// 0x54
+    CertificateVerificationFailedInvalidFormat,
+    /// 0x55
+    CertificateVerificationFailedInvalidContent,
+    /// 0x56
+    CertificateVerificationFailedInvalidScope,
+    /// 0x57
+    CertificateVerificationFailedInvalidCertificate,
+    /// 0x58
+    OwnershipVerificationFailed,
+    /// 0x59
+    ChallengeCalculationFailed,
+    /// 0x5A
+    SettingAccessRightsFailed,
+    /// 0x5B
+    SessionKeyCreationDerivationFailed,
+    /// 0x5C
+    ConfigurationDataUsageFailed,
+    /// 0x5D
+    DeAuthenticationFailed,
+    /// 0x70
+    UploadDownloadNotAccepted,
+    /// 0x71
+    TransferDataSuspended,
+    /// 0x72
+    GeneralProgrammingFailure,
+    /// 0x73
+    WrongBlockSequenceCounter,
+    /// 0x78
+    RequestCorrectlyReceivedResponsePending,
+    /// 0x7E
+    SubFunctionNotSupportedInActiveSession,
+    /// 0x7F
+    ServiceNotSupportedInActiveSession,
+    /// 0x81
+    RpmTooHigh,
+    /// 0x82
+    RpmTooLow,
+    /// 0x83
+    EngineIsRunning,
+    /// 0x84
+    EngineIsNotRunning,
+    /// 0x85
+    EngineRunTimeTooLow,
+    /// 0x86
+    TemperatureTooHigh,
+    /// 0x87
+    TemperatureTooLow,
+    /// 0x88
+    VehicleSpeedTooHigh,
+    /// 0x89
+    VehicleSpeedTooLow,
+    /// 0x8A
+    ThrottlePedalTooHigh,
+    /// 0x8B
+    ThrottlePedalTooLow,
+    /// 0x8C
+    TransmissionRangeNotInNeutral,
+    /// 0x8D
+    TransmissionRangeNotInGear,
+    /// 0x8F
+    BrakeSwitchesNotClosed,
+    /// 0x90
+    ShifterLeverNotInPark,
+    /// 0x91
+    TorqueConverterClutchLocked,
+    /// 0x92
+    VoltageTooHigh,
+    /// 0x93
+    VoltageTooLow,
+    /// 0x94
+    ResourceTemporarilyNotAvailable,
+    /// Reserved or vehicle manufacturer specific code
+    Other(u8),
+}
+
+/// Code, variant and description of every standardized negative response code
+const NRCS: &[(u8, Nrc, &str)] = &[
+    (0x10, Nrc::GeneralReject, "general reject"),
+    (0x11, Nrc::ServiceNotSupported, "service not supported"),
+    (
+        0x12,
+        Nrc::SubFunctionNotSupported,
+        "sub-function not supported",
+    ),
+    (
+        0x13,
+        Nrc::IncorrectMessageLengthOrInvalidFormat,
+        "incorrect message length or invalid format",
+    ),
+    (0x14, Nrc::ResponseTooLong, "response too long"),
+    (0x21, Nrc::BusyRepeatRequest, "busy, repeat request"),
+    (0x22, Nrc::ConditionsNotCorrect, "conditions not correct"),
+    (0x24, Nrc::RequestSequenceError, "request sequence error"),
+    (
+        0x25,
+        Nrc::NoResponseFromSubnetComponent,
+        "no response from subnet component",
+    ),
+    (
+        0x26,
+        Nrc::FailurePreventsExecutionOfRequestedAction,
+        "failure prevents execution of requested action",
+    ),
+    (0x31, Nrc::RequestOutOfRange, "request out of range"),
+    (0x33, Nrc::SecurityAccessDenied, "security access denied"),
+    (0x34, Nrc::AuthenticationRequired, "authentication required"),
+    (0x35, Nrc::InvalidKey, "invalid key"),
+    (
+        0x36,
+        Nrc::ExceedNumberOfAttempts,
+        "exceeded number of attempts",
+    ),
+    (
+        0x37,
+        Nrc::RequiredTimeDelayNotExpired,
+        "required time delay not expired",
+    ),
+    (
+        0x38,
+        Nrc::SecureDataTransmissionRequired,
+        "secure data transmission required",
+    ),
+    (
+        0x39,
+        Nrc::SecureDataTransmissionNotAllowed,
+        "secure data transmission not allowed",
+    ),
+    (
+        0x3A,
+        Nrc::SecureDataVerificationFailed,
+        "secure data verification failed",
+    ),
+    (
+        0x50,
+        Nrc::CertificateVerificationFailedInvalidTimePeriod,
+        "certificate verification failed, invalid time period",
+    ),
+    (
+        0x51,
+        Nrc::CertificateVerificationFailedInvalidSignature,
+        "certificate verification failed, invalid signature",
+    ),
+    (
+        0x52,
+        Nrc::CertificateVerificationFailedInvalidChainOfTrust,
+        "certificate verification failed, invalid chain of trust",
+    ),
+    (
+        0x53,
+        Nrc::CertificateVerificationFailedInvalidType,
+        "certificate verification failed, invalid type",
+    ),
+    (
+        0x54,
+        Nrc::CertificateVerificationFailedInvalidFormat,
+        "certificate verification failed, invalid format",
+    ),
+    (
+        0x55,
+        Nrc::CertificateVerificationFailedInvalidContent,
+        "certificate verification failed, invalid content",
+    ),
+    (
+        0x56,
+        Nrc::CertificateVerificationFailedInvalidScope,
+        "certificate verification failed, invalid scope",
+    ),
+    (
+        0x57,
+        Nrc::CertificateVerificationFailedInvalidCertificate,
+        "certificate verification failed, invalid certificate",
+    ),
+    (
+        0x58,
+        Nrc::OwnershipVerificationFailed,
+        "ownership verification failed",
+    ),
+    (
+        0x59,
+        Nrc::ChallengeCalculationFailed,
+        "challenge calculation failed",
+    ),
+    (
+        0x5A,
+        Nrc::SettingAccessRightsFailed,
+        "setting access rights failed",
+    ),
+    (
+        0x5B,
+        Nrc::SessionKeyCreationDerivationFailed,
+        "session key creation/derivation failed",
+    ),
+    (
+        0x5C,
+        Nrc::ConfigurationDataUsageFailed,
+        "configuration data usage failed",
+    ),
+    (
+        0x5D,
+        Nrc::DeAuthenticationFailed,
+        "de-authentication failed",
+    ),
+    (
+        0x70,
+        Nrc::UploadDownloadNotAccepted,
+        "upload/download not accepted",
+    ),
+    (0x71, Nrc::TransferDataSuspended, "transfer data suspended"),
+    (
+        0x72,
+        Nrc::GeneralProgrammingFailure,
+        "general programming failure",
+    ),
+    (
+        0x73,
+        Nrc::WrongBlockSequenceCounter,
+        "wrong block sequence counter",
+    ),
+    (
+        0x78,
+        Nrc::RequestCorrectlyReceivedResponsePending,
+        "request correctly received, response pending",
+    ),
+    (
+        0x7E,
+        Nrc::SubFunctionNotSupportedInActiveSession,
+        "sub-function not supported in active session",
+    ),
+    (
+        0x7F,
+        Nrc::ServiceNotSupportedInActiveSession,
+        "service not supported in active session",
+    ),
+    (0x81, Nrc::RpmTooHigh, "rpm too high"),
+    (0x82, Nrc::RpmTooLow, "rpm too low"),
+    (0x83, Nrc::EngineIsRunning, "engine is running"),
+    (0x84, Nrc::EngineIsNotRunning, "engine is not running"),
+    (0x85, Nrc::EngineRunTimeTooLow, "engine run time too low"),
+    (0x86, Nrc::TemperatureTooHigh, "temperature too high"),
+    (0x87, Nrc::TemperatureTooLow, "temperature too low"),
+    (0x88, Nrc::VehicleSpeedTooHigh, "vehicle speed too high"),
+    (0x89, Nrc::VehicleSpeedTooLow, "vehicle speed too low"),
+    (0x8A, Nrc::ThrottlePedalTooHigh, "throttle/pedal too high"),
+    (0x8B, Nrc::ThrottlePedalTooLow, "throttle/pedal too low"),
+    (
+        0x8C,
+        Nrc::TransmissionRangeNotInNeutral,
+        "transmission range not in neutral",
+    ),
+    (
+        0x8D,
+        Nrc::TransmissionRangeNotInGear,
+        "transmission range not in gear",
+    ),
+    (
+        0x8F,
+        Nrc::BrakeSwitchesNotClosed,
+        "brake switch(es) not closed",
+    ),
+    (
+        0x90,
+        Nrc::ShifterLeverNotInPark,
+        "shifter lever not in park",
+    ),
+    (
+        0x91,
+        Nrc::TorqueConverterClutchLocked,
+        "torque converter clutch locked",
+    ),
+    (0x92, Nrc::VoltageTooHigh, "voltage too high"),
+    (0x93, Nrc::VoltageTooLow, "voltage too low"),
+    (
+        0x94,
+        Nrc::ResourceTemporarilyNotAvailable,
+        "resource temporarily not available",
+    ),
+];
+
+impl Nrc {
+    /// The encoded response code
+    pub fn as_u8(self) -> u8 {
+        match self {
+            Nrc::Other(code) => code,
+            nrc => NRCS
+                .iter()
+                .find(|(_, known, _)| *known == nrc)
+                .map(|(code, _, _)| *code)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Description as given by ISO 14229-1, `None` for [`Nrc::Other`]
+    pub fn description(self) -> Option<&'static str> {
+        NRCS.iter()
+            .find(|(_, known, _)| *known == self)
+            .map(|(_, _, description)| *description)
+    }
+}
+
+impl From<u8> for Nrc {
+    fn from(code: u8) -> Self {
+        NRCS.iter()
+            .find(|(known, _, _)| *known == code)
+            .map(|(_, nrc, _)| *nrc)
+            .unwrap_or(Nrc::Other(code))
+    }
+}
+
+impl From<Nrc> for u8 {
+    fn from(nrc: Nrc) -> Self {
+        nrc.as_u8()
+    }
+}
+
+impl fmt::Display for Nrc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.description() {
+            Some(description) => write!(f, "{} ({:#04X})", description, self.as_u8()),
+            None if (0xF0..=0xFE).contains(&self.as_u8()) => {
+                write!(f, "vehicle manufacturer specific ({:#04X})", self.as_u8())
+            }
+            None => write!(f, "reserved ({:#04X})", self.as_u8()),
+        }
+    }
+}
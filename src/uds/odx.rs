@@ -0,0 +1,502 @@
+use super::{Did, DidCodec, DidRegistry, Rid, ServiceId};
+use roxmltree::{Document, Node};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use thiserror::Error;
+
+const XSI_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// Errors loading ODX/PDX diagnostic descriptions
+#[derive(Error, Debug)]
+pub enum OdxError {
+    /// Reading the file failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// The ODX document is not well-formed XML
+    #[error("Invalid ODX document: {0}")]
+    Xml(#[from] roxmltree::Error),
+
+    /// The PDX archive is not a valid zip file
+    #[error("Invalid PDX archive: {0}")]
+    Pdx(#[from] zip::result::ZipError),
+}
+
+/// A diagnostic service of the description with its preconditions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OdxService {
+    /// SHORT-NAME of the service
+    pub short_name: String,
+    /// Constant request prefix, e.g. `[0x22, 0xF1, 0x90]`
+    pub request: Vec<u8>,
+    /// SHORT-NAMEs of the session states the service is allowed in, empty if unrestricted
+    pub sessions: Vec<String>,
+    /// SHORT-NAMEs of the security states the service requires, empty if unrestricted
+    pub security_levels: Vec<String>,
+}
+
+impl OdxService {
+    /// The request service identifier
+    pub fn service_id(&self) -> Option<u8> {
+        self.request.first().copied()
+    }
+
+    /// The data identifier of ReadDataByIdentifier and WriteDataByIdentifier services
+    pub fn did(&self) -> Option<Did> {
+        match *self.request {
+            [sid, high, low, ..]
+                if sid == ServiceId::ReadDataByIdentifier.as_u8()
+                    || sid == ServiceId::WriteDataByIdentifier.as_u8() =>
+            {
+                Some(Did::from_be_bytes([high, low]))
+            }
+            _ => None,
+        }
+    }
+
+    /// The routine identifier of RoutineControl services
+    pub fn rid(&self) -> Option<Rid> {
+        match *self.request {
+            [sid, _, high, low, ..] if sid == ServiceId::RoutineControl.as_u8() => {
+                Some(Rid::from_be_bytes([high, low]))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Services, data identifiers and routines of ODX diagnostic descriptions (ISO 22901)
+///
+/// Only the constant request parameters, the coded types of data identifiers and the
+/// session and security preconditions are evaluated. Services of all diagnostic layers
+/// are merged. The database keeps the documents, so references into documents added later
+/// resolve.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::OdxDatabase;
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::OdxError> {
+///     let database = OdxDatabase::load("ecu.pdx")?;
+///     for service in database.services() {
+///         println!("{} {:02X?} {:?}", service.short_name, service.request, service.sessions);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct OdxDatabase {
+    services: Vec<OdxService>,
+    dids: DidRegistry,
+    did_names: BTreeMap<Did, String>,
+    routines: BTreeMap<Rid, String>,
+    /// the documents added, evaluated again on each addition
+    sources: Vec<String>,
+}
+
+impl OdxDatabase {
+    /// Create an empty database
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load an `.odx` document or a `.pdx` archive, chosen by the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, OdxError> {
+        let path = path.as_ref();
+        let mut database = Self::new();
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("pdx"))
+        {
+            database.add_pdx(File::open(path)?)?;
+        } else {
+            let mut document = String::new();
+            File::open(path)?.read_to_string(&mut document)?;
+            database.add_odx(&document)?;
+        }
+        Ok(database)
+    }
+
+    /// Add the services of all ODX documents of a PDX archive
+    pub fn add_pdx(&mut self, archive: impl Read + Seek) -> Result<(), OdxError> {
+        let mut archive = zip::ZipArchive::new(archive)?;
+        let mut documents = Vec::new();
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index)?;
+            let is_odx = Path::new(file.name())
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| extension.to_ascii_lowercase().starts_with("odx"));
+            if is_odx {
+                let mut document = String::new();
+                file.read_to_string(&mut document)?;
+                documents.push(document);
+            }
+        }
+        self.add_documents(documents)
+    }
+
+    /// Add the services of an ODX document.
+    ///
+    /// References to other documents (DOCREF) resolve against all documents added to the
+    /// database, in any order.
+    pub fn add_odx(&mut self, document: &str) -> Result<(), OdxError> {
+        self.add_documents(vec![document.to_string()])
+    }
+
+    /// Keep `documents` and evaluate all documents again, so references between them
+    /// resolve
+    fn add_documents(&mut self, documents: Vec<String>) -> Result<(), OdxError> {
+        let mut sources = self.sources.clone();
+        sources.extend(documents);
+        let mut database = Self::evaluate(&sources)?;
+        database.sources = sources;
+        *self = database;
+        Ok(())
+    }
+
+    fn evaluate(sources: &[String]) -> Result<Self, OdxError> {
+        let documents = sources
+            .iter()
+            .map(|source| Document::parse(source))
+            .collect::<Result<Vec<_>, _>>()?;
+        let index = IdIndex::new(&documents);
+        let mut database = Self::new();
+        for (document_index, document) in documents.iter().enumerate() {
+            database.add_services(document, |reference| {
+                index.resolve(document_index, reference)
+            });
+        }
+        Ok(database)
+    }
+
+    /// Add the services of `document`, resolving ID-REF elements with `resolve`
+    fn add_services<'a, 'input: 'a>(
+        &mut self,
+        document: &'a Document<'input>,
+        resolve_ref: impl Fn(Node<'a, 'input>) -> Option<Node<'a, 'input>>,
+    ) {
+        let resolve = |node: Node<'a, 'input>, tag: &str| child(node, tag).and_then(&resolve_ref);
+
+        for diag_service in document
+            .descendants()
+            .filter(|node| node.has_tag_name("DIAG-SERVICE"))
+        {
+            let Some(request) = resolve(diag_service, "REQUEST-REF") else {
+                continue;
+            };
+            let mut service = OdxService {
+                short_name: child_text(diag_service, "SHORT-NAME").unwrap_or_default(),
+                request: coded_const_prefix(request),
+                sessions: Vec::new(),
+                security_levels: Vec::new(),
+            };
+            if service.request.is_empty() {
+                continue;
+            }
+            for state in child(diag_service, "PRE-CONDITION-STATE-REFS")
+                .into_iter()
+                .flat_map(|refs| refs.children())
+                .filter(|reference| reference.is_element())
+                .filter_map(&resolve_ref)
+            {
+                let name = child_text(state, "SHORT-NAME").unwrap_or_default();
+                let semantic = state
+                    .ancestors()
+                    .find(|node| node.has_tag_name("STATE-CHART"))
+                    .and_then(|chart| child_text(chart, "SEMANTIC"))
+                    .unwrap_or_default();
+                if semantic.eq_ignore_ascii_case("SECURITY") {
+                    service.security_levels.push(name);
+                } else {
+                    service.sessions.push(name);
+                }
+            }
+
+            if let Some(did) = service.did() {
+                self.did_names.insert(did, service.short_name.clone());
+                let codec = child(diag_service, "POS-RESPONSE-REFS")
+                    .and_then(|refs| refs.children().find(|node| node.is_element()))
+                    .and_then(&resolve_ref)
+                    .and_then(|response| value_codec(response, &resolve_ref));
+                if let (Some(codec), true) = (
+                    codec,
+                    service.service_id() == Some(ServiceId::ReadDataByIdentifier.as_u8()),
+                ) {
+                    self.dids.register(did, codec);
+                }
+            }
+            if let Some(rid) = service.rid() {
+                self.routines
+                    .entry(rid)
+                    .or_insert_with(|| service.short_name.clone());
+            }
+            self.services.push(service);
+        }
+    }
+
+    /// get all services
+    pub fn services(&self) -> &[OdxService] {
+        &self.services
+    }
+
+    /// Find a service by its SHORT-NAME
+    pub fn service(&self, short_name: &str) -> Option<&OdxService> {
+        self.services
+            .iter()
+            .find(|service| service.short_name == short_name)
+    }
+
+    /// get the codecs of the data identifiers read by ReadDataByIdentifier services
+    pub fn dids(&self) -> &DidRegistry {
+        &self.dids
+    }
+
+    /// get the SHORT-NAME of the service reading or writing `did`
+    pub fn did_name(&self, did: Did) -> Option<&str> {
+        self.did_names.get(&did).map(String::as_str)
+    }
+
+    /// get the routine identifiers with the SHORT-NAME of their first service
+    pub fn routines(&self) -> &BTreeMap<Rid, String> {
+        &self.routines
+    }
+}
+
+/// Elements with an ID in all documents of the database
+struct IdIndex<'a, 'input> {
+    /// by document and ID, for references within a document
+    local: HashMap<(usize, &'a str), Node<'a, 'input>>,
+    /// by SHORT-NAME of the enclosing diagnostic layer or container and ID, for references
+    /// into other documents (DOCREF)
+    documents: HashMap<(&'a str, &'a str), Node<'a, 'input>>,
+}
+
+/// Elements named by the DOCREF of references into other documents
+const DOCUMENT_ELEMENTS: [&str; 10] = [
+    "DIAG-LAYER-CONTAINER",
+    "ECU-SHARED-DATA",
+    "PROTOCOL",
+    "FUNCTIONAL-GROUP",
+    "BASE-VARIANT",
+    "ECU-VARIANT",
+    "COMPARAM-SPEC",
+    "COMPARAM-SUBSET",
+    "VEHICLE-INFO-SPEC",
+    "MULTIPLE-ECU-JOB-SPEC",
+];
+
+impl<'a, 'input> IdIndex<'a, 'input> {
+    fn new(documents: &'a [Document<'input>]) -> Self {
+        let mut index = Self {
+            local: HashMap::new(),
+            documents: HashMap::new(),
+        };
+        for (document_index, document) in documents.iter().enumerate() {
+            for node in document.descendants() {
+                let Some(id) = node.attribute("ID") else {
+                    continue;
+                };
+                index.local.insert((document_index, id), node);
+                for short_name in node
+                    .ancestors()
+                    .filter(|ancestor| {
+                        DOCUMENT_ELEMENTS
+                            .iter()
+                            .any(|tag| ancestor.has_tag_name(*tag))
+                    })
+                    .filter_map(|ancestor| child(ancestor, "SHORT-NAME")?.text())
+                {
+                    index.documents.insert((short_name.trim(), id), node);
+                }
+            }
+        }
+        index
+    }
+
+    /// The element referenced by the ID-REF and DOCREF of `reference` in document
+    /// `document_index`
+    fn resolve(&self, document_index: usize, reference: Node) -> Option<Node<'a, 'input>> {
+        let id = reference.attribute("ID-REF")?;
+        match reference.attribute("DOCREF") {
+            Some(docref) => self.documents.get(&(docref, id)).copied(),
+            None => self.local.get(&(document_index, id)).copied(),
+        }
+    }
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn child_text(node: Node, tag: &str) -> Option<String> {
+    child(node, tag)
+        .and_then(|child| child.text())
+        .map(|text| text.trim().to_string())
+}
+
+fn xsi_type<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute((XSI_NAMESPACE, "type"))
+        .or_else(|| node.attribute("type"))
+}
+
+fn bit_length(diag_coded_type: Option<Node>) -> Option<usize> {
+    child_text(diag_coded_type?, "BIT-LENGTH")?.parse().ok()
+}
+
+/// Encode the CODED-CONST parameters up to the first non constant byte
+fn coded_const_prefix(request: Node) -> Vec<u8> {
+    let mut bytes = BTreeMap::new();
+    for param in child(request, "PARAMS")
+        .into_iter()
+        .flat_map(|params| params.children())
+        .filter(|param| param.has_tag_name("PARAM") && xsi_type(*param) == Some("CODED-CONST"))
+    {
+        let Some(position) =
+            child_text(param, "BYTE-POSITION").and_then(|p| p.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let Some(value) = child_text(param, "CODED-VALUE").and_then(|v| parse_coded_value(&v))
+        else {
+            continue;
+        };
+        let len = bit_length(child(param, "DIAG-CODED-TYPE"))
+            .unwrap_or(8)
+            .div_ceil(8);
+        let be_bytes = value.to_be_bytes();
+        for (offset, byte) in be_bytes[8 - len.clamp(1, 8)..].iter().enumerate() {
+            let Some(position) = position.checked_add(offset) else {
+                break;
+            };
+            bytes.insert(position, *byte);
+        }
+    }
+    bytes
+        .into_iter()
+        .enumerate()
+        .take_while(|(index, (position, _))| index == position)
+        .map(|(_, (_, byte))| byte)
+        .collect()
+}
+
+fn parse_coded_value(value: &str) -> Option<u64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Codec of the first VALUE parameter of a positive response
+fn value_codec<'a, 'input>(
+    response: Node<'a, 'input>,
+    resolve_ref: impl Fn(Node<'a, 'input>) -> Option<Node<'a, 'input>>,
+) -> Option<DidCodec> {
+    let param = child(response, "PARAMS")?
+        .children()
+        .find(|param| param.has_tag_name("PARAM") && xsi_type(*param) == Some("VALUE"))?;
+    let dop = child(param, "DOP-REF").and_then(resolve_ref)?;
+    let diag_coded_type = child(dop, "DIAG-CODED-TYPE")?;
+    let len = bit_length(Some(diag_coded_type)).map(|bits| bits.div_ceil(8));
+    Some(match (diag_coded_type.attribute("BASE-DATA-TYPE")?, len) {
+        ("A_ASCIISTRING" | "A_UTF8STRING", Some(len)) => DidCodec::Ascii(len),
+        ("A_UINT32", Some(len)) => DidCodec::Unsigned(len),
+        ("A_INT32", Some(len)) => DidCodec::Signed(len),
+        (_, len) => DidCodec::Raw(len),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ECU layer reading DID 0xF190 with a DOP of the shared data document
+    const ECU: &str = r#"<ODX xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <DIAG-LAYER-CONTAINER ID="DLC_ECU">
+        <SHORT-NAME>ECU</SHORT-NAME>
+        <BASE-VARIANTS><BASE-VARIANT ID="BV_ECU">
+          <SHORT-NAME>ECU</SHORT-NAME>
+          <DIAG-COMMS><DIAG-SERVICE ID="DS_Speed">
+            <SHORT-NAME>ReadSpeed</SHORT-NAME>
+            <REQUEST-REF ID-REF="RQ_Speed"/>
+            <POS-RESPONSE-REFS><POS-RESPONSE-REF ID-REF="PR_Speed"/></POS-RESPONSE-REFS>
+          </DIAG-SERVICE></DIAG-COMMS>
+          <REQUESTS><REQUEST ID="RQ_Speed"><PARAMS>
+            <PARAM xsi:type="CODED-CONST"><BYTE-POSITION>0</BYTE-POSITION>
+              <CODED-VALUE>0x22</CODED-VALUE>
+              <DIAG-CODED-TYPE><BIT-LENGTH>8</BIT-LENGTH></DIAG-CODED-TYPE></PARAM>
+            <PARAM xsi:type="CODED-CONST"><BYTE-POSITION>1</BYTE-POSITION>
+              <CODED-VALUE>0xF190</CODED-VALUE>
+              <DIAG-CODED-TYPE><BIT-LENGTH>16</BIT-LENGTH></DIAG-CODED-TYPE></PARAM>
+          </PARAMS></REQUEST></REQUESTS>
+          <POS-RESPONSES><POS-RESPONSE ID="PR_Speed"><PARAMS>
+            <PARAM xsi:type="VALUE"><BYTE-POSITION>3</BYTE-POSITION>
+              <DOP-REF ID-REF="DOP_UINT16" DOCREF="Shared" DOCTYPE="LAYER"/></PARAM>
+          </PARAMS></POS-RESPONSE></POS-RESPONSES>
+        </BASE-VARIANT></BASE-VARIANTS>
+      </DIAG-LAYER-CONTAINER>
+    </ODX>"#;
+
+    const SHARED: &str = r#"<ODX>
+      <DIAG-LAYER-CONTAINER ID="DLC_Shared">
+        <SHORT-NAME>SharedContainer</SHORT-NAME>
+        <ECU-SHARED-DATAS><ECU-SHARED-DATA ID="ESD_Shared">
+          <SHORT-NAME>Shared</SHORT-NAME>
+          <DATA-OBJECT-PROPS><DATA-OBJECT-PROP ID="DOP_UINT16">
+            <SHORT-NAME>UInt16</SHORT-NAME>
+            <DIAG-CODED-TYPE BASE-DATA-TYPE="A_UINT32"><BIT-LENGTH>16</BIT-LENGTH></DIAG-CODED-TYPE>
+          </DATA-OBJECT-PROP></DATA-OBJECT-PROPS>
+        </ECU-SHARED-DATA></ECU-SHARED-DATAS>
+      </DIAG-LAYER-CONTAINER>
+    </ODX>"#;
+
+    #[test]
+    fn docref_resolves_in_any_document_order() {
+        for documents in [[ECU, SHARED], [SHARED, ECU]] {
+            let mut database = OdxDatabase::new();
+            for document in documents {
+                database.add_odx(document).unwrap();
+            }
+            assert_eq!(database.services().len(), 1);
+            assert_eq!(
+                database.service("ReadSpeed").unwrap().request,
+                [0x22, 0xF1, 0x90]
+            );
+            assert_eq!(database.dids().get(0xF190), Some(DidCodec::Unsigned(2)));
+        }
+    }
+
+    #[test]
+    fn docref_does_not_resolve_local_ids() {
+        // the referenced DOP exists in the document itself, but not in the named layer
+        let ecu = ECU.replace(
+            "</BASE-VARIANT>",
+            r#"<DATA-OBJECT-PROPS><DATA-OBJECT-PROP ID="DOP_UINT16">
+              <DIAG-CODED-TYPE BASE-DATA-TYPE="A_UINT32"><BIT-LENGTH>8</BIT-LENGTH></DIAG-CODED-TYPE>
+            </DATA-OBJECT-PROP></DATA-OBJECT-PROPS></BASE-VARIANT>"#,
+        );
+        let mut database = OdxDatabase::new();
+        database.add_odx(&ecu).unwrap();
+        assert_eq!(database.dids().get(0xF190), None);
+    }
+
+    #[test]
+    fn coded_const_prefix_ignores_overflowing_positions() {
+        let document = format!(
+            r#"<REQUEST xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><PARAMS>
+              <PARAM xsi:type="CODED-CONST"><BYTE-POSITION>0</BYTE-POSITION>
+                <CODED-VALUE>0x3E</CODED-VALUE></PARAM>
+              <PARAM xsi:type="CODED-CONST"><BYTE-POSITION>{}</BYTE-POSITION>
+                <CODED-VALUE>0x1234</CODED-VALUE>
+                <DIAG-CODED-TYPE><BIT-LENGTH>16</BIT-LENGTH></DIAG-CODED-TYPE></PARAM>
+            </PARAMS></REQUEST>"#,
+            usize::MAX
+        );
+        let document = Document::parse(&document).unwrap();
+        assert_eq!(coded_const_prefix(document.root_element()), [0x3E]);
+    }
+}
@@ -0,0 +1,165 @@
+use super::{DidDecode, ServiceId, UdsClient, UdsError, UdsTransport, POSITIVE_RESPONSE_OFFSET};
+use crate::IsoTpSocket;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+/// Response service identifier of ReadDataByPeriodicIdentifier, also used by the periodic
+/// responses
+pub(super) const PERIODIC_RESPONSE_SID: u8 =
+    ServiceId::ReadDataByPeriodicIdentifier as u8 + POSITIVE_RESPONSE_OFFSET;
+
+/// transmissionMode of ReadDataByPeriodicIdentifier (0x2A)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransmissionMode {
+    /// sendAtSlowRate (0x01)
+    Slow,
+    /// sendAtMediumRate (0x02)
+    Medium,
+    /// sendAtFastRate (0x03)
+    Fast,
+    /// stopSending (0x04)
+    Stop,
+    /// Reserved transmission mode
+    Other(u8),
+}
+
+impl TransmissionMode {
+    /// The encoded transmission mode
+    pub fn as_u8(self) -> u8 {
+        match self {
+            TransmissionMode::Slow => 0x01,
+            TransmissionMode::Medium => 0x02,
+            TransmissionMode::Fast => 0x03,
+            TransmissionMode::Stop => 0x04,
+            TransmissionMode::Other(mode) => mode,
+        }
+    }
+}
+
+/// Periodic responses of scheduled periodic data identifiers decoded as `V`, see
+/// [`UdsClient::periodic_stream`]
+///
+/// Yields the periodic data identifier (the low byte of DID 0xF2xx) with its value and ends
+/// once no periodic response arrived within the timeout. Dropping the stream stops sending
+/// the scheduled identifiers.
+///
+/// Only type 2 periodic responses are received, ISO-TP messages with the service
+/// identifier 0x6A on the response identifier of the client. Type 1 periodic messages,
+/// single CAN frames without service identifier on a separate CAN identifier, are not
+/// visible to the ISO-TP socket and have to be read from a raw CAN socket.
+pub struct PeriodicStream<'a, V, T: UdsTransport = IsoTpSocket> {
+    client: &'a mut UdsClient<T>,
+    periodic_dids: Vec<u8>,
+    timeout: Duration,
+    _marker: PhantomData<fn() -> V>,
+}
+
+impl<V, T: UdsTransport> PeriodicStream<'_, V, T> {
+    /// Get the client, e.g. to send requests while the identifiers are scheduled
+    pub fn client(&mut self) -> &mut UdsClient<T> {
+        self.client
+    }
+}
+
+impl<V: DidDecode, T: UdsTransport> Iterator for PeriodicStream<'_, V, T> {
+    type Item = Result<(u8, V), UdsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let response = match self.client.next_event(self.timeout) {
+                Ok(Some(response)) => response,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            // skip ResponseOnEvent responses and unscheduled identifiers
+            match *response {
+                [PERIODIC_RESPONSE_SID, periodic_did, ref data @ ..]
+                    if self.periodic_dids.contains(&periodic_did) =>
+                {
+                    return Some(V::decode(data).map(|value| (periodic_did, value)));
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+impl<V, T: UdsTransport> Drop for PeriodicStream<'_, V, T> {
+    fn drop(&mut self) {
+        // the server stops sending on session changes anyway
+        let _ = self.client.stop_periodic_identifiers(&self.periodic_dids);
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ReadDataByPeriodicIdentifier (0x2A), scheduling the periodic data identifiers
+    /// `periodic_dids` (the low bytes of DIDs 0xF2xx) at the rate of `mode`.
+    ///
+    /// The type 2 periodic responses are received as events, see [`UdsClient::next_event`]
+    /// and [`PeriodicStream`]. Type 1 periodic messages are not visible to the ISO-TP socket
+    /// and have to be read from a raw CAN socket.
+    pub fn read_data_by_periodic_identifier(
+        &mut self,
+        mode: TransmissionMode,
+        periodic_dids: &[u8],
+    ) -> Result<(), UdsError> {
+        if mode != TransmissionMode::Stop && periodic_dids.is_empty() {
+            return Err(UdsError::InvalidRequest(
+                "at least one periodic data identifier must be scheduled",
+            ));
+        }
+        let mut request = Vec::with_capacity(1 + periodic_dids.len());
+        request.push(mode.as_u8());
+        request.extend_from_slice(periodic_dids);
+        self.request(ServiceId::ReadDataByPeriodicIdentifier, &request)?;
+        if mode == TransmissionMode::Stop {
+            if periodic_dids.is_empty() {
+                self.event_responses
+                    .retain(|prefix| prefix != &[PERIODIC_RESPONSE_SID]);
+            }
+        } else if !self.event_responses.contains(&vec![PERIODIC_RESPONSE_SID]) {
+            self.event_responses.push(vec![PERIODIC_RESPONSE_SID]);
+        }
+        Ok(())
+    }
+
+    /// Stop sending `periodic_dids`, all scheduled identifiers if empty
+    pub fn stop_periodic_identifiers(&mut self, periodic_dids: &[u8]) -> Result<(), UdsError> {
+        self.read_data_by_periodic_identifier(TransmissionMode::Stop, periodic_dids)
+    }
+
+    /// Schedule `periodic_dids` and stream their periodic responses decoded as `V`
+    ///
+    /// ```rust,no_run
+    /// # fn example(client: &mut socketcan_isotp::uds::UdsClient) -> Result<(), socketcan_isotp::uds::UdsError> {
+    /// use socketcan_isotp::uds::TransmissionMode;
+    /// use std::time::Duration;
+    ///
+    /// let stream = client.periodic_stream::<u16>(
+    ///     TransmissionMode::Fast,
+    ///     &[0x01, 0x02],
+    ///     Duration::from_secs(1),
+    /// )?;
+    /// for sample in stream.take(100) {
+    ///     let (periodic_did, value) = sample?;
+    ///     println!("0xF2{periodic_did:02X}: {value}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn periodic_stream<V: DidDecode>(
+        &mut self,
+        mode: TransmissionMode,
+        periodic_dids: &[u8],
+        timeout: Duration,
+    ) -> Result<PeriodicStream<'_, V, T>, UdsError> {
+        self.read_data_by_periodic_identifier(mode, periodic_dids)?;
+        Ok(PeriodicStream {
+            client: self,
+            periodic_dids: periodic_dids.to_vec(),
+            timeout,
+            _marker: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,394 @@
+use super::trace::hex;
+use super::UdsTransport;
+use crate::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Errors loading a [`Recording`] or replaying a session against it
+#[derive(thiserror::Error, Debug)]
+pub enum ReplayError {
+    /// Reading or writing the recording failed
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    /// A line of the recording could not be parsed
+    #[error("Invalid recording in line {line}: {reason}")]
+    Parse {
+        /// 1-based line number
+        line: usize,
+        /// What is wrong with the line
+        reason: &'static str,
+    },
+
+    /// The session sent or received a PDU differing from the recording
+    #[error("PDU {index} diverges from the recording, expected {expected}, got {actual}")]
+    Diverged {
+        /// Position of the PDU in the recording
+        index: usize,
+        /// The recorded PDU, `end of recording` if the session went on
+        expected: String,
+        /// The PDU of the session, `end of session` if it stopped early
+        actual: String,
+    },
+}
+
+/// Direction of a [`RecordedPdu`] as seen by the client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PduDirection {
+    /// Request sent by the client
+    Tx,
+    /// Response received by the client
+    Rx,
+}
+
+impl fmt::Display for PduDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tx => write!(f, "TX"),
+            Self::Rx => write!(f, "RX"),
+        }
+    }
+}
+
+/// A PDU of a [`Recording`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RecordedPdu {
+    /// Time since the start of the recording
+    pub at: Duration,
+    /// Sent or received
+    pub direction: PduDirection,
+    /// The payload
+    pub data: Vec<u8>,
+}
+
+impl fmt::Display for RecordedPdu {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.direction, hex(&self.data))
+    }
+}
+
+/// Sent and received PDUs of a session in order, see [`Recorder`] and [`Replayer`]
+///
+/// Stored as text, one line per PDU: `<seconds> <TX|RX> <hex bytes>`. Empty lines and
+/// lines starting with `#` are ignored, so golden recordings can be annotated.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Recording {
+    pdus: Vec<RecordedPdu>,
+}
+
+impl Recording {
+    /// Create an empty recording
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the recorded PDUs in order
+    pub fn pdus(&self) -> &[RecordedPdu] {
+        &self.pdus
+    }
+
+    /// Append a PDU
+    pub fn push(&mut self, pdu: RecordedPdu) {
+        self.pdus.push(pdu);
+    }
+
+    /// Write the recording as text
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for pdu in &self.pdus {
+            writeln!(writer, "{:.6} {}", pdu.at.as_secs_f64(), pdu)?;
+        }
+        Ok(())
+    }
+
+    /// Read a recording written by [`write_to`](Self::write_to)
+    pub fn read_from(reader: impl BufRead) -> Result<Self, ReplayError> {
+        let mut recording = Self::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason| ReplayError::Parse {
+                line: index + 1,
+                reason,
+            };
+            let mut fields = line.split_whitespace();
+            let at = fields
+                .next()
+                .and_then(|at| at.parse::<f64>().ok())
+                .and_then(|at| Duration::try_from_secs_f64(at).ok())
+                .ok_or_else(|| error("invalid timestamp"))?;
+            let direction = match fields.next() {
+                Some("TX") => PduDirection::Tx,
+                Some("RX") => PduDirection::Rx,
+                _ => return Err(error("direction is neither TX nor RX")),
+            };
+            let data = fields.next().unwrap_or_default();
+            if data.len() % 2 != 0 || fields.next().is_some() {
+                return Err(error("invalid payload"));
+            }
+            // parse bytes, slicing the line could split a multibyte character
+            let digit = |byte: u8| char::from(byte).to_digit(16);
+            let data = data
+                .as_bytes()
+                .chunks_exact(2)
+                .map(|pair| Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8))
+                .collect::<Option<_>>()
+                .ok_or_else(|| error("invalid payload"))?;
+            recording.push(RecordedPdu {
+                at,
+                direction,
+                data,
+            });
+        }
+        Ok(recording)
+    }
+
+    /// Write the recording to the file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Read a recording from the file at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Compare the directions and payloads of `actual` with this golden recording,
+    /// ignoring the timestamps
+    pub fn compare(&self, actual: &Recording) -> Result<(), ReplayError> {
+        let len = self.pdus.len().max(actual.pdus.len());
+        for index in 0..len {
+            let expected = self.pdus.get(index);
+            let session = actual.pdus.get(index);
+            let same = match (expected, session) {
+                (Some(expected), Some(session)) => {
+                    expected.direction == session.direction && expected.data == session.data
+                }
+                _ => false,
+            };
+            if !same {
+                return Err(ReplayError::Diverged {
+                    index,
+                    expected: expected
+                        .map_or("end of recording".to_string(), |pdu| pdu.to_string()),
+                    actual: session.map_or("end of session".to_string(), |pdu| pdu.to_string()),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`UdsTransport`] decorator recording all sent and received PDUs with timestamps
+///
+/// ```rust
+/// use socketcan_isotp::uds::{MockTransport, Recorder, UdsClient};
+///
+/// let mock = MockTransport::new().on_request(&[0x3E, 0x00], &[0x7E, 0x00]);
+/// let mut client = UdsClient::new(Recorder::new(mock));
+/// client.request(0x3E, &[0x00]).unwrap();
+///
+/// let mut text = Vec::new();
+/// client.get_ref().get_recording().write_to(&mut text).unwrap();
+/// assert!(String::from_utf8(text).unwrap().ends_with("RX 7E00\n"));
+/// ```
+pub struct Recorder<T> {
+    transport: T,
+    started: Instant,
+    recording: Recording,
+}
+
+impl<T: UdsTransport> Recorder<T> {
+    /// Record the PDUs of `transport`, timestamps are relative to now
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            started: Instant::now(),
+            recording: Recording::new(),
+        }
+    }
+
+    /// Get the PDUs recorded so far
+    pub fn get_recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    /// Take the PDUs recorded so far, keeping the time base
+    pub fn take_recording(&mut self) -> Recording {
+        std::mem::take(&mut self.recording)
+    }
+
+    /// Get the wrapped transport
+    pub fn get_ref(&self) -> &T {
+        &self.transport
+    }
+
+    /// Get the wrapped transport mutably
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Unwrap the transport and the recording
+    pub fn into_parts(self) -> (T, Recording) {
+        (self.transport, self.recording)
+    }
+}
+
+impl<T: UdsTransport> UdsTransport for Recorder<T> {
+    fn send(&mut self, request: &[u8]) -> Result<(), Error> {
+        self.recording.push(RecordedPdu {
+            at: self.started.elapsed(),
+            direction: PduDirection::Tx,
+            data: request.to_vec(),
+        });
+        self.transport.send(request)
+    }
+
+    fn receive(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        let response = self.transport.receive(timeout)?;
+        if let Some(response) = response {
+            self.recording.push(RecordedPdu {
+                at: self.started.elapsed(),
+                direction: PduDirection::Rx,
+                data: response.to_vec(),
+            });
+        }
+        Ok(response)
+    }
+}
+
+/// [`UdsTransport`] re-running a session against a [`Recording`]
+///
+/// Sent requests are compared with the recorded ones, the recorded responses following a
+/// request are received in order without delay. The first divergence fails the request and
+/// is reported by [`finish`](Self::finish).
+///
+/// ```rust
+/// use socketcan_isotp::uds::{Recording, Replayer, UdsClient};
+///
+/// let golden = "\
+/// ## TesterPresent
+/// 0.000000 TX 3E00
+/// 0.000800 RX 7E00
+/// ";
+/// let recording = Recording::read_from(golden.as_bytes()).unwrap();
+/// let mut client = UdsClient::new(Replayer::new(recording));
+/// client.request(0x3E, &[0x00]).unwrap();
+/// client.into_inner().finish().unwrap();
+/// ```
+pub struct Replayer {
+    recording: Recording,
+    position: usize,
+    divergence: Option<ReplayError>,
+}
+
+impl Replayer {
+    /// Replay `recording` from its start
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            position: 0,
+            divergence: None,
+        }
+    }
+
+    /// Get the index of the next recorded PDU
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    /// Check that the session matched the whole recording
+    pub fn finish(self) -> Result<(), ReplayError> {
+        if let Some(divergence) = self.divergence {
+            return Err(divergence);
+        }
+        match self.recording.pdus.get(self.position) {
+            Some(pdu) => Err(ReplayError::Diverged {
+                index: self.position,
+                expected: pdu.to_string(),
+                actual: "end of session".to_string(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl UdsTransport for Replayer {
+    fn send(&mut self, request: &[u8]) -> Result<(), Error> {
+        if self.divergence.is_none() {
+            let expected = self.recording.pdus.get(self.position);
+            match expected {
+                Some(pdu) if pdu.direction == PduDirection::Tx && pdu.data == request => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                _ => {
+                    self.divergence = Some(ReplayError::Diverged {
+                        index: self.position,
+                        expected: expected
+                            .map_or("end of recording".to_string(), |pdu| pdu.to_string()),
+                        actual: format!("{} {}", PduDirection::Tx, hex(request)),
+                    })
+                }
+            }
+        }
+        Err(Error::from(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "request diverges from the recording",
+        )))
+    }
+
+    fn receive(&mut self, _timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        if self.divergence.is_some() {
+            return Ok(None);
+        }
+        match self.recording.pdus.get(self.position) {
+            Some(pdu) if pdu.direction == PduDirection::Rx => {
+                self.position += 1;
+                Ok(Some(&pdu.data))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_from_parses_payloads() {
+        let recording =
+            Recording::read_from(&b"# session\n0.0 TX 3E00\n0.01 RX 7e00\n"[..]).unwrap();
+        assert_eq!(recording.pdus()[0].data, [0x3E, 0x00]);
+        assert_eq!(recording.pdus()[1].data, [0x7E, 0x00]);
+    }
+
+    #[test]
+    fn read_from_rejects_invalid_payloads() {
+        for line in [
+            "0.0 TX \u{20AC}a",
+            "0.0 TX 3\u{E9}",
+            "0.0 TX +1",
+            "0.0 TX 3E0",
+        ] {
+            assert!(
+                matches!(
+                    Recording::read_from(line.as_bytes()),
+                    Err(ReplayError::Parse { line: 1, .. })
+                ),
+                "{line}"
+            );
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::{MockReply, Nrc, UdsTransport, NEGATIVE_RESPONSE_SID};
+use crate::clock::{Clock, SystemClock};
+use crate::{Error, IsoTpSocket};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Interval the responder thread checks for [`ResponderHandle::stop`]
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Table driven ECU simulation answering requests with canned responses, e.g. to run
+/// examples and tests without an external UDS server
+///
+/// Requests are matched against the request prefixes in the order they were added, the
+/// first matching rule answers with its [`MockReply`], sleeping the delay before each
+/// response. Requests without a matching rule are answered by
+/// [`otherwise`](Self::otherwise) or not at all.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{MockReply, Nrc, ScriptedResponder, UdsClient};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let (tester, ecu) = IsoTpSocket::pair(
+///         "vcan0",
+///         StandardId::new(0x7E0).expect("Invalid id"),
+///         StandardId::new(0x7E8).expect("Invalid id"),
+///     )?;
+///     let responder = ScriptedResponder::new()
+///         .reply(&[0x22, 0xF1, 0x89], b"\x62\xF1\x891.0.0")
+///         .reply(
+///             &[0x31, 0x01, 0xFF, 0x00],
+///             MockReply::new()
+///                 .response(&[0x7F, 0x31, 0x78])
+///                 .delayed_response(Duration::from_millis(500), &[0x71, 0x01, 0xFF, 0x00]),
+///         )
+///         .negative(&[0x27], Nrc::SecurityAccessDenied)
+///         .otherwise(Nrc::ServiceNotSupported)
+///         .spawn(ecu);
+///
+///     let mut client = UdsClient::new(tester);
+///     println!("{:X?}", client.read_data_by_identifier(0xF189)?);
+///     responder.stop()?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+pub struct ScriptedResponder {
+    rules: Vec<(Vec<u8>, MockReply)>,
+    otherwise: Option<Nrc>,
+    clock: Arc<dyn Clock>,
+}
+
+impl ScriptedResponder {
+    /// Create a responder without rules, answering no requests
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            otherwise: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Wait for the response delays on `clock` instead of the system time
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Answer requests starting with `prefix` with `reply`
+    pub fn reply(mut self, prefix: &[u8], reply: impl Into<MockReply>) -> Self {
+        self.rules.push((prefix.to_vec(), reply.into()));
+        self
+    }
+
+    /// Answer requests starting with `prefix` with the negative response `nrc`
+    pub fn negative(self, prefix: &[u8], nrc: Nrc) -> Self {
+        let service = prefix.first().copied().unwrap_or_default();
+        self.reply(prefix, &[NEGATIVE_RESPONSE_SID, service, nrc.as_u8()])
+    }
+
+    /// Answer requests starting with `prefix` with the negative response `nrc` after
+    /// `delay`, e.g. to provoke a timeout
+    pub fn delayed_negative(self, prefix: &[u8], delay: Duration, nrc: Nrc) -> Self {
+        let service = prefix.first().copied().unwrap_or_default();
+        self.reply(
+            prefix,
+            MockReply::new()
+                .delayed_response(delay, &[NEGATIVE_RESPONSE_SID, service, nrc.as_u8()]),
+        )
+    }
+
+    /// Answer requests without a matching rule with the negative response `nrc` instead
+    /// of ignoring them
+    pub fn otherwise(mut self, nrc: Nrc) -> Self {
+        self.otherwise = Some(nrc);
+        self
+    }
+
+    /// Get the responses to `request` with their delays, empty if it is not answered
+    pub fn responses(&self, request: &[u8]) -> Vec<(Duration, Vec<u8>)> {
+        if let Some((_, reply)) = self
+            .rules
+            .iter()
+            .find(|(prefix, _)| request.starts_with(prefix))
+        {
+            return reply.get_responses().to_vec();
+        }
+        match (self.otherwise, request.first()) {
+            (Some(nrc), Some(&service)) => vec![(
+                Duration::ZERO,
+                vec![NEGATIVE_RESPONSE_SID, service, nrc.as_u8()],
+            )],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Receive one request on `socket` and send the scripted responses
+    pub fn serve_one(&self, socket: &mut IsoTpSocket) -> Result<(), Error> {
+        let request = socket.read()?.to_vec();
+        self.answer(socket, &request)
+    }
+
+    /// Answer requests on `socket` until an error occurs
+    pub fn run(&self, socket: &mut IsoTpSocket) -> Result<(), Error> {
+        loop {
+            self.serve_one(socket)?;
+        }
+    }
+
+    /// Answer requests on `socket` in a background thread until stopped
+    pub fn spawn(self, mut socket: IsoTpSocket) -> ResponderHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                let Some(request) = socket.receive(STOP_POLL_INTERVAL)? else {
+                    continue;
+                };
+                let request = request.to_vec();
+                self.answer(&socket, &request)?;
+            }
+            Ok(())
+        });
+        ResponderHandle { stop, thread }
+    }
+
+    fn answer(&self, socket: &IsoTpSocket, request: &[u8]) -> Result<(), Error> {
+        for (delay, response) in self.responses(request) {
+            self.clock.sleep(delay);
+            socket.write(&response)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for ScriptedResponder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ScriptedResponder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptedResponder")
+            .field("rules", &self.rules)
+            .field("otherwise", &self.otherwise)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`ScriptedResponder`] running in a background thread, see
+/// [`ScriptedResponder::spawn`]
+#[derive(Debug)]
+pub struct ResponderHandle {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Result<(), Error>>,
+}
+
+impl ResponderHandle {
+    /// Check if the responder thread ended, e.g. due to a socket error
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Stop the responder thread, returning the error it ended with
+    pub fn stop(self) -> Result<(), Error> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
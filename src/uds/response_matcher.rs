@@ -0,0 +1,165 @@
+use super::{
+    decode_negative_response, Nrc, Request, ServiceId, UdsError, POSITIVE_RESPONSE_OFFSET,
+};
+
+/// What the client does with a received response, see [`ResponseMatcher::on_response`]
+#[derive(Debug)]
+pub(super) enum Matched {
+    /// The request completed, `None` if the positive response was suppressed
+    Done(Result<Option<Vec<u8>>, UdsError>),
+    /// A pending response, wait up to P2* for the next response
+    Pending,
+    /// An event or periodic response to queue
+    Event,
+    /// A response to a previous request, wait on until the same deadline
+    Skip,
+}
+
+/// Matches the responses received for one request, shared by the blocking and asynchronous
+/// clients which only do the sending, receiving and timekeeping
+#[derive(Debug)]
+pub(super) struct ResponseMatcher<'a> {
+    service: u8,
+    suppressed: bool,
+    pending_responses: u32,
+    max_pending_responses: Option<u32>,
+    /// prefixes of ResponseOnEvent and periodic responses, see [`is_event`]
+    event_responses: &'a [Vec<u8>],
+}
+
+impl<'a> ResponseMatcher<'a> {
+    pub(super) fn new(
+        request: &Request,
+        max_pending_responses: Option<u32>,
+        event_responses: &'a [Vec<u8>],
+    ) -> Self {
+        Self {
+            service: request.get_service(),
+            suppressed: request.is_positive_response_suppressed(),
+            pending_responses: 0,
+            max_pending_responses,
+            event_responses,
+        }
+    }
+
+    /// The result if no response arrived in time
+    pub(super) fn on_timeout(&self) -> Result<Option<Vec<u8>>, UdsError> {
+        if self.suppressed {
+            Ok(None)
+        } else {
+            Err(UdsError::Timeout)
+        }
+    }
+
+    pub(super) fn on_response(&mut self, response: &[u8]) -> Matched {
+        let service = self.service;
+        match decode_negative_response(response) {
+            Some((sid, Nrc::RequestCorrectlyReceivedResponsePending)) if sid == service => {
+                self.pending_responses += 1;
+                if self
+                    .max_pending_responses
+                    .is_some_and(|max_pending_responses| {
+                        self.pending_responses > max_pending_responses
+                    })
+                {
+                    return Matched::Done(Err(UdsError::TooManyPendingResponses(
+                        self.pending_responses,
+                    )));
+                }
+                // after a pending response the server always sends a final response
+                self.suppressed = false;
+                return Matched::Pending;
+            }
+            Some((sid, nrc)) if sid == service => {
+                return Matched::Done(Err(UdsError::Negative(nrc)))
+            }
+            Some(_) => return Matched::Skip,
+            None => (),
+        }
+        // events may use the response service identifier of the request
+        if is_event(self.event_responses, response) {
+            return Matched::Event;
+        }
+        match *response {
+            // periodic responses carry a periodic data identifier, the response to
+            // ReadDataByPeriodicIdentifier doesn't
+            [sid, ref parameters @ ..]
+                if sid == service.wrapping_add(POSITIVE_RESPONSE_OFFSET)
+                    && (service != ServiceId::ReadDataByPeriodicIdentifier.as_u8()
+                        || parameters.is_empty()) =>
+            {
+                Matched::Done(Ok(Some(parameters.to_vec())))
+            }
+            // response to a previous request
+            _ => Matched::Skip,
+        }
+    }
+}
+
+/// Whether `response` is an event or periodic response, starting with the response service
+/// identifier and echoed parameters of one of `event_responses` and carrying data
+pub(super) fn is_event(event_responses: &[Vec<u8>], response: &[u8]) -> bool {
+    event_responses
+        .iter()
+        .any(|prefix| response.len() > prefix.len() && response.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matched(matcher: &mut ResponseMatcher<'_>, response: &[u8]) -> String {
+        format!("{:?}", matcher.on_response(response))
+    }
+
+    #[test]
+    fn positive_response_completes_request() {
+        let request = Request::new(0x22).data(&[0xF1, 0x90]);
+        let mut matcher = ResponseMatcher::new(&request, None, &[]);
+        assert_eq!(matched(&mut matcher, &[0x51, 0x01]), "Skip");
+        assert_eq!(matched(&mut matcher, &[0x7F, 0x11, 0x10]), "Skip");
+        assert_eq!(
+            matched(&mut matcher, &[0x62, 0xF1, 0x90, 0x57]),
+            "Done(Ok(Some([241, 144, 87])))"
+        );
+    }
+
+    #[test]
+    fn pending_response_waits_for_final_response() {
+        let request = Request::new(0x31)
+            .sub_function(0x01)
+            .suppress_positive_response(true);
+        let mut matcher = ResponseMatcher::new(&request, Some(1), &[]);
+        assert!(matches!(matcher.on_timeout(), Ok(None)));
+        assert_eq!(matched(&mut matcher, &[0x7F, 0x31, 0x78]), "Pending");
+        assert!(matches!(matcher.on_timeout(), Err(UdsError::Timeout)));
+        assert!(matches!(
+            matcher.on_response(&[0x7F, 0x31, 0x78]),
+            Matched::Done(Err(UdsError::TooManyPendingResponses(2)))
+        ));
+    }
+
+    #[test]
+    fn negative_response_fails_request() {
+        let request = Request::new(0x27).sub_function(0x01);
+        let mut matcher = ResponseMatcher::new(&request, None, &[]);
+        assert!(matches!(
+            matcher.on_response(&[0x7F, 0x27, 0x35]),
+            Matched::Done(Err(UdsError::Negative(Nrc::InvalidKey)))
+        ));
+    }
+
+    #[test]
+    fn events_take_precedence_over_positive_responses() {
+        let events = [vec![0x62, 0xF4, 0x0D], vec![0x6A]];
+        let request = Request::new(0x22).data(&[0xF1, 0x90]);
+        let mut matcher = ResponseMatcher::new(&request, None, &events);
+        assert_eq!(matched(&mut matcher, &[0x62, 0xF4, 0x0D, 0x32]), "Event");
+        assert_eq!(matched(&mut matcher, &[0x6A, 0x01, 0x32]), "Event");
+
+        // the response to ReadDataByPeriodicIdentifier has no periodic data identifier
+        let request = Request::new(0x2A).data(&[0x03, 0x01]);
+        let mut matcher = ResponseMatcher::new(&request, None, &events);
+        assert_eq!(matched(&mut matcher, &[0x6A]), "Done(Ok(Some([])))");
+    }
+}
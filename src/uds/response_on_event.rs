@@ -0,0 +1,270 @@
+use super::periodic::PERIODIC_RESPONSE_SID;
+use super::{
+    Did, DtcStatus, ServiceId, UdsClient, UdsError, UdsTransport, POSITIVE_RESPONSE_OFFSET,
+};
+use crate::IsoTpSocket;
+use std::time::Duration;
+
+/// storeEvent bit of the ResponseOnEvent event type
+const STORE_EVENT: u8 = 0x40;
+
+/// ResponseOnEvent (0x86) event setup, the server sends the response to the
+/// serviceToRespondTo request whenever the event occurs
+///
+/// ```rust,no_run
+/// # fn example(client: &mut socketcan_isotp::uds::UdsClient) -> Result<(), socketcan_isotp::uds::UdsError> {
+/// use socketcan_isotp::uds::ResponseOnEvent;
+/// use std::time::Duration;
+///
+/// client.setup_response_on_event(&ResponseOnEvent::on_change_of_did(0xF40D))?;
+/// client.start_response_on_event()?;
+/// for event in client.events(Duration::from_secs(10)) {
+///     println!("Event: {:X?}", event?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ResponseOnEvent {
+    event_type: u8,
+    store_event: bool,
+    window_time: u8,
+    event_type_record: Vec<u8>,
+    service_to_respond_to: Vec<u8>,
+}
+
+impl ResponseOnEvent {
+    /// eventWindowTime of an event window without end
+    pub const INFINITE_WINDOW: u8 = 0x02;
+
+    /// Setup the event `event_type` with its eventTypeRecord and the request
+    /// `service_to_respond_to` the server answers on the event
+    pub fn new(event_type: u8, event_type_record: &[u8], service_to_respond_to: &[u8]) -> Self {
+        Self {
+            event_type: event_type & !STORE_EVENT,
+            store_event: false,
+            window_time: Self::INFINITE_WINDOW,
+            event_type_record: event_type_record.to_vec(),
+            service_to_respond_to: service_to_respond_to.to_vec(),
+        }
+    }
+
+    /// onDTCStatusChange (0x01), reporting DTCs matching `mask` by ReadDTCInformation
+    pub fn on_dtc_status_change(mask: DtcStatus) -> Self {
+        Self::new(
+            0x01,
+            &[mask.bits()],
+            &[ServiceId::ReadDtcInformation.as_u8(), 0x02, mask.bits()],
+        )
+    }
+
+    /// onChangeOfDataIdentifier (0x03), reporting the value of `did`
+    pub fn on_change_of_did(did: Did) -> Self {
+        let [high, low] = did.to_be_bytes();
+        Self::new(
+            0x03,
+            &[high, low],
+            &[ServiceId::ReadDataByIdentifier.as_u8(), high, low],
+        )
+    }
+
+    /// Keep the event setup over power cycles (storeEvent)
+    pub fn store_event(mut self, store_event: bool) -> Self {
+        self.store_event = store_event;
+        self
+    }
+
+    /// Set the eventWindowTime, [`ResponseOnEvent::INFINITE_WINDOW`] by default
+    pub fn window_time(mut self, window_time: u8) -> Self {
+        self.window_time = window_time;
+        self
+    }
+
+    /// get the event type without the storeEvent bit
+    pub fn get_event_type(&self) -> u8 {
+        self.event_type
+    }
+
+    /// get the serviceToRespondToRecord
+    pub fn get_service_to_respond_to(&self) -> &[u8] {
+        &self.service_to_respond_to
+    }
+
+    fn sub_function(&self) -> u8 {
+        if self.store_event {
+            self.event_type | STORE_EVENT
+        } else {
+            self.event_type
+        }
+    }
+}
+
+/// Iterator over ResponseOnEvent event responses, see [`UdsClient::events`]
+pub struct Events<'a, T: UdsTransport = IsoTpSocket> {
+    client: &'a mut UdsClient<T>,
+    timeout: Duration,
+}
+
+impl<T: UdsTransport> Iterator for Events<'_, T> {
+    type Item = Result<Vec<u8>, UdsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.client.next_event(self.timeout).transpose()
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// Setup a ResponseOnEvent event, returning the numberOfIdentifiedEvents.
+    ///
+    /// Responses to the serviceToRespondTo request are handled as events from now on, see
+    /// [`UdsClient::next_event`]. They are told apart from responses to requests by the
+    /// echoed data identifier or sub-function, so the serviceToRespondTo request itself can't
+    /// be sent while the event is set up.
+    pub fn setup_response_on_event(&mut self, event: &ResponseOnEvent) -> Result<u8, UdsError> {
+        let &[service, ref parameters @ ..] = event.service_to_respond_to.as_slice() else {
+            return Err(UdsError::InvalidRequest("serviceToRespondTo is empty"));
+        };
+        let mut request = vec![event.sub_function(), event.window_time];
+        request.extend_from_slice(&event.event_type_record);
+        request.extend_from_slice(&event.service_to_respond_to);
+        let identified_events = match *self.request_response_on_event(&request)? {
+            [number_of_identified_events, ..] => number_of_identified_events,
+            [] => {
+                return Err(UdsError::InvalidResponse(
+                    "response lacks the number of identified events",
+                ))
+            }
+        };
+        let prefix = event_response_prefix(service, parameters);
+        if !self.event_responses.contains(&prefix) {
+            self.event_responses.push(prefix);
+        }
+        Ok(identified_events)
+    }
+
+    /// startResponseOnEvent (0x05)
+    pub fn start_response_on_event(&mut self) -> Result<(), UdsError> {
+        self.request_response_on_event(&[0x05, 0x00]).map(drop)
+    }
+
+    /// stopResponseOnEvent (0x00), the setup is kept
+    pub fn stop_response_on_event(&mut self) -> Result<(), UdsError> {
+        self.request_response_on_event(&[0x00, 0x00]).map(drop)
+    }
+
+    /// clearResponseOnEvent (0x06), removing all events
+    pub fn clear_response_on_event(&mut self) -> Result<(), UdsError> {
+        self.request_response_on_event(&[0x06, 0x00])?;
+        self.event_responses
+            .retain(|prefix| prefix == &[PERIODIC_RESPONSE_SID]);
+        Ok(())
+    }
+
+    /// reportActivatedEvents (0x04), returning the numberOfActivatedEvents followed by the
+    /// event type records
+    pub fn report_activated_events(&mut self) -> Result<Vec<u8>, UdsError> {
+        self.request_response_on_event(&[0x04])
+    }
+
+    /// Iterate over event responses, ending once none arrived within `timeout`
+    pub fn events(&mut self, timeout: Duration) -> Events<'_, T> {
+        Events {
+            client: self,
+            timeout,
+        }
+    }
+
+    /// Send a ResponseOnEvent request, returning the response after the echoed event type
+    fn request_response_on_event(&mut self, request: &[u8]) -> Result<Vec<u8>, UdsError> {
+        let response = self.request(ServiceId::ResponseOnEvent, request)?;
+        match response.split_first() {
+            Some((&event_type, rest)) if event_type == request[0] => Ok(rest.to_vec()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the event type",
+            )),
+        }
+    }
+}
+
+/// Start of the responses to the serviceToRespondTo request: the response service
+/// identifier followed by the echoed data identifier or sub-function
+fn event_response_prefix(service: u8, parameters: &[u8]) -> Vec<u8> {
+    let echoed = match ServiceId::from_u8(service) {
+        Some(ServiceId::ReadDataByIdentifier) => parameters.get(..2),
+        Some(service) if service.has_sub_function() => parameters.get(..1),
+        _ => None,
+    };
+    let mut prefix = vec![service.wrapping_add(POSITIVE_RESPONSE_OFFSET)];
+    prefix.extend_from_slice(echoed.unwrap_or_default());
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::{MockReply, MockTransport};
+
+    /// ECU reporting changes of DID 0xF40D on ResponseOnEvent
+    fn ecu() -> MockTransport {
+        MockTransport::new()
+            .on_request(&[0x86, 0x03], &[0xC6, 0x03, 0x01, 0x02])
+            .on_request(&[0x86, 0x05], &[0xC6, 0x05])
+    }
+
+    #[test]
+    fn event_interleaved_with_response_is_queued() {
+        // the event arrives before the response to a request of the same service
+        let mut mock = ecu().on_request(
+            &[0x22, 0xF1, 0x90],
+            MockReply::new()
+                .response(&[0x62, 0xF4, 0x0D, 0x32])
+                .response(&[0x62, 0xF1, 0x90, 0x57]),
+        );
+        let mut client = UdsClient::new(&mut mock);
+        client
+            .setup_response_on_event(&ResponseOnEvent::on_change_of_did(0xF40D))
+            .unwrap();
+        client.start_response_on_event().unwrap();
+
+        assert_eq!(client.read_data_by_identifier(0xF190).unwrap(), [0x57]);
+        let event = client.next_event(Duration::ZERO).unwrap();
+        assert_eq!(event, Some(vec![0x62, 0xF4, 0x0D, 0x32]));
+        assert_eq!(client.next_event(Duration::ZERO).unwrap(), None);
+    }
+
+    #[test]
+    fn queued_events_are_bounded() {
+        let mut reply = MockReply::new();
+        for speed in 0..=u8::MAX {
+            reply = reply.response(&[0x62, 0xF4, 0x0D, speed]);
+        }
+        let mut mock = ecu().on_request(
+            &[0x22, 0xF1, 0x90],
+            reply
+                .response(&[0x62, 0xF4, 0x0D, 0x00])
+                .response(&[0x62, 0xF1, 0x90, 0x57]),
+        );
+        let mut client = UdsClient::new(&mut mock);
+        client
+            .setup_response_on_event(&ResponseOnEvent::on_change_of_did(0xF40D))
+            .unwrap();
+        client.read_data_by_identifier(0xF190).unwrap();
+
+        // the oldest event was dropped
+        let events: Vec<_> = client.events(Duration::ZERO).map(Result::unwrap).collect();
+        assert_eq!(events.len(), 256);
+        assert_eq!(events[0], [0x62, 0xF4, 0x0D, 0x01]);
+        assert_eq!(events[255], [0x62, 0xF4, 0x0D, 0x00]);
+    }
+
+    #[test]
+    fn event_response_prefix_echoes_did_or_sub_function() {
+        assert_eq!(
+            event_response_prefix(0x22, &[0xF4, 0x0D]),
+            [0x62, 0xF4, 0x0D]
+        );
+        assert_eq!(event_response_prefix(0x19, &[0x02, 0x08]), [0x59, 0x02]);
+        assert_eq!(event_response_prefix(0x2F, &[0xF4, 0x0D]), [0x6F]);
+    }
+}
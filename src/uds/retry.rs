@@ -0,0 +1,109 @@
+use super::{Nrc, UdsError};
+use std::time::Duration;
+
+/// Repetition of requests failing with transient errors, see
+/// [`UdsClient::set_retry_policy`](super::UdsClient::set_retry_policy)
+///
+/// ```rust
+/// use socketcan_isotp::uds::{Nrc, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(5)
+///     .exponential_backoff(Duration::from_millis(20), Duration::from_millis(500))
+///     .retryable_nrcs(&[Nrc::BusyRepeatRequest, Nrc::ConditionsNotCorrect])
+///     .retry_timeouts(true);
+/// assert_eq!(policy.backoff(3), Duration::from_millis(80));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    exponential: bool,
+    retryable_nrcs: Vec<Nrc>,
+    retry_timeouts: bool,
+}
+
+impl RetryPolicy {
+    /// Default wait before repeating a request
+    pub const DEFAULT_BACKOFF: Duration = Duration::from_millis(10);
+
+    /// Send each request up to `max_attempts` times, repeating it after
+    /// busyRepeatRequest (NRC 0x21)
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: Self::DEFAULT_BACKOFF,
+            max_backoff: Self::DEFAULT_BACKOFF,
+            exponential: false,
+            retryable_nrcs: vec![Nrc::BusyRepeatRequest],
+            retry_timeouts: false,
+        }
+    }
+
+    /// Send each request once
+    pub fn never() -> Self {
+        Self::new(1)
+    }
+
+    /// Wait `backoff` before each repetition
+    pub fn fixed_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self.max_backoff = backoff;
+        self.exponential = false;
+        self
+    }
+
+    /// Wait `initial` before the first repetition, doubling the wait for each further
+    /// repetition up to `max`
+    pub fn exponential_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self.exponential = true;
+        self
+    }
+
+    /// Negative response codes repeating the request
+    pub fn retryable_nrcs(mut self, nrcs: &[Nrc]) -> Self {
+        self.retryable_nrcs = nrcs.to_vec();
+        self
+    }
+
+    /// Repeat requests without response
+    pub fn retry_timeouts(mut self, retry_timeouts: bool) -> Self {
+        self.retry_timeouts = retry_timeouts;
+        self
+    }
+
+    /// get the maximum number of attempts per request
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Wait before the repetition following the failed attempt `attempt`, starting at 1
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        if !self.exponential {
+            return self.initial_backoff;
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Whether `error` is transient according to the policy
+    pub fn is_retryable(&self, error: &UdsError) -> bool {
+        match error {
+            UdsError::Negative(nrc) => self.retryable_nrcs.contains(nrc),
+            UdsError::Timeout => self.retry_timeouts,
+            _ => false,
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::never()
+    }
+}
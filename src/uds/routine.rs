@@ -0,0 +1,89 @@
+use super::{Nrc, ServiceId, UdsClient, UdsError, UdsTransport};
+use std::time::Duration;
+
+/// Routine identifier (RID)
+pub type Rid = u16;
+
+/// Sub-functions of RoutineControl (0x31)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum RoutineControlType {
+    /// startRoutine
+    Start = 0x01,
+    /// stopRoutine
+    Stop = 0x02,
+    /// requestRoutineResults
+    RequestResults = 0x03,
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// RoutineControl (0x31), returning the routineInfo and routineStatusRecord following
+    /// the echoed routine identifier
+    pub fn routine_control(
+        &mut self,
+        control_type: RoutineControlType,
+        rid: Rid,
+        option_record: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = Vec::with_capacity(3 + option_record.len());
+        request.push(control_type as u8);
+        request.extend_from_slice(&rid.to_be_bytes());
+        request.extend_from_slice(option_record);
+        let response = self.request(ServiceId::RoutineControl, &request)?;
+        match response.as_slice() {
+            [echo, hi, lo, status_record @ ..]
+                if *echo == control_type as u8 && Rid::from_be_bytes([*hi, *lo]) == rid =>
+            {
+                Ok(status_record.to_vec())
+            }
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the routine control type and identifier",
+            )),
+        }
+    }
+
+    /// Start the routine `rid` with the routineControlOptionRecord `option_record`
+    pub fn start_routine(&mut self, rid: Rid, option_record: &[u8]) -> Result<Vec<u8>, UdsError> {
+        self.routine_control(RoutineControlType::Start, rid, option_record)
+    }
+
+    /// Stop the routine `rid`
+    pub fn stop_routine(&mut self, rid: Rid, option_record: &[u8]) -> Result<Vec<u8>, UdsError> {
+        self.routine_control(RoutineControlType::Stop, rid, option_record)
+    }
+
+    /// Request the results of the routine `rid`
+    pub fn request_routine_results(&mut self, rid: Rid) -> Result<Vec<u8>, UdsError> {
+        self.routine_control(RoutineControlType::RequestResults, rid, &[])
+    }
+
+    /// Poll the results of the routine `rid` every `interval` until `is_complete` accepts
+    /// the status record.
+    ///
+    /// Negative responses [`Nrc::BusyRepeatRequest`] and
+    /// [`Nrc::ConditionsNotCorrect`] are treated as still running. Fails with
+    /// [`UdsError::Timeout`] if the routine doesn't complete within `timeout`.
+    pub fn wait_for_routine(
+        &mut self,
+        rid: Rid,
+        interval: Duration,
+        timeout: Duration,
+        mut is_complete: impl FnMut(&[u8]) -> bool,
+    ) -> Result<Vec<u8>, UdsError> {
+        let deadline = self.clock.now() + timeout;
+        loop {
+            match self.request_routine_results(rid) {
+                Ok(status_record) if is_complete(&status_record) => return Ok(status_record),
+                Ok(_)
+                | Err(UdsError::Negative(Nrc::BusyRepeatRequest | Nrc::ConditionsNotCorrect)) => (),
+                Err(error) => return Err(error),
+            }
+
+            if self.clock.now() + interval > deadline {
+                return Err(UdsError::Timeout);
+            }
+            self.clock.sleep(interval);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use super::{UdsError, UdsTransport};
+use crate::{poll, Id, IsoTpSocket, StandardId};
+use libc::{pollfd, POLLIN};
+use std::ops::RangeInclusive;
+use std::os::unix::io::AsRawFd;
+use std::time::{Duration, Instant};
+
+/// An ECU answering the scan request, see [`Scanner`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiscoveredEcu {
+    /// CAN id the ECU receives physically addressed requests on
+    pub request_id: Id,
+    /// CAN id the ECU sends responses on
+    pub response_id: Id,
+    /// The response, positive or negative
+    pub response: Vec<u8>,
+    /// Time from sending the request until the response was received
+    pub response_time: Duration,
+}
+
+/// Discovers ECUs by sending a lightweight request to candidate CAN id pairs
+///
+/// Candidates are probed one after another, so a scan of the default range takes about
+/// 248 times the timeout.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::Scanner;
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     for ecu in Scanner::new("vcan0").scan()? {
+///         println!(
+///             "{:?} -> {:?} answered in {:?}",
+///             ecu.request_id, ecu.response_id, ecu.response_time
+///         );
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scanner {
+    interface: String,
+    candidates: Vec<(Id, Id)>,
+    request: Vec<u8>,
+    timeout: Duration,
+}
+
+impl Scanner {
+    /// Default request, TesterPresent (0x3E) without suppressed response
+    pub const TESTER_PRESENT: [u8; 2] = [0x3E, 0x00];
+
+    /// Request switching to the default session, answered by ECUs not supporting
+    /// TesterPresent in the default session
+    pub const DEFAULT_SESSION: [u8; 2] = [0x10, 0x01];
+
+    /// Default time to wait for a response per candidate
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+
+    /// Scan `interface` with the physical request ids 0x700 - 0x7F7 responding on
+    /// request id + 8
+    pub fn new(interface: impl Into<String>) -> Self {
+        Self {
+            interface: interface.into(),
+            candidates: Vec::new(),
+            request: Self::TESTER_PRESENT.to_vec(),
+            timeout: Self::DEFAULT_TIMEOUT,
+        }
+        .physical_range(0x700..=0x7F7, 8)
+    }
+
+    /// Replace the candidates by the standard request ids `request_ids`, each responding
+    /// on request id + `response_offset`
+    pub fn physical_range(
+        mut self,
+        request_ids: RangeInclusive<u16>,
+        response_offset: u16,
+    ) -> Self {
+        self.candidates = request_ids
+            .filter_map(|request_id| {
+                let request_id = StandardId::new(request_id)?;
+                let response_id =
+                    StandardId::new(request_id.as_raw().checked_add(response_offset)?)?;
+                Some((Id::from(request_id), Id::from(response_id)))
+            })
+            .collect();
+        self
+    }
+
+    /// Add a candidate pair, e.g. with extended ids
+    pub fn pair(mut self, request_id: impl Into<Id>, response_id: impl Into<Id>) -> Self {
+        self.candidates
+            .push((request_id.into(), response_id.into()));
+        self
+    }
+
+    /// Send `request` instead of [`Scanner::TESTER_PRESENT`]
+    pub fn request(mut self, request: &[u8]) -> Self {
+        self.request = request.to_vec();
+        self
+    }
+
+    /// Time to wait for a response per candidate
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// get the candidate pairs of request and response id
+    pub fn get_candidates(&self) -> &[(Id, Id)] {
+        &self.candidates
+    }
+
+    /// Probe each candidate with a physically addressed request
+    pub fn scan(&self) -> Result<Vec<DiscoveredEcu>, UdsError> {
+        let mut discovered = Vec::new();
+        for &(request_id, response_id) in &self.candidates {
+            let mut socket = IsoTpSocket::open(&self.interface, response_id, request_id)?;
+            let sent_at = Instant::now();
+            socket.send(&self.request)?;
+            if let Some(response) = socket.receive(self.timeout)? {
+                discovered.push(DiscoveredEcu {
+                    request_id,
+                    response_id,
+                    response: response.to_vec(),
+                    response_time: sent_at.elapsed(),
+                });
+            }
+        }
+        Ok(discovered)
+    }
+
+    /// Send a single functionally addressed request on `functional_id`, e.g. 0x7DF, and
+    /// collect the responses on the response ids of all candidates in the order they arrived
+    ///
+    /// A candidate whose response fails to be received, e.g. because a multi-frame response
+    /// was aborted, is skipped without losing the responses of the other ECUs.
+    pub fn scan_functional(
+        &self,
+        functional_id: impl Into<Id>,
+    ) -> Result<Vec<DiscoveredEcu>, UdsError> {
+        let mut sockets = self
+            .candidates
+            .iter()
+            .map(|&(request_id, response_id)| {
+                IsoTpSocket::open(&self.interface, response_id, request_id)
+                    .map(|socket| (request_id, response_id, socket))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let functional = IsoTpSocket::open_functional(&self.interface, functional_id)?;
+        let sent_at = Instant::now();
+        functional.write(&self.request)?;
+
+        // all sockets are polled together, so each response is timed when it arrives
+        let deadline = sent_at + self.timeout;
+        let mut fds: Vec<pollfd> = sockets
+            .iter()
+            .map(|(_, _, socket)| pollfd {
+                fd: socket.as_raw_fd(),
+                events: POLLIN,
+                revents: 0,
+            })
+            .collect();
+        let mut discovered = Vec::new();
+        let mut pending = sockets.len();
+        while pending > 0 {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let ready = poll::poll(&mut fds, Some(timeout)).map_err(crate::Error::from)?;
+            let received_at = Instant::now();
+            if ready == 0 {
+                break;
+            }
+            for (fd, (request_id, response_id, socket)) in fds.iter_mut().zip(&mut sockets) {
+                if fd.revents == 0 {
+                    continue;
+                }
+                // negative descriptors are ignored by poll
+                fd.fd = -1;
+                pending -= 1;
+                let Ok(response) = socket.read() else {
+                    continue;
+                };
+                discovered.push(DiscoveredEcu {
+                    request_id: *request_id,
+                    response_id: *response_id,
+                    response: response.to_vec(),
+                    response_time: received_at - sent_at,
+                });
+            }
+        }
+        Ok(discovered)
+    }
+}
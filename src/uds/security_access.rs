@@ -0,0 +1,323 @@
+use super::{Nrc, ServiceId, UdsClient, UdsError, UdsTransport};
+#[cfg(feature = "tokio")]
+use crate::{transport::AsyncIsoTpTransport, uds::AsyncUdsClient};
+use std::future::Future;
+use std::time::Duration;
+
+/// Error returned by seed-key algorithms
+pub type SeedKeyError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Computes the SecurityAccess key for a seed sent by the server
+///
+/// Implemented for closures, so an OEM algorithm can be passed directly:
+///
+/// ```rust
+/// use socketcan_isotp::uds::SeedKeyAlgorithm;
+///
+/// fn xor_key(_level: u8, seed: &[u8]) -> Result<Vec<u8>, socketcan_isotp::uds::SeedKeyError> {
+///     Ok(seed.iter().map(|byte| byte ^ 0x5A).collect())
+/// }
+///
+/// let mut algorithm = xor_key;
+/// assert_eq!(algorithm.compute_key(0x01, &[0x12, 0x34]).unwrap(), vec![0x48, 0x6E]);
+/// ```
+pub trait SeedKeyAlgorithm {
+    /// Compute the key for `seed` received for the requestSeed sub-function `level`
+    fn compute_key(&mut self, level: u8, seed: &[u8]) -> Result<Vec<u8>, SeedKeyError>;
+}
+
+impl<F> SeedKeyAlgorithm for F
+where
+    F: FnMut(u8, &[u8]) -> Result<Vec<u8>, SeedKeyError>,
+{
+    fn compute_key(&mut self, level: u8, seed: &[u8]) -> Result<Vec<u8>, SeedKeyError> {
+        self(level, seed)
+    }
+}
+
+/// Computes the SecurityAccess key asynchronously, e.g. by asking a signing service
+pub trait AsyncSeedKeyAlgorithm {
+    /// Compute the key for `seed` received for the requestSeed sub-function `level`
+    fn compute_key(
+        &mut self,
+        level: u8,
+        seed: &[u8],
+    ) -> impl Future<Output = Result<Vec<u8>, SeedKeyError>> + Send;
+}
+
+/// SecurityAccess (0x27) requestSeed/sendKey sequence for one security level
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{SecurityAccess, SeedKeyError, UdsClient};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::uds::UdsError> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = UdsClient::new(tp_socket);
+///     let mut algorithm = |_level: u8, seed: &[u8]| -> Result<Vec<u8>, SeedKeyError> {
+///         Ok(seed.iter().map(|byte| !byte).collect())
+///     };
+///     SecurityAccess::new(0x01)?.unlock(&mut client, &mut algorithm)
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SecurityAccess {
+    level: u8,
+    retry_delay: Duration,
+    max_retries: u32,
+}
+
+impl SecurityAccess {
+    /// Default wait before requesting a seed again after requiredTimeDelayNotExpired
+    pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+    /// Unlock the security level requested by the odd requestSeed sub-function `level`
+    pub fn new(level: u8) -> Result<Self, UdsError> {
+        if level % 2 == 0 || !(0x01..=0x7D).contains(&level) {
+            return Err(UdsError::InvalidRequest(
+                "requestSeed level must be odd and within 0x01 - 0x7D",
+            ));
+        }
+        Ok(Self {
+            level,
+            retry_delay: Self::DEFAULT_RETRY_DELAY,
+            max_retries: 0,
+        })
+    }
+
+    /// Request a seed again up to `max_retries` times after requiredTimeDelayNotExpired
+    /// (NRC 0x37), waiting `retry_delay` before each retry
+    pub fn retry(mut self, max_retries: u32, retry_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// get the requestSeed sub-function
+    pub fn get_level(&self) -> u8 {
+        self.level
+    }
+
+    /// Request a seed and send the key computed by `algorithm`.
+    ///
+    /// Returns without sending a key if the server sends an all zero seed, as the level
+    /// is unlocked already.
+    pub fn unlock(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        algorithm: &mut impl SeedKeyAlgorithm,
+    ) -> Result<(), UdsError> {
+        let Some(seed) = self.request_seed(client)? else {
+            return Ok(());
+        };
+        let key = algorithm
+            .compute_key(self.level, &seed)
+            .map_err(UdsError::SeedKey)?;
+        self.send_key(client, &key)
+    }
+
+    /// Request the seed, `None` if the level is unlocked already
+    pub fn request_seed(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+    ) -> Result<Option<Vec<u8>>, UdsError> {
+        let mut retries = 0;
+        let response = loop {
+            match client.request(ServiceId::SecurityAccess, &[self.level]) {
+                Err(UdsError::Negative(Nrc::RequiredTimeDelayNotExpired))
+                    if retries < self.max_retries =>
+                {
+                    retries += 1;
+                    client.clock.sleep(self.retry_delay);
+                }
+                response => break response?,
+            }
+        };
+
+        self.seed(&response)
+    }
+
+    /// Send the key for the seed of [`SecurityAccess::request_seed`]
+    pub fn send_key(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        key: &[u8],
+    ) -> Result<(), UdsError> {
+        let response = client.request(ServiceId::SecurityAccess, &self.send_key_request(key))?;
+        self.check_key_accepted(&response)
+    }
+
+    /// The seed of a requestSeed response, `None` if the level is unlocked already
+    fn seed(&self, response: &[u8]) -> Result<Option<Vec<u8>>, UdsError> {
+        match response.split_first() {
+            Some((&level, seed)) if level == self.level => {
+                Ok((!seed.iter().all(|byte| *byte == 0)).then(|| seed.to_vec()))
+            }
+            _ => Err(UdsError::InvalidResponse(
+                "security access response does not echo the requestSeed level",
+            )),
+        }
+    }
+
+    fn send_key_request(&self, key: &[u8]) -> Vec<u8> {
+        let mut request = Vec::with_capacity(1 + key.len());
+        request.push(self.level + 1);
+        request.extend_from_slice(key);
+        request
+    }
+
+    fn check_key_accepted(&self, response: &[u8]) -> Result<(), UdsError> {
+        match response.first() {
+            Some(&level) if level == self.level + 1 => Ok(()),
+            _ => Err(UdsError::InvalidResponse(
+                "security access response does not echo the sendKey level",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl SecurityAccess {
+    /// Request a seed and send the key computed by `algorithm` on an asynchronous client.
+    ///
+    /// See [`SecurityAccess::unlock`], retries wait without blocking the executor.
+    pub async fn unlock_async(
+        &self,
+        client: &mut AsyncUdsClient<impl AsyncIsoTpTransport>,
+        algorithm: &mut impl AsyncSeedKeyAlgorithm,
+    ) -> Result<(), UdsError> {
+        let mut retries = 0;
+        let response = loop {
+            match client
+                .request(ServiceId::SecurityAccess, &[self.level])
+                .await
+            {
+                Err(UdsError::Negative(Nrc::RequiredTimeDelayNotExpired))
+                    if retries < self.max_retries =>
+                {
+                    retries += 1;
+                    tokio::time::sleep(self.retry_delay).await;
+                }
+                response => break response?,
+            }
+        };
+        let Some(seed) = self.seed(&response)? else {
+            return Ok(());
+        };
+        let key = algorithm
+            .compute_key(self.level, &seed)
+            .await
+            .map_err(UdsError::SeedKey)?;
+        let response = client
+            .request(ServiceId::SecurityAccess, &self.send_key_request(&key))
+            .await?;
+        self.check_key_accepted(&response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::uds::MockTransport;
+    use std::sync::Arc;
+
+    /// ECU sending the seed 0x1234 and expecting its bitwise inverse
+    fn ecu() -> MockTransport {
+        MockTransport::new()
+            .on_request(&[0x27, 0x01], &[0x67, 0x01, 0x12, 0x34])
+            .on_request(&[0x27, 0x02, 0xED, 0xCB], &[0x67, 0x02])
+            .on_request(&[0x27, 0x02], &[0x7F, 0x27, 0x35])
+    }
+
+    fn invert(_level: u8, seed: &[u8]) -> Result<Vec<u8>, SeedKeyError> {
+        Ok(seed.iter().map(|byte| !byte).collect())
+    }
+
+    #[test]
+    fn unlock_sends_key_for_seed() {
+        let mut mock = ecu();
+        let mut client = UdsClient::new(&mut mock);
+        SecurityAccess::new(0x01)
+            .unwrap()
+            .unlock(&mut client, &mut invert)
+            .unwrap();
+        assert_eq!(
+            mock.sent(),
+            [vec![0x27, 0x01], vec![0x27, 0x02, 0xED, 0xCB]]
+        );
+    }
+
+    #[test]
+    fn unlock_reports_invalid_key() {
+        let mut mock = ecu();
+        let mut client = UdsClient::new(&mut mock);
+        let mut wrong_key =
+            |_level: u8, _seed: &[u8]| -> Result<Vec<u8>, SeedKeyError> { Ok(vec![0x00, 0x00]) };
+        let result = SecurityAccess::new(0x01)
+            .unwrap()
+            .unlock(&mut client, &mut wrong_key);
+        assert!(matches!(result, Err(UdsError::Negative(Nrc::InvalidKey))));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn unlock_async_sends_key_for_seed() {
+        struct Invert;
+
+        impl AsyncSeedKeyAlgorithm for Invert {
+            async fn compute_key(
+                &mut self,
+                level: u8,
+                seed: &[u8],
+            ) -> Result<Vec<u8>, SeedKeyError> {
+                invert(level, seed)
+            }
+        }
+
+        let mut mock = ecu();
+        let mut client = AsyncUdsClient::new(&mut mock);
+        SecurityAccess::new(0x01)
+            .unwrap()
+            .unlock_async(&mut client, &mut Invert)
+            .await
+            .unwrap();
+        assert_eq!(
+            mock.sent(),
+            [vec![0x27, 0x01], vec![0x27, 0x02, 0xED, 0xCB]]
+        );
+    }
+
+    #[test]
+    fn unlock_skips_key_for_zero_seed() {
+        let mut mock = MockTransport::new().on_request(&[0x27, 0x01], &[0x67, 0x01, 0x00, 0x00]);
+        let mut client = UdsClient::new(&mut mock);
+        SecurityAccess::new(0x01)
+            .unwrap()
+            .unlock(&mut client, &mut invert)
+            .unwrap();
+        assert_eq!(mock.sent(), [vec![0x27, 0x01]]);
+    }
+
+    #[test]
+    fn request_seed_waits_for_time_delay() {
+        let mut mock = MockTransport::new()
+            .on_request_times(&[0x27, 0x01], 2, &[0x7F, 0x27, 0x37])
+            .on_request(&[0x27, 0x01], &[0x67, 0x01, 0x12, 0x34]);
+        let mut client = UdsClient::new(&mut mock);
+        let clock = SimulatedClock::new();
+        client.set_clock(Arc::new(clock.clone()));
+        let seed = SecurityAccess::new(0x01)
+            .unwrap()
+            .retry(2, SecurityAccess::DEFAULT_RETRY_DELAY)
+            .request_seed(&mut client)
+            .unwrap();
+        assert_eq!(seed, Some(vec![0x12, 0x34]));
+        assert_eq!(clock.elapsed(), 2 * SecurityAccess::DEFAULT_RETRY_DELAY);
+        assert_eq!(mock.sent().len(), 3);
+    }
+}
@@ -0,0 +1,374 @@
+use super::{
+    DiagnosticSession, Did, Download, DownloadProgress, ResetType, Rid, SecurityAccess,
+    SeedKeyError, UdsClient, UdsError, UdsTransport,
+};
+use std::time::Duration;
+use thiserror::Error;
+
+/// Interval of requestRoutineResults while waiting for a [`Step::Routine`] to complete
+pub const ROUTINE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A step of a [`Sequence`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Step {
+    /// DiagnosticSessionControl (0x10) to the session
+    Session(DiagnosticSession),
+    /// SecurityAccess (0x27) of the odd requestSeed `level`, the key is computed by
+    /// [`SequenceHooks::compute_key`]
+    SecurityAccess {
+        /// requestSeed sub-function
+        level: u8,
+    },
+    /// WriteDataByIdentifier (0x2E)
+    WriteDid {
+        /// data identifier
+        did: Did,
+        /// data record
+        data: Vec<u8>,
+    },
+    /// Start a routine, waiting up to `timeout` for it to complete if set
+    Routine {
+        /// routine identifier
+        rid: Rid,
+        /// routineControlOptionRecord
+        option_record: Vec<u8>,
+        /// time to poll requestRoutineResults until the routine completes
+        timeout: Option<Duration>,
+        /// leading bytes of the status record reporting completion, empty to accept the
+        /// first results of servers answering with a negative response while running
+        #[cfg_attr(feature = "serde", serde(default))]
+        completed_status: Vec<u8>,
+    },
+    /// [`Download`] of the image provided by [`SequenceHooks::image`]
+    Download {
+        /// memory address
+        address: u64,
+        /// name of the image passed to [`SequenceHooks::image`]
+        image: String,
+        /// repetitions of a failed block
+        max_retries: u32,
+    },
+    /// ECUReset (0x11), delaying the next request by `reset_delay`
+    Reset {
+        /// reset type
+        reset_type: ResetType,
+        /// time the server needs to restart
+        reset_delay: Duration,
+    },
+    /// Any request, the response is ignored
+    Request {
+        /// service identifier
+        service: u8,
+        /// request parameters
+        data: Vec<u8>,
+    },
+    /// Wait before the next step
+    Delay(Duration),
+}
+
+impl Step {
+    fn run(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        hooks: &mut impl SequenceHooks,
+        index: usize,
+    ) -> Result<(), UdsError> {
+        match self {
+            Step::Session(session) => client.diagnostic_session_control(*session).map(drop),
+            Step::SecurityAccess { level } => {
+                let mut algorithm = |level: u8, seed: &[u8]| -> Result<Vec<u8>, SeedKeyError> {
+                    hooks.compute_key(level, seed)
+                };
+                SecurityAccess::new(*level)?.unlock(client, &mut algorithm)
+            }
+            Step::WriteDid { did, data } => client.write_data_by_identifier(*did, data),
+            Step::Routine {
+                rid,
+                option_record,
+                timeout,
+                completed_status,
+            } => {
+                client.start_routine(*rid, option_record)?;
+                if let Some(timeout) = timeout {
+                    client.wait_for_routine(*rid, ROUTINE_POLL_INTERVAL, *timeout, |status| {
+                        status.starts_with(completed_status)
+                    })?;
+                }
+                Ok(())
+            }
+            Step::Download {
+                address,
+                image,
+                max_retries,
+            } => {
+                let image = hooks
+                    .image(image)
+                    .ok_or(UdsError::InvalidRequest("sequence image is not available"))?;
+                Download::new(*address)
+                    .max_retries(*max_retries)
+                    .run(client, &image, |progress| {
+                        hooks.progress(SequenceProgress::Download {
+                            step: index,
+                            progress,
+                        })
+                    })
+            }
+            Step::Reset {
+                reset_type,
+                reset_delay,
+            } => client.ecu_reset(*reset_type, *reset_delay).map(drop),
+            Step::Request { service, data } => client.request(*service, data).map(drop),
+            Step::Delay(delay) => {
+                client.clock.sleep(*delay);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Progress of a [`Sequence`], passed to [`SequenceHooks::progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SequenceProgress {
+    /// The step with index `step` of `total` steps started
+    Started {
+        /// index of the step
+        step: usize,
+        /// number of steps of the sequence
+        total: usize,
+    },
+    /// A block of the [`Step::Download`] with index `step` was transferred
+    Download {
+        /// index of the step
+        step: usize,
+        /// progress of the download
+        progress: DownloadProgress,
+    },
+    /// The step with index `step` of `total` steps completed
+    Finished {
+        /// index of the step
+        step: usize,
+        /// number of steps of the sequence
+        total: usize,
+    },
+}
+
+/// Callbacks of a running [`Sequence`], providing keys and images and observing progress
+///
+/// All methods have defaults, `()` implements the trait for sequences without security
+/// access and downloads.
+pub trait SequenceHooks {
+    /// Compute the SecurityAccess key for `seed` received for the requestSeed `level`
+    fn compute_key(&mut self, _level: u8, _seed: &[u8]) -> Result<Vec<u8>, SeedKeyError> {
+        Err("no seed-key algorithm configured".into())
+    }
+
+    /// Provide the image named by a [`Step::Download`], `None` if it doesn't exist
+    fn image(&mut self, _name: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Called as the sequence progresses
+    fn progress(&mut self, _progress: SequenceProgress) {}
+
+    /// Called after the step with index `step` failed, before the rollback steps run
+    fn rollback(&mut self, _step: usize, _error: &UdsError) {}
+}
+
+impl SequenceHooks for () {}
+
+/// A [`Sequence`] step failed
+#[derive(Error, Debug)]
+#[error("Sequence step {step} failed: {source}")]
+pub struct SequenceError {
+    /// index of the failed step
+    pub step: usize,
+    /// error of the step
+    #[source]
+    pub source: UdsError,
+}
+
+/// A diagnostic sequence described as data, e.g. the flashing procedure of a production
+/// station
+///
+/// Steps run in order until one fails. The rollback steps then run best effort to bring
+/// the server back into a defined state, their errors are ignored. With the `serde`
+/// feature sequences can be loaded from configuration files.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{
+///     DiagnosticSession, ResetType, Sequence, SequenceHooks, SequenceProgress, SeedKeyError,
+///     Step, UdsClient,
+/// };
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+/// use std::time::Duration;
+///
+/// struct Station;
+///
+/// impl SequenceHooks for Station {
+///     fn compute_key(&mut self, _level: u8, seed: &[u8]) -> Result<Vec<u8>, SeedKeyError> {
+///         Ok(seed.iter().map(|byte| !byte).collect())
+///     }
+///
+///     fn image(&mut self, name: &str) -> Option<Vec<u8>> {
+///         std::fs::read(name).ok()
+///     }
+///
+///     fn progress(&mut self, progress: SequenceProgress) {
+///         println!("{:?}", progress);
+///     }
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///     )?;
+///     let mut client = UdsClient::new(tp_socket);
+///
+///     let reset = Step::Reset {
+///         reset_type: ResetType::Hard,
+///         reset_delay: Duration::from_secs(1),
+///     };
+///     Sequence::new()
+///         .step(Step::Session(DiagnosticSession::Programming))
+///         .step(Step::SecurityAccess { level: 0x11 })
+///         .step(Step::Routine {
+///             rid: 0xFF00,
+///             option_record: vec![],
+///             timeout: Some(Duration::from_secs(30)),
+///             completed_status: vec![0x00],
+///         })
+///         .step(Step::Download {
+///             address: 0x0800_0000,
+///             image: "firmware.bin".into(),
+///             max_retries: 3,
+///         })
+///         .step(reset.clone())
+///         .rollback_step(reset)
+///         .run(&mut client, &mut Station)?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sequence {
+    steps: Vec<Step>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    rollback: Vec<Step>,
+}
+
+impl Sequence {
+    /// An empty sequence
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a step
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Append a step run after a step failed
+    pub fn rollback_step(mut self, step: Step) -> Self {
+        self.rollback.push(step);
+        self
+    }
+
+    /// get the steps
+    pub fn get_steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    /// get the rollback steps
+    pub fn get_rollback_steps(&self) -> &[Step] {
+        &self.rollback
+    }
+
+    /// Run the steps, running the rollback steps if one fails
+    pub fn run(
+        &self,
+        client: &mut UdsClient<impl UdsTransport>,
+        hooks: &mut impl SequenceHooks,
+    ) -> Result<(), SequenceError> {
+        let total = self.steps.len();
+        for (step, action) in self.steps.iter().enumerate() {
+            hooks.progress(SequenceProgress::Started { step, total });
+            if let Err(source) = action.run(client, hooks, step) {
+                hooks.rollback(step, &source);
+                for (index, rollback) in self.rollback.iter().enumerate() {
+                    // best effort, the original error is reported
+                    let _ = rollback.run(client, hooks, total + index);
+                }
+                return Err(SequenceError { step, source });
+            }
+            hooks.progress(SequenceProgress::Finished { step, total });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::SimulatedClock;
+    use crate::uds::MockTransport;
+    use std::sync::Arc;
+
+    fn erase_memory(timeout: Duration) -> Sequence {
+        Sequence::new().step(Step::Routine {
+            rid: 0xFF00,
+            option_record: vec![],
+            timeout: Some(timeout),
+            completed_status: vec![0x00],
+        })
+    }
+
+    #[test]
+    fn routine_step_waits_for_completed_status() {
+        // the routine reports 0x01 while running and 0x00 once completed
+        let mut mock = MockTransport::new()
+            .on_request(&[0x31, 0x01, 0xFF, 0x00], &[0x71, 0x01, 0xFF, 0x00])
+            .on_request_times(
+                &[0x31, 0x03, 0xFF, 0x00],
+                2,
+                &[0x71, 0x03, 0xFF, 0x00, 0x01],
+            )
+            .on_request(&[0x31, 0x03, 0xFF, 0x00], &[0x71, 0x03, 0xFF, 0x00, 0x00]);
+        let mut client = UdsClient::new(&mut mock);
+        client.set_clock(Arc::new(SimulatedClock::new()));
+        erase_memory(Duration::from_secs(1))
+            .run(&mut client, &mut ())
+            .unwrap();
+        assert_eq!(mock.sent().len(), 4);
+    }
+
+    #[test]
+    fn routine_step_times_out_while_running() {
+        let mut mock = MockTransport::new()
+            .on_request(&[0x31, 0x01, 0xFF, 0x00], &[0x71, 0x01, 0xFF, 0x00])
+            .on_request(&[0x31, 0x03, 0xFF, 0x00], &[0x71, 0x03, 0xFF, 0x00, 0x01]);
+        let mut client = UdsClient::new(&mut mock);
+        client.set_clock(Arc::new(SimulatedClock::new()));
+        let error = erase_memory(Duration::from_secs(1))
+            .run(&mut client, &mut ())
+            .unwrap_err();
+        assert!(matches!(error.source, UdsError::Timeout));
+    }
+
+    #[test]
+    fn delay_step_waits_on_client_clock() {
+        let mut mock = MockTransport::new();
+        let mut client = UdsClient::new(&mut mock);
+        let clock = SimulatedClock::new();
+        client.set_clock(Arc::new(clock.clone()));
+        Sequence::new()
+            .step(Step::Delay(Duration::from_secs(30)))
+            .run(&mut client, &mut ())
+            .unwrap();
+        assert_eq!(clock.elapsed(), Duration::from_secs(30));
+    }
+}
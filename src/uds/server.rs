@@ -0,0 +1,525 @@
+use super::{
+    DiagnosticSession, Did, Nrc, Rid, RoutineControlType, SeedKeyAlgorithm, ServiceId,
+    UdsTransport, NEGATIVE_RESPONSE_SID, POSITIVE_RESPONSE_OFFSET, SUPPRESS_POSITIVE_RESPONSE,
+};
+use crate::{Error, IsoTpSocket};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Time waited for a request before waiting again, the server waits for requests forever
+const REQUEST_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Handler of a service, receiving the request parameters following the service identifier
+/// and returning the response parameters following the positive response identifier
+pub type ServiceHandler = Box<dyn FnMut(&mut ServerState, &[u8]) -> Result<Vec<u8>, Nrc> + Send>;
+
+/// Handler of a routine, receiving the routineControlOptionRecord and returning the
+/// routineStatusRecord
+pub type RoutineHandler = Box<dyn FnMut(RoutineControlType, &[u8]) -> Result<Vec<u8>, Nrc> + Send>;
+
+/// Session and security state of a [`UdsServer`], visible to service handlers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServerState {
+    /// Active diagnostic session
+    pub session: DiagnosticSession,
+    /// Unlocked requestSeed level, `None` while locked
+    pub security_level: Option<u8>,
+    /// requestSeed level of the seed sent last, awaiting the key
+    pending_seed: Option<(u8, Vec<u8>)>,
+}
+
+impl ServerState {
+    fn reset(&mut self) {
+        self.session = DiagnosticSession::Default;
+        self.security_level = None;
+        self.pending_seed = None;
+    }
+}
+
+struct DidEntry {
+    data: Vec<u8>,
+    writable: bool,
+    security_level: Option<u8>,
+}
+
+struct SecurityAccessHandler {
+    seed: Box<dyn FnMut(u8) -> Vec<u8> + Send>,
+    algorithm: Box<dyn SeedKeyAlgorithm + Send>,
+}
+
+/// A UDS server answering requests received on an [`IsoTpSocket`] or another
+/// [`UdsTransport`], e.g. to simulate an ECU
+///
+/// Implements DiagnosticSessionControl, ECUReset, TesterPresent, SecurityAccess,
+/// ReadDataByIdentifier, WriteDataByIdentifier and RoutineControl on top of registered
+/// data identifiers and routines. Other services are answered by registered handlers or
+/// with serviceNotSupported.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::uds::{Nrc, UdsServer};
+/// use socketcan_isotp::{IsoTpSocket, StandardId};
+///
+/// fn main() -> Result<(), socketcan_isotp::Error> {
+///     let tp_socket = IsoTpSocket::open(
+///         "vcan0",
+///         StandardId::new(0x7E0).expect("Invalid rx id"),
+///         StandardId::new(0x7E8).expect("Invalid tx id"),
+///     )?;
+///     let mut server = UdsServer::new(tp_socket);
+///     server
+///         .register_did(0xF190, b"WVWZZZ1JZXW000001".to_vec())
+///         .register_writable_did(0x0100, vec![0x00; 4])
+///         .register_routine(0xFF00, |_, _| Err(Nrc::ConditionsNotCorrect));
+///     server.run()
+/// }
+/// ```
+pub struct UdsServer<T = IsoTpSocket> {
+    transport: T,
+    state: ServerState,
+    timing: (Duration, Duration),
+    dids: HashMap<Did, DidEntry>,
+    routines: HashMap<Rid, RoutineHandler>,
+    services: HashMap<u8, ServiceHandler>,
+    security_access: Option<SecurityAccessHandler>,
+}
+
+impl<T: UdsTransport> UdsServer<T> {
+    /// Create a server in the default session with all security levels locked
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            state: ServerState {
+                session: DiagnosticSession::Default,
+                security_level: None,
+                pending_seed: None,
+            },
+            timing: (super::DEFAULT_P2, super::DEFAULT_P2_EXTENDED),
+            dids: HashMap::new(),
+            routines: HashMap::new(),
+            services: HashMap::new(),
+            security_access: None,
+        }
+    }
+
+    /// Register a read-only data identifier
+    pub fn register_did(&mut self, did: Did, data: Vec<u8>) -> &mut Self {
+        self.dids.insert(
+            did,
+            DidEntry {
+                data,
+                writable: false,
+                security_level: None,
+            },
+        );
+        self
+    }
+
+    /// Register a data identifier that can be written with WriteDataByIdentifier
+    ///
+    /// Writes must keep the length of `data`.
+    pub fn register_writable_did(&mut self, did: Did, data: Vec<u8>) -> &mut Self {
+        self.dids.insert(
+            did,
+            DidEntry {
+                data,
+                writable: true,
+                security_level: None,
+            },
+        );
+        self
+    }
+
+    /// Require the security level unlocked by requestSeed `level` to access `did`
+    pub fn require_security(&mut self, did: Did, level: u8) -> &mut Self {
+        if let Some(entry) = self.dids.get_mut(&did) {
+            entry.security_level = Some(level);
+        }
+        self
+    }
+
+    /// get the current data of `did`
+    pub fn get_did(&self, did: Did) -> Option<&[u8]> {
+        self.dids.get(&did).map(|entry| entry.data.as_slice())
+    }
+
+    /// Register a routine for RoutineControl
+    pub fn register_routine(
+        &mut self,
+        rid: Rid,
+        handler: impl FnMut(RoutineControlType, &[u8]) -> Result<Vec<u8>, Nrc> + Send + 'static,
+    ) -> &mut Self {
+        self.routines.insert(rid, Box::new(handler));
+        self
+    }
+
+    /// Register a handler for `service`, replacing the built-in implementation
+    pub fn register_service(
+        &mut self,
+        service: impl Into<u8>,
+        handler: impl FnMut(&mut ServerState, &[u8]) -> Result<Vec<u8>, Nrc> + Send + 'static,
+    ) -> &mut Self {
+        self.services.insert(service.into(), Box::new(handler));
+        self
+    }
+
+    /// Enable SecurityAccess, sending seeds generated by `seed` and accepting keys
+    /// computed by `algorithm`
+    pub fn security_access(
+        &mut self,
+        seed: impl FnMut(u8) -> Vec<u8> + Send + 'static,
+        algorithm: impl SeedKeyAlgorithm + Send + 'static,
+    ) -> &mut Self {
+        self.security_access = Some(SecurityAccessHandler {
+            seed: Box::new(seed),
+            algorithm: Box::new(algorithm),
+        });
+        self
+    }
+
+    /// Set the P2 and P2* timing reported in DiagnosticSessionControl responses
+    pub fn set_timing(&mut self, p2: Duration, p2_extended: Duration) {
+        self.timing = (p2, p2_extended);
+    }
+
+    /// get the session and security state
+    pub fn get_state(&self) -> &ServerState {
+        &self.state
+    }
+
+    /// Answer requests until receiving or sending fails
+    pub fn run(&mut self) -> Result<(), Error> {
+        loop {
+            self.serve_one()?;
+        }
+    }
+
+    /// Wait for a single request and send the response, if any
+    pub fn serve_one(&mut self) -> Result<(), Error> {
+        let request = loop {
+            if let Some(request) = self.transport.receive(REQUEST_WAIT_INTERVAL)? {
+                break request.to_vec();
+            }
+        };
+        if let Some(response) = self.handle(&request) {
+            self.transport.send(&response)?;
+        }
+        Ok(())
+    }
+
+    /// Compute the response to `request`, `None` if the response is suppressed
+    pub fn handle(&mut self, request: &[u8]) -> Option<Vec<u8>> {
+        let (&service, parameters) = request.split_first()?;
+        let suppress = ServiceId::from_u8(service).is_some_and(ServiceId::has_sub_function)
+            && parameters
+                .first()
+                .is_some_and(|sub_function| sub_function & SUPPRESS_POSITIVE_RESPONSE != 0);
+        let parameters = match parameters.split_first() {
+            Some((sub_function, rest)) if suppress => {
+                [&[sub_function & !SUPPRESS_POSITIVE_RESPONSE], rest].concat()
+            }
+            _ => parameters.to_vec(),
+        };
+
+        match self.dispatch(service, &parameters) {
+            Ok(_) if suppress => None,
+            Ok(response) => Some(
+                [
+                    &[service.wrapping_add(POSITIVE_RESPONSE_OFFSET)],
+                    response.as_slice(),
+                ]
+                .concat(),
+            ),
+            Err(nrc) => Some(vec![NEGATIVE_RESPONSE_SID, service, nrc.as_u8()]),
+        }
+    }
+
+    fn dispatch(&mut self, service: u8, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        if let Some(handler) = self.services.get_mut(&service) {
+            return handler(&mut self.state, parameters);
+        }
+        match ServiceId::from_u8(service) {
+            Some(ServiceId::DiagnosticSessionControl) => self.session_control(parameters),
+            Some(ServiceId::EcuReset) => match parameters {
+                [reset_type] => {
+                    self.state.reset();
+                    Ok(vec![*reset_type])
+                }
+                _ => Err(Nrc::IncorrectMessageLengthOrInvalidFormat),
+            },
+            Some(ServiceId::TesterPresent) => match parameters {
+                [0x00] => Ok(vec![0x00]),
+                [_] => Err(Nrc::SubFunctionNotSupported),
+                _ => Err(Nrc::IncorrectMessageLengthOrInvalidFormat),
+            },
+            Some(ServiceId::SecurityAccess) => self.handle_security_access(parameters),
+            Some(ServiceId::ReadDataByIdentifier) => self.read_dids(parameters),
+            Some(ServiceId::WriteDataByIdentifier) => self.write_did(parameters),
+            Some(ServiceId::RoutineControl) => self.routine_control(parameters),
+            _ => Err(Nrc::ServiceNotSupported),
+        }
+    }
+
+    fn session_control(&mut self, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        let [session] = *parameters else {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        };
+        let session = DiagnosticSession::from(session);
+        if matches!(session, DiagnosticSession::Other(_)) {
+            return Err(Nrc::SubFunctionNotSupported);
+        }
+        if session != self.state.session {
+            // a session change locks all security levels
+            self.state.security_level = None;
+            self.state.pending_seed = None;
+        }
+        self.state.session = session;
+
+        let p2 = u16::try_from(self.timing.0.as_millis()).unwrap_or(u16::MAX);
+        let p2_extended = u16::try_from(self.timing.1.as_millis() / 10).unwrap_or(u16::MAX);
+        let mut response = vec![session.as_u8()];
+        response.extend_from_slice(&p2.to_be_bytes());
+        response.extend_from_slice(&p2_extended.to_be_bytes());
+        Ok(response)
+    }
+
+    fn handle_security_access(&mut self, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        let Some(security_access) = &mut self.security_access else {
+            return Err(Nrc::ServiceNotSupported);
+        };
+        if self.state.session == DiagnosticSession::Default {
+            return Err(Nrc::ServiceNotSupportedInActiveSession);
+        }
+        let Some((&level, key)) = parameters.split_first() else {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        };
+
+        if level % 2 == 1 {
+            if self.state.security_level == Some(level) {
+                // already unlocked, signaled by an all zero seed
+                let seed_len = (security_access.seed)(level).len();
+                return Ok([&[level], vec![0; seed_len].as_slice()].concat());
+            }
+            let seed = (security_access.seed)(level);
+            self.state.pending_seed = Some((level, seed.clone()));
+            return Ok([&[level], seed.as_slice()].concat());
+        }
+
+        let Some((seed_level, seed)) = self.state.pending_seed.take() else {
+            return Err(Nrc::RequestSequenceError);
+        };
+        if seed_level + 1 != level {
+            return Err(Nrc::RequestSequenceError);
+        }
+        match security_access.algorithm.compute_key(seed_level, &seed) {
+            Ok(expected) if expected == key => {
+                self.state.security_level = Some(seed_level);
+                Ok(vec![level])
+            }
+            _ => Err(Nrc::InvalidKey),
+        }
+    }
+
+    fn did_entry(&mut self, did: Did) -> Result<&mut DidEntry, Nrc> {
+        let entry = self.dids.get_mut(&did).ok_or(Nrc::RequestOutOfRange)?;
+        match entry.security_level {
+            Some(level) if self.state.security_level != Some(level) => {
+                Err(Nrc::SecurityAccessDenied)
+            }
+            _ => Ok(entry),
+        }
+    }
+
+    /// Read the supported data identifiers of `parameters`, failing with requestOutOfRange
+    /// only if none is supported, as ISO 14229-1 asks for
+    fn read_dids(&mut self, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        if parameters.is_empty() || parameters.len() % 2 != 0 {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        }
+        let mut response = Vec::new();
+        for did in parameters.chunks_exact(2) {
+            let entry = match self.did_entry(Did::from_be_bytes([did[0], did[1]])) {
+                Ok(entry) => entry,
+                Err(Nrc::RequestOutOfRange) => continue,
+                Err(nrc) => return Err(nrc),
+            };
+            response.extend_from_slice(did);
+            response.extend_from_slice(&entry.data);
+        }
+        if response.is_empty() {
+            return Err(Nrc::RequestOutOfRange);
+        }
+        Ok(response)
+    }
+
+    fn write_did(&mut self, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        let Some((did, data)) = parameters.split_first_chunk::<2>() else {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        };
+        let entry = self.did_entry(Did::from_be_bytes(*did))?;
+        if !entry.writable {
+            return Err(Nrc::RequestOutOfRange);
+        }
+        if entry.data.len() != data.len() {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        }
+        entry.data = data.to_vec();
+        Ok(did.to_vec())
+    }
+
+    fn routine_control(&mut self, parameters: &[u8]) -> Result<Vec<u8>, Nrc> {
+        let [control_type, hi, lo, ref option_record @ ..] = *parameters else {
+            return Err(Nrc::IncorrectMessageLengthOrInvalidFormat);
+        };
+        let routine_control_type = match control_type {
+            0x01 => RoutineControlType::Start,
+            0x02 => RoutineControlType::Stop,
+            0x03 => RoutineControlType::RequestResults,
+            _ => return Err(Nrc::SubFunctionNotSupported),
+        };
+        let handler = self
+            .routines
+            .get_mut(&Rid::from_be_bytes([hi, lo]))
+            .ok_or(Nrc::RequestOutOfRange)?;
+        let status_record = handler(routine_control_type, option_record)?;
+        Ok([&[control_type, hi, lo], status_record.as_slice()].concat())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::uds::MockTransport;
+
+    fn server() -> UdsServer<MockTransport> {
+        let mut server = UdsServer::new(MockTransport::new());
+        server
+            .register_did(0xF190, b"VIN".to_vec())
+            .register_did(0xF18C, vec![0x12, 0x34])
+            .register_writable_did(0x0100, vec![0x00; 2])
+            .register_did(0x0200, vec![0xAB])
+            .require_security(0x0200, 0x01)
+            .register_routine(0xFF00, |control_type, option_record| match control_type {
+                RoutineControlType::Start => Ok(option_record.to_vec()),
+                _ => Err(Nrc::RequestSequenceError),
+            })
+            .security_access(
+                |_| vec![0x11, 0x22],
+                |_: u8, seed: &[u8]| Ok(seed.iter().map(|byte| !byte).collect()),
+            );
+        server
+    }
+
+    #[test]
+    fn read_dids_skips_unsupported_identifiers() {
+        let mut server = server();
+        assert_eq!(
+            server.handle(&[0x22, 0xF1, 0x90, 0x12, 0x34, 0xF1, 0x8C]),
+            Some(vec![
+                0x62, 0xF1, 0x90, b'V', b'I', b'N', 0xF1, 0x8C, 0x12, 0x34
+            ])
+        );
+        // requestOutOfRange only if no identifier is supported
+        assert_eq!(
+            server.handle(&[0x22, 0x12, 0x34, 0x56, 0x78]),
+            Some(vec![0x7F, 0x22, 0x31])
+        );
+        assert_eq!(
+            server.handle(&[0x22, 0xF1, 0x90, 0x02, 0x00]),
+            Some(vec![0x7F, 0x22, 0x33])
+        );
+        assert_eq!(server.handle(&[0x22, 0xF1]), Some(vec![0x7F, 0x22, 0x13]));
+    }
+
+    #[test]
+    fn write_did_keeps_length() {
+        let mut server = server();
+        assert_eq!(
+            server.handle(&[0x2E, 0x01, 0x00, 0xBE, 0xEF]),
+            Some(vec![0x6E, 0x01, 0x00])
+        );
+        assert_eq!(server.get_did(0x0100), Some([0xBE, 0xEF].as_slice()));
+        assert_eq!(
+            server.handle(&[0x2E, 0x01, 0x00, 0xBE]),
+            Some(vec![0x7F, 0x2E, 0x13])
+        );
+        assert_eq!(
+            server.handle(&[0x2E, 0xF1, 0x90, 0x00, 0x00, 0x00]),
+            Some(vec![0x7F, 0x2E, 0x31])
+        );
+    }
+
+    #[test]
+    fn session_change_locks_security_access() {
+        let mut server = server();
+        assert_eq!(server.handle(&[0x27, 0x01]), Some(vec![0x7F, 0x27, 0x7F]));
+        assert_eq!(
+            server.handle(&[0x10, 0x03]),
+            Some(vec![0x50, 0x03, 0x00, 0x32, 0x01, 0xF4])
+        );
+        assert_eq!(
+            server.handle(&[0x27, 0x02, 0xEE, 0xDD]),
+            Some(vec![0x7F, 0x27, 0x24])
+        );
+        assert_eq!(
+            server.handle(&[0x27, 0x01]),
+            Some(vec![0x67, 0x01, 0x11, 0x22])
+        );
+        assert_eq!(
+            server.handle(&[0x27, 0x02, 0xEE, 0xDD]),
+            Some(vec![0x67, 0x02])
+        );
+        assert_eq!(server.get_state().security_level, Some(0x01));
+        assert_eq!(
+            server.handle(&[0x22, 0x02, 0x00]),
+            Some(vec![0x62, 0x02, 0x00, 0xAB])
+        );
+
+        assert_eq!(
+            server.handle(&[0x10, 0x02]),
+            Some(vec![0x50, 0x02, 0x00, 0x32, 0x01, 0xF4])
+        );
+        assert_eq!(server.get_state().security_level, None);
+    }
+
+    #[test]
+    fn suppressed_positive_responses_are_not_sent() {
+        let mut server = server();
+        assert_eq!(server.handle(&[0x3E, 0x80]), None);
+        assert_eq!(server.handle(&[0x3E, 0x00]), Some(vec![0x7E, 0x00]));
+        // negative responses are sent regardless
+        assert_eq!(server.handle(&[0x3E, 0x81]), Some(vec![0x7F, 0x3E, 0x12]));
+        assert_eq!(server.handle(&[0x11, 0x81]), None);
+        assert_eq!(server.get_state().session, DiagnosticSession::Default);
+    }
+
+    #[test]
+    fn routines_and_registered_services() {
+        let mut server = server();
+        assert_eq!(
+            server.handle(&[0x31, 0x01, 0xFF, 0x00, 0x05]),
+            Some(vec![0x71, 0x01, 0xFF, 0x00, 0x05])
+        );
+        assert_eq!(
+            server.handle(&[0x31, 0x02, 0xFF, 0x00]),
+            Some(vec![0x7F, 0x31, 0x24])
+        );
+        assert_eq!(
+            server.handle(&[0x31, 0x01, 0xFF, 0x01]),
+            Some(vec![0x7F, 0x31, 0x31])
+        );
+        assert_eq!(server.handle(&[0x85, 0x02]), Some(vec![0x7F, 0x85, 0x11]));
+
+        server.register_service(0x85, |_, parameters| Ok(parameters.to_vec()));
+        assert_eq!(server.handle(&[0x85, 0x02]), Some(vec![0xC5, 0x02]));
+    }
+
+    #[test]
+    fn serve_one_answers_received_request() {
+        let mut mock = MockTransport::new();
+        mock.push_response(Duration::ZERO, &[0x22, 0xF1, 0x8C]);
+        let mut server = UdsServer::new(&mut mock);
+        server.register_did(0xF18C, vec![0x12, 0x34]);
+        server.serve_one().unwrap();
+        assert_eq!(mock.sent(), [vec![0x62, 0xF1, 0x8C, 0x12, 0x34]]);
+    }
+}
@@ -0,0 +1,250 @@
+use std::fmt;
+
+/// Bit of the sub-function byte suppressing the positive response (SPRMIB)
+pub const SUPPRESS_POSITIVE_RESPONSE: u8 = 0x80;
+
+/// Service identifiers of ISO 14229-1 requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum ServiceId {
+    /// DiagnosticSessionControl
+    DiagnosticSessionControl = 0x10,
+    /// ECUReset
+    EcuReset = 0x11,
+    /// ClearDiagnosticInformation
+    ClearDiagnosticInformation = 0x14,
+    /// ReadDTCInformation
+    ReadDtcInformation = 0x19,
+    /// ReadDataByIdentifier
+    ReadDataByIdentifier = 0x22,
+    /// ReadMemoryByAddress
+    ReadMemoryByAddress = 0x23,
+    /// ReadScalingDataByIdentifier
+    ReadScalingDataByIdentifier = 0x24,
+    /// SecurityAccess
+    SecurityAccess = 0x27,
+    /// CommunicationControl
+    CommunicationControl = 0x28,
+    /// Authentication
+    Authentication = 0x29,
+    /// ReadDataByPeriodicIdentifier
+    ReadDataByPeriodicIdentifier = 0x2A,
+    /// DynamicallyDefineDataIdentifier
+    DynamicallyDefineDataIdentifier = 0x2C,
+    /// WriteDataByIdentifier
+    WriteDataByIdentifier = 0x2E,
+    /// InputOutputControlByIdentifier
+    InputOutputControlByIdentifier = 0x2F,
+    /// RoutineControl
+    RoutineControl = 0x31,
+    /// RequestDownload
+    RequestDownload = 0x34,
+    /// RequestUpload
+    RequestUpload = 0x35,
+    /// TransferData
+    TransferData = 0x36,
+    /// RequestTransferExit
+    RequestTransferExit = 0x37,
+    /// RequestFileTransfer
+    RequestFileTransfer = 0x38,
+    /// WriteMemoryByAddress
+    WriteMemoryByAddress = 0x3D,
+    /// TesterPresent
+    TesterPresent = 0x3E,
+    /// AccessTimingParameter
+    AccessTimingParameter = 0x83,
+    /// SecuredDataTransmission
+    SecuredDataTransmission = 0x84,
+    /// ControlDTCSetting
+    ControlDtcSetting = 0x85,
+    /// ResponseOnEvent
+    ResponseOnEvent = 0x86,
+    /// LinkControl
+    LinkControl = 0x87,
+}
+
+impl ServiceId {
+    /// All service identifiers defined by ISO 14229-1
+    pub const ALL: [ServiceId; 27] = [
+        ServiceId::DiagnosticSessionControl,
+        ServiceId::EcuReset,
+        ServiceId::ClearDiagnosticInformation,
+        ServiceId::ReadDtcInformation,
+        ServiceId::ReadDataByIdentifier,
+        ServiceId::ReadMemoryByAddress,
+        ServiceId::ReadScalingDataByIdentifier,
+        ServiceId::SecurityAccess,
+        ServiceId::CommunicationControl,
+        ServiceId::Authentication,
+        ServiceId::ReadDataByPeriodicIdentifier,
+        ServiceId::DynamicallyDefineDataIdentifier,
+        ServiceId::WriteDataByIdentifier,
+        ServiceId::InputOutputControlByIdentifier,
+        ServiceId::RoutineControl,
+        ServiceId::RequestDownload,
+        ServiceId::RequestUpload,
+        ServiceId::TransferData,
+        ServiceId::RequestTransferExit,
+        ServiceId::RequestFileTransfer,
+        ServiceId::WriteMemoryByAddress,
+        ServiceId::TesterPresent,
+        ServiceId::AccessTimingParameter,
+        ServiceId::SecuredDataTransmission,
+        ServiceId::ControlDtcSetting,
+        ServiceId::ResponseOnEvent,
+        ServiceId::LinkControl,
+    ];
+
+    /// The encoded service identifier
+    pub fn as_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// Decode a request service identifier, `None` for unknown identifiers
+    pub fn from_u8(sid: u8) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|service| service.as_u8() == sid)
+    }
+
+    /// Whether the first request parameter is a sub-function byte supporting the SPRMIB
+    pub fn has_sub_function(self) -> bool {
+        matches!(
+            self,
+            ServiceId::DiagnosticSessionControl
+                | ServiceId::EcuReset
+                | ServiceId::ReadDtcInformation
+                | ServiceId::SecurityAccess
+                | ServiceId::CommunicationControl
+                | ServiceId::Authentication
+                | ServiceId::DynamicallyDefineDataIdentifier
+                | ServiceId::RoutineControl
+                | ServiceId::TesterPresent
+                | ServiceId::AccessTimingParameter
+                | ServiceId::ControlDtcSetting
+                | ServiceId::ResponseOnEvent
+                | ServiceId::LinkControl
+        )
+    }
+}
+
+impl From<ServiceId> for u8 {
+    fn from(service: ServiceId) -> Self {
+        service.as_u8()
+    }
+}
+
+impl fmt::Display for ServiceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} ({:#04X})", self, self.as_u8())
+    }
+}
+
+/// An encoded UDS request
+///
+/// ```rust
+/// use socketcan_isotp::uds::{Request, ServiceId};
+///
+/// // TesterPresent, positive response suppressed
+/// let request = Request::new(ServiceId::TesterPresent)
+///     .sub_function(0x00)
+///     .suppress_positive_response(true);
+/// assert_eq!(request.as_bytes(), &[0x3E, 0x80]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Request {
+    bytes: Vec<u8>,
+    has_sub_function: bool,
+    wait_tx_done: bool,
+    /// no positive response is sent, although the SPRMIB bit is clear
+    positive_response_not_required: bool,
+}
+
+impl Request {
+    /// A request of `service` without parameters
+    pub fn new(service: impl Into<u8>) -> Self {
+        Self {
+            bytes: vec![service.into()],
+            has_sub_function: false,
+            wait_tx_done: false,
+            positive_response_not_required: false,
+        }
+    }
+
+    /// Set the sub-function, the SPRMIB bit of `sub_function` is ignored
+    pub fn sub_function(mut self, sub_function: u8) -> Self {
+        let sub_function = sub_function & !SUPPRESS_POSITIVE_RESPONSE;
+        if self.has_sub_function {
+            self.bytes[1] = (self.bytes[1] & SUPPRESS_POSITIVE_RESPONSE) | sub_function;
+        } else {
+            self.bytes.insert(1, sub_function);
+            self.has_sub_function = true;
+        }
+        self
+    }
+
+    /// Ask the server not to send a positive response, requires a sub-function
+    ///
+    /// Negative responses are still sent by the server.
+    pub fn suppress_positive_response(mut self, suppress: bool) -> Self {
+        if !self.has_sub_function {
+            self = self.sub_function(0x00);
+        }
+        if suppress {
+            self.bytes[1] |= SUPPRESS_POSITIVE_RESPONSE;
+        } else {
+            self.bytes[1] &= !SUPPRESS_POSITIVE_RESPONSE;
+        }
+        self
+    }
+
+    /// Expect no positive response without setting the SPRMIB bit, e.g. for KWP2000
+    /// testerPresent with responseRequired `0x02`
+    #[cfg(feature = "kwp")]
+    pub(crate) fn positive_response_not_required(mut self) -> Self {
+        self.positive_response_not_required = true;
+        self
+    }
+
+    /// Return from sending only after the request was transmitted, see
+    /// [`IsoTpTransport::send_wait_tx_done`](crate::transport::IsoTpTransport::send_wait_tx_done)
+    pub fn wait_tx_done(mut self, wait_tx_done: bool) -> Self {
+        self.wait_tx_done = wait_tx_done;
+        self
+    }
+
+    /// Append request parameters
+    pub fn data(mut self, data: &[u8]) -> Self {
+        self.bytes.extend_from_slice(data);
+        self
+    }
+
+    /// get the service identifier
+    pub fn get_service(&self) -> u8 {
+        self.bytes[0]
+    }
+
+    /// get the sub-function without SPRMIB, `None` if no sub-function is set
+    pub fn get_sub_function(&self) -> Option<u8> {
+        self.has_sub_function
+            .then(|| self.bytes[1] & !SUPPRESS_POSITIVE_RESPONSE)
+    }
+
+    /// Whether sending waits for the transmission of the request
+    pub fn get_wait_tx_done(&self) -> bool {
+        self.wait_tx_done
+    }
+
+    /// Whether the positive response is suppressed
+    pub fn is_positive_response_suppressed(&self) -> bool {
+        self.positive_response_not_required
+            || self.has_sub_function && self.bytes[1] & SUPPRESS_POSITIVE_RESPONSE != 0
+    }
+
+    /// The encoded request
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
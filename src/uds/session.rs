@@ -0,0 +1,176 @@
+use super::{ServiceId, UdsClient, UdsError, UdsTransport, DEFAULT_P2, DEFAULT_P2_EXTENDED};
+use std::time::Duration;
+
+/// Diagnostic sessions of DiagnosticSessionControl (0x10)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiagnosticSession {
+    /// defaultSession (0x01)
+    Default,
+    /// programmingSession (0x02)
+    Programming,
+    /// extendedDiagnosticSession (0x03)
+    Extended,
+    /// safetySystemDiagnosticSession (0x04)
+    SafetySystem,
+    /// Vehicle manufacturer or system supplier specific session
+    Other(u8),
+}
+
+impl DiagnosticSession {
+    /// The encoded sub-function
+    pub fn as_u8(self) -> u8 {
+        match self {
+            DiagnosticSession::Default => 0x01,
+            DiagnosticSession::Programming => 0x02,
+            DiagnosticSession::Extended => 0x03,
+            DiagnosticSession::SafetySystem => 0x04,
+            DiagnosticSession::Other(session) => session,
+        }
+    }
+}
+
+impl From<u8> for DiagnosticSession {
+    fn from(session: u8) -> Self {
+        match session {
+            0x01 => DiagnosticSession::Default,
+            0x02 => DiagnosticSession::Programming,
+            0x03 => DiagnosticSession::Extended,
+            0x04 => DiagnosticSession::SafetySystem,
+            session => DiagnosticSession::Other(session),
+        }
+    }
+}
+
+/// Reset types of ECUReset (0x11)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ResetType {
+    /// hardReset (0x01)
+    Hard,
+    /// keyOffOnReset (0x02)
+    KeyOffOn,
+    /// softReset (0x03)
+    Soft,
+    /// enableRapidPowerShutDown (0x04)
+    EnableRapidPowerShutDown,
+    /// disableRapidPowerShutDown (0x05)
+    DisableRapidPowerShutDown,
+    /// Vehicle manufacturer or system supplier specific reset
+    Other(u8),
+}
+
+impl ResetType {
+    /// The encoded sub-function
+    pub fn as_u8(self) -> u8 {
+        match self {
+            ResetType::Hard => 0x01,
+            ResetType::KeyOffOn => 0x02,
+            ResetType::Soft => 0x03,
+            ResetType::EnableRapidPowerShutDown => 0x04,
+            ResetType::DisableRapidPowerShutDown => 0x05,
+            ResetType::Other(reset_type) => reset_type,
+        }
+    }
+}
+
+/// Server timing parameters reported in the DiagnosticSessionControl response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionTiming {
+    /// P2server_max, the response timeout
+    pub p2: Duration,
+    /// P2*server_max, the response timeout after a pending response
+    pub p2_extended: Duration,
+}
+
+impl SessionTiming {
+    /// Decode `[P2 ms (2 bytes), P2* 10 ms (2 bytes)]`
+    pub fn from_bytes(bytes: [u8; 4]) -> Self {
+        Self {
+            p2: Duration::from_millis(u16::from_be_bytes([bytes[0], bytes[1]]).into()),
+            p2_extended: Duration::from_millis(
+                u64::from(u16::from_be_bytes([bytes[2], bytes[3]])) * 10,
+            ),
+        }
+    }
+
+    /// Encode as `[P2 ms (2 bytes), P2* 10 ms (2 bytes)]`, saturating at the maximum values
+    pub fn to_bytes(&self) -> [u8; 4] {
+        let p2 = u16::try_from(self.p2.as_millis()).unwrap_or(u16::MAX);
+        let p2_extended = u16::try_from(self.p2_extended.as_millis() / 10).unwrap_or(u16::MAX);
+        let [p2_hi, p2_lo] = p2.to_be_bytes();
+        let [p2_ext_hi, p2_ext_lo] = p2_extended.to_be_bytes();
+        [p2_hi, p2_lo, p2_ext_hi, p2_ext_lo]
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// DiagnosticSessionControl (0x10), switching to `session`.
+    ///
+    /// Applies the timing parameters reported by the server to the client's P2 and P2*
+    /// timeouts and returns them.
+    pub fn diagnostic_session_control(
+        &mut self,
+        session: DiagnosticSession,
+    ) -> Result<SessionTiming, UdsError> {
+        let response = self.request(ServiceId::DiagnosticSessionControl, &[session.as_u8()])?;
+        let timing = match *response {
+            [echo, p2_hi, p2_lo, p2_ext_hi, p2_ext_lo, ..] if echo == session.as_u8() => {
+                SessionTiming::from_bytes([p2_hi, p2_lo, p2_ext_hi, p2_ext_lo])
+            }
+            _ => {
+                return Err(UdsError::InvalidResponse(
+                    "response does not echo the session or lacks timing parameters",
+                ))
+            }
+        };
+        self.session_changed(session);
+        self.set_p2(timing.p2);
+        self.set_p2_extended(timing.p2_extended);
+        Ok(timing)
+    }
+
+    /// ECUReset (0x11), returning the powerDownTime of enableRapidPowerShutDown
+    ///
+    /// The server falls back to the default session with default timing. The next request
+    /// is delayed by `reset_delay`, the time the server needs to restart.
+    pub fn ecu_reset(
+        &mut self,
+        reset_type: ResetType,
+        reset_delay: Duration,
+    ) -> Result<Option<u8>, UdsError> {
+        let response = self.request(ServiceId::EcuReset, &[reset_type.as_u8()])?;
+        let power_down_time = match *response {
+            [echo, ref rest @ ..] if echo == reset_type.as_u8() => rest.first().copied(),
+            _ => {
+                return Err(UdsError::InvalidResponse(
+                    "response does not echo the reset type",
+                ))
+            }
+        };
+        if !matches!(
+            reset_type,
+            ResetType::EnableRapidPowerShutDown | ResetType::DisableRapidPowerShutDown
+        ) {
+            self.session_changed(DiagnosticSession::Default);
+            self.set_p2(DEFAULT_P2);
+            self.set_p2_extended(DEFAULT_P2_EXTENDED);
+            self.delay_next_request(reset_delay);
+        }
+        Ok(power_down_time)
+    }
+
+    /// Get the diagnostic session last confirmed by the server
+    pub fn get_session(&self) -> DiagnosticSession {
+        self.session
+    }
+
+    /// Track the session change, servers turn DTC setting on in the default session
+    pub(super) fn session_changed(&mut self, session: DiagnosticSession) {
+        self.session = session;
+        if session == DiagnosticSession::Default {
+            self.dtc_setting_off = false;
+        }
+    }
+}
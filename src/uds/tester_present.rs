@@ -0,0 +1,78 @@
+use super::{Request, ServiceId};
+use libc::{c_void, write};
+use std::io;
+use std::os::unix::io::{AsRawFd, OwnedFd};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Background thread sending TesterPresent with suppressed positive response.
+///
+/// Created by [`UdsClient::tester_present_keeper`](super::UdsClient::tester_present_keeper),
+/// the keep-alive pauses while the client waits for a response. Dropping the keeper stops
+/// the thread.
+pub struct TesterPresentKeeper {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<io::Result<()>>>,
+}
+
+impl TesterPresentKeeper {
+    pub(crate) fn spawn(fd: OwnedFd, activity: Arc<Mutex<Instant>>, interval: Duration) -> Self {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let request = Request::new(ServiceId::TesterPresent).suppress_positive_response(true);
+
+        let thread = thread::spawn(move || loop {
+            match stopped.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => (),
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+
+            let mut last_request = activity
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if last_request.elapsed() < interval {
+                continue;
+            }
+
+            let bytes = request.as_bytes();
+            let write_rv =
+                unsafe { write(fd.as_raw_fd(), bytes.as_ptr() as *const c_void, bytes.len()) };
+            if write_rv < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            *last_request = Instant::now();
+        });
+
+        Self {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop sending keep-alives, returning the error that stopped the thread early if any
+    pub fn stop(mut self) -> io::Result<()> {
+        self.shutdown()
+    }
+
+    /// Whether the thread stopped because sending a keep-alive failed
+    pub fn is_finished(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    fn shutdown(&mut self) -> io::Result<()> {
+        drop(self.stop.take());
+        match self.thread.take() {
+            Some(thread) => thread
+                .join()
+                .unwrap_or_else(|_| Err(io::Error::other("tester present thread panicked"))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TesterPresentKeeper {
+    fn drop(&mut self) {
+        let _ = self.shutdown();
+    }
+}
@@ -0,0 +1,105 @@
+use super::{
+    ServiceId, SessionTiming, UdsClient, UdsError, UdsTransport, DEFAULT_P2, DEFAULT_P2_EXTENDED,
+};
+
+/// Sub-functions of AccessTimingParameter (0x83)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimingParameterAccessType {
+    /// readExtendedTimingParameterSet (0x01)
+    ReadExtendedSet,
+    /// setTimingParametersToDefaultValues (0x02)
+    SetToDefaultValues,
+    /// readCurrentlyActiveTimingParameters (0x03)
+    ReadCurrentlyActive,
+    /// setTimingParametersToGivenValues (0x04)
+    SetToGivenValues,
+    /// Vehicle manufacturer or system supplier specific access type
+    Other(u8),
+}
+
+impl TimingParameterAccessType {
+    /// The encoded sub-function
+    pub fn as_u8(self) -> u8 {
+        match self {
+            TimingParameterAccessType::ReadExtendedSet => 0x01,
+            TimingParameterAccessType::SetToDefaultValues => 0x02,
+            TimingParameterAccessType::ReadCurrentlyActive => 0x03,
+            TimingParameterAccessType::SetToGivenValues => 0x04,
+            TimingParameterAccessType::Other(access_type) => access_type,
+        }
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// AccessTimingParameter (0x83) with a raw TimingParameterRequestRecord, returning the
+    /// TimingParameterResponseRecord
+    pub fn access_timing_parameter(
+        &mut self,
+        access_type: TimingParameterAccessType,
+        record: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let mut request = Vec::with_capacity(1 + record.len());
+        request.push(access_type.as_u8());
+        request.extend_from_slice(record);
+        let response = self.request(ServiceId::AccessTimingParameter, &request)?;
+        match response.split_first() {
+            Some((&echo, record)) if echo == access_type.as_u8() => Ok(record.to_vec()),
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the timing parameter access type",
+            )),
+        }
+    }
+
+    /// Read the extended timing parameter set supported by the server
+    pub fn read_extended_timing_parameters(&mut self) -> Result<SessionTiming, UdsError> {
+        let record =
+            self.access_timing_parameter(TimingParameterAccessType::ReadExtendedSet, &[])?;
+        decode_timing(&record)
+    }
+
+    /// Read the active timing parameters and apply them to the client's P2 and P2*
+    pub fn read_timing_parameters(&mut self) -> Result<SessionTiming, UdsError> {
+        let record =
+            self.access_timing_parameter(TimingParameterAccessType::ReadCurrentlyActive, &[])?;
+        let timing = decode_timing(&record)?;
+        self.apply_timing(timing);
+        Ok(timing)
+    }
+
+    /// Reset the timing parameters of the server and the client to the default values
+    pub fn set_default_timing_parameters(&mut self) -> Result<(), UdsError> {
+        self.access_timing_parameter(TimingParameterAccessType::SetToDefaultValues, &[])?;
+        self.apply_timing(SessionTiming {
+            p2: DEFAULT_P2,
+            p2_extended: DEFAULT_P2_EXTENDED,
+        });
+        Ok(())
+    }
+
+    /// Set the timing parameters of the server and apply them to the client's P2 and P2*
+    pub fn set_timing_parameters(&mut self, timing: SessionTiming) -> Result<(), UdsError> {
+        self.access_timing_parameter(
+            TimingParameterAccessType::SetToGivenValues,
+            &timing.to_bytes(),
+        )?;
+        self.apply_timing(timing);
+        Ok(())
+    }
+
+    fn apply_timing(&mut self, timing: SessionTiming) {
+        self.set_p2(timing.p2);
+        self.set_p2_extended(timing.p2_extended);
+    }
+}
+
+fn decode_timing(record: &[u8]) -> Result<SessionTiming, UdsError> {
+    match *record {
+        [p2_hi, p2_lo, p2_ext_hi, p2_ext_lo, ..] => Ok(SessionTiming::from_bytes([
+            p2_hi, p2_lo, p2_ext_hi, p2_ext_lo,
+        ])),
+        _ => Err(UdsError::InvalidResponse(
+            "timing parameter record lacks P2 and P2*",
+        )),
+    }
+}
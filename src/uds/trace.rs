@@ -0,0 +1,133 @@
+use super::UdsError;
+use std::fmt::Write as _;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// How a traced request ended
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceOutcome {
+    /// The request is still in progress
+    Pending,
+    /// Final positive response
+    Positive,
+    /// Suppressed positive response, no answer from the server
+    Suppressed,
+    /// Failed with the given error, e.g. a negative response or timeout
+    Failed(String),
+}
+
+/// A request with all responses received while waiting for its final response
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TraceEntry {
+    /// Sequential id correlating the request with its responses
+    pub correlation_id: u64,
+    /// Time since the start of the trace the request was sent at
+    pub sent_at: Duration,
+    /// The encoded request
+    pub request: Vec<u8>,
+    /// Received PDUs with the time since the start of the trace, including pending
+    /// responses and responses skipped as unrelated
+    pub responses: Vec<(Duration, Vec<u8>)>,
+    /// How the request ended
+    pub outcome: TraceOutcome,
+}
+
+/// Record of the requests sent by a [`UdsClient`](super::UdsClient) for post-mortem
+/// analysis, see [`UdsClient::enable_trace`](super::UdsClient::enable_trace)
+///
+/// Timestamps are monotonic and relative to the creation of the trace.
+#[derive(Debug, Clone)]
+pub struct SessionTrace {
+    started: Instant,
+    entries: Vec<TraceEntry>,
+    next_correlation_id: u64,
+}
+
+impl SessionTrace {
+    /// Start an empty trace
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            entries: Vec::new(),
+            next_correlation_id: 0,
+        }
+    }
+
+    /// get the traced requests in order
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// Remove all entries, keeping the time base
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Write the trace as text, one line per PDU:
+    /// `<seconds> <correlation id> <TX|RX> <hex bytes>` followed by the outcome
+    pub fn write_to(&self, mut writer: impl io::Write) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{:.6} {} TX {}",
+                entry.sent_at.as_secs_f64(),
+                entry.correlation_id,
+                hex(&entry.request)
+            )?;
+            for (received_at, response) in &entry.responses {
+                writeln!(
+                    writer,
+                    "{:.6} {} RX {}",
+                    received_at.as_secs_f64(),
+                    entry.correlation_id,
+                    hex(response)
+                )?;
+            }
+            writeln!(writer, "# {} {:?}", entry.correlation_id, entry.outcome)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn begin(&mut self, request: &[u8]) {
+        self.entries.push(TraceEntry {
+            correlation_id: self.next_correlation_id,
+            sent_at: self.started.elapsed(),
+            request: request.to_vec(),
+            responses: Vec::new(),
+            outcome: TraceOutcome::Pending,
+        });
+        self.next_correlation_id += 1;
+    }
+
+    pub(super) fn record_response(&mut self, response: &[u8]) {
+        let received_at = self.started.elapsed();
+        if let Some(entry) = self.entries.last_mut() {
+            entry.responses.push((received_at, response.to_vec()));
+        }
+    }
+
+    pub(super) fn end(&mut self, result: &Result<Option<Vec<u8>>, UdsError>) {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.outcome = match result {
+                Ok(Some(_)) => TraceOutcome::Positive,
+                Ok(None) => TraceOutcome::Suppressed,
+                Err(e) => TraceOutcome::Failed(e.to_string()),
+            };
+        }
+    }
+}
+
+impl Default for SessionTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(super) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02X}");
+        hex
+    })
+}
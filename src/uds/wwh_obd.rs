@@ -0,0 +1,140 @@
+use super::dtc::parse_dtc_records;
+use super::{Did, Dtc, DtcRecord, DtcStatus, UdsClient, UdsError, UdsTransport};
+use crate::obd::{decode_pid, ObdError, PidValue, TestResult, INFO_TYPE_VIN};
+
+/// FunctionalGroupIdentifier of the emission related systems
+pub const WWH_OBD_EMISSIONS_GROUP: u8 = 0x33;
+
+/// DID range mapping the OBD Mode 01 PIDs, DID = 0xF400 + PID
+pub const WWH_OBD_PID_DIDS: Did = 0xF400;
+
+/// DID range mapping the OBD Mode 06 monitors, DID = 0xF600 + OBDMID
+pub const WWH_OBD_MONITOR_DIDS: Did = 0xF600;
+
+/// DID range mapping the OBD Mode 09 InfoTypes, DID = 0xF800 + InfoType
+pub const WWH_OBD_INFO_TYPE_DIDS: Did = 0xF800;
+
+/// Sub-functions of ReadDTCInformation (0x19) defined for WWH-OBD
+const REPORT_WWH_OBD_DTC_BY_MASK_RECORD: u8 = 0x42;
+const REPORT_WWH_OBD_DTC_WITH_PERMANENT_STATUS: u8 = 0x55;
+
+/// A DTC reported by reportWWHOBDDTCByMaskRecord with its severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WwhObdDtcRecord {
+    /// DTCSeverity, the upper 3 bits rate the severity, the lower 5 bits hold the
+    /// WWH-OBD DTC class
+    pub severity: u8,
+    /// The trouble code and its status
+    pub record: DtcRecord,
+}
+
+fn invalid_response(error: ObdError) -> UdsError {
+    match error {
+        ObdError::InvalidResponse(message) => UdsError::InvalidResponse(message),
+        _ => UdsError::InvalidResponse("PID data could not be decoded"),
+    }
+}
+
+impl<T: UdsTransport> UdsClient<T> {
+    /// ReadDataByIdentifier (0x22) of DID 0xF400 + `pid`, decoded like OBD Mode 01
+    pub fn read_wwh_obd_pid(&mut self, pid: u8) -> Result<PidValue, UdsError> {
+        let data = self.read_data_by_identifier(WWH_OBD_PID_DIDS | Did::from(pid))?;
+        decode_pid(pid, &data).map_err(invalid_response)
+    }
+
+    /// ReadDataByIdentifier (0x22) of DID 0xF600 + `mid`, the test results of an on-board
+    /// monitor decoded like OBD Mode 06
+    pub fn read_wwh_obd_test_results(&mut self, mid: u8) -> Result<Vec<TestResult>, UdsError> {
+        let data = self.read_data_by_identifier(WWH_OBD_MONITOR_DIDS | Did::from(mid))?;
+        // 8 byte test records without the OBDMID, which is part of the DID
+        let records = data.chunks_exact(8);
+        if !records.remainder().is_empty() {
+            return Err(UdsError::InvalidResponse("truncated test record"));
+        }
+        Ok(records
+            .map(|record| {
+                let mut bytes = [mid; 9];
+                bytes[1..].copy_from_slice(record);
+                TestResult::from_bytes(bytes)
+            })
+            .collect())
+    }
+
+    /// ReadDataByIdentifier (0x22) of DID 0xF800 + `info_type`, the raw vehicle information
+    /// of OBD Mode 09
+    pub fn read_wwh_obd_info(&mut self, info_type: u8) -> Result<Vec<u8>, UdsError> {
+        self.read_data_by_identifier(WWH_OBD_INFO_TYPE_DIDS | Did::from(info_type))
+    }
+
+    /// ReadDataByIdentifier (0x22) of DID 0xF802, the vehicle identification number
+    pub fn read_wwh_obd_vin(&mut self) -> Result<String, UdsError> {
+        let vin = self.read_wwh_obd_info(INFO_TYPE_VIN)?;
+        Ok(String::from_utf8_lossy(&vin)
+            .trim_matches(|c: char| c == '\0' || c.is_whitespace())
+            .to_string())
+    }
+
+    /// reportWWHOBDDTCByMaskRecord (0x19 0x42) of the emission related systems, returning
+    /// the status availability mask and the DTCs matching `status_mask` and
+    /// `severity_mask`
+    pub fn read_wwh_obd_dtcs(
+        &mut self,
+        status_mask: DtcStatus,
+        severity_mask: u8,
+    ) -> Result<(DtcStatus, Vec<WwhObdDtcRecord>), UdsError> {
+        let response = self.read_dtc_information(
+            REPORT_WWH_OBD_DTC_BY_MASK_RECORD,
+            &[WWH_OBD_EMISSIONS_GROUP, status_mask.bits(), severity_mask],
+        )?;
+        let [group, availability, _severity_availability, _format, ref records @ ..] = *response
+        else {
+            return Err(UdsError::InvalidResponse(
+                "missing DTC status and severity availability masks",
+            ));
+        };
+        if group != WWH_OBD_EMISSIONS_GROUP {
+            return Err(UdsError::InvalidResponse(
+                "response does not echo the functional group",
+            ));
+        }
+        let records = records.chunks_exact(5);
+        if !records.remainder().is_empty() {
+            return Err(UdsError::InvalidResponse("truncated DTC record"));
+        }
+        Ok((
+            DtcStatus::from_bits_retain(availability),
+            records
+                .map(|record| WwhObdDtcRecord {
+                    severity: record[0],
+                    record: DtcRecord {
+                        dtc: Dtc::from_bytes([record[1], record[2], record[3]]),
+                        status: DtcStatus::from_bits_retain(record[4]),
+                    },
+                })
+                .collect(),
+        ))
+    }
+
+    /// reportWWHOBDDTCWithPermanentStatus (0x19 0x55), returning the status availability
+    /// mask and the permanent DTCs of the emission related systems
+    pub fn read_wwh_obd_permanent_dtcs(&mut self) -> Result<(DtcStatus, Vec<DtcRecord>), UdsError> {
+        let response = self.read_dtc_information(
+            REPORT_WWH_OBD_DTC_WITH_PERMANENT_STATUS,
+            &[WWH_OBD_EMISSIONS_GROUP],
+        )?;
+        match *response {
+            [group, availability, _format, ref records @ ..]
+                if group == WWH_OBD_EMISSIONS_GROUP =>
+            {
+                Ok((
+                    DtcStatus::from_bits_retain(availability),
+                    parse_dtc_records(records)?,
+                ))
+            }
+            _ => Err(UdsError::InvalidResponse(
+                "response does not echo the functional group",
+            )),
+        }
+    }
+}
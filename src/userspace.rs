@@ -0,0 +1,1041 @@
+//! ISO-TP implemented in userspace on top of a `CAN_RAW` socket.
+//!
+//! For systems without the can-isotp kernel module, e.g. old kernels, locked-down
+//! containers or Android. [`UserspaceIsoTpSocket`] runs the single, first, consecutive and
+//! flow control frame state machines itself, paces consecutive frames by the STmin and
+//! block size of the receiver and honours wait frames. It accepts the same option structs
+//! as [`IsoTpSocket`], except for listen mode and broadcast transmission.
+//!
+//! Unlike the kernel the stack runs in the calling thread: a PDU is sent and received
+//! within [`write`](UserspaceIsoTpSocket::write) and [`read`](UserspaceIsoTpSocket::read),
+//! frames arriving in between are not processed. The CAN frames are exchanged through a
+//! [`CanBackend`], by default a [`RawCanSocket`], or a [`SlcanPort`] for serial-line CAN
+//! adapters.
+//!
+//! [`IsoTpSocketBuilder::open_with_fallback`] picks the kernel module where available and
+//! this stack otherwise.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::userspace::UserspaceIsoTpSocket;
+//! use socketcan_isotp::StandardId;
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     let mut tp_socket = UserspaceIsoTpSocket::open(
+//!         "vcan0",
+//!         StandardId::new(0x7E8).expect("Invalid rx id"),
+//!         StandardId::new(0x7E0).expect("Invalid tx id"),
+//!     )?;
+//!     tp_socket.write(&[0x22, 0xF1, 0x90])?;
+//!     println!("{:02X?}", tp_socket.read()?);
+//!     Ok(())
+//! }
+//! ```
+
+mod raw;
+mod slcan;
+
+pub use raw::{RawCanSocket, CAN_RAW_FD_FRAMES};
+pub use slcan::{SlcanBitrate, SlcanDataBitrate, SlcanPort, DEFAULT_BAUD_RATE};
+
+use crate::clock::{Clock, SystemClock};
+use crate::codec::{self, CodecError, FlowStatus, Frame};
+use crate::defaults::CAN_ISOTP_DEFAULT_PAD_CONTENT;
+use crate::{
+    interface_index, interface_mtu, Error, FlowControlOptions, FrameTxTime, Id, IsoTpBehaviour,
+    IsoTpOptions, IsoTpSocket, IsoTpSocketBuilder, LinkLayerOptions, Mtu, NetworkTimer,
+    SeparationTime, CANFD_MTU,
+};
+use libc::c_int;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default time to wait for a flow control (N_Bs) or consecutive frame (N_Cr), the
+/// timeout of the kernel
+pub const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default number of consecutive wait frames (N_WFTmax) accepted before a transmission is
+/// aborted
+pub const DEFAULT_MAX_WAIT_FRAMES: u32 = 10;
+
+/// Largest PDU accepted, longer PDUs are rejected with a flow control overflow like the
+/// kernel does with its default `max_pdu_size`
+pub const MAX_PDU_LEN: usize = 8300;
+
+/// A classic CAN or CAN FD data frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CanFrame {
+    id: Id,
+    data: [u8; 64],
+    len: u8,
+    fd: bool,
+    bit_rate_switch: bool,
+}
+
+impl CanFrame {
+    /// Classic CAN frame, fails with [`Error::InvalidInput`] for more than 8 bytes
+    pub fn new(id: impl Into<Id>, data: &[u8]) -> Result<Self, Error> {
+        if data.len() > 8 {
+            return Err(Error::InvalidInput("CAN frame exceeds 8 bytes"));
+        }
+        Ok(Self::from_parts(id.into(), data, false, false))
+    }
+
+    /// CAN FD frame, fails with [`Error::InvalidInput`] unless `data` has a valid CAN FD
+    /// length, i.e. 0 - 8, 12, 16, 20, 24, 32, 48 or 64 bytes
+    pub fn new_fd(id: impl Into<Id>, data: &[u8], bit_rate_switch: bool) -> Result<Self, Error> {
+        if data.len() > 64 || codec::fd_frame_len(data.len()) != data.len() {
+            return Err(Error::InvalidInput("invalid CAN FD frame length"));
+        }
+        Ok(Self::from_parts(id.into(), data, true, bit_rate_switch))
+    }
+
+    fn from_parts(id: Id, data: &[u8], fd: bool, bit_rate_switch: bool) -> Self {
+        let mut frame = Self {
+            id,
+            data: [0; 64],
+            len: data.len() as u8,
+            fd,
+            bit_rate_switch,
+        };
+        frame.data[..data.len()].copy_from_slice(data);
+        frame
+    }
+
+    /// get the CAN identifier
+    pub fn get_id(&self) -> Id {
+        self.id
+    }
+
+    /// get the payload
+    pub fn get_data(&self) -> &[u8] {
+        &self.data[..usize::from(self.len)]
+    }
+
+    /// get whether this is a CAN FD frame
+    pub fn is_fd(&self) -> bool {
+        self.fd
+    }
+
+    /// get whether the CAN FD data phase switches to the data bit rate
+    pub fn get_bit_rate_switch(&self) -> bool {
+        self.bit_rate_switch
+    }
+}
+
+/// Transmits and receives the CAN frames of a [`UserspaceIsoTpSocket`]
+pub trait CanBackend {
+    /// Transmit a frame
+    fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error>;
+
+    /// Wait up to `timeout`, forever if `None`, for the next frame, `None` if none arrived
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error>;
+}
+
+impl<B: CanBackend + ?Sized> CanBackend for &mut B {
+    fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error> {
+        (**self).send_frame(frame)
+    }
+
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+        (**self).receive_frame(timeout)
+    }
+}
+
+impl<B: CanBackend + ?Sized> CanBackend for Box<B> {
+    fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error> {
+        (**self).send_frame(frame)
+    }
+
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+        (**self).receive_frame(timeout)
+    }
+}
+
+/// Total length and next sequence number of a multi-frame PDU being received
+struct Reception {
+    len: usize,
+    sequence_number: u8,
+    block: u8,
+}
+
+/// An ISO-TP connection run in userspace, see the [module documentation](self)
+pub struct UserspaceIsoTpSocket<B = RawCanSocket> {
+    backend: B,
+    rx_id: Id,
+    tx_id: Id,
+    isotp_options: IsoTpOptions,
+    rx_flow_control_options: FlowControlOptions,
+    link_layer_options: LinkLayerOptions,
+    /// time to wait for a flow control frame
+    bs_timeout: Duration,
+    /// time to wait for a consecutive frame
+    cr_timeout: Duration,
+    /// wait frames accepted in a row
+    max_wait_frames: u32,
+    clock: Arc<dyn Clock>,
+    recv_buffer: Vec<u8>,
+}
+
+impl UserspaceIsoTpSocket {
+    /// Open a named CAN device.
+    pub fn open(ifname: &str, rx_id: impl Into<Id>, tx_id: impl Into<Id>) -> Result<Self, Error> {
+        Self::open_with_opts(ifname, rx_id, tx_id, None, None, None)
+    }
+
+    /// Open a named CAN device, passing additional options.
+    ///
+    /// Fails with [`Error::InvalidLinkLayerOptions`] if CAN FD is requested on a classic
+    /// CAN interface.
+    pub fn open_with_opts(
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        isotp_options: Option<IsoTpOptions>,
+        rx_flow_control_options: Option<FlowControlOptions>,
+        link_layer_options: Option<LinkLayerOptions>,
+    ) -> Result<Self, Error> {
+        let if_index = interface_index(ifname)?;
+        let rx_id = rx_id.into();
+        let link_layer_options = link_layer_options.unwrap_or_default();
+        let socket = RawCanSocket::open_if(if_index, rx_id)?;
+        enable_fd_frames(&socket, if_index, &link_layer_options)?;
+        Self::with_backend(
+            socket,
+            rx_id,
+            tx_id,
+            isotp_options,
+            rx_flow_control_options,
+            Some(link_layer_options),
+        )
+    }
+}
+
+/// When [`IsoTpSocketBuilder::open_with_fallback`] resorts to the userspace stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Fallback {
+    /// Always use the kernel module, fail if it is missing
+    Never,
+    /// Use the userspace stack if the kernel lacks ISO-TP support
+    /// ([`Error::ProtocolNotSupported`])
+    #[default]
+    IfUnsupported,
+    /// Always use the userspace stack
+    Always,
+}
+
+/// A kernel [`IsoTpSocket`] or the userspace stack replacing it, opened by
+/// [`IsoTpSocketBuilder::open_with_fallback`]
+///
+/// Implements [`IsoTpTransport`](crate::transport::IsoTpTransport), so code written
+/// against the trait doesn't care which one it got.
+pub enum FallbackIsoTpSocket {
+    /// The can-isotp kernel module, boxed for its receive buffer
+    Kernel(Box<IsoTpSocket>),
+    /// The userspace stack on a `CAN_RAW` socket
+    Userspace(UserspaceIsoTpSocket),
+}
+
+impl FallbackIsoTpSocket {
+    /// Whether the socket runs the userspace stack
+    pub fn is_userspace(&self) -> bool {
+        matches!(self, FallbackIsoTpSocket::Userspace(_))
+    }
+
+    /// Send a PDU.
+    pub fn write(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        match self {
+            FallbackIsoTpSocket::Kernel(socket) => Ok(socket.write(pdu)?),
+            FallbackIsoTpSocket::Userspace(socket) => socket.write(pdu),
+        }
+    }
+
+    /// Blocking read a PDU.
+    pub fn read(&mut self) -> Result<&[u8], Error> {
+        match self {
+            FallbackIsoTpSocket::Kernel(socket) => Ok(socket.read()?),
+            FallbackIsoTpSocket::Userspace(socket) => socket.read(),
+        }
+    }
+}
+
+impl IsoTpSocketBuilder {
+    /// Open a named CAN ISO-TP device, falling back to the userspace stack as configured
+    /// by `fallback`.
+    ///
+    /// Lets one binary run on systems with and without the can-isotp kernel module. The
+    /// userspace stack is opened with the same options, except for `rx_stmin`, which it
+    /// doesn't enforce. Listen mode and broadcast transmission fail with
+    /// [`Error::InvalidIsoTpOptions`] once the userspace stack is used.
+    ///
+    /// ```rust,no_run
+    /// use socketcan_isotp::userspace::Fallback;
+    /// use socketcan_isotp::{IsoTpSocketBuilder, StandardId};
+    ///
+    /// fn main() -> Result<(), socketcan_isotp::Error> {
+    ///     let mut tp_socket = IsoTpSocketBuilder::new().open_with_fallback(
+    ///         "vcan0",
+    ///         StandardId::new(0x7E8).expect("Invalid rx id"),
+    ///         StandardId::new(0x7E0).expect("Invalid tx id"),
+    ///         Fallback::IfUnsupported,
+    ///     )?;
+    ///     println!("userspace stack: {}", tp_socket.is_userspace());
+    ///     tp_socket.write(&[0x3E, 0x00])?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn open_with_fallback(
+        self,
+        ifname: &str,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        fallback: Fallback,
+    ) -> Result<FallbackIsoTpSocket, Error> {
+        let if_index = interface_index(ifname)?;
+        self.open_if_with_fallback(if_index, rx_id, tx_id, fallback)
+    }
+
+    /// Open CAN ISO-TP device by interface number, falling back to the userspace stack
+    /// as configured by `fallback`.
+    ///
+    /// See [`IsoTpSocketBuilder::open_with_fallback`].
+    pub fn open_if_with_fallback(
+        self,
+        if_index: c_int,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        fallback: Fallback,
+    ) -> Result<FallbackIsoTpSocket, Error> {
+        let rx_id = rx_id.into();
+        let tx_id = tx_id.into();
+        match fallback {
+            Fallback::Never => {
+                return self
+                    .open_if(if_index, rx_id, tx_id)
+                    .map(|socket| FallbackIsoTpSocket::Kernel(Box::new(socket)))
+            }
+            Fallback::IfUnsupported => match self.clone().open_if(if_index, rx_id, tx_id) {
+                Err(Error::ProtocolNotSupported { .. }) => {}
+                result => {
+                    return result.map(|socket| FallbackIsoTpSocket::Kernel(Box::new(socket)))
+                }
+            },
+            Fallback::Always => {}
+        }
+
+        if if_index <= 0 {
+            return Err(Error::InvalidInput("interface index must be positive"));
+        }
+        if rx_id == tx_id && !self.allow_identical_ids {
+            return Err(Error::InvalidInput(
+                "rx_id and tx_id must differ, see IsoTpSocketBuilder::allow_identical_ids",
+            ));
+        }
+        let socket = RawCanSocket::open_if(if_index, rx_id)?;
+        let link_layer_options = self
+            .link_layer_options_for(socket.as_raw_fd(), if_index)?
+            .unwrap_or_default();
+        enable_fd_frames(&socket, if_index, &link_layer_options)?;
+        UserspaceIsoTpSocket::with_backend(
+            socket,
+            rx_id,
+            tx_id,
+            self.isotp_options_with_flags(),
+            self.rx_flow_control_options,
+            Some(link_layer_options),
+        )
+        .map(FallbackIsoTpSocket::Userspace)
+    }
+}
+
+impl<B: CanBackend> UserspaceIsoTpSocket<B> {
+    /// Run ISO-TP over `backend`, receiving frames with `rx_id` and transmitting on `tx_id`.
+    ///
+    /// Fails with [`Error::InvalidIsoTpOptions`] for listen mode and broadcast
+    /// transmission, which the userspace stack doesn't support.
+    pub fn with_backend(
+        backend: B,
+        rx_id: impl Into<Id>,
+        tx_id: impl Into<Id>,
+        isotp_options: Option<IsoTpOptions>,
+        rx_flow_control_options: Option<FlowControlOptions>,
+        link_layer_options: Option<LinkLayerOptions>,
+    ) -> Result<Self, Error> {
+        let isotp_options = isotp_options.unwrap_or_default();
+        let link_layer_options = link_layer_options.unwrap_or_default();
+        isotp_options.validate()?;
+        link_layer_options.validate()?;
+        if isotp_options.get_flags().is_some_and(|flags| {
+            flags.intersects(
+                IsoTpBehaviour::CAN_ISOTP_LISTEN_MODE
+                    | IsoTpBehaviour::CAN_ISOTP_SF_BROADCAST
+                    | IsoTpBehaviour::CAN_ISOTP_CF_BROADCAST,
+            )
+        }) {
+            return Err(Error::InvalidIsoTpOptions(
+                "listen mode and broadcast are not supported by the userspace stack",
+            ));
+        }
+
+        Ok(Self {
+            backend,
+            rx_id: rx_id.into(),
+            tx_id: tx_id.into(),
+            isotp_options,
+            rx_flow_control_options: rx_flow_control_options.unwrap_or_default(),
+            link_layer_options,
+            bs_timeout: DEFAULT_NETWORK_TIMEOUT,
+            cr_timeout: DEFAULT_NETWORK_TIMEOUT,
+            max_wait_frames: DEFAULT_MAX_WAIT_FRAMES,
+            clock: Arc::new(SystemClock),
+            recv_buffer: Vec::new(),
+        })
+    }
+
+    /// Time to wait for a flow control frame (N_Bs) or consecutive frame (N_Cr) before
+    /// aborting the transfer, [`DEFAULT_NETWORK_TIMEOUT`] by default
+    pub fn set_network_timeout(&mut self, timer: NetworkTimer, timeout: Duration) {
+        match timer {
+            NetworkTimer::Bs => self.bs_timeout = timeout,
+            NetworkTimer::Cr => self.cr_timeout = timeout,
+        }
+    }
+
+    /// get the time to wait for a flow control or consecutive frame
+    pub fn get_network_timeout(&self, timer: NetworkTimer) -> Duration {
+        match timer {
+            NetworkTimer::Bs => self.bs_timeout,
+            NetworkTimer::Cr => self.cr_timeout,
+        }
+    }
+
+    /// Number of wait frames (N_WFTmax) the receiver may send in a row before the
+    /// transmission fails with [`Error::TransferAborted`], [`DEFAULT_MAX_WAIT_FRAMES`] by
+    /// default
+    pub fn set_max_wait_frames(&mut self, max_wait_frames: u32) {
+        self.max_wait_frames = max_wait_frames;
+    }
+
+    /// get the number of wait frames accepted in a row
+    pub fn get_max_wait_frames(&self) -> u32 {
+        self.max_wait_frames
+    }
+
+    /// Replace the clock timing separation times and timeouts
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// get the clock timing separation times and timeouts
+    pub fn get_clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// get the ISO-TP options
+    pub fn get_isotp_options(&self) -> IsoTpOptions {
+        self.isotp_options
+    }
+
+    /// get the flow control options sent to the transmitter of a PDU
+    pub fn get_flow_control_options(&self) -> FlowControlOptions {
+        self.rx_flow_control_options
+    }
+
+    /// get the link layer options
+    pub fn get_link_layer_options(&self) -> LinkLayerOptions {
+        self.link_layer_options
+    }
+
+    /// Gets a reference to the underlying backend
+    pub fn get_ref(&self) -> &B {
+        &self.backend
+    }
+
+    /// Gets a mutable reference to the underlying backend
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Unwraps the underlying backend
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    /// Send a PDU, blocking until its last frame is transmitted.
+    ///
+    /// Fails with [`Error::TransferAborted`] if the receiver sends no flow control frame
+    /// in time or too many wait frames and with [`Error::Overflow`] if it rejects the PDU as
+    /// too long.
+    pub fn write(&mut self, pdu: &[u8]) -> Result<(), Error> {
+        if pdu.is_empty() {
+            return Err(Error::InvalidInput("empty PDU"));
+        }
+        if pdu.len() > MAX_PDU_LEN {
+            return Err(protocol_error(libc::EMSGSIZE));
+        }
+
+        let ext_address = self.tx_ext_address();
+        let tx_dl = usize::from(self.link_layer_options.get_tx_dl().as_u8());
+        let mut buf = [0; 64];
+        if pdu.len() <= codec::max_single_frame_len(tx_dl, ext_address.is_some()) {
+            let len = codec::encode_single(&mut buf, ext_address, pdu).map_err(encode_error)?;
+            return self.transmit(&mut buf, len);
+        }
+
+        let mut offset =
+            codec::encode_first(&mut buf[..tx_dl], ext_address, pdu).map_err(encode_error)?;
+        self.transmit(&mut buf, tx_dl)?;
+        let frame_txtime = match self.isotp_options.get_frame_txtime() {
+            FrameTxTime::Duration(frame_txtime) => frame_txtime,
+            FrameTxTime::Off | FrameTxTime::Zero => Duration::ZERO,
+        };
+        let mut sequence_number = 1;
+        while offset < pdu.len() {
+            let (block_size, stmin) = self.wait_flow_control()?;
+            let mut sent = 0;
+            while offset < pdu.len() && (block_size == 0 || sent < usize::from(block_size)) {
+                if sent > 0 {
+                    self.clock.sleep(frame_txtime + stmin);
+                }
+                let (len, consumed) = codec::encode_consecutive(
+                    &mut buf[..tx_dl],
+                    ext_address,
+                    sequence_number,
+                    &pdu[offset..],
+                )
+                .map_err(encode_error)?;
+                self.transmit(&mut buf, len)?;
+                offset += consumed;
+                sequence_number = (sequence_number + 1) & 0x0F;
+                sent += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Blocking read of the next PDU.
+    ///
+    /// Fails with [`Error::Timeout`] if a consecutive frame is missing and with
+    /// [`Error::SequenceError`] if one arrives out of order.
+    pub fn read(&mut self) -> Result<&[u8], Error> {
+        self.receive_pdu(None)?;
+        Ok(&self.recv_buffer)
+    }
+
+    /// Wait up to `timeout` for the first frame of a PDU and read it, `None` if no PDU
+    /// started in time
+    pub fn read_timeout(&mut self, timeout: Duration) -> Result<Option<&[u8]>, Error> {
+        if self.receive_pdu(Some(timeout))? {
+            Ok(Some(&self.recv_buffer))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Receive a PDU into `recv_buffer`, `false` if none started within `timeout`
+    fn receive_pdu(&mut self, timeout: Option<Duration>) -> Result<bool, Error> {
+        let deadline = timeout.map(|timeout| self.clock.now() + timeout);
+        let ext_len = self.ext_len();
+        let mut reception: Option<Reception> = None;
+        loop {
+            let wait = match reception {
+                Some(_) => Some(self.cr_timeout),
+                None => {
+                    deadline.map(|deadline| deadline.saturating_duration_since(self.clock.now()))
+                }
+            };
+            let Some(frame) = self.receive_frame(wait)? else {
+                return match reception {
+                    Some(_) => Err(protocol_error(libc::ETIMEDOUT)),
+                    None => Ok(false),
+                };
+            };
+
+            let payload = &frame.get_data()[ext_len..];
+            match codec::decode(payload).ok() {
+                // a new PDU replaces one still being received
+                Some(Frame::Single { data }) => {
+                    let header_len = if payload[0] & 0x0F == 0 { 2 } else { 1 };
+                    self.check_padding(&frame, ext_len + header_len + data.len())?;
+                    self.recv_buffer.clear();
+                    self.recv_buffer.extend_from_slice(data);
+                    return Ok(true);
+                }
+                Some(Frame::First { len, data }) => {
+                    let len = len as usize;
+                    if len <= data.len() {
+                        continue;
+                    }
+                    if len > MAX_PDU_LEN {
+                        self.send_flow_control(FlowStatus::Overflow)?;
+                        reception = None;
+                        continue;
+                    }
+                    self.recv_buffer.clear();
+                    self.recv_buffer.extend_from_slice(data);
+                    self.send_flow_control(FlowStatus::ContinueToSend)?;
+                    reception = Some(Reception {
+                        len,
+                        sequence_number: 1,
+                        block: 0,
+                    });
+                }
+                Some(Frame::Consecutive {
+                    sequence_number,
+                    data,
+                }) => {
+                    let Some(current) = reception.as_mut() else {
+                        continue;
+                    };
+                    if sequence_number != current.sequence_number {
+                        return Err(protocol_error(libc::EILSEQ));
+                    }
+                    let consumed = data.len().min(current.len - self.recv_buffer.len());
+                    self.recv_buffer.extend_from_slice(&data[..consumed]);
+                    if self.recv_buffer.len() == current.len {
+                        self.check_padding(&frame, ext_len + 1 + consumed)?;
+                        return Ok(true);
+                    }
+                    current.sequence_number = (current.sequence_number + 1) & 0x0F;
+                    let block_size = self.rx_flow_control_options.get_bs();
+                    if block_size != 0 {
+                        current.block += 1;
+                        if current.block == block_size {
+                            current.block = 0;
+                            self.send_flow_control(FlowStatus::ContinueToSend)?;
+                        }
+                    }
+                }
+                // flow control frames belong to a transmission, malformed frames are dropped
+                Some(Frame::FlowControl { .. }) | None => {}
+            }
+        }
+    }
+
+    /// Wait for a flow control frame permitting to send, returning block size and STmin
+    fn wait_flow_control(&mut self) -> Result<(u8, Duration), Error> {
+        let ext_len = self.ext_len();
+        let mut wait_frames = 0;
+        loop {
+            let Some(frame) = self.receive_frame(Some(self.bs_timeout))? else {
+                return Err(protocol_error(libc::ECOMM));
+            };
+            if let Ok(Frame::FlowControl {
+                status,
+                block_size,
+                stmin,
+            }) = codec::decode(&frame.get_data()[ext_len..])
+            {
+                self.check_padding(&frame, ext_len + 3)?;
+                match status {
+                    FlowStatus::ContinueToSend => {
+                        let stmin = SeparationTime::from_raw(stmin).unwrap_or(SeparationTime::MAX);
+                        return Ok((block_size, stmin.as_duration()));
+                    }
+                    // wait frames restart N_Bs, up to N_WFTmax in a row
+                    FlowStatus::Wait => {
+                        wait_frames += 1;
+                        if wait_frames > self.max_wait_frames {
+                            return Err(protocol_error(libc::ECOMM));
+                        }
+                    }
+                    FlowStatus::Overflow => return Err(protocol_error(libc::EMSGSIZE)),
+                }
+            }
+        }
+    }
+
+    /// Receive the next frame addressed to this connection within `timeout`
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+        let deadline = timeout.map(|timeout| self.clock.now() + timeout);
+        let rx_ext_address = self
+            .isotp_options
+            .get_ext_addressing()
+            .map(|ext_address| ext_address.get_rx());
+        loop {
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(self.clock.now()));
+            let Some(frame) = self.backend.receive_frame(remaining)? else {
+                return Ok(None);
+            };
+            if frame.get_id() != self.rx_id {
+                continue;
+            }
+            match rx_ext_address {
+                Some(rx_ext_address) if frame.get_data().first() != Some(&rx_ext_address) => {}
+                _ => return Ok(Some(frame)),
+            }
+        }
+    }
+
+    /// Check the padding of the last frame of a PDU with `used` significant bytes
+    fn check_padding(&self, frame: &CanFrame, used: usize) -> Result<(), Error> {
+        let flags = self
+            .isotp_options
+            .get_flags()
+            .unwrap_or(IsoTpBehaviour::empty());
+        if !flags.contains(IsoTpBehaviour::CAN_ISOTP_RX_PADDING) {
+            return Ok(());
+        }
+        let data = frame.get_data();
+        let padded_len = if frame.is_fd() {
+            codec::fd_frame_len(used).max(8)
+        } else {
+            8
+        };
+        if flags.contains(IsoTpBehaviour::CAN_ISOTP_CHK_PAD_LEN) && data.len() != padded_len {
+            return Err(protocol_error(libc::EBADMSG));
+        }
+        let rxpad_content = self.isotp_options.get_rxpad_content();
+        if flags.contains(IsoTpBehaviour::CAN_ISOTP_CHK_PAD_DATA)
+            && data.iter().skip(used).any(|&byte| byte != rxpad_content)
+        {
+            return Err(protocol_error(libc::EBADMSG));
+        }
+        Ok(())
+    }
+
+    /// Send a flow control frame with the configured block size and STmin
+    fn send_flow_control(&mut self, status: FlowStatus) -> Result<(), Error> {
+        let mut buf = [0; 64];
+        let len = codec::encode_flow_control(
+            &mut buf,
+            self.tx_ext_address(),
+            status,
+            self.rx_flow_control_options.get_bs(),
+            self.rx_flow_control_options.get_stmin().as_raw(),
+        )
+        .map_err(encode_error)?;
+        self.transmit(&mut buf, len)
+    }
+
+    /// Pad and send the first `len` bytes of `buf`
+    fn transmit(&mut self, buf: &mut [u8; 64], len: usize) -> Result<(), Error> {
+        let tx_padding = self
+            .isotp_options
+            .get_flags()
+            .is_some_and(|flags| flags.contains(IsoTpBehaviour::CAN_ISOTP_TX_PADDING));
+        let fd = self.link_layer_options.get_mtu() == Mtu::Fd;
+        let padded_len = codec::padded_len(len, fd, tx_padding);
+        buf[len..padded_len].fill(if tx_padding {
+            self.isotp_options.get_txpad_content()
+        } else {
+            CAN_ISOTP_DEFAULT_PAD_CONTENT
+        });
+
+        let frame = CanFrame::from_parts(
+            self.tx_id,
+            &buf[..padded_len],
+            fd,
+            self.link_layer_options.get_bit_rate_switch(),
+        );
+        self.backend.send_frame(&frame)
+    }
+
+    /// Number of address extension bytes in front of each frame
+    fn ext_len(&self) -> usize {
+        usize::from(self.isotp_options.get_ext_addressing().is_some())
+    }
+
+    /// Address extension of transmitted frames
+    fn tx_ext_address(&self) -> Option<u8> {
+        self.isotp_options
+            .get_ext_addressing()
+            .map(|ext_address| ext_address.get_tx())
+    }
+}
+
+impl<B> std::fmt::Debug for UserspaceIsoTpSocket<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserspaceIsoTpSocket")
+            .field("rx_id", &self.rx_id)
+            .field("tx_id", &self.tx_id)
+            .field("isotp_options", &self.isotp_options)
+            .field("rx_flow_control_options", &self.rx_flow_control_options)
+            .field("link_layer_options", &self.link_layer_options)
+            .field("bs_timeout", &self.bs_timeout)
+            .field("cr_timeout", &self.cr_timeout)
+            .field("max_wait_frames", &self.max_wait_frames)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Let `socket` exchange CAN FD frames if `link_layer_options` ask for them
+fn enable_fd_frames(
+    socket: &RawCanSocket,
+    if_index: c_int,
+    link_layer_options: &LinkLayerOptions,
+) -> Result<(), Error> {
+    if link_layer_options.get_mtu() == Mtu::Fd {
+        if interface_mtu(socket.as_raw_fd(), if_index)? < c_int::from(CANFD_MTU) {
+            return Err(Error::InvalidLinkLayerOptions(
+                "CAN FD requested on a classic CAN interface",
+            ));
+        }
+        socket.set_fd_frames(true)?;
+    }
+    Ok(())
+}
+
+/// The encoders only fail for frame buffers too small, which the option validation rules out
+fn encode_error(error: CodecError) -> Error {
+    Error::from(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        error.to_string(),
+    ))
+}
+
+/// The error the kernel reports with `errno`
+fn protocol_error(errno: c_int) -> Error {
+    Error::from(io::Error::from_raw_os_error(errno))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addressing::ExtAddress;
+    use crate::clock::SimulatedClock;
+    use crate::{StandardId, TxDataLength, TxFlags};
+    use std::collections::VecDeque;
+
+    const TESTER_ID: u16 = 0x7E0;
+    const ECU_ID: u16 = 0x7E8;
+
+    /// Bus delivering scripted frames and recording the sent frames with their send time on
+    /// a simulated clock
+    struct ScriptedBus {
+        clock: SimulatedClock,
+        incoming: VecDeque<CanFrame>,
+        sent: Vec<(Duration, CanFrame)>,
+    }
+
+    impl CanBackend for ScriptedBus {
+        fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error> {
+            self.sent.push((self.clock.elapsed(), *frame));
+            Ok(())
+        }
+
+        fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+            if let Some(frame) = self.incoming.pop_front() {
+                return Ok(Some(frame));
+            }
+            self.clock
+                .sleep(timeout.expect("blocking forever on an empty bus"));
+            Ok(None)
+        }
+    }
+
+    fn id(raw: u16) -> Id {
+        Id::from(StandardId::new(raw).unwrap())
+    }
+
+    /// Tester socket on a bus delivering `incoming` frames sent by the ECU
+    fn tester(incoming: &[&[u8]]) -> (UserspaceIsoTpSocket<ScriptedBus>, SimulatedClock) {
+        let incoming = incoming
+            .iter()
+            .map(|data| CanFrame::new(id(ECU_ID), data).unwrap())
+            .collect();
+        tester_with(incoming, None, None, None)
+    }
+
+    /// Tester socket with options on a bus delivering `incoming` frames
+    fn tester_with(
+        incoming: VecDeque<CanFrame>,
+        isotp_options: Option<IsoTpOptions>,
+        rx_flow_control_options: Option<FlowControlOptions>,
+        link_layer_options: Option<LinkLayerOptions>,
+    ) -> (UserspaceIsoTpSocket<ScriptedBus>, SimulatedClock) {
+        let clock = SimulatedClock::new();
+        let bus = ScriptedBus {
+            clock: clock.clone(),
+            incoming,
+            sent: Vec::new(),
+        };
+        let mut socket = UserspaceIsoTpSocket::with_backend(
+            bus,
+            id(ECU_ID),
+            id(TESTER_ID),
+            isotp_options,
+            rx_flow_control_options,
+            link_layer_options,
+        )
+        .unwrap();
+        socket.set_clock(Arc::new(clock.clone()));
+        (socket, clock)
+    }
+
+    fn sent(socket: &UserspaceIsoTpSocket<ScriptedBus>) -> Vec<&[u8]> {
+        socket
+            .get_ref()
+            .sent
+            .iter()
+            .map(|(_, frame)| frame.get_data())
+            .collect()
+    }
+
+    fn pdu(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    #[test]
+    fn write_sends_single_frame() {
+        let (mut socket, _) = tester(&[]);
+        socket.write(&[0x3E, 0x00]).unwrap();
+        assert_eq!(sent(&socket), [&[0x02, 0x3E, 0x00][..]]);
+    }
+
+    #[test]
+    fn read_receives_single_frame() {
+        let (mut socket, _) = tester(&[&[0x03, 0x7F, 0x22, 0x31]]);
+        assert_eq!(socket.read().unwrap(), [0x7F, 0x22, 0x31]);
+        assert!(sent(&socket).is_empty());
+    }
+
+    #[test]
+    fn read_receives_first_and_consecutive_frames() {
+        let (mut socket, _) = tester(&[&[0x10, 0x0A, 0, 1, 2, 3, 4, 5], &[0x21, 6, 7, 8, 9]]);
+        assert_eq!(socket.read().unwrap(), pdu(10));
+        assert_eq!(sent(&socket), [&[0x30, 0x00, 0x00][..]]);
+    }
+
+    #[test]
+    fn read_sends_flow_control_after_each_block() {
+        let incoming = [
+            &[0x10, 0x14, 0, 1, 2, 3, 4, 5][..],
+            &[0x21, 6, 7, 8, 9, 10, 11, 12],
+            &[0x22, 13, 14, 15, 16, 17, 18, 19],
+        ]
+        .iter()
+        .map(|data| CanFrame::new(id(ECU_ID), data).unwrap())
+        .collect();
+        let flow_control = FlowControlOptions::new(1, SeparationTime::ZERO, 0);
+        let (mut socket, _) = tester_with(incoming, None, Some(flow_control), None);
+        assert_eq!(socket.read().unwrap(), pdu(20));
+        assert_eq!(
+            sent(&socket),
+            [&[0x30, 0x01, 0x00][..], &[0x30, 0x01, 0x00]]
+        );
+    }
+
+    #[test]
+    fn write_waits_for_flow_control_after_each_block() {
+        let (mut socket, _) = tester(&[&[0x30, 0x01, 0x00], &[0x30, 0x01, 0x00]]);
+        socket.write(&pdu(20)).unwrap();
+        assert_eq!(
+            sent(&socket),
+            [
+                &[0x10, 0x14, 0, 1, 2, 3, 4, 5][..],
+                &[0x21, 6, 7, 8, 9, 10, 11, 12],
+                &[0x22, 13, 14, 15, 16, 17, 18, 19],
+            ]
+        );
+        assert!(socket.get_ref().incoming.is_empty());
+    }
+
+    #[test]
+    fn write_continues_after_wait_frames() {
+        let (mut socket, _) = tester(&[&[0x31, 0x00, 0x00], &[0x31, 0x00, 0x00], &[0x30, 0, 0]]);
+        socket.write(&pdu(10)).unwrap();
+        assert_eq!(sent(&socket).len(), 2);
+    }
+
+    #[test]
+    fn write_aborts_after_max_wait_frames() {
+        let (mut socket, clock) = tester(&[&[0x31, 0x00, 0x00][..]; 3]);
+        socket.set_max_wait_frames(2);
+        let error = socket.write(&pdu(10)).unwrap_err();
+        assert!(matches!(error, Error::TransferAborted { .. }), "{error:?}");
+        assert_eq!(clock.elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn write_fails_on_overflow() {
+        let (mut socket, _) = tester(&[&[0x32, 0x00, 0x00]]);
+        let error = socket.write(&pdu(10)).unwrap_err();
+        assert!(matches!(error, Error::Overflow { .. }), "{error:?}");
+    }
+
+    #[test]
+    fn read_rejects_first_frame_beyond_max_pdu_len() {
+        // escaped FF_DL of 0x10000 bytes
+        let (mut socket, _) = tester(&[&[0x10, 0x00, 0x00, 0x01, 0x00, 0x00, 0, 1]]);
+        assert_eq!(
+            socket.read_timeout(Duration::from_millis(100)).unwrap(),
+            None
+        );
+        assert_eq!(sent(&socket), [&[0x32, 0x00, 0x00][..]]);
+    }
+
+    #[test]
+    fn read_fails_on_sequence_error() {
+        let (mut socket, _) = tester(&[&[0x10, 0x14, 0, 1, 2, 3, 4, 5], &[0x22, 6, 7, 8, 9]]);
+        let error = socket.read().unwrap_err();
+        assert!(matches!(error, Error::SequenceError { .. }), "{error:?}");
+    }
+
+    #[test]
+    fn canfd_single_frame_uses_escape_sequence() {
+        let link_layer = LinkLayerOptions::canfd(TxDataLength::Dl64, TxFlags::empty());
+        let mut frame = vec![0x00, 0x14];
+        frame.extend(pdu(20));
+        frame.resize(24, CAN_ISOTP_DEFAULT_PAD_CONTENT);
+        let incoming = [CanFrame::new_fd(id(ECU_ID), &frame, false).unwrap()].into();
+        let (mut socket, _) = tester_with(incoming, None, None, Some(link_layer));
+        assert_eq!(socket.read().unwrap(), pdu(20));
+
+        socket.write(&pdu(20)).unwrap();
+        assert_eq!(sent(&socket), [&frame[..]]);
+        assert!(socket.get_ref().sent[0].1.is_fd());
+    }
+
+    #[test]
+    fn extended_addressing_prefixes_and_filters_frames() {
+        let mut isotp_options = IsoTpOptions::default();
+        isotp_options.set_ext_addressing(ExtAddress::mixed(0x10, 0xF1));
+        let incoming = [&[0x20, 0x02, 0x7F, 0x3E][..], &[0xF1, 0x02, 0x7E, 0x00]]
+            .iter()
+            .map(|data| CanFrame::new(id(ECU_ID), data).unwrap())
+            .collect();
+        let (mut socket, _) = tester_with(incoming, Some(isotp_options), None, None);
+        socket.write(&[0x3E, 0x00]).unwrap();
+        assert_eq!(sent(&socket), [&[0x10, 0x02, 0x3E, 0x00][..]]);
+        // the frame for another address is ignored
+        assert_eq!(socket.read().unwrap(), [0x7E, 0x00]);
+    }
+
+    #[test]
+    fn write_aborts_after_n_bs_without_flow_control() {
+        let (mut socket, clock) = tester(&[]);
+        socket.set_network_timeout(NetworkTimer::Bs, Duration::from_millis(300));
+        let error = socket.write(&[0x55; 20]).unwrap_err();
+        assert!(matches!(error, Error::TransferAborted { .. }), "{error:?}");
+        assert_eq!(error.network_timer(), Some(NetworkTimer::Bs));
+        assert_eq!(clock.elapsed(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn write_paces_consecutive_frames_by_stmin() {
+        // continue to send, no block size limit, STmin 10ms
+        let (mut socket, _) = tester(&[&[0x30, 0x00, 0x0A]]);
+        socket.write(&[0x55; 27]).unwrap();
+        let sent = &socket.get_ref().sent;
+        let times: Vec<Duration> = sent.iter().map(|(at, _)| *at).collect();
+        assert_eq!(times, [0, 0, 10, 20].map(Duration::from_millis), "{sent:?}");
+        assert_eq!(sent[3].1.get_data()[0], 0x23);
+    }
+
+    #[test]
+    fn read_times_out_after_n_cr_without_consecutive_frame() {
+        let (mut socket, clock) = tester(&[&[0x10, 0x14, 0, 1, 2, 3, 4, 5]]);
+        socket.set_network_timeout(NetworkTimer::Cr, Duration::from_millis(200));
+        let error = socket.read().unwrap_err();
+        assert!(matches!(error, Error::Timeout { .. }), "{error:?}");
+        assert_eq!(error.network_timer(), Some(NetworkTimer::Cr));
+        assert_eq!(clock.elapsed(), Duration::from_millis(200));
+        assert_eq!(
+            socket.get_network_timeout(NetworkTimer::Bs),
+            DEFAULT_NETWORK_TIMEOUT
+        );
+    }
+}
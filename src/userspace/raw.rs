@@ -0,0 +1,221 @@
+use super::{CanBackend, CanFrame};
+use crate::error_monitor::{RawCanAddr, CAN_RAW, CAN_RAW_FILTER, SOL_CAN_RAW};
+use crate::{
+    id_from_raw, id_to_raw, interface_index, poll, Error, Id, TxFlags, CANFD_MTU, CAN_MTU,
+    EFF_FLAG, EFF_MASK, PF_CAN, RTR_FLAG, SFF_MASK,
+};
+use libc::{bind, c_int, c_void, read, setsockopt, sockaddr, socket, socklen_t, write, SOCK_RAW};
+use std::io;
+use std::mem::{size_of, size_of_val};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd};
+use std::time::Duration;
+
+/// allow CAN FD frames on a raw socket
+pub const CAN_RAW_FD_FRAMES: c_int = 5;
+
+/// `struct canfd_frame`, `struct can_frame` shares the layout of its first 16 bytes
+#[repr(C)]
+struct RawFrame {
+    can_id: u32,
+    len: u8,
+    flags: u8,
+    _res0: u8,
+    _res1: u8,
+    data: [u8; 64],
+}
+
+/// `struct can_filter`
+#[repr(C)]
+struct RawFilter {
+    can_id: u32,
+    can_mask: u32,
+}
+
+/// A raw CAN socket, the default [`CanBackend`] of the userspace stack
+#[derive(Debug)]
+pub struct RawCanSocket {
+    fd: OwnedFd,
+}
+
+impl RawCanSocket {
+    /// Open a raw CAN socket on a named CAN device receiving only frames with `rx_id`.
+    pub fn open(ifname: &str, rx_id: impl Into<Id>) -> Result<Self, Error> {
+        Self::open_if(interface_index(ifname)?, rx_id)
+    }
+
+    /// Open a raw CAN socket on a CAN device by interface number receiving only frames
+    /// with `rx_id`.
+    pub fn open_if(if_index: c_int, rx_id: impl Into<Id>) -> Result<Self, Error> {
+        let sock_fd = unsafe { socket(PF_CAN, SOCK_RAW, CAN_RAW) };
+        if sock_fd == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+        let socket = Self {
+            fd: unsafe { OwnedFd::from_raw_fd(sock_fd) },
+        };
+        socket.set_filter(&[rx_id.into()])?;
+
+        let addr = RawCanAddr::new(if_index);
+        let bind_rv = unsafe {
+            bind(
+                sock_fd,
+                &addr as *const _ as *const sockaddr,
+                size_of::<RawCanAddr>() as socklen_t,
+            )
+        };
+        if bind_rv == -1 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+
+        Ok(socket)
+    }
+
+    /// Receive only data frames with one of `ids`, remote and error frames are dropped
+    pub fn set_filter(&self, ids: &[Id]) -> io::Result<()> {
+        let filters: Vec<RawFilter> = ids
+            .iter()
+            .map(|&id| RawFilter {
+                can_id: id_to_raw(id),
+                can_mask: match id {
+                    Id::Standard(_) => EFF_FLAG | RTR_FLAG | SFF_MASK,
+                    Id::Extended(_) => EFF_FLAG | RTR_FLAG | EFF_MASK,
+                },
+            })
+            .collect();
+        let rv = unsafe {
+            setsockopt(
+                self.fd.as_raw_fd(),
+                SOL_CAN_RAW,
+                CAN_RAW_FILTER,
+                filters.as_ptr() as *const c_void,
+                size_of_val(filters.as_slice()) as socklen_t,
+            )
+        };
+        if rv == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Send and receive CAN FD frames in addition to classic CAN frames
+    pub fn set_fd_frames(&self, enable: bool) -> io::Result<()> {
+        let enable = c_int::from(enable);
+        let rv = unsafe {
+            setsockopt(
+                self.fd.as_raw_fd(),
+                SOL_CAN_RAW,
+                CAN_RAW_FD_FRAMES,
+                &enable as *const _ as *const c_void,
+                size_of::<c_int>() as socklen_t,
+            )
+        };
+        if rv == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl CanBackend for RawCanSocket {
+    fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error> {
+        let data = frame.get_data();
+        let mut raw = RawFrame {
+            can_id: id_to_raw(frame.get_id()),
+            len: data.len() as u8,
+            flags: 0,
+            _res0: 0,
+            _res1: 0,
+            data: [0; 64],
+        };
+        raw.data[..data.len()].copy_from_slice(data);
+        let size = if frame.is_fd() {
+            if frame.get_bit_rate_switch() {
+                raw.flags = TxFlags::CANFD_BRS.bits();
+            }
+            CANFD_MTU
+        } else {
+            CAN_MTU
+        };
+
+        let write_rv = unsafe {
+            write(
+                self.fd.as_raw_fd(),
+                &raw as *const _ as *const c_void,
+                usize::from(size),
+            )
+        };
+        if write_rv < 0 {
+            return Err(Error::from(io::Error::last_os_error()));
+        }
+        if write_rv as usize != usize::from(size) {
+            return Err(Error::from(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "CAN frame written partially",
+            )));
+        }
+        Ok(())
+    }
+
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+        loop {
+            if !poll::wait_readable(self.fd.as_raw_fd(), timeout)? {
+                return Ok(None);
+            }
+
+            let mut raw = RawFrame {
+                can_id: 0,
+                len: 0,
+                flags: 0,
+                _res0: 0,
+                _res1: 0,
+                data: [0; 64],
+            };
+            let read_rv = unsafe {
+                read(
+                    self.fd.as_raw_fd(),
+                    &mut raw as *mut _ as *mut c_void,
+                    size_of::<RawFrame>(),
+                )
+            };
+            if read_rv < 0 {
+                return Err(Error::from(io::Error::last_os_error()));
+            }
+
+            let fd = match u8::try_from(read_rv) {
+                Ok(CAN_MTU) => false,
+                Ok(CANFD_MTU) => true,
+                _ => {
+                    return Err(Error::from(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "unexpected CAN frame size",
+                    )))
+                }
+            };
+            // remote and error frames pass no filter, skip them anyway
+            let Some(id) = id_from_raw(raw.can_id) else {
+                continue;
+            };
+            let len = usize::from(raw.len).min(if fd { 64 } else { 8 });
+            return Ok(Some(CanFrame {
+                id,
+                data: raw.data,
+                len: len as u8,
+                fd,
+                bit_rate_switch: fd
+                    && TxFlags::from_bits_retain(raw.flags).contains(TxFlags::CANFD_BRS),
+            }));
+        }
+    }
+}
+
+impl AsRawFd for RawCanSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for RawCanSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
@@ -0,0 +1,489 @@
+use super::{CanBackend, CanFrame};
+use crate::{id_to_raw, poll, Error, ExtendedId, Id, StandardId, EFF_MASK};
+use libc::{
+    cfmakeraw, cfsetispeed, cfsetospeed, speed_t, tcflush, tcgetattr, tcsetattr, termios, O_NOCTTY,
+    TCIFLUSH, TCSANOW,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Baud rate of the serial line used by [`SlcanPort::open`], USB adapters ignore it
+pub const DEFAULT_BAUD_RATE: u32 = 115_200;
+
+/// Longest line of a received frame, `B` + 8 id digits + length digit + 128 data digits
+const MAX_LINE_LEN: usize = 138;
+
+/// Time waited for the adapter to acknowledge a command
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Silence after which the replies to the reset sequence on open are complete
+const RESET_SETTLE_TIME: Duration = Duration::from_millis(50);
+
+/// Reply of the adapter rejecting a command
+const BEL: u8 = 0x07;
+
+/// CAN FD data lengths by length code
+const FD_LENGTHS: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Nominal CAN bit rates selectable with the SLCAN `S` command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlcanBitrate {
+    /// 10 kbit/s (`S0`)
+    Kbit10,
+    /// 20 kbit/s (`S1`)
+    Kbit20,
+    /// 50 kbit/s (`S2`)
+    Kbit50,
+    /// 100 kbit/s (`S3`)
+    Kbit100,
+    /// 125 kbit/s (`S4`)
+    Kbit125,
+    /// 250 kbit/s (`S5`)
+    Kbit250,
+    /// 500 kbit/s (`S6`)
+    Kbit500,
+    /// 800 kbit/s (`S7`)
+    Kbit800,
+    /// 1 Mbit/s (`S8`)
+    Mbit1,
+}
+
+impl SlcanBitrate {
+    /// The argument of the `S` command
+    fn code(self) -> u8 {
+        match self {
+            SlcanBitrate::Kbit10 => b'0',
+            SlcanBitrate::Kbit20 => b'1',
+            SlcanBitrate::Kbit50 => b'2',
+            SlcanBitrate::Kbit100 => b'3',
+            SlcanBitrate::Kbit125 => b'4',
+            SlcanBitrate::Kbit250 => b'5',
+            SlcanBitrate::Kbit500 => b'6',
+            SlcanBitrate::Kbit800 => b'7',
+            SlcanBitrate::Mbit1 => b'8',
+        }
+    }
+}
+
+/// CAN FD data phase bit rates selectable with the `Y` command of CAN FD capable firmwares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlcanDataBitrate {
+    /// 1 Mbit/s (`Y1`)
+    Mbit1,
+    /// 2 Mbit/s (`Y2`)
+    Mbit2,
+    /// 4 Mbit/s (`Y4`)
+    Mbit4,
+    /// 5 Mbit/s (`Y5`)
+    Mbit5,
+}
+
+impl SlcanDataBitrate {
+    /// The argument of the `Y` command
+    fn code(self) -> u8 {
+        match self {
+            SlcanDataBitrate::Mbit1 => b'1',
+            SlcanDataBitrate::Mbit2 => b'2',
+            SlcanDataBitrate::Mbit4 => b'4',
+            SlcanDataBitrate::Mbit5 => b'5',
+        }
+    }
+}
+
+/// A CAN adapter speaking the SLCAN (Lawicel) ASCII protocol on a serial port, a
+/// [`CanBackend`] for USB CAN dongles without a socketcan driver
+///
+/// Classic frames are exchanged with the `t`/`T` commands, CAN FD frames with the `d`/`D`
+/// and, for bit rate switching, the `b`/`B` commands of CAN FD capable firmwares such as
+/// the CANable 2.0, whose data phase bit rate is set with [`SlcanPort::set_data_bitrate`].
+/// Opening the channel fails if the adapter rejects a command with an error bell. On receive
+/// acknowledgements, error bells and remote frames are skipped, so a rejected transmission
+/// is not reported. The channel is closed on drop.
+///
+/// The serial line is configured with termios, like the rest of the crate this builds on
+/// Linux only.
+///
+/// ```rust,no_run
+/// use socketcan_isotp::userspace::{SlcanBitrate, SlcanPort, UserspaceIsoTpSocket};
+/// use socketcan_isotp::StandardId;
+///
+/// fn main() -> Result<(), socketcan_isotp::Error> {
+///     let port = SlcanPort::open("/dev/ttyACM0", SlcanBitrate::Kbit500)?;
+///     let mut tp_socket = UserspaceIsoTpSocket::with_backend(
+///         port,
+///         StandardId::new(0x7E8).expect("Invalid rx id"),
+///         StandardId::new(0x7E0).expect("Invalid tx id"),
+///         None,
+///         None,
+///         None,
+///     )?;
+///     tp_socket.write(&[0x22, 0xF1, 0x90])?;
+///     println!("{:02X?}", tp_socket.read()?);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SlcanPort {
+    port: File,
+    /// received bytes not yet terminated by a carriage return
+    buffer: Vec<u8>,
+}
+
+impl SlcanPort {
+    /// Open the serial port at `path` and the CAN channel with `bitrate`.
+    pub fn open(path: impl AsRef<Path>, bitrate: SlcanBitrate) -> Result<Self, Error> {
+        Self::open_with_baud_rate(path, bitrate, DEFAULT_BAUD_RATE)
+    }
+
+    /// Open the serial port at `path` with `baud_rate` and the CAN channel with `bitrate`.
+    ///
+    /// Fails with [`Error::InvalidInput`] for baud rates the serial line doesn't support and
+    /// with [`Error::Timeout`] if the adapter doesn't acknowledge the commands.
+    pub fn open_with_baud_rate(
+        path: impl AsRef<Path>,
+        bitrate: SlcanBitrate,
+        baud_rate: u32,
+    ) -> Result<Self, Error> {
+        let speed = baud_rate_speed(baud_rate)?;
+        let port = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(O_NOCTTY)
+            .open(path)?;
+        configure_tty(port.as_raw_fd(), speed)?;
+        Self::open_channel(port, bitrate)
+    }
+
+    /// Set the CAN FD data phase bit rate, closing and reopening the channel
+    pub fn set_data_bitrate(&mut self, data_bitrate: SlcanDataBitrate) -> Result<(), Error> {
+        self.command(b"C\r")?;
+        self.command(&[b'Y', data_bitrate.code(), b'\r'])?;
+        self.command(b"O\r")
+    }
+
+    fn open_channel(port: File, bitrate: SlcanBitrate) -> Result<Self, Error> {
+        let mut slcan = Self {
+            port,
+            buffer: Vec::new(),
+        };
+        // empty carriage returns discard a partial command left in the adapter, closing
+        // first lets the bit rate be changed on a channel left open. Whether these are
+        // acknowledged or rejected depends on the state of the adapter, so their replies
+        // are discarded.
+        slcan.port.write_all(b"\r\r\rC\r")?;
+        let deadline = Instant::now() + COMMAND_TIMEOUT;
+        while Instant::now() < deadline && slcan.fill_buffer(Some(RESET_SETTLE_TIME))? {}
+        slcan.buffer.clear();
+
+        slcan.command(&[b'S', bitrate.code(), b'\r'])?;
+        slcan.command(b"O\r")?;
+        Ok(slcan)
+    }
+
+    /// Send a command and wait for the adapter to acknowledge it with a carriage return
+    ///
+    /// Fails if the adapter rejects the command with an error bell. Frames received in the
+    /// meantime stay buffered.
+    fn command(&mut self, command: &[u8]) -> Result<(), Error> {
+        self.port.write_all(command)?;
+        let deadline = Instant::now() + COMMAND_TIMEOUT;
+        loop {
+            // the reply is a carriage return or bell at the start of a line
+            let reply = (0..self.buffer.len()).find(|&i| {
+                matches!(self.buffer[i], b'\r' | BEL)
+                    && (i == 0 || matches!(self.buffer[i - 1], b'\r' | b'\n' | BEL))
+            });
+            if let Some(i) = reply {
+                return match self.buffer.remove(i) {
+                    BEL => Err(Error::from(io::Error::other(format!(
+                        "SLCAN adapter rejected the {:?} command",
+                        char::from(command[0])
+                    )))),
+                    _ => Ok(()),
+                };
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self.fill_buffer(Some(remaining))? {
+                return Err(Error::from(io::Error::from_raw_os_error(libc::ETIMEDOUT)));
+            }
+        }
+    }
+
+    /// Append the bytes received within `timeout` to the buffer, `false` if none arrived
+    fn fill_buffer(&mut self, timeout: Option<Duration>) -> Result<bool, Error> {
+        if !poll::wait_readable(self.port.as_raw_fd(), timeout)? {
+            return Ok(false);
+        }
+        let mut chunk = [0; 256];
+        let len = match self.port.read(&mut chunk) {
+            Ok(0) => {
+                return Err(Error::from(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "SLCAN serial port closed",
+                )))
+            }
+            Ok(len) => len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => 0,
+            Err(err) => return Err(Error::from(err)),
+        };
+        self.buffer.extend_from_slice(&chunk[..len]);
+        Ok(true)
+    }
+
+    /// Take the next complete frame from the buffer, skipping all other lines
+    fn next_buffered_frame(&mut self) -> Option<CanFrame> {
+        while let Some(end) = self
+            .buffer
+            .iter()
+            .position(|&b| matches!(b, b'\r' | b'\n' | BEL))
+        {
+            let frame = parse_frame(&self.buffer[..end]);
+            self.buffer.drain(..=end);
+            if frame.is_some() {
+                return frame;
+            }
+        }
+        // a line this long is garbage, e.g. from a wrong baud rate
+        if self.buffer.len() > MAX_LINE_LEN {
+            self.buffer.clear();
+        }
+        None
+    }
+}
+
+impl CanBackend for SlcanPort {
+    fn send_frame(&mut self, frame: &CanFrame) -> Result<(), Error> {
+        let data = frame.get_data();
+        let extended = matches!(frame.get_id(), Id::Extended(_));
+        let command = match (frame.is_fd(), frame.get_bit_rate_switch()) {
+            (false, _) => b't',
+            (true, false) => b'd',
+            (true, true) => b'b',
+        };
+        let (command, id_digits) = if extended {
+            (command.to_ascii_uppercase(), 8)
+        } else {
+            (command, 3)
+        };
+        let length_code = if frame.is_fd() {
+            FD_LENGTHS.iter().position(|&len| len == data.len())
+        } else {
+            Some(data.len())
+        }
+        .ok_or(Error::InvalidInput("invalid CAN FD frame length"))?;
+
+        let mut line = Vec::with_capacity(MAX_LINE_LEN + 1);
+        line.push(command);
+        push_hex(&mut line, id_to_raw(frame.get_id()) & EFF_MASK, id_digits);
+        push_hex(&mut line, length_code as u32, 1);
+        for &byte in data {
+            push_hex(&mut line, u32::from(byte), 2);
+        }
+        line.push(b'\r');
+        self.port.write_all(&line)?;
+        Ok(())
+    }
+
+    fn receive_frame(&mut self, timeout: Option<Duration>) -> Result<Option<CanFrame>, Error> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(frame) = self.next_buffered_frame() {
+                return Ok(Some(frame));
+            }
+
+            let remaining =
+                deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            if !self.fill_buffer(remaining)? {
+                return Ok(None);
+            }
+        }
+    }
+}
+
+impl Drop for SlcanPort {
+    fn drop(&mut self) {
+        self.port.write_all(b"C\r").ok();
+    }
+}
+
+impl AsRawFd for SlcanPort {
+    fn as_raw_fd(&self) -> RawFd {
+        self.port.as_raw_fd()
+    }
+}
+
+impl AsFd for SlcanPort {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.port.as_fd()
+    }
+}
+
+/// Switch the serial line to raw 8N1 at `speed` and discard pending input
+fn configure_tty(fd: RawFd, speed: speed_t) -> io::Result<()> {
+    let mut tty: termios = unsafe { std::mem::zeroed() };
+    if unsafe { tcgetattr(fd, &mut tty) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe {
+        cfmakeraw(&mut tty);
+        cfsetispeed(&mut tty, speed);
+        cfsetospeed(&mut tty, speed);
+    }
+    if unsafe { tcsetattr(fd, TCSANOW, &tty) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    if unsafe { tcflush(fd, TCIFLUSH) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn baud_rate_speed(baud_rate: u32) -> Result<speed_t, Error> {
+    Ok(match baud_rate {
+        9_600 => libc::B9600,
+        19_200 => libc::B19200,
+        38_400 => libc::B38400,
+        57_600 => libc::B57600,
+        115_200 => libc::B115200,
+        230_400 => libc::B230400,
+        460_800 => libc::B460800,
+        921_600 => libc::B921600,
+        1_000_000 => libc::B1000000,
+        2_000_000 => libc::B2000000,
+        3_000_000 => libc::B3000000,
+        _ => return Err(Error::InvalidInput("unsupported serial baud rate")),
+    })
+}
+
+/// Append the `digits` lowest hex digits of `value`
+fn push_hex(line: &mut Vec<u8>, value: u32, digits: u32) {
+    for shift in (0..digits).rev() {
+        let nibble = (value >> (shift * 4)) & 0x0F;
+        line.push(b"0123456789ABCDEF"[nibble as usize]);
+    }
+}
+
+/// Parse `digits` hex digits, `None` if missing or invalid
+fn parse_hex(line: &[u8], digits: usize) -> Option<u32> {
+    let hex = std::str::from_utf8(line.get(..digits)?).ok()?;
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Parse a received data frame line without its carriage return, `None` for any other line
+///
+/// Trailing characters, e.g. timestamps, are ignored.
+fn parse_frame(line: &[u8]) -> Option<CanFrame> {
+    let (&command, line) = line.split_first()?;
+    let (fd, bit_rate_switch) = match command.to_ascii_lowercase() {
+        b't' => (false, false),
+        b'd' => (true, false),
+        b'b' => (true, true),
+        _ => return None,
+    };
+    let id_digits = if command.is_ascii_uppercase() { 8 } else { 3 };
+    let raw_id = parse_hex(line, id_digits)?;
+    let id = if id_digits == 8 {
+        Id::Extended(ExtendedId::new(raw_id)?)
+    } else {
+        Id::Standard(StandardId::new(raw_id as u16)?)
+    };
+    let line = &line[id_digits..];
+
+    let length_code = parse_hex(line, 1)? as usize;
+    let len = if fd {
+        FD_LENGTHS[length_code]
+    } else {
+        length_code.min(8)
+    };
+    let mut data = [0; 64];
+    for (i, byte) in data[..len].iter_mut().enumerate() {
+        *byte = parse_hex(line.get(1 + 2 * i..)?, 2)? as u8;
+    }
+
+    if fd {
+        CanFrame::new_fd(id, &data[..len], bit_rate_switch).ok()
+    } else {
+        CanFrame::new(id, &data[..len]).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::OwnedFd;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    /// Open a channel on one end of a socket pair, `adapter` answering on the other
+    fn open_with_adapter(
+        adapter: impl FnOnce(&mut UnixStream) + Send + 'static,
+    ) -> (Result<SlcanPort, Error>, UnixStream) {
+        let (port, mut peer) = UnixStream::pair().unwrap();
+        let adapter = thread::spawn(move || {
+            adapter(&mut peer);
+            peer
+        });
+        let slcan = SlcanPort::open_channel(File::from(OwnedFd::from(port)), SlcanBitrate::Kbit500);
+        (slcan, adapter.join().unwrap())
+    }
+
+    fn expect(adapter: &mut UnixStream, command: &[u8]) {
+        let mut received = vec![0; command.len()];
+        adapter.read_exact(&mut received).unwrap();
+        assert_eq!(received, command);
+    }
+
+    #[test]
+    fn open_waits_for_acknowledgements() {
+        let (slcan, mut adapter) = open_with_adapter(|adapter| {
+            expect(adapter, b"\r\r\rC\r");
+            adapter.write_all(b"\r\x07\r\x07").unwrap();
+            expect(adapter, b"S6\r");
+            adapter.write_all(b"\r").unwrap();
+            expect(adapter, b"O\r");
+            adapter.write_all(b"\rt7E823E00\r").unwrap();
+        });
+        let mut slcan = slcan.unwrap();
+        let frame = slcan.receive_frame(Some(COMMAND_TIMEOUT)).unwrap().unwrap();
+        assert_eq!(frame.get_data(), [0x3E, 0x00]);
+
+        let updater = thread::spawn(move || {
+            slcan.set_data_bitrate(SlcanDataBitrate::Mbit2).unwrap();
+            slcan
+        });
+        expect(&mut adapter, b"C\r");
+        // a frame received before the acknowledgement stays buffered
+        adapter.write_all(b"t7E8101\r\r").unwrap();
+        expect(&mut adapter, b"Y2\r");
+        adapter.write_all(b"\r").unwrap();
+        expect(&mut adapter, b"O\r");
+        adapter.write_all(b"\r").unwrap();
+        let mut slcan = updater.join().unwrap();
+        let frame = slcan.receive_frame(Some(COMMAND_TIMEOUT)).unwrap().unwrap();
+        assert_eq!(frame.get_data(), [0x01]);
+    }
+
+    #[test]
+    fn open_fails_on_rejected_command() {
+        let (slcan, _adapter) = open_with_adapter(|adapter| {
+            expect(adapter, b"\r\r\rC\r");
+            expect(adapter, b"S6\r");
+            adapter.write_all(b"\x07").unwrap();
+        });
+        let error = slcan.unwrap_err().to_string();
+        assert!(error.contains("rejected the 'S' command"), "{error}");
+    }
+
+    #[test]
+    fn open_times_out_without_acknowledgement() {
+        let (slcan, _adapter) = open_with_adapter(|adapter| {
+            expect(adapter, b"\r\r\rC\r");
+            expect(adapter, b"S6\r");
+        });
+        assert!(matches!(slcan, Err(Error::Timeout { .. })));
+    }
+}
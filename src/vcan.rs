@@ -0,0 +1,103 @@
+//! Virtual CAN interfaces for integration tests.
+//!
+//! [`VcanInterface`] creates a uniquely named vcan interface over rtnetlink, brings it up
+//! and deletes it again on drop, so tests can run ISO-TP end-to-end without manual
+//! `ip link` setup. Requires the `vcan` kernel module and `CAP_NET_ADMIN`.
+//!
+//! ```rust,no_run
+//! use socketcan_isotp::vcan::VcanInterface;
+//! use socketcan_isotp::{IsoTpSocket, StandardId};
+//!
+//! fn main() -> Result<(), socketcan_isotp::Error> {
+//!     let vcan = VcanInterface::create()?;
+//!     let (tester, mut ecu) = IsoTpSocket::pair(
+//!         vcan.get_name(),
+//!         StandardId::new(0x7E0).expect("Invalid id"),
+//!         StandardId::new(0x7E8).expect("Invalid id"),
+//!     )?;
+//!
+//!     tester.write(&[0x3E, 0x00])?;
+//!     assert_eq!(ecu.read()?, [0x3E, 0x00]);
+//!     Ok(())
+//! }
+//! ```
+
+use crate::netlink::{push_attribute, LinkRequest};
+use crate::{Error, CANFD_MTU};
+use libc::{
+    c_int, IFLA_IFNAME, IFLA_INFO_KIND, IFLA_LINKINFO, IFLA_MTU, NLM_F_CREATE, NLM_F_EXCL,
+    RTM_DELLINK, RTM_NEWLINK,
+};
+use nix::net::if_::if_nametoindex;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// Suffix making interface names unique within the process
+static NEXT_INTERFACE: AtomicU32 = AtomicU32::new(0);
+
+/// A virtual CAN interface deleted on drop, see the [module documentation](self)
+#[derive(Debug)]
+pub struct VcanInterface {
+    name: String,
+    index: u32,
+}
+
+impl VcanInterface {
+    /// Create and bring up a classic CAN vcan interface
+    pub fn create() -> Result<Self, Error> {
+        Self::create_with_mtu(None)
+    }
+
+    /// Create and bring up a CAN FD capable vcan interface with an MTU of [`CANFD_MTU`]
+    pub fn create_canfd() -> Result<Self, Error> {
+        Self::create_with_mtu(Some(CANFD_MTU as u32))
+    }
+
+    fn create_with_mtu(mtu: Option<u32>) -> Result<Self, Error> {
+        // IFNAMSIZ allows 15 characters, pid and counter fit in hex
+        let name = format!(
+            "vcan{:x}x{:x}",
+            std::process::id(),
+            NEXT_INTERFACE.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut request = LinkRequest::new(RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL, 0);
+        request.set_up(true);
+        request.attribute(IFLA_IFNAME, &[name.as_bytes(), &[0]].concat());
+        if let Some(mtu) = mtu {
+            request.attribute(IFLA_MTU, &mtu.to_ne_bytes());
+        }
+        let mut kind = Vec::new();
+        push_attribute(&mut kind, IFLA_INFO_KIND, b"vcan");
+        request.attribute(IFLA_LINKINFO, &kind);
+        request.execute()?;
+
+        let index = if_nametoindex(name.as_str())?;
+        Ok(Self { name, index })
+    }
+
+    /// Get the interface name to open sockets on
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the kernel interface number
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+
+    /// Delete the interface, reporting failures unlike drop
+    pub fn delete(self) -> Result<(), Error> {
+        let result = self.delete_link();
+        std::mem::forget(self);
+        result
+    }
+
+    fn delete_link(&self) -> Result<(), Error> {
+        LinkRequest::new(RTM_DELLINK, 0, self.index as c_int).execute()
+    }
+}
+
+impl Drop for VcanInterface {
+    fn drop(&mut self) {
+        self.delete_link().ok(); // ignore result
+    }
+}
@@ -0,0 +1,90 @@
+//! Extended and mixed addressing against the kernel ISO-TP implementation.
+//!
+//! Runs on a vcan interface and is skipped without the vcan module or `CAP_NET_ADMIN`.
+
+#![cfg(feature = "vcan")]
+
+use socketcan_isotp::addressing::ExtAddress;
+use socketcan_isotp::vcan::VcanInterface;
+use socketcan_isotp::{IsoTpSocket, IsoTpSocketBuilder, StandardId};
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// Tester request and ECU response identifiers
+const TESTER_ID: u16 = 0x7E0;
+const ECU_ID: u16 = 0x7E8;
+
+fn vcan() -> Option<VcanInterface> {
+    match VcanInterface::create() {
+        Ok(vcan) => Some(vcan),
+        Err(error) => {
+            eprintln!("skipped, no vcan interface: {error}");
+            None
+        }
+    }
+}
+
+fn open(vcan: &VcanInterface, rx_id: u16, tx_id: u16, ext_address: ExtAddress) -> IsoTpSocket {
+    IsoTpSocketBuilder::new()
+        .ext_address(ext_address)
+        .open(
+            vcan.get_name(),
+            StandardId::new(rx_id).unwrap(),
+            StandardId::new(tx_id).unwrap(),
+        )
+        .unwrap()
+}
+
+fn pdu(len: usize) -> Vec<u8> {
+    (0..len).map(|i| i as u8).collect()
+}
+
+#[test]
+fn mixed_addressing_round_trip() {
+    let Some(vcan) = vcan() else { return };
+    // the tester addresses the ECU with 0x10, the ECU answers with 0xF1
+    let mut tester = open(&vcan, ECU_ID, TESTER_ID, ExtAddress::mixed(0x10, 0xF1));
+    let mut ecu = open(&vcan, TESTER_ID, ECU_ID, ExtAddress::mixed(0xF1, 0x10));
+
+    // single frames hold 6 bytes next to the address byte, the rest is segmented
+    for len in [1, 6, 7, 62, 4095] {
+        let pdu = pdu(len);
+        tester.write(&pdu).unwrap();
+        assert_eq!(ecu.read().unwrap(), &pdu[..]);
+        ecu.write(&pdu).unwrap();
+        assert_eq!(tester.read().unwrap(), &pdu[..]);
+    }
+}
+
+#[test]
+fn mixed_addressing_ignores_other_rx_address() {
+    let Some(vcan) = vcan() else { return };
+    let tester = open(&vcan, ECU_ID, TESTER_ID, ExtAddress::mixed(0x10, 0xF1));
+    let mut ecu = open(&vcan, TESTER_ID, ECU_ID, ExtAddress::mixed(0xF1, 0x10));
+    let mut other_ecu = open(&vcan, TESTER_ID, ECU_ID, ExtAddress::mixed(0xF1, 0x20));
+    other_ecu.set_nonblocking(true).unwrap();
+
+    tester.write(&[0x3E, 0x00]).unwrap();
+    assert_eq!(ecu.read().unwrap(), [0x3E, 0x00]);
+    thread::sleep(Duration::from_millis(20));
+    let error = other_ecu.read().unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::WouldBlock);
+}
+
+#[cfg(feature = "userspace")]
+#[test]
+fn mixed_addressing_prefixes_frames_with_tx_address() {
+    use socketcan_isotp::userspace::{CanBackend, RawCanSocket};
+
+    let Some(vcan) = vcan() else { return };
+    let tester = open(&vcan, ECU_ID, TESTER_ID, ExtAddress::mixed(0x10, 0xF1));
+    let mut bus = RawCanSocket::open(vcan.get_name(), StandardId::new(TESTER_ID).unwrap()).unwrap();
+
+    tester.write(&[0x3E, 0x00]).unwrap();
+    let frame = bus
+        .receive_frame(Some(Duration::from_secs(1)))
+        .unwrap()
+        .expect("no frame on the bus");
+    assert_eq!(frame.get_data(), [0x10, 0x02, 0x3E, 0x00]);
+}
@@ -0,0 +1,45 @@
+//! OBD requests against an ECU simulated on a vcan interface.
+//!
+//! Skipped without the vcan module or `CAP_NET_ADMIN`.
+
+#![cfg(all(feature = "vcan", feature = "obd"))]
+
+use socketcan_isotp::obd::{ObdClient, FUNCTIONAL_REQUEST_ID, INFO_TYPE_CVN, INFO_TYPE_VIN};
+use socketcan_isotp::vcan::VcanInterface;
+use socketcan_isotp::{IsoTpSocket, StandardId};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn supported_info_types_decodes_bitmaps_without_count() {
+    let vcan = match VcanInterface::create() {
+        Ok(vcan) => vcan,
+        Err(error) => {
+            eprintln!("skipped, no vcan interface: {error}");
+            return;
+        }
+    };
+    let mut ecu = IsoTpSocket::open(
+        vcan.get_name(),
+        StandardId::new(FUNCTIONAL_REQUEST_ID).unwrap(),
+        StandardId::new(0x7E8).unwrap(),
+    )
+    .unwrap();
+    let mut client = ObdClient::open(vcan.get_name()).unwrap();
+    client.set_timeout(Duration::from_millis(200));
+    let ecu = thread::spawn(move || {
+        // InfoTypes 0x02, 0x06 and the next range, then InfoType 0x22
+        assert_eq!(ecu.read().unwrap(), [0x09, 0x00]);
+        ecu.write(&[0x49, 0x00, 0x44, 0x00, 0x00, 0x01]).unwrap();
+        assert_eq!(ecu.read().unwrap(), [0x09, 0x20]);
+        ecu.write(&[0x49, 0x20, 0x40, 0x00, 0x00, 0x00]).unwrap();
+    });
+
+    let supported = client.supported_info_types().unwrap();
+    ecu.join().unwrap();
+    assert_eq!(supported.len(), 1);
+    assert_eq!(
+        supported[0].value.iter().collect::<Vec<_>>(),
+        [INFO_TYPE_VIN, INFO_TYPE_CVN, 0x20, 0x22]
+    );
+}
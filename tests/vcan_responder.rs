@@ -0,0 +1,47 @@
+//! Scripted ECU simulation on a vcan interface.
+//!
+//! Skipped without the vcan module or `CAP_NET_ADMIN`.
+
+#![cfg(all(feature = "vcan", feature = "uds"))]
+
+use socketcan_isotp::clock::SimulatedClock;
+use socketcan_isotp::uds::{MockReply, ScriptedResponder, UdsClient};
+use socketcan_isotp::vcan::VcanInterface;
+use socketcan_isotp::{IsoTpSocket, StandardId};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn responder_delays_on_simulated_clock() {
+    let vcan = match VcanInterface::create() {
+        Ok(vcan) => vcan,
+        Err(error) => {
+            eprintln!("skipped, no vcan interface: {error}");
+            return;
+        }
+    };
+    let (tester, ecu) = IsoTpSocket::pair(
+        vcan.get_name(),
+        StandardId::new(0x7E0).unwrap(),
+        StandardId::new(0x7E8).unwrap(),
+    )
+    .unwrap();
+    let clock = SimulatedClock::new();
+    let responder = ScriptedResponder::new()
+        .with_clock(Arc::new(clock.clone()))
+        .reply(
+            &[0x31, 0x01, 0xFF, 0x00],
+            MockReply::new()
+                .response(&[0x7F, 0x31, 0x78])
+                .delayed_response(Duration::from_secs(4), &[0x71, 0x01, 0xFF, 0x00]),
+        )
+        .spawn(ecu);
+
+    let start = Instant::now();
+    let mut client = UdsClient::new(tester);
+    let status = client.start_routine(0xFF00, &[]).unwrap();
+    responder.stop().unwrap();
+    assert!(status.is_empty());
+    assert_eq!(clock.elapsed(), Duration::from_secs(4));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
@@ -0,0 +1,98 @@
+//! UDS scanner against ECUs simulated on a vcan interface.
+//!
+//! Skipped without the vcan module or `CAP_NET_ADMIN`.
+
+#![cfg(all(feature = "vcan", feature = "uds"))]
+
+use socketcan_isotp::uds::Scanner;
+use socketcan_isotp::vcan::VcanInterface;
+use socketcan_isotp::{Id, IsoTpSocket, StandardId};
+use std::thread;
+use std::time::Duration;
+
+const FUNCTIONAL_ID: u16 = 0x7DF;
+
+/// Answer the next functional request on `response_id` after `delay`
+fn spawn_ecu(vcan: &VcanInterface, response_id: u16, delay: Duration) -> thread::JoinHandle<()> {
+    let mut ecu = IsoTpSocket::open(
+        vcan.get_name(),
+        StandardId::new(FUNCTIONAL_ID).unwrap(),
+        StandardId::new(response_id).unwrap(),
+    )
+    .unwrap();
+    thread::spawn(move || {
+        ecu.read().unwrap();
+        thread::sleep(delay);
+        ecu.write(&[0x7E, 0x00]).unwrap();
+    })
+}
+
+#[test]
+fn scan_functional_times_each_response() {
+    let vcan = match VcanInterface::create() {
+        Ok(vcan) => vcan,
+        Err(error) => {
+            eprintln!("skipped, no vcan interface: {error}");
+            return;
+        }
+    };
+    let slow = spawn_ecu(&vcan, 0x7E8, Duration::from_millis(200));
+    let fast = spawn_ecu(&vcan, 0x7E9, Duration::from_millis(10));
+
+    let discovered = Scanner::new(vcan.get_name())
+        .physical_range(0x7E0..=0x7E1, 8)
+        .timeout(Duration::from_secs(1))
+        .scan_functional(StandardId::new(FUNCTIONAL_ID).unwrap())
+        .unwrap();
+    slow.join().unwrap();
+    fast.join().unwrap();
+
+    // the responses are listed in the order they arrived
+    let response_ids: Vec<Id> = discovered.iter().map(|ecu| ecu.response_id).collect();
+    assert_eq!(
+        response_ids,
+        [
+            Id::from(StandardId::new(0x7E9).unwrap()),
+            Id::from(StandardId::new(0x7E8).unwrap())
+        ]
+    );
+    assert!(discovered[0].response_time < Duration::from_millis(150));
+    assert!(discovered[1].response_time >= Duration::from_millis(200));
+    assert!(discovered.iter().all(|ecu| ecu.response == [0x7E, 0x00]));
+}
+
+#[cfg(feature = "userspace")]
+#[test]
+fn scan_functional_skips_failed_responses() {
+    use socketcan_isotp::userspace::{CanBackend, CanFrame, RawCanSocket};
+
+    let vcan = match VcanInterface::create() {
+        Ok(vcan) => vcan,
+        Err(error) => {
+            eprintln!("skipped, no vcan interface: {error}");
+            return;
+        }
+    };
+    let healthy = spawn_ecu(&vcan, 0x7E8, Duration::from_millis(10));
+    // sends the first frame of a response without its consecutive frames
+    let mut broken =
+        RawCanSocket::open(vcan.get_name(), StandardId::new(FUNCTIONAL_ID).unwrap()).unwrap();
+    let broken = thread::spawn(move || {
+        broken.receive_frame(None).unwrap();
+        let first_frame = [0x10, 0x14, 0x62, 0xF1, 0x90, 0x57, 0x56, 0x57];
+        broken
+            .send_frame(&CanFrame::new(StandardId::new(0x7E9).unwrap(), &first_frame).unwrap())
+            .unwrap();
+    });
+
+    let discovered = Scanner::new(vcan.get_name())
+        .physical_range(0x7E0..=0x7E1, 8)
+        .timeout(Duration::from_secs(3))
+        .scan_functional(StandardId::new(FUNCTIONAL_ID).unwrap())
+        .unwrap();
+    healthy.join().unwrap();
+    broken.join().unwrap();
+
+    let response_ids: Vec<Id> = discovered.iter().map(|ecu| ecu.response_id).collect();
+    assert_eq!(response_ids, [Id::from(StandardId::new(0x7E8).unwrap())]);
+}